@@ -1,5 +1,92 @@
 #![expect(missing_docs)]
 
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    feature = "build-env",
+    feature = "build-env-build"
+))]
+mod build_env {
+    include!("src/build_env.rs");
+}
+
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    feature = "build-host",
+    feature = "build-host-build"
+))]
+#[expect(dead_code)] // The build script only uses the builder
+mod build_host {
+    include!("src/build_host.rs");
+}
+
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    feature = "build-id",
+    feature = "build-id-build"
+))]
+mod build_id {
+    include!("src/build_id.rs");
+}
+
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    any(
+        all(feature = "cargo-lock", feature = "cargo-lock-build"),
+        feature = "provenance-build",
+        feature = "sbom-build"
+    )
+))]
+mod metadata {
+    include!("src/metadata.rs");
+}
+
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    any(
+        all(feature = "vergen-gix", feature = "vergen-gix-build"),
+        feature = "provenance-build"
+    )
+))]
+mod git {
+    include!("src/git.rs");
+}
+
+#[cfg(all(feature = "framework-libs-test-env", feature = "provenance-build"))]
+#[expect(dead_code)] // The build script only uses the builder
+mod provenance {
+    include!("src/provenance.rs");
+}
+
+/// A signer reversing the message, for testing the signing of the provenance.
+#[cfg(all(feature = "framework-libs-test-env", feature = "provenance-build"))]
+struct ReverseSigner;
+
+#[cfg(all(feature = "framework-libs-test-env", feature = "provenance-build"))]
+impl provenance::ProvenanceSigner for ReverseSigner {
+    fn key_id(&self) -> Option<String> {
+        Some("reverse".to_owned())
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(message.iter().rev().copied().collect())
+    }
+}
+
+#[cfg(all(feature = "framework-libs-test-env", feature = "sbom-build"))]
+#[expect(dead_code)] // The build script only uses the builder
+mod sbom {
+    include!("src/sbom.rs");
+}
+
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    feature = "cargo-lock",
+    feature = "cargo-lock-build"
+))]
+mod cargo_lock {
+    include!("src/cargo_lock.rs");
+}
+
 #[cfg(feature = "framework-libs-members-env")]
 mod cargo_workspace {
     include!("src/cargo_workspace.rs");
@@ -35,10 +122,84 @@ fn set_framework_libs_workspace_members_env() {
     .expect("Failed to set FRAMEWORK_LIBS_WORKSPACE_MEMBERS env var");
 }
 
+#[cfg(all(
+    feature = "framework-libs-test-env",
+    feature = "vergen-gix",
+    feature = "vergen-gix-build"
+))]
+#[expect(dead_code)] // The build script only uses the builder
+mod vergen {
+    include!("src/vergen.rs");
+}
+
+// The environment variables required by the tests of this crate are only generated with the
+// private `framework-libs-test-env` feature, so that dependents enabling the build-time features
+// do not generate them (or sign the provenance using the test signer)
 fn main() {
+    // Use a custom prefix, so that the `try_` variants of the macros without a prefix can be
+    // tested as well
+    #[cfg(all(
+        feature = "framework-libs-test-env",
+        feature = "vergen-gix",
+        feature = "vergen-gix-build"
+    ))]
+    vergen::VergenInstructions::default()
+        .prefix("BUILD_INFO_TEST_")
+        .constants(true)
+        .json(true)
+        .emit();
+
+    #[cfg(all(
+        feature = "framework-libs-test-env",
+        feature = "build-id",
+        feature = "build-id-build"
+    ))]
+    build_id::set_build_id_env();
+
+    #[cfg(all(
+        feature = "framework-libs-test-env",
+        feature = "build-env",
+        feature = "build-env-build"
+    ))]
+    build_env::set_build_env(&["CARGO_PKG_NAME", "BUILD_INFO_TEST_UNSET"]);
+
+    #[cfg(all(
+        feature = "framework-libs-test-env",
+        feature = "build-host",
+        feature = "build-host-build"
+    ))]
+    build_host::BuildHostInstructions::default()
+        .hostname(true)
+        .emit();
+
+    #[cfg(all(feature = "framework-libs-test-env", feature = "sbom-build"))]
+    sbom::SbomInstructions::default().embed_hash(true).emit();
+
+    #[cfg(all(feature = "framework-libs-test-env", feature = "provenance-build"))]
+    provenance::ProvenanceInstructions::default()
+        .signer(&ReverseSigner)
+        .emit();
+
+    #[cfg(all(
+        feature = "framework-libs-test-env",
+        feature = "cargo-lock",
+        feature = "cargo-lock-build"
+    ))]
+    {
+        cargo_lock::set_cargo_lock_hash_env();
+        cargo_lock::set_direct_dependencies_env();
+    }
+
     #[cfg(feature = "framework-libs-members-env")]
     {
         cargo_workspace::set_cargo_workspace_members_env();
+        cargo_workspace::set_cargo_workspace_member_metadata_env();
+        cargo_workspace::set_cargo_workspace_dependency_graph_env();
+        cargo_workspace::CargoWorkspaceInstructions::default()
+            .prefix("BUILD_INFO_TEST_")
+            .path_dependencies(true)
+            .emit();
         set_framework_libs_workspace_members_env();
+        cargo_workspace::set_cargo_workspace_member_metadata_env_with_prefix("FRAMEWORK_LIBS_");
     }
 }
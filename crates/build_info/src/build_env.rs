@@ -0,0 +1,89 @@
+/// The separator between the entries of the `BUILD_ENV` environment variable, the ASCII unit
+/// separator, which is not expected to occur in the values of environment variables.
+pub(crate) const ENTRY_SEPARATOR: char = '\u{1f}';
+
+/// Set the `BUILD_ENV_<NAME>` environment variables to the values of the allow-listed environment
+/// variables present at build time, so that the deployment context baked into the binary at build
+/// time (such as `DEPLOY_ENV` or `IMAGE_TAG`) is queryable at runtime.
+///
+/// The `BUILD_ENV` environment variable is set as well, to all of the captured environment
+/// variables, for access as a map using the [`build_env!()`][crate::build_env] macro.
+/// Only the environment variables which are set (to a value without line breaks) are captured,
+/// with the others being omitted, and the build script is re-run whenever any of the
+/// allow-listed environment variables changes.
+///
+/// Note that the captured values are embedded into the compiled binary as is, and hence secrets
+/// must never be allow-listed.
+///
+/// This function should be typically called within build scripts, so that the environment
+/// variables are available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if any of the allow-listed names is empty or contains `=`, or if writing the build
+/// instructions to the standard output fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_build_env(&["DEPLOY_ENV", "IMAGE_TAG"]);
+/// ```
+#[expect(clippy::expect_used, clippy::panic)]
+pub fn set_build_env(allow_list: &[&str]) {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    for name in allow_list {
+        if name.is_empty() || name.contains('=') {
+            panic!("Invalid build environment variable name `{name}`");
+        }
+        writeln!(&mut stdout, "cargo:rerun-if-env-changed={name}")
+            .expect("Failed to emit `rerun-if-env-changed` instruction");
+    }
+
+    let variables = build_env_variables(allow_list, |name| std::env::var(name).ok());
+    for (name, value) in &variables {
+        writeln!(&mut stdout, "cargo:rustc-env=BUILD_ENV_{name}={value}")
+            .expect("Failed to set `BUILD_ENV_*` environment variable");
+    }
+    writeln!(
+        &mut stdout,
+        "cargo:rustc-env=BUILD_ENV={}",
+        encode_build_env(&variables)
+    )
+    .expect("Failed to set `BUILD_ENV` environment variable");
+}
+
+/// Returns the allow-listed environment variables to capture along with their values, reading the
+/// environment variables using `var`.
+///
+/// The environment variables with line breaks or the [`ENTRY_SEPARATOR`] in their values are
+/// skipped, since they cannot be passed to the compiler as is.
+pub(crate) fn build_env_variables<'a>(
+    allow_list: &[&'a str],
+    var: impl Fn(&str) -> Option<String>,
+) -> Vec<(&'a str, String)> {
+    let mut variables = Vec::new();
+    for name in allow_list {
+        if variables.iter().any(|(captured, _)| captured == name) {
+            continue;
+        }
+        if let Some(value) =
+            var(name).filter(|value| !value.contains(['\n', '\r', ENTRY_SEPARATOR]))
+        {
+            variables.push((*name, value));
+        }
+    }
+    variables
+}
+
+/// Encodes the captured environment variables as `NAME=value` entries separated by the
+/// [`ENTRY_SEPARATOR`], for the `BUILD_ENV` environment variable.
+pub(crate) fn encode_build_env(variables: &[(&str, String)]) -> String {
+    variables
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(&ENTRY_SEPARATOR.to_string())
+}
@@ -0,0 +1,44 @@
+//! Macros for accessing the allow-listed build environment variables captured by the build script.
+
+/// Returns the value of the allow-listed environment variable at build time, such as
+/// `build_env_var!("DEPLOY_ENV")`, if it was captured.
+///
+/// Reads the `BUILD_ENV_<NAME>` environment variable, returning `None` if it is unset (such as if
+/// the environment variable was not allow-listed or was unset at build time).
+#[macro_export]
+macro_rules! build_env_var {
+    ($name:literal) => {
+        option_env!(concat!("BUILD_ENV_", $name))
+    };
+}
+
+/// Obtain the allow-listed environment variables captured at build time as a `HashMap` of the
+/// names to the values, such as for exposing the deployment context of the binary.
+///
+/// This macro requires that [`set_build_env()`][crate::set_build_env] function be called in the
+/// build script of the crate where this macro is being called.
+/// Reads the `BUILD_ENV` environment variable, holding `NAME=value` entries separated by the
+/// ASCII unit separator.
+///
+/// # Errors
+///
+/// Causes a compilation error if the `BUILD_ENV` environment variable is unset.
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// let build_env = build_info::build_env!();
+/// if let Some(deploy_env) = build_env.get("DEPLOY_ENV") {
+///     println!("Built for {deploy_env}");
+/// }
+/// ```
+#[macro_export]
+macro_rules! build_env {
+    () => {
+        std::env!("BUILD_ENV")
+            .split('\u{1f}')
+            .filter_map(|entry| entry.split_once('='))
+            .collect::<std::collections::HashMap<&'static str, &'static str>>()
+    };
+}
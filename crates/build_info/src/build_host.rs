@@ -0,0 +1,108 @@
+/// Set the `BUILD_HOST_*` environment variables with information about the machine the crate is
+/// being built on, to help triage issues traced to a specific build machine or toolchain.
+///
+/// This sets the following environment variables:
+///
+/// - `BUILD_HOST_OS`: the operating system of the build host, such as `linux` or `macos`
+/// - `BUILD_HOST_ARCH`: the architecture of the build host, such as `x86_64` or `aarch64`
+/// - `BUILD_HOST_TRIPLE`: the target triple of the build host, such as `x86_64-unknown-linux-gnu`
+///
+/// Use [`BuildHostInstructions`] instead to also capture the username of the builder and the
+/// hostname of the build host, which are excluded by default for privacy.
+///
+/// This function should be typically called within build scripts, so that the environment
+/// variables are available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if writing the build instructions to the standard output fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_build_host_env();
+/// ```
+pub fn set_build_host_env() {
+    BuildHostInstructions::default().emit();
+}
+
+/// A builder for selecting the information about the build host to capture, with the username
+/// of the builder and the hostname of the build host being explicitly opt-in for privacy.
+///
+/// Refer to the documentation of [`set_build_host_env()`] for the information captured by
+/// default.
+/// Note that the username and hostname vary between build hosts, and hence should not be enabled
+/// for reproducible builds.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::BuildHostInstructions::default()
+///     .username(true)
+///     .hostname(true)
+///     .emit();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildHostInstructions {
+    username: bool,
+    hostname: bool,
+}
+
+impl BuildHostInstructions {
+    /// Specifies whether to set the `BUILD_HOST_USER` environment variable to the username of the
+    /// builder, read from the `USER` (or `USERNAME` on Windows) environment variable.
+    pub fn username(mut self, enabled: bool) -> Self {
+        self.username = enabled;
+        self
+    }
+
+    /// Specifies whether to set the `BUILD_HOST_NAME` environment variable to the hostname of the
+    /// build host.
+    pub fn hostname(mut self, enabled: bool) -> Self {
+        self.hostname = enabled;
+        self
+    }
+
+    /// Generates the selected `cargo` build instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if writing the build instructions to the standard output fails.
+    #[expect(clippy::expect_used)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        // The build script runs on the build host, so the constants of the standard library
+        // describe the build host rather than the target
+        let mut variables = vec![
+            ("BUILD_HOST_OS", std::env::consts::OS.to_owned()),
+            ("BUILD_HOST_ARCH", std::env::consts::ARCH.to_owned()),
+        ];
+        if let Ok(host) = std::env::var("HOST") {
+            variables.push(("BUILD_HOST_TRIPLE", host));
+        }
+        if self.username {
+            if let Some(username) = std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .ok()
+                .filter(|username| !username.is_empty())
+            {
+                variables.push(("BUILD_HOST_USER", username));
+            }
+        }
+        if self.hostname {
+            variables.push((
+                "BUILD_HOST_NAME",
+                gethostname::gethostname().to_string_lossy().into_owned(),
+            ));
+        }
+
+        let mut stdout = std::io::stdout();
+        for (name, value) in variables {
+            writeln!(&mut stdout, "cargo:rustc-env={name}={value}")
+                .expect("Failed to set `BUILD_HOST_*` environment variable");
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Macros for accessing the build host information generated by the build script.
+
+/// Returns the operating system of the build host, such as `linux` or `macos`.
+///
+/// Reads the `BUILD_HOST_OS` environment variable.
+#[macro_export]
+macro_rules! build_host_os {
+    () => {
+        env!("BUILD_HOST_OS")
+    };
+}
+
+/// Returns the architecture of the build host, such as `x86_64` or `aarch64`.
+///
+/// Reads the `BUILD_HOST_ARCH` environment variable.
+#[macro_export]
+macro_rules! build_host_arch {
+    () => {
+        env!("BUILD_HOST_ARCH")
+    };
+}
+
+/// Returns the target triple of the build host, such as `x86_64-unknown-linux-gnu`.
+///
+/// Reads the `BUILD_HOST_TRIPLE` environment variable.
+#[macro_export]
+macro_rules! build_host_triple {
+    () => {
+        env!("BUILD_HOST_TRIPLE")
+    };
+}
+
+/// Returns the username of the builder, if it was captured.
+///
+/// Reads the `BUILD_HOST_USER` environment variable, returning `None` if it is unset (such as if
+/// capturing the username was not enabled in the build script).
+#[macro_export]
+macro_rules! build_host_user {
+    () => {
+        option_env!("BUILD_HOST_USER")
+    };
+}
+
+/// Returns the hostname of the build host, if it was captured.
+///
+/// Reads the `BUILD_HOST_NAME` environment variable, returning `None` if it is unset (such as if
+/// capturing the hostname was not enabled in the build script).
+#[macro_export]
+macro_rules! build_host_name {
+    () => {
+        option_env!("BUILD_HOST_NAME")
+    };
+}
@@ -0,0 +1,50 @@
+/// Set the `BUILD_ID` environment variable to a random (version 4) UUID, so that binaries built
+/// from the same commit at different times can be distinguished in logs and crash reports.
+///
+/// A new build ID is generated whenever the build script is run, such as on clean builds, or when
+/// the inputs the build script depends on change.
+/// Note that the build ID is inherently non-deterministic, and hence should not be used for
+/// reproducible builds.
+///
+/// This function should be typically called within build scripts, so that the environment
+/// variable is available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if obtaining random bytes from the operating system fails, or if writing the build
+/// instruction to the standard output fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_build_id_env();
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_build_id_env() {
+    use std::io::Write;
+
+    let mut bytes = [0; 16];
+    getrandom::fill(&mut bytes).expect("Failed to obtain random bytes");
+
+    writeln!(
+        &mut std::io::stdout(),
+        "cargo:rustc-env=BUILD_ID={}",
+        uuid_v4(u128::from_be_bytes(bytes))
+    )
+    .expect("Failed to set `BUILD_ID` environment variable");
+}
+
+/// Formats the random bits as a version 4 UUID, overwriting the version and variant bits.
+fn uuid_v4(random: u128) -> String {
+    let uuid = (random & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        uuid >> 96,
+        (uuid >> 80) & 0xffff,
+        (uuid >> 64) & 0xffff,
+        (uuid >> 48) & 0xffff,
+        uuid & 0xffff_ffff_ffff
+    )
+}
@@ -0,0 +1,104 @@
+/// Set the `CARGO_LOCK_SHA256` environment variable to the SHA-256 hash of the `Cargo.lock` file
+/// of the current cargo workspace.
+///
+/// This allows distinguishing binaries built from the same git commit but with different resolved
+/// dependencies (such as when the `Cargo.lock` file is not committed, or is updated during the
+/// build).
+/// The build script is re-run whenever the `Cargo.lock` file changes.
+///
+/// This function should be typically called within build scripts, so that the environment variable
+/// is available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command or reading the `Cargo.lock` file fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_lock_hash_env();
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_cargo_lock_hash_env() {
+    use std::io::Write;
+
+    use sha2::{Digest, Sha256};
+
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .expect("Failed to obtain cargo metadata");
+
+    let lock_file = metadata.workspace_root.join("Cargo.lock");
+    let contents = std::fs::read(&lock_file).expect("Failed to read `Cargo.lock` file");
+    let hash = Sha256::digest(contents);
+
+    let mut stdout = std::io::stdout();
+    writeln!(&mut stdout, "cargo:rerun-if-changed={lock_file}")
+        .expect("Failed to emit `rerun-if-changed` instruction");
+    writeln!(&mut stdout, "cargo:rustc-env=CARGO_LOCK_SHA256={hash:x}")
+        .expect("Failed to set `CARGO_LOCK_SHA256` environment variable");
+}
+
+/// Set the `CARGO_DIRECT_DEPENDENCIES` environment variable to the resolved versions of the
+/// direct (normal) dependencies of the current crate, as a comma-separated list of
+/// `name@version` entries sorted by name.
+///
+/// The dependencies are resolved for the features the crate is being compiled with and for the
+/// target platform, so that optional and platform-specific dependencies which are not compiled
+/// are excluded.
+/// Development and build dependencies are excluded as well.
+/// The build script is re-run whenever the `Cargo.lock` file changes.
+///
+/// This function should be typically called within build scripts, so that the environment variable
+/// is available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails, or if the current crate is not present
+/// in its output.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::set_direct_dependencies_env();
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_direct_dependencies_env() {
+    use std::io::Write;
+
+    use crate::metadata::{
+        current_package, is_normal_dependency, resolve_current_package, resolve_node,
+    };
+
+    let metadata = resolve_current_package();
+    let node = resolve_node(&metadata, &current_package(&metadata).id);
+
+    let mut dependencies = node
+        .deps
+        .iter()
+        .filter(|dependency| is_normal_dependency(dependency))
+        .map(|dependency| {
+            let package = &metadata[&dependency.pkg];
+            format!("{}@{}", package.name.as_str(), package.version)
+        })
+        .collect::<Vec<_>>();
+    dependencies.sort_unstable();
+    dependencies.dedup();
+
+    let mut stdout = std::io::stdout();
+    writeln!(
+        &mut stdout,
+        "cargo:rerun-if-changed={}",
+        metadata.workspace_root.join("Cargo.lock")
+    )
+    .expect("Failed to emit `rerun-if-changed` instruction");
+    writeln!(
+        &mut stdout,
+        "cargo:rustc-env=CARGO_DIRECT_DEPENDENCIES={}",
+        dependencies.join(",")
+    )
+    .expect("Failed to set `CARGO_DIRECT_DEPENDENCIES` environment variable");
+}
@@ -1,6 +1,11 @@
 /// Sets the `CARGO_WORKSPACE_MEMBERS` environment variable to include a comma-separated list of
 /// names of all crates in the current cargo workspace.
 ///
+/// The `CARGO_WORKSPACE_MEMBER_VERSIONS` environment variable is set as well, to a
+/// comma-separated list of `name@version` entries of all crates in the current cargo workspace.
+/// Use [`CargoWorkspaceInstructions`] instead to also include the local path dependencies which
+/// are not workspace members.
+///
 /// This function should be typically called within build scripts, so that the environment variable
 /// is available to the corresponding crate at compile time.
 ///
@@ -14,26 +19,332 @@
 /// // In your crate's build script (build.rs):
 /// build_info::set_cargo_workspace_members_env();
 /// ```
-#[expect(clippy::expect_used)]
 pub fn set_cargo_workspace_members_env() {
+    set_cargo_workspace_members_env_with_prefix("");
+}
+
+/// Sets the `<prefix>CARGO_WORKSPACE_MEMBERS` environment variable to include a comma-separated
+/// list of names of all crates in the current cargo workspace, along with the
+/// `<prefix>CARGO_WORKSPACE_MEMBER_VERSIONS` environment variable to include a comma-separated
+/// list of `name@version` entries of all crates in the current cargo workspace.
+///
+/// This is similar to [`set_cargo_workspace_members_env()`], with the name of the environment
+/// variable prefixed to avoid collisions when multiple build scripts in a dependency tree set it.
+/// The same prefix must then be passed to the
+/// [`cargo_workspace_members!()`][crate::cargo_workspace_members] and
+/// [`cargo_workspace_member_versions!()`][crate::cargo_workspace_member_versions] macros.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_workspace_members_env_with_prefix("MYAPP_");
+/// ```
+pub fn set_cargo_workspace_members_env_with_prefix(prefix: &str) {
+    CargoWorkspaceInstructions::default().prefix(prefix).emit();
+}
+
+/// A builder for configuring the `CARGO_WORKSPACE_MEMBERS` and `CARGO_WORKSPACE_MEMBER_VERSIONS`
+/// environment variables set by [`set_cargo_workspace_members_env()`].
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::CargoWorkspaceInstructions::default()
+///     .prefix("MYAPP_")
+///     .path_dependencies(true)
+///     .emit();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CargoWorkspaceInstructions<'a> {
+    prefix: &'a str,
+    path_dependencies: bool,
+}
+
+impl<'a> CargoWorkspaceInstructions<'a> {
+    /// Specifies the prefix of the names of the environment variables to set, such as `MYAPP_`
+    /// for `MYAPP_CARGO_WORKSPACE_MEMBERS`, to avoid collisions when multiple build scripts in a
+    /// dependency tree set them.
+    /// The same prefix must then be passed to the
+    /// [`cargo_workspace_members!()`][crate::cargo_workspace_members] and
+    /// [`cargo_workspace_member_versions!()`][crate::cargo_workspace_member_versions] macros.
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Specifies whether to also include the local path dependencies which are not members of the
+    /// current cargo workspace (such as crates checked out from other repositories alongside the
+    /// workspace), so that they are treated like the workspace members.
+    pub fn path_dependencies(mut self, enabled: bool) -> Self {
+        self.path_dependencies = enabled;
+        self
+    }
+
+    /// Generates the configured `cargo` build instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if running the `cargo metadata` command fails.
+    #[expect(clippy::expect_used)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .exec()
+            .expect("Failed to obtain cargo metadata");
+
+        // Packages without a source are local path dependencies
+        let mut packages = metadata.workspace_packages();
+        if self.path_dependencies {
+            packages.extend(metadata.packages.iter().filter(|package| {
+                package.source.is_none() && !metadata.workspace_members.contains(&package.id)
+            }));
+        }
+
+        let workspace_members = packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let workspace_member_versions = packages
+            .iter()
+            .map(|package| format!("{}@{}", package.name.as_str(), package.version))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let prefix = self.prefix;
+        let mut stdout = std::io::stdout();
+        writeln!(
+            &mut stdout,
+            "cargo:rustc-env={prefix}CARGO_WORKSPACE_MEMBERS={workspace_members}"
+        )
+        .expect("Failed to set `CARGO_WORKSPACE_MEMBERS` environment variable");
+        writeln!(
+            &mut stdout,
+            "cargo:rustc-env={prefix}CARGO_WORKSPACE_MEMBER_VERSIONS={workspace_member_versions}"
+        )
+        .expect("Failed to set `CARGO_WORKSPACE_MEMBER_VERSIONS` environment variable");
+    }
+}
+
+/// Sets the `CARGO_WORKSPACE_MEMBER_METADATA` environment variable to include the version,
+/// library target name, manifest path, `publish` status, Rust edition, declared features and
+/// default features of all crates in the current cargo workspace.
+///
+/// The crates are listed in the order they are listed by cargo.
+/// The manifest paths are relative to the workspace root, so that the environment variable does
+/// not depend on the directory the workspace was built in.
+/// A crate is considered published unless its `publish` field is set to `false` (or to an empty
+/// list of registries).
+/// The declared features exclude the `default` feature, whose contents are captured as the default
+/// features instead.
+///
+/// This function should be typically called within build scripts, so that the environment variable
+/// is available to the corresponding crate at compile time, where it can be read using the
+/// [`cargo_workspace_member_metadata!()`][crate::cargo_workspace_member_metadata] macro.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_workspace_member_metadata_env();
+/// ```
+pub fn set_cargo_workspace_member_metadata_env() {
+    set_cargo_workspace_member_metadata_env_with_prefix("");
+}
+
+/// Sets the `<prefix>CARGO_WORKSPACE_MEMBER_METADATA` environment variable to include the version,
+/// library target name, manifest path, `publish` status, Rust edition, declared features and
+/// default features of all crates in the current cargo workspace.
+///
+/// This is similar to [`set_cargo_workspace_member_metadata_env()`], with the name of the
+/// environment variable prefixed to avoid collisions when multiple build scripts in a dependency
+/// tree set it.
+/// The same prefix must then be passed to the
+/// [`cargo_workspace_member_metadata!()`][crate::cargo_workspace_member_metadata] macro.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_workspace_member_metadata_env_with_prefix("MYAPP_");
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_cargo_workspace_member_metadata_env_with_prefix(prefix: &str) {
     use std::io::Write;
 
+    use cargo_metadata::TargetKind;
+
     let metadata = cargo_metadata::MetadataCommand::new()
+        .no_deps()
         .exec()
         .expect("Failed to obtain cargo metadata");
 
-    let workspace_members = metadata
-        .workspace_packages()
+    // Each member is encoded as
+    // `name|version|lib_name|publish|edition|features|default_features|manifest_path`, with the
+    // features separated by `,` and the members separated by `;`, in the order the members are
+    // listed by cargo
+    let member_metadata = metadata
+        .workspace_members
+        .iter()
+        .filter_map(|id| metadata.packages.iter().find(|package| package.id == *id))
+        .map(|package| {
+            let lib_name = package
+                .targets
+                .iter()
+                .find(|target| {
+                    target.kind.iter().any(|kind| {
+                        matches!(
+                            kind,
+                            TargetKind::Lib
+                                | TargetKind::RLib
+                                | TargetKind::DyLib
+                                | TargetKind::CDyLib
+                                | TargetKind::StaticLib
+                                | TargetKind::ProcMacro
+                        )
+                    })
+                })
+                .map(|target| target.name.as_str())
+                .unwrap_or_default();
+            let publish = package
+                .publish
+                .as_ref()
+                .is_none_or(|registries| !registries.is_empty());
+            let manifest_path = package
+                .manifest_path
+                .strip_prefix(&metadata.workspace_root)
+                .unwrap_or(&package.manifest_path);
+
+            let features = package
+                .features
+                .keys()
+                .filter(|feature| *feature != "default")
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(",");
+            let default_features = package
+                .features
+                .get("default")
+                .map(|features| features.join(","))
+                .unwrap_or_default();
+
+            format!(
+                "{}|{}|{lib_name}|{publish}|{}|{features}|{default_features}|{manifest_path}",
+                package.name.as_str(),
+                package.version,
+                package.edition
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+
+    writeln!(
+        &mut std::io::stdout(),
+        "cargo:rustc-env={prefix}CARGO_WORKSPACE_MEMBER_METADATA={member_metadata}"
+    )
+    .expect("Failed to set `CARGO_WORKSPACE_MEMBER_METADATA` environment variable");
+}
+
+/// Sets the `CARGO_WORKSPACE_DEPENDENCY_GRAPH` environment variable to include the dependencies of
+/// each crate in the current cargo workspace on the other crates in the workspace.
+///
+/// The dependencies declared in the manifests of the crates are included regardless of the
+/// enabled features, along with all kinds of dependencies (normal, development and build
+/// dependencies), since changes to any of them may affect the dependent crate.
+///
+/// This function should be typically called within build scripts, so that the environment variable
+/// is available to the corresponding crate at compile time, where it can be read using the
+/// [`cargo_workspace_dependency_graph!()`][crate::cargo_workspace_dependency_graph] macro.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_workspace_dependency_graph_env();
+/// ```
+pub fn set_cargo_workspace_dependency_graph_env() {
+    set_cargo_workspace_dependency_graph_env_with_prefix("");
+}
+
+/// Sets the `<prefix>CARGO_WORKSPACE_DEPENDENCY_GRAPH` environment variable to include the
+/// dependencies of each crate in the current cargo workspace on the other crates in the workspace.
+///
+/// This is similar to [`set_cargo_workspace_dependency_graph_env()`], with the name of the
+/// environment variable prefixed to avoid collisions when multiple build scripts in a dependency
+/// tree set it.
+/// The same prefix must then be passed to the
+/// [`cargo_workspace_dependency_graph!()`][crate::cargo_workspace_dependency_graph] macro.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_cargo_workspace_dependency_graph_env_with_prefix("MYAPP_");
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_cargo_workspace_dependency_graph_env_with_prefix(prefix: &str) {
+    use std::io::Write;
+
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .expect("Failed to obtain cargo metadata");
+
+    let workspace_packages = metadata.workspace_packages();
+    let workspace_members = workspace_packages
         .iter()
         .map(|package| package.name.as_str())
+        .collect::<std::collections::HashSet<_>>();
+
+    // Each member is encoded as `name:dependency,dependency`, with the members separated by `;`
+    let dependency_graph = workspace_packages
+        .iter()
+        .map(|package| {
+            let mut dependencies = package
+                .dependencies
+                .iter()
+                .filter(|dependency| {
+                    dependency.path.is_some()
+                        && workspace_members.contains(dependency.name.as_str())
+                })
+                .map(|dependency| dependency.name.as_str())
+                .collect::<Vec<_>>();
+            dependencies.sort_unstable();
+            dependencies.dedup();
+
+            format!("{}:{}", package.name.as_str(), dependencies.join(","))
+        })
         .collect::<Vec<_>>()
-        .join(",");
+        .join(";");
 
     writeln!(
         &mut std::io::stdout(),
-        "cargo:rustc-env=CARGO_WORKSPACE_MEMBERS={workspace_members}"
+        "cargo:rustc-env={prefix}CARGO_WORKSPACE_DEPENDENCY_GRAPH={dependency_graph}"
     )
-    .expect("Failed to set `CARGO_WORKSPACE_MEMBERS` environment variable");
+    .expect("Failed to set `CARGO_WORKSPACE_DEPENDENCY_GRAPH` environment variable");
 }
 
 #[cfg(test)]
@@ -70,4 +381,106 @@ mod tests {
             "Current crate is not present in the output of `cargo_workspace_members!()` macro"
         );
     }
+
+    #[cfg(feature = "framework-libs-members-env")]
+    #[test]
+    fn test_cargo_workspace_member_versions_contains_current_crate() {
+        let versions = crate::cargo_workspace_member_versions!();
+
+        assert_eq!(
+            versions.get(env!("CARGO_PKG_NAME")),
+            Some(&env!("CARGO_PKG_VERSION")),
+            "Current crate version is not present in the output of \
+             `cargo_workspace_member_versions!()` macro"
+        );
+        assert_eq!(
+            versions
+                .keys()
+                .copied()
+                .collect::<std::collections::HashSet<_>>(),
+            crate::cargo_workspace_members!()
+        );
+    }
+
+    #[cfg(feature = "framework-libs-members-env")]
+    #[test]
+    fn test_cargo_workspace_members_with_path_dependencies() {
+        // The workspace has no path dependencies outside the workspace, so including them does
+        // not change the members
+        assert_eq!(
+            crate::cargo_workspace_members!("BUILD_INFO_TEST_"),
+            crate::cargo_workspace_members!()
+        );
+        assert_eq!(
+            crate::cargo_workspace_member_versions!("BUILD_INFO_TEST_"),
+            crate::cargo_workspace_member_versions!()
+        );
+    }
+
+    #[cfg(feature = "framework-libs-members-env")]
+    #[test]
+    fn test_cargo_workspace_member_metadata_contains_current_crate() {
+        let members = crate::cargo_workspace_member_metadata!();
+        let current_crate = members
+            .iter()
+            .find(|member| member.name == env!("CARGO_PKG_NAME"))
+            .expect("Current crate is not present in the workspace member metadata");
+
+        assert_eq!(current_crate.manifest_path, "crates/build_info/Cargo.toml");
+        assert!(current_crate.publish);
+        assert_eq!(current_crate.edition, "2021");
+        assert!(current_crate.features.contains(&"cargo-workspace-build"));
+        assert!(!current_crate.features.contains(&"default"));
+        assert!(current_crate.default_features.is_empty());
+        assert_eq!(current_crate.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(current_crate.lib_name, Some("build_info"));
+        // The examples are binary-only crates which are not published
+        assert!(members.iter().any(|member| {
+            member.name == "vergen_integration" && !member.publish && member.lib_name.is_none()
+        }));
+    }
+
+    #[cfg(feature = "framework-libs-members-env")]
+    #[test]
+    fn test_workspace_filtering_directive() {
+        let directive = crate::workspace_filtering_directive!("debug");
+
+        assert_eq!(
+            directive.split(',').count(),
+            crate::cargo_workspace_members!().len()
+        );
+        assert!(
+            directive
+                .split(',')
+                .any(|entry| entry == "build_info=debug")
+        );
+        assert_eq!(
+            crate::workspace_filtering_directive("trace", ["b-crate", "a_crate", "b_crate"]),
+            "a_crate=trace,b_crate=trace"
+        );
+    }
+
+    #[cfg(feature = "framework-libs-members-env")]
+    #[test]
+    fn test_cargo_workspace_dependency_graph() {
+        let graph = crate::cargo_workspace_dependency_graph!();
+
+        assert_eq!(
+            graph.members().collect::<std::collections::HashSet<_>>(),
+            crate::cargo_workspace_members!()
+        );
+        assert_eq!(
+            graph.dependencies("log_utils"),
+            ["build_info", "hyperswitch_masking"]
+        );
+        assert!(graph.dependencies("build_info").is_empty());
+        assert!(graph.dependents("build_info").contains(&"log_utils"));
+        assert!(graph.depends_on("vergen_integration", "build_info"));
+        assert!(!graph.depends_on("build_info", "log_utils"));
+        assert!(
+            graph
+                .transitive_dependents("build_info")
+                .contains("vergen_integration")
+        );
+    }
 }
@@ -0,0 +1,130 @@
+/// The CI systems that are detected, along with the environment variable whose presence
+/// identifies each of them, and the environment variables providing the run ID, workflow, actor
+/// and run URL, in that order (empty if the CI system does not provide one).
+const CI_SYSTEMS: &[(&str, &str, [&str; 4])] = &[
+    (
+        "github-actions",
+        "GITHUB_ACTIONS",
+        ["GITHUB_RUN_ID", "GITHUB_WORKFLOW", "GITHUB_ACTOR", ""],
+    ),
+    (
+        "gitlab",
+        "GITLAB_CI",
+        [
+            "CI_PIPELINE_ID",
+            "CI_JOB_NAME",
+            "GITLAB_USER_LOGIN",
+            "CI_PIPELINE_URL",
+        ],
+    ),
+    (
+        "jenkins",
+        "JENKINS_URL",
+        ["BUILD_NUMBER", "JOB_NAME", "BUILD_USER_ID", "BUILD_URL"],
+    ),
+];
+
+/// The names of the emitted environment variables, corresponding to the environment variables
+/// of each of the [`CI_SYSTEMS`].
+const CI_VARIABLES: [&str; 4] = [
+    "BUILD_CI_RUN_ID",
+    "BUILD_CI_WORKFLOW",
+    "BUILD_CI_ACTOR",
+    "BUILD_CI_RUN_URL",
+];
+
+/// Set the `BUILD_CI_*` environment variables with information about the CI pipeline run the
+/// crate is being built in, linking the built binaries back to the pipeline run that produced
+/// them.
+///
+/// The following CI systems are detected, with the `BUILD_CI_SYSTEM` environment variable set to
+/// the value in parentheses, and the `BUILD_CI_RUN_ID`, `BUILD_CI_WORKFLOW` and `BUILD_CI_ACTOR`
+/// environment variables set to the values of the listed environment variables of the CI system
+/// respectively:
+///
+/// - GitHub Actions (`github-actions`): `GITHUB_RUN_ID`, `GITHUB_WORKFLOW` and `GITHUB_ACTOR`
+/// - GitLab CI (`gitlab`): `CI_PIPELINE_ID`, `CI_JOB_NAME` and `GITLAB_USER_LOGIN`
+/// - Jenkins (`jenkins`): `BUILD_NUMBER`, `JOB_NAME` and `BUILD_USER_ID` (set by the Build User
+///   Vars plugin)
+///
+/// The `BUILD_CI_RUN_URL` environment variable is set to the URL of the pipeline run, derived
+/// from `GITHUB_SERVER_URL`, `GITHUB_REPOSITORY` and `GITHUB_RUN_ID` for GitHub Actions, and read
+/// from `CI_PIPELINE_URL` and `BUILD_URL` for GitLab CI and Jenkins respectively.
+///
+/// Only the variables whose information is available are set, and none of them are set when
+/// building outside a CI system.
+/// The build script is re-run whenever any of the environment variables read changes.
+///
+/// This function should be typically called within build scripts, so that the environment
+/// variables are available to the corresponding crate at compile time.
+///
+/// # Panics
+///
+/// Panics if writing the build instructions to the standard output fails.
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::set_ci_env();
+/// ```
+#[expect(clippy::expect_used)]
+pub fn set_ci_env() {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    for name in read_variables() {
+        writeln!(&mut stdout, "cargo:rerun-if-env-changed={name}")
+            .expect("Failed to emit `rerun-if-env-changed` instruction");
+    }
+    for (name, value) in ci_variables(|name| std::env::var(name).ok()) {
+        writeln!(&mut stdout, "cargo:rustc-env={name}={value}")
+            .expect("Failed to set `BUILD_CI_*` environment variable");
+    }
+}
+
+/// Returns the `BUILD_CI_*` environment variables to set, reading the environment variables of the
+/// CI systems using `var`.
+pub(crate) fn ci_variables(var: impl Fn(&str) -> Option<String>) -> Vec<(&'static str, String)> {
+    let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+    let Some((system, _, sources)) = CI_SYSTEMS
+        .iter()
+        .find(|(_, detection, _)| var(detection).is_some())
+    else {
+        return Vec::new();
+    };
+
+    let mut variables = vec![("BUILD_CI_SYSTEM", (*system).to_owned())];
+    variables.extend(
+        CI_VARIABLES
+            .iter()
+            .zip(sources)
+            .filter_map(|(name, source)| Some((*name, var(source)?))),
+    );
+
+    // GitHub Actions does not provide the URL of the run directly
+    if *system == "github-actions" {
+        if let (Some(server), Some(repository), Some(run_id)) = (
+            var("GITHUB_SERVER_URL"),
+            var("GITHUB_REPOSITORY"),
+            var("GITHUB_RUN_ID"),
+        ) {
+            variables.push((
+                "BUILD_CI_RUN_URL",
+                format!("{server}/{repository}/actions/runs/{run_id}"),
+            ));
+        }
+    }
+
+    variables
+}
+
+/// Returns the names of all the environment variables read when detecting the CI system.
+fn read_variables() -> impl Iterator<Item = &'static str> {
+    CI_SYSTEMS
+        .iter()
+        .flat_map(|(_, detection, sources)| std::iter::once(*detection).chain(*sources))
+        .chain(["GITHUB_SERVER_URL", "GITHUB_REPOSITORY"])
+        .filter(|name| !name.is_empty())
+}
@@ -0,0 +1,57 @@
+//! Macros for accessing the CI pipeline run information generated by the build script.
+
+/// Returns the CI system the crate was built in (`github-actions`, `gitlab` or `jenkins`), if
+/// available.
+///
+/// Reads the `BUILD_CI_SYSTEM` environment variable, returning `None` if it is unset (such as if
+/// the crate was not built in a CI system).
+#[macro_export]
+macro_rules! ci_system {
+    () => {
+        option_env!("BUILD_CI_SYSTEM")
+    };
+}
+
+/// Returns the ID of the CI pipeline run the crate was built in, if available.
+///
+/// Reads the `BUILD_CI_RUN_ID` environment variable, returning `None` if it is unset (such as if
+/// the crate was not built in a CI system).
+#[macro_export]
+macro_rules! ci_run_id {
+    () => {
+        option_env!("BUILD_CI_RUN_ID")
+    };
+}
+
+/// Returns the name of the CI workflow (or job) the crate was built in, if available.
+///
+/// Reads the `BUILD_CI_WORKFLOW` environment variable, returning `None` if it is unset (such as if
+/// the crate was not built in a CI system).
+#[macro_export]
+macro_rules! ci_workflow {
+    () => {
+        option_env!("BUILD_CI_WORKFLOW")
+    };
+}
+
+/// Returns the user who triggered the CI pipeline run the crate was built in, if available.
+///
+/// Reads the `BUILD_CI_ACTOR` environment variable, returning `None` if it is unset (such as if the
+/// crate was not built in a CI system).
+#[macro_export]
+macro_rules! ci_actor {
+    () => {
+        option_env!("BUILD_CI_ACTOR")
+    };
+}
+
+/// Returns the URL of the CI pipeline run the crate was built in, if available.
+///
+/// Reads the `BUILD_CI_RUN_URL` environment variable, returning `None` if it is unset (such as if
+/// the crate was not built in a CI system).
+#[macro_export]
+macro_rules! ci_run_url {
+    () => {
+        option_env!("BUILD_CI_RUN_URL")
+    };
+}
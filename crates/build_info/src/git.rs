@@ -0,0 +1,103 @@
+/// Returns the paths within the git directory of the repository containing `dir` whose changes
+/// affect the git information, or an empty list if `dir` is not within a git repository.
+///
+/// These include `HEAD`, the file of the checked out branch, the `packed-refs` file (which holds
+/// the branches and tags without a file of their own) and the `refs/tags` directory (since new
+/// tags affect the `git describe` output), along with the operation heads of the `jj` repository
+/// if `dir` is within a `jj` workspace.
+/// Only the paths which exist are returned, since `cargo` always re-runs the build script if any
+/// of the paths to watch does not exist.
+pub(crate) fn git_rerun_paths(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut paths = Vec::new();
+    if let Some((git_dir, common_dir)) = git_dirs(dir) {
+        let head = git_dir.join("HEAD");
+        if let Some(reference) = std::fs::read_to_string(&head)
+            .ok()
+            .and_then(|head| Some(head.trim().strip_prefix("ref:")?.trim().to_owned()))
+        {
+            paths.push(common_dir.join(reference));
+        }
+        paths.push(head);
+        paths.push(common_dir.join("packed-refs"));
+        paths.push(common_dir.join("refs").join("tags"));
+    }
+
+    // The operation log of `jj` changes on every `jj` command which modifies the repository,
+    // including the ones which do not touch the git references in non-colocated repositories
+    if let Some(jj_repo) = jujutsu_repo_dir(dir) {
+        paths.push(jj_repo.join("op_heads").join("heads"));
+    }
+
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Returns the git directory of the repository containing `dir` and its common directory, or
+/// `None` if `dir` is not within a git repository.
+///
+/// The two directories differ for worktrees, which have a git directory of their own holding
+/// `HEAD`, while sharing the references of the repository they belong to.
+pub(crate) fn git_dirs(dir: &std::path::Path) -> Option<(std::path::PathBuf, std::path::PathBuf)> {
+    let dot_git = dir
+        .ancestors()
+        .map(|ancestor| ancestor.join(".git"))
+        .find(|dot_git| dot_git.exists())?;
+
+    // Worktrees and submodules have a `.git` file pointing to their git directory instead
+    let git_dir = if dot_git.is_file() {
+        let contents = std::fs::read_to_string(&dot_git).ok()?;
+        let git_dir = contents.trim().strip_prefix("gitdir:")?.trim();
+        dot_git.parent().unwrap_or(dir).join(git_dir)
+    } else {
+        dot_git
+    };
+
+    let common_dir = std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|common_dir| git_dir.join(common_dir.trim()))
+        .unwrap_or_else(|_| git_dir.clone());
+
+    Some((git_dir, common_dir))
+}
+
+/// Returns the `jj` repository directory of the workspace containing `dir`, or `None` if `dir`
+/// is not within a `jj` workspace.
+///
+/// The closest ancestor containing either a `.jj` or a `.git` directory is considered, so that a
+/// git repository nested within a `jj` workspace is not mistaken for a `jj` repository.
+pub(crate) fn jujutsu_repo_dir(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    let root = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join(".jj").is_dir() || ancestor.join(".git").exists())?;
+    let repo = root.join(".jj").join("repo");
+
+    // Additional workspaces have a `repo` file pointing to the repository of the main workspace
+    if repo.is_file() {
+        let contents = std::fs::read_to_string(&repo).ok()?;
+        return Some(repo.parent().unwrap_or(root).join(contents.trim()));
+    }
+    repo.is_dir().then_some(repo)
+}
+
+/// The layout of the repository containing the crate being built, for the layouts where the git
+/// information obtained using `gix` or the `git` command is inaccurate.
+#[cfg(feature = "vergen-gix-build")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct RepositoryLayout {
+    /// Whether the repository is managed using `jj`, in which case the working copy is a commit of
+    /// its own (with the git `HEAD` detached at its parent in colocated repositories), and the
+    /// branches are `jj` bookmarks.
+    pub(crate) jujutsu: bool,
+
+    /// Whether the repository is a shallow clone, in which case the history before the shallow
+    /// boundary is missing, and the commit count cannot be determined.
+    pub(crate) shallow: bool,
+}
+
+/// Detects the layout of the repository containing `dir`.
+#[cfg(feature = "vergen-gix-build")]
+pub(crate) fn detect_repository_layout(dir: &std::path::Path) -> RepositoryLayout {
+    RepositoryLayout {
+        jujutsu: jujutsu_repo_dir(dir).is_some(),
+        shallow: git_dirs(dir).is_some_and(|(_, common_dir)| common_dir.join("shallow").is_file()),
+    }
+}
@@ -0,0 +1,228 @@
+//! The [`GitVersion`] struct, holding the version information parsed from the `git describe`
+//! output generated by `vergen`.
+
+/// The version of a crate, as parsed from the output of the `git describe` command for the commit
+/// the crate was built from, such as `v1.2.3-4-gc9bd26d-dirty`.
+///
+/// This is typically obtained using the [`git_version!()`][crate::git_version] macro, rather than
+/// parsing the output of the [`git_describe!()`][crate::git_describe] macro.
+///
+/// Versions are ordered by the semantic version of the tag first, and then by the number of
+/// commits since the tag, so that a build of a commit after a tag is considered newer than a build
+/// of the tag itself.
+/// The dirty state and the commit hash are only used as tie-breakers, for consistency with
+/// equality.
+///
+/// # Example
+///
+/// ```
+/// use build_info::GitVersion;
+///
+/// let version = GitVersion::parse("v1.2.3-4-gc9bd26d-dirty").unwrap();
+/// assert_eq!(version.tag_version, semver::Version::new(1, 2, 3));
+/// assert_eq!(version.commits_since_tag, 4);
+/// assert_eq!(version.sha, Some("c9bd26d"));
+/// assert!(version.dirty);
+///
+/// assert!(GitVersion::parse("v1.2.3").unwrap() < version);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GitVersion<'a> {
+    /// The semantic version of the most recent tag reachable from the commit, with the leading
+    /// `v` (if any) stripped.
+    pub tag_version: semver::Version,
+
+    /// The number of commits since the tag, `0` if the crate was built from the tagged commit.
+    pub commits_since_tag: u64,
+
+    /// Whether the working tree had uncommitted changes to tracked files when the crate was built.
+    pub dirty: bool,
+
+    /// The short hash of the git commit the crate was built from, `None` if the crate was built
+    /// from the tagged commit.
+    pub sha: Option<&'a str>,
+}
+
+impl<'a> GitVersion<'a> {
+    /// Parses the output of the `git describe` command, in the `<tag>[-<commits>-g<sha>][-dirty]`
+    /// format.
+    ///
+    /// Returns `None` if the output does not start with a tag which is a semantic version
+    /// (optionally prefixed with `v`), such as when no tags are reachable from the commit, or if
+    /// the git information was unavailable when the build instructions were generated (see
+    /// [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT]).
+    pub fn parse(describe: &'a str) -> Option<Self> {
+        let (describe, dirty) = match describe.strip_suffix("-dirty") {
+            Some(describe) => (describe, true),
+            None => (describe, false),
+        };
+
+        let (tag, commits_since_tag, sha) = match Self::split_commits_and_sha(describe) {
+            Some((tag, commits_since_tag, sha)) => (tag, commits_since_tag, Some(sha)),
+            None => (describe, 0, None),
+        };
+        let tag_version = tag.strip_prefix('v').unwrap_or(tag).parse().ok()?;
+
+        Some(Self {
+            tag_version,
+            commits_since_tag,
+            dirty,
+            sha,
+        })
+    }
+
+    /// Compares this version against another version, which may be a semantic version (optionally
+    /// prefixed with `v`, such as a release tag) or `git describe` output.
+    ///
+    /// Only the semantic version of the tag and the number of commits since the tag are compared,
+    /// so that a build of a commit after a release is considered newer than the release, while
+    /// the dirty state and the commit hash are ignored.
+    ///
+    /// Returns `None` if the other version cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use build_info::GitVersion;
+    ///
+    /// let version = GitVersion::parse("v1.4.0-2-gc9bd26d").unwrap();
+    /// assert_eq!(version.cmp_to("v1.4.0"), Some(Ordering::Greater));
+    /// assert_eq!(version.cmp_to("1.5.0-rc.1"), Some(Ordering::Less));
+    /// assert_eq!(
+    ///     version.cmp_to("v1.4.0-2-g0123abc-dirty"),
+    ///     Some(Ordering::Equal)
+    /// );
+    /// assert_eq!(version.cmp_to("latest"), None);
+    /// ```
+    pub fn cmp_to(&self, other: &str) -> Option<std::cmp::Ordering> {
+        let other = GitVersion::parse(other)?;
+        Some(
+            (&self.tag_version, self.commits_since_tag)
+                .cmp(&(&other.tag_version, other.commits_since_tag)),
+        )
+    }
+
+    /// Returns whether this version is the same as or newer than another version, as compared by
+    /// [`cmp_to()`][Self::cmp_to], such as for refusing to start when deployed older than an
+    /// expected release.
+    ///
+    /// Returns `false` if the other version cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use build_info::GitVersion;
+    ///
+    /// let version = GitVersion::parse("v1.4.0").unwrap();
+    /// assert!(version.is_at_least("1.4.0"));
+    /// assert!(version.is_at_least("v1.3.9"));
+    /// assert!(!version.is_at_least("1.4.1"));
+    /// ```
+    pub fn is_at_least(&self, other: &str) -> bool {
+        self.cmp_to(other).is_some_and(std::cmp::Ordering::is_ge)
+    }
+
+    /// Splits the `<tag>-<commits>-g<sha>` format into its parts, returning `None` if the output
+    /// does not end with the number of commits and the commit hash.
+    fn split_commits_and_sha(describe: &'a str) -> Option<(&'a str, u64, &'a str)> {
+        let (rest, sha) = describe.rsplit_once("-g")?;
+        if sha.is_empty() || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let (tag, commits_since_tag) = rest.rsplit_once('-')?;
+        Some((tag, commits_since_tag.parse().ok()?, sha))
+    }
+}
+
+/// Returns the [`GitVersion`] of the calling crate, parsed from the `VERGEN_GIT_DESCRIBE`
+/// environment variable (or `<prefix>GIT_DESCRIBE` if a prefix is specified), or `None` if the
+/// `git describe` output does not start with a semantic version tag.
+///
+/// This macro requires that the `generate_vergen_cargo_instructions()` function be called in the
+/// build script of the crate where this macro is being called.
+///
+/// # Errors
+///
+/// Causes a compilation error if the `VERGEN_GIT_DESCRIBE` environment variable is unset.
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// if let Some(version) = build_info::git_version!() {
+///     println!("Built {} commits after {}", version.commits_since_tag, version.tag_version);
+/// }
+/// ```
+#[macro_export]
+macro_rules! git_version {
+    () => {
+        $crate::git_version!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        $crate::GitVersion::parse($crate::git_describe!($prefix))
+    };
+}
+
+/// Compares the version of the calling crate (as returned by
+/// [`git_version!()`][crate::git_version]) against another version, as compared by
+/// [`GitVersion::cmp_to()`].
+///
+/// Returns `None` if either the version of the calling crate or the other version cannot be
+/// parsed.
+/// If the build instructions were generated with a custom prefix, the same prefix must be
+/// specified before the version, such as `cmp_running_to!("MYAPP_", "v1.4.0")`.
+///
+/// # Errors
+///
+/// Causes a compilation error if the `VERGEN_GIT_DESCRIBE` environment variable is unset.
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// if build_info::cmp_running_to!("v1.4.0") == Some(std::cmp::Ordering::Less) {
+///     println!("Running a version older than v1.4.0");
+/// }
+/// ```
+#[macro_export]
+macro_rules! cmp_running_to {
+    ($version:expr) => {
+        $crate::cmp_running_to!("VERGEN_", $version)
+    };
+    ($prefix:literal, $version:expr) => {
+        $crate::git_version!($prefix).and_then(|running| running.cmp_to($version))
+    };
+}
+
+/// Returns whether the version of the calling crate (as returned by
+/// [`git_version!()`][crate::git_version]) is the same as or newer than another version, as
+/// compared by [`GitVersion::is_at_least()`], such as for gating features on the deployed release.
+///
+/// Returns `false` if either the version of the calling crate or the other version cannot be
+/// parsed.
+/// If the build instructions were generated with a custom prefix, the same prefix must be
+/// specified before the version, such as `is_at_least!("MYAPP_", "1.4.0")`.
+///
+/// # Errors
+///
+/// Causes a compilation error if the `VERGEN_GIT_DESCRIBE` environment variable is unset.
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// if !build_info::is_at_least!("1.4.0") {
+///     panic!("Expected v1.4.0 or newer to be deployed");
+/// }
+/// ```
+#[macro_export]
+macro_rules! is_at_least {
+    ($version:expr) => {
+        $crate::is_at_least!("VERGEN_", $version)
+    };
+    ($prefix:literal, $version:expr) => {
+        $crate::git_version!($prefix).is_some_and(|running| running.is_at_least($version))
+    };
+}
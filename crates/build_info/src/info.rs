@@ -0,0 +1,158 @@
+//! The [`BuildInfo`] struct, holding the information about the build environment generated by
+//! `vergen`.
+
+/// Information about the build environment of a crate, as generated by
+/// [`generate_vergen_cargo_instructions()`][crate::generate_vergen_cargo_instructions] in the
+/// build script of the crate.
+///
+/// This is typically populated using the [`collect!()`][crate::collect] macro, rather than reading
+/// each of the fields using the individual macros.
+///
+/// With the `serde` feature enabled, this implements [`serde::Serialize`], and can be serialized
+/// to JSON using [`to_json()`][Self::to_json] or [`to_pretty_json()`][Self::to_pretty_json], such
+/// as for exposing or logging the build information as a single structured object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BuildInfo {
+    /// The short hash of the git commit the crate was built from.
+    pub git_sha: &'static str,
+
+    /// The output of the `git describe` command for the commit the crate was built from.
+    pub git_describe: &'static str,
+
+    /// The git branch the crate was built from, `HEAD` if built from a detached checkout (such as
+    /// in some CI environments).
+    pub git_branch: &'static str,
+
+    /// Whether the working tree had uncommitted changes to tracked files when the crate was built.
+    pub git_dirty: bool,
+
+    /// The timestamp of the git commit the crate was built from.
+    pub git_commit_timestamp: &'static str,
+
+    /// The date on which the crate was built.
+    pub build_date: &'static str,
+
+    /// The timestamp at which the crate was built.
+    pub build_timestamp: &'static str,
+
+    /// The semantic version of the Rust compiler used to build the crate.
+    pub rustc_version: &'static str,
+
+    /// The commit hash of the Rust compiler used to build the crate.
+    pub rustc_commit_hash: &'static str,
+
+    /// The commit date of the Rust compiler used to build the crate.
+    pub rustc_commit_date: &'static str,
+
+    /// The target triple the crate was built for.
+    pub target_triple: &'static str,
+
+    /// The cargo profile the crate was built with, `debug` or `release` (custom profiles are
+    /// reported as the profile they inherit from).
+    pub cargo_profile: &'static str,
+
+    /// The optimization level the crate was built with, such as `0`, `3` or `s`.
+    pub cargo_opt_level: &'static str,
+
+    /// Whether the crate was built with debug information.
+    pub cargo_debug: bool,
+
+    /// Whether the crate was built with debug assertions enabled.
+    pub cargo_debug_assertions: bool,
+}
+
+#[cfg(feature = "serde")]
+impl BuildInfo {
+    /// Serializes the build information as compact JSON.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let build_info = build_info::BuildInfo {
+    /// #     git_sha: "c9bd26d",
+    /// #     git_describe: "v0.1.0-3-gc9bd26d",
+    /// #     git_branch: "main",
+    /// #     git_dirty: false,
+    /// #     git_commit_timestamp: "2025-01-01T00:00:00.000000000Z",
+    /// #     build_date: "2025-01-01",
+    /// #     build_timestamp: "2025-01-01T00:00:00.000000000Z",
+    /// #     rustc_version: "1.85.0",
+    /// #     rustc_commit_hash: "4d91de4e48198da2e33413efdcd9cd2cc0c46688",
+    /// #     rustc_commit_date: "2025-02-17",
+    /// #     target_triple: "x86_64-unknown-linux-gnu",
+    /// #     cargo_profile: "release",
+    /// #     cargo_opt_level: "3",
+    /// #     cargo_debug: false,
+    /// #     cargo_debug_assertions: false,
+    /// # };
+    /// // let build_info = build_info::collect!();
+    /// let json = build_info.to_json();
+    /// assert!(json.starts_with(r#"{"git_sha":"c9bd26d","#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        // Serialization cannot fail, since all the fields are strings or booleans
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Serializes the build information as pretty-printed JSON.
+    pub fn to_pretty_json(&self) -> String {
+        // Serialization cannot fail, since all the fields are strings or booleans
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Returns the [`BuildInfo`] of the calling crate, populated from the environment variables
+/// generated by `vergen` at compile time.
+///
+/// This macro requires that the `generate_vergen_cargo_instructions()` function be called in the
+/// build script of the crate where this macro is being called.
+///
+/// If the build instructions were generated with a custom prefix (using
+/// [`VergenInstructions::prefix()`][crate::VergenInstructions::prefix]), the same prefix must be
+/// specified, such as `collect!("MYAPP_")`.
+///
+/// # Errors
+///
+/// Causes a compilation error if any of the `vergen` environment variables is unset.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "vergen-gix-build")]
+/// # {
+/// // In your crate's build script (build.rs):
+/// build_info::generate_vergen_cargo_instructions();
+/// # }
+/// ```
+///
+/// ```text
+/// // In your crate:
+/// let build_info = build_info::collect!();
+/// println!("Git SHA: {}", build_info.git_sha);
+/// ```
+#[macro_export]
+macro_rules! collect {
+    () => {
+        $crate::collect!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        $crate::BuildInfo {
+            git_sha: $crate::git_sha!($prefix),
+            git_describe: $crate::git_describe!($prefix),
+            git_branch: $crate::git_branch!($prefix),
+            git_dirty: $crate::git_dirty!($prefix),
+            git_commit_timestamp: $crate::git_commit_timestamp!($prefix),
+            build_date: $crate::build_date!($prefix),
+            build_timestamp: $crate::build_timestamp!($prefix),
+            rustc_version: $crate::rustc_semver!($prefix),
+            rustc_commit_hash: $crate::rustc_commit_hash!($prefix),
+            rustc_commit_date: $crate::rustc_commit_date!($prefix),
+            target_triple: $crate::cargo_target_triple!($prefix),
+            cargo_profile: $crate::cargo_profile!($prefix),
+            cargo_opt_level: $crate::cargo_opt_level!($prefix),
+            cargo_debug: $crate::cargo_debug!($prefix),
+            cargo_debug_assertions: $crate::cargo_debug_assertions!($prefix),
+        }
+    };
+}
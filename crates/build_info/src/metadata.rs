@@ -0,0 +1,108 @@
+#[cfg(any(feature = "sbom-build", feature = "provenance-build"))]
+use std::collections::{BTreeMap, BTreeSet};
+
+use cargo_metadata::{
+    CargoOpt, DependencyKind, Metadata, MetadataCommand, Node, NodeDep, Package, PackageId,
+};
+
+/// Runs the `cargo metadata` command, resolving the dependencies for the features the current
+/// crate is being compiled with and for the target platform, so that optional and
+/// platform-specific dependencies which are not compiled are excluded.
+///
+/// This must be called within build scripts, since it relies on the environment variables set by
+/// cargo for build scripts.
+#[expect(clippy::expect_used)]
+pub(crate) fn resolve_current_package() -> Metadata {
+    let target = std::env::var("TARGET").expect("`TARGET` is unset");
+
+    // Cargo exposes the enabled features to build scripts as `CARGO_FEATURE_<NAME>` environment
+    // variables, with the names uppercased and hyphens replaced by underscores, so the original
+    // names are recovered from the features declared in the manifest.
+    // The features are qualified with the package name, since they would otherwise be applied to
+    // all the members of the workspace.
+    let metadata = MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .expect("Failed to obtain cargo metadata");
+    let package = current_package(&metadata);
+    let enabled_features = package
+        .features
+        .keys()
+        .filter(|feature| {
+            let variable = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+            std::env::var_os(variable).is_some()
+        })
+        .map(|feature| format!("{}/{feature}", package.name.as_str()))
+        .collect::<Vec<_>>();
+
+    MetadataCommand::new()
+        .features(CargoOpt::NoDefaultFeatures)
+        .features(CargoOpt::SomeFeatures(enabled_features))
+        .other_options(vec!["--filter-platform".to_owned(), target])
+        .exec()
+        .expect("Failed to obtain cargo metadata")
+}
+
+/// Returns the current crate (whose build script is running) from the `cargo metadata` output.
+#[expect(clippy::expect_used)]
+pub(crate) fn current_package(metadata: &Metadata) -> &Package {
+    let name = std::env::var("CARGO_PKG_NAME").expect("`CARGO_PKG_NAME` is unset");
+
+    metadata
+        .workspace_packages()
+        .into_iter()
+        .find(|package| package.name.as_str() == name)
+        .expect("Current crate is not present in `cargo metadata` output")
+}
+
+/// Returns the node of the package in the resolved dependency graph.
+#[expect(clippy::expect_used)]
+pub(crate) fn resolve_node<'a>(metadata: &'a Metadata, package_id: &PackageId) -> &'a Node {
+    metadata
+        .resolve
+        .as_ref()
+        .and_then(|resolve| resolve.nodes.iter().find(|node| &node.id == package_id))
+        .expect("Package is not present in the `cargo metadata` dependency graph")
+}
+
+/// Returns the packages the package transitively depends on (including the package itself)
+/// through normal dependencies, mapped to the packages they directly depend on, keyed by the
+/// package IDs.
+#[cfg(any(feature = "sbom-build", feature = "provenance-build"))]
+pub(crate) fn normal_dependency_graph<'a>(
+    metadata: &'a Metadata,
+    package_id: &'a PackageId,
+) -> BTreeMap<&'a str, BTreeSet<&'a str>> {
+    let mut dependencies = BTreeMap::new();
+    let mut pending = vec![package_id];
+    while let Some(package_id) = pending.pop() {
+        if dependencies.contains_key(package_id.repr.as_str()) {
+            continue;
+        }
+
+        let depends_on = resolve_node(metadata, package_id)
+            .deps
+            .iter()
+            .filter(|dependency| is_normal_dependency(dependency))
+            .map(|dependency| &dependency.pkg)
+            .collect::<Vec<_>>();
+        pending.extend(depends_on.iter().copied());
+        dependencies.insert(
+            package_id.repr.as_str(),
+            depends_on
+                .into_iter()
+                .map(|dependency| dependency.repr.as_str())
+                .collect(),
+        );
+    }
+    dependencies
+}
+
+/// Returns `true` if the dependency is a normal dependency, rather than only a development or
+/// build dependency.
+pub(crate) fn is_normal_dependency(dependency: &NodeDep) -> bool {
+    dependency
+        .dep_kinds
+        .iter()
+        .any(|kind| kind.kind == DependencyKind::Normal)
+}
@@ -0,0 +1,516 @@
+/// The type of the in-toto statement generated.
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// The type of the predicate of the in-toto statement generated.
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// The type of the build, describing how the build definition is to be interpreted.
+const BUILD_TYPE: &str = "https://github.com/juspay/framework-libs-rs/tree/main/crates/build_info";
+
+/// The ID of the builder used if none is specified and the builder cannot be detected.
+const LOCAL_BUILDER_ID: &str = "urn:build_info:local";
+
+/// The type of the payload of the DSSE envelope.
+const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// The environment variables of GitHub Actions read when detecting the builder.
+const GITHUB_VARIABLES: &[&str] = &[
+    "GITHUB_SERVER_URL",
+    "GITHUB_WORKFLOW_REF",
+    "GITHUB_REPOSITORY",
+    "GITHUB_RUN_ID",
+    "GITHUB_RUN_ATTEMPT",
+];
+
+/// Generate a [SLSA] provenance document for the current crate, as an unsigned [in-toto]
+/// statement in JSON format.
+///
+/// This function should be typically called within build scripts, so that the provenance is
+/// written to the `OUT_DIR` of the crate, and its path is available to the crate at compile time
+/// in the `PROVENANCE_PATH` environment variable.
+/// Use [`ProvenanceInstructions`] instead to configure the builder ID or to sign the provenance.
+///
+/// Refer to the documentation of [`ProvenanceInstructions`] for the contents of the provenance.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command or writing the provenance fails.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::generate_slsa_provenance();
+/// ```
+///
+/// [SLSA]: https://slsa.dev/spec/v1.0/provenance
+/// [in-toto]: https://in-toto.io/
+pub fn generate_slsa_provenance() {
+    ProvenanceInstructions::default().emit();
+}
+
+/// A signer for the [SLSA] provenance generated using [`ProvenanceInstructions`], such as one
+/// backed by a key management service or a local private key.
+///
+/// The provenance is signed following the [DSSE] specification, with the signer being passed the
+/// pre-authentication encoding of the provenance to sign.
+///
+/// # Example
+///
+/// ```no_run
+/// struct KmsSigner;
+///
+/// impl build_info::ProvenanceSigner for KmsSigner {
+///     fn key_id(&self) -> Option<String> {
+///         Some("projects/my-project/keys/provenance".to_owned())
+///     }
+///
+///     fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+///         // Sign the message using the key management service
+///         # let _ = message;
+///         unimplemented!()
+///     }
+/// }
+///
+/// // In your crate's build script (build.rs):
+/// build_info::ProvenanceInstructions::default()
+///     .signer(&KmsSigner)
+///     .emit();
+/// ```
+///
+/// [SLSA]: https://slsa.dev/spec/v1.0/provenance
+/// [DSSE]: https://github.com/secure-systems-lab/dsse/blob/master/protocol.md
+pub trait ProvenanceSigner {
+    /// Returns the ID of the key used for signing, for verifiers to select the key to verify the
+    /// signature with, `None` by default.
+    fn key_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Signs the message, returning the raw signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing the message fails, which fails the build.
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+}
+
+/// A builder for configuring the generation of the [SLSA] provenance of the current crate.
+///
+/// The provenance is written to the `provenance.intoto.json` file in the `OUT_DIR` of the crate,
+/// as an [in-toto] statement with a SLSA v1.0 provenance predicate, and includes:
+///
+/// - The current crate, as the subject of the statement, identified by the git commit it is built
+///   from (since the compiled artifact does not exist yet when the build script runs)
+/// - The builder, identified by the GitHub Actions workflow when building in GitHub Actions, or
+///   by the ID specified using [`builder_id()`][Self::builder_id]
+/// - The cargo profile and target triple of the build
+/// - The source repository and git commit, obtained using the `git` command
+/// - The packages the current crate transitively depends on, as recorded in the `Cargo.lock`
+///   file, with their checksums (or git commits for git dependencies)
+///
+/// The dependencies are resolved for the features the crate is being compiled with and for the
+/// target platform, and only the normal dependencies are included, since development and build
+/// dependencies are not part of the compiled artifact.
+/// Any credentials in the URL of the source repository are removed, and the provenance does not
+/// include any timestamps, so that it is reproducible for the same source and dependencies.
+///
+/// The path of the provenance is available to the crate at compile time in the
+/// `PROVENANCE_PATH` environment variable.
+/// If a [`signer()`][Self::signer] is specified, the provenance is also signed and written as a
+/// [DSSE] envelope to the `provenance.dsse.json` file in the `OUT_DIR` of the crate, whose path is
+/// available in the `PROVENANCE_ENVELOPE_PATH` environment variable.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::ProvenanceInstructions::default()
+///     .builder_id("https://ci.example.com/pipelines/release")
+///     .emit();
+/// ```
+///
+/// ```text
+/// // In your crate:
+/// const PROVENANCE: &str = include_str!(env!("PROVENANCE_PATH"));
+/// ```
+///
+/// [SLSA]: https://slsa.dev/spec/v1.0/provenance
+/// [in-toto]: https://in-toto.io/
+/// [DSSE]: https://github.com/secure-systems-lab/dsse/blob/master/protocol.md
+#[derive(Clone, Copy, Default)]
+pub struct ProvenanceInstructions<'a> {
+    builder_id: Option<&'a str>,
+    signer: Option<&'a dyn ProvenanceSigner>,
+}
+
+impl std::fmt::Debug for ProvenanceInstructions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProvenanceInstructions")
+            .field("builder_id", &self.builder_id)
+            .field("signer", &self.signer.map(|_| ".."))
+            .finish()
+    }
+}
+
+impl<'a> ProvenanceInstructions<'a> {
+    /// Specifies the ID of the builder (the build platform the crate is built on, as a URI),
+    /// instead of detecting it.
+    ///
+    /// When building in GitHub Actions, the builder is identified by the workflow by default
+    /// (such as `https://github.com/owner/repo/.github/workflows/release.yml@refs/heads/main`),
+    /// and by `urn:build_info:local` otherwise.
+    pub fn builder_id(mut self, builder_id: &'a str) -> Self {
+        self.builder_id = Some(builder_id);
+        self
+    }
+
+    /// Specifies the signer to sign the provenance with, which is only written unsigned by
+    /// default.
+    pub fn signer(mut self, signer: &'a dyn ProvenanceSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Generates the provenance (and the signed envelope if a signer is specified), along with
+    /// the `cargo` build instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if running the `cargo metadata` command, reading the `Cargo.lock` file, signing the
+    /// provenance or writing the provenance fails.
+    #[expect(clippy::expect_used)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let metadata = crate::metadata::resolve_current_package();
+        let lock_file = metadata.workspace_root.join("Cargo.lock");
+        let lock_file_contents =
+            std::fs::read_to_string(&lock_file).expect("Failed to read `Cargo.lock` file");
+        let current_dir = std::env::current_dir().expect("Failed to obtain current directory");
+
+        let builder = Builder {
+            id: self
+                .builder_id
+                .map(ToOwned::to_owned)
+                .or_else(|| {
+                    Some(format!(
+                        "{}/{}",
+                        var("GITHUB_SERVER_URL")?,
+                        var("GITHUB_WORKFLOW_REF")?
+                    ))
+                })
+                .unwrap_or_else(|| LOCAL_BUILDER_ID.to_owned()),
+            invocation_id: github_invocation_id(var),
+        };
+        let statement = provenance_statement(
+            &metadata,
+            &lock_file_contents,
+            git_source(&current_dir).as_ref(),
+            &builder,
+        );
+        let contents = serde_json::to_string_pretty(&statement)
+            .expect("Failed to serialize provenance statement");
+
+        let out_dir = std::env::var_os("OUT_DIR").expect("`OUT_DIR` is unset");
+        let provenance_path = std::path::Path::new(&out_dir).join("provenance.intoto.json");
+        std::fs::write(&provenance_path, &contents).expect("Failed to write provenance");
+
+        let mut stdout = std::io::stdout();
+        writeln!(&mut stdout, "cargo:rerun-if-changed={lock_file}")
+            .expect("Failed to emit `rerun-if-changed` instruction");
+        for path in crate::git::git_rerun_paths(&current_dir) {
+            writeln!(&mut stdout, "cargo:rerun-if-changed={}", path.display())
+                .expect("Failed to emit `rerun-if-changed` instruction");
+        }
+        for name in GITHUB_VARIABLES {
+            writeln!(&mut stdout, "cargo:rerun-if-env-changed={name}")
+                .expect("Failed to emit `rerun-if-env-changed` instruction");
+        }
+        writeln!(
+            &mut stdout,
+            "cargo:rustc-env=PROVENANCE_PATH={}",
+            provenance_path.display()
+        )
+        .expect("Failed to set `PROVENANCE_PATH` environment variable");
+
+        if let Some(signer) = self.signer {
+            let envelope =
+                dsse_envelope(contents.as_bytes(), signer).expect("Failed to sign provenance");
+            let envelope_path = std::path::Path::new(&out_dir).join("provenance.dsse.json");
+            std::fs::write(
+                &envelope_path,
+                serde_json::to_string_pretty(&envelope)
+                    .expect("Failed to serialize provenance envelope"),
+            )
+            .expect("Failed to write provenance envelope");
+            writeln!(
+                &mut stdout,
+                "cargo:rustc-env=PROVENANCE_ENVELOPE_PATH={}",
+                envelope_path.display()
+            )
+            .expect("Failed to set `PROVENANCE_ENVELOPE_PATH` environment variable");
+        }
+    }
+}
+
+/// The builder of the crate, as recorded in the provenance.
+struct Builder {
+    id: String,
+    invocation_id: Option<String>,
+}
+
+/// The source repository and git commit the crate is built from.
+pub(crate) struct GitSource {
+    pub(crate) repository: Option<String>,
+    pub(crate) commit: String,
+}
+
+/// Returns the URL of the GitHub Actions workflow run attempt the crate is being built in, reading
+/// the environment variables of GitHub Actions using `var`.
+fn github_invocation_id(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    Some(format!(
+        "{}/{}/actions/runs/{}/attempts/{}",
+        var("GITHUB_SERVER_URL")?,
+        var("GITHUB_REPOSITORY")?,
+        var("GITHUB_RUN_ID")?,
+        var("GITHUB_RUN_ATTEMPT")?
+    ))
+}
+
+/// Obtains the git commit checked out in `dir` and the URL of the `origin` remote using the `git`
+/// command, or `None` if `dir` is not within a git repository or the `git` command is
+/// unavailable.
+fn git_source(dir: &std::path::Path) -> Option<GitSource> {
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+            .filter(|output| !output.is_empty())
+    };
+
+    Some(GitSource {
+        commit: git(&["rev-parse", "HEAD"])?,
+        repository: git(&["remote", "get-url", "origin"])
+            .map(|repository| strip_credentials(&repository)),
+    })
+}
+
+/// Removes the user information (such as access tokens used by CI systems for cloning) from the
+/// URL of a repository.
+pub(crate) fn strip_credentials(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_owned();
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    match rest
+        .get(..authority_end)
+        .and_then(|authority| authority.rfind('@'))
+    {
+        Some(user_info_end) => format!(
+            "{scheme}://{}",
+            rest.get(user_info_end + 1..).unwrap_or_default()
+        ),
+        None => url.to_owned(),
+    }
+}
+
+/// Builds the in-toto statement holding the SLSA provenance of the current crate from the
+/// resolved `cargo metadata` output and the contents of the `Cargo.lock` file.
+fn provenance_statement(
+    metadata: &cargo_metadata::Metadata,
+    lock_file: &str,
+    source: Option<&GitSource>,
+    builder: &Builder,
+) -> serde_json::Value {
+    use crate::metadata::{current_package, normal_dependency_graph};
+
+    let root = current_package(metadata);
+    let dependencies = normal_dependency_graph(metadata, &root.id);
+    let locked_packages = parse_lock_file(lock_file);
+
+    let mut resolved_dependencies = Vec::new();
+    if let Some(source) = source {
+        let mut descriptor = serde_json::json!({ "digest": { "gitCommit": source.commit } });
+        if let (Some(object), Some(repository)) = (descriptor.as_object_mut(), &source.repository) {
+            object.insert("uri".to_owned(), format!("git+{repository}").into());
+        }
+        resolved_dependencies.push(descriptor);
+    }
+    resolved_dependencies.extend(
+        metadata
+            .packages
+            .iter()
+            .filter(|package| {
+                package.id != root.id && dependencies.contains_key(package.id.repr.as_str())
+            })
+            .map(|package| {
+                let name = package.name.as_str();
+                let version = package.version.to_string();
+                let mut descriptor = serde_json::json!({
+                    "uri": format!("pkg:cargo/{name}@{version}"),
+                });
+                let digest = locked_packages
+                    .iter()
+                    .find(|locked| locked.name == name && locked.version == version)
+                    .and_then(LockedPackage::digest);
+                if let (Some(object), Some(digest)) = (descriptor.as_object_mut(), digest) {
+                    object.insert("digest".to_owned(), digest);
+                }
+                descriptor
+            }),
+    );
+
+    let subject_digest = source
+        .map(|source| serde_json::json!({ "gitCommit": source.commit }))
+        .unwrap_or_else(|| serde_json::json!({}));
+    let mut run_details = serde_json::json!({ "builder": { "id": builder.id } });
+    if let (Some(object), Some(invocation_id)) =
+        (run_details.as_object_mut(), &builder.invocation_id)
+    {
+        object.insert(
+            "metadata".to_owned(),
+            serde_json::json!({ "invocationId": invocation_id }),
+        );
+    }
+
+    serde_json::json!({
+        "_type": STATEMENT_TYPE,
+        "subject": [{
+            "name": format!("pkg:cargo/{}@{}", root.name.as_str(), root.version),
+            "digest": subject_digest,
+        }],
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "buildDefinition": {
+                "buildType": BUILD_TYPE,
+                "externalParameters": {
+                    "package": root.name.as_str(),
+                    "profile": std::env::var("PROFILE").unwrap_or_default(),
+                    "target": std::env::var("TARGET").unwrap_or_default(),
+                },
+                "resolvedDependencies": resolved_dependencies,
+            },
+            "runDetails": run_details,
+        },
+    })
+}
+
+/// A package recorded in the `Cargo.lock` file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct LockedPackage<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) version: &'a str,
+    pub(crate) source: Option<&'a str>,
+    pub(crate) checksum: Option<&'a str>,
+}
+
+impl LockedPackage<'_> {
+    /// Returns the digest of the package for the provenance, which is the SHA-256 checksum of the
+    /// package for registry packages, and the commit for git packages.
+    fn digest(&self) -> Option<serde_json::Value> {
+        if let Some(checksum) = self.checksum {
+            return Some(serde_json::json!({ "sha256": checksum }));
+        }
+        let (_, commit) = self.source?.strip_prefix("git+")?.rsplit_once('#')?;
+        Some(serde_json::json!({ "gitCommit": commit }))
+    }
+}
+
+/// Parses the packages recorded in the contents of a `Cargo.lock` file.
+///
+/// Only the `name`, `version`, `source` and `checksum` fields of the `[[package]]` tables are
+/// read, which are always written on lines of their own by cargo.
+pub(crate) fn parse_lock_file(contents: &str) -> Vec<LockedPackage<'_>> {
+    let mut packages = Vec::new();
+    let mut current: Option<LockedPackage<'_>> = None;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            packages.extend(current.take());
+            if line == "[[package]]" {
+                current = Some(LockedPackage::default());
+            }
+            continue;
+        }
+
+        let (Some(package), Some((key, value))) = (current.as_mut(), line.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        match key.trim() {
+            "name" => package.name = value,
+            "version" => package.version = value,
+            "source" => package.source = Some(value),
+            "checksum" => package.checksum = Some(value),
+            _ => {}
+        }
+    }
+    packages.extend(current);
+    packages
+}
+
+/// Signs the payload using the signer, returning the DSSE envelope holding the payload and the
+/// signature.
+pub(crate) fn dsse_envelope(
+    payload: &[u8],
+    signer: &dyn ProvenanceSigner,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let signature = signer.sign(&pre_authentication_encoding(PAYLOAD_TYPE, payload))?;
+
+    let mut signature = serde_json::json!({ "sig": base64_encode(&signature) });
+    if let (Some(object), Some(key_id)) = (signature.as_object_mut(), signer.key_id()) {
+        object.insert("keyid".to_owned(), key_id.into());
+    }
+
+    Ok(serde_json::json!({
+        "payloadType": PAYLOAD_TYPE,
+        "payload": base64_encode(payload),
+        "signatures": [signature],
+    }))
+}
+
+/// Returns the DSSE pre-authentication encoding of the payload, which is the message signed.
+pub(crate) fn pre_authentication_encoding(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {payload_type} {} ",
+        payload_type.len(),
+        payload.len()
+    )
+    .into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Encodes the bytes using the standard base64 alphabet, with padding.
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let [first, second, third] = [0, 1, 2].map(|index| chunk.get(index).copied());
+        let group = u32::from(first.unwrap_or_default()) << 16
+            | u32::from(second.unwrap_or_default()) << 8
+            | u32::from(third.unwrap_or_default());
+
+        // Each group of 3 bytes is encoded as 4 characters, with the characters corresponding
+        // to missing bytes being replaced by padding
+        let present = [true, true, second.is_some(), third.is_some()];
+        for (index, present) in present.into_iter().enumerate() {
+            let sextet = (group >> (18 - 6 * index)) & 0x3f;
+            let character = ALPHABET
+                .get(usize::try_from(sextet).unwrap_or_default())
+                .copied()
+                .unwrap_or(b'=');
+            encoded.push(if present { char::from(character) } else { '=' });
+        }
+    }
+    encoded
+}
@@ -0,0 +1,186 @@
+/// Generate a [CycloneDX] software bill of materials (SBOM) for the current crate in JSON format,
+/// using the dependencies resolved by `cargo metadata`.
+///
+/// This function should be typically called within build scripts, so that the SBOM is written to
+/// the `OUT_DIR` of the crate, and its path is available to the crate at compile time in the
+/// `CYCLONEDX_SBOM_PATH` environment variable.
+/// Use [`SbomInstructions`] instead to also embed the hash of the SBOM as an environment variable.
+///
+/// Refer to the documentation of [`SbomInstructions`] for the contents of the SBOM.
+///
+/// # Panics
+///
+/// Panics if running the `cargo metadata` command or writing the SBOM fails.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::generate_cyclonedx_sbom();
+/// ```
+///
+/// [CycloneDX]: https://cyclonedx.org/
+pub fn generate_cyclonedx_sbom() {
+    SbomInstructions::default().emit();
+}
+
+/// A builder for configuring the generation of the [CycloneDX] software bill of materials (SBOM)
+/// of the current crate.
+///
+/// The SBOM is written to the `sbom.cdx.json` file in the `OUT_DIR` of the crate, following
+/// version 1.5 of the CycloneDX specification, and includes:
+///
+/// - The current crate, as the component the SBOM describes
+/// - The packages the current crate transitively depends on, with their versions, package URLs
+///   and license expressions
+/// - The dependency graph between the packages
+///
+/// The dependencies are resolved for the features the crate is being compiled with and for the
+/// target platform, and only the normal dependencies are included, since development and build
+/// dependencies are not part of the compiled artifact.
+/// The SBOM does not include any timestamps, so that it is reproducible for the same resolved
+/// dependencies.
+///
+/// The path of the SBOM is available to the crate at compile time in the `CYCLONEDX_SBOM_PATH`
+/// environment variable, and the SHA-256 hash of the SBOM in the `CYCLONEDX_SBOM_SHA256`
+/// environment variable, if [`embed_hash()`][Self::embed_hash] is enabled.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::SbomInstructions::default()
+///     .embed_hash(true)
+///     .emit();
+/// ```
+///
+/// ```text
+/// // In your crate:
+/// const SBOM: &str = include_str!(env!("CYCLONEDX_SBOM_PATH"));
+/// const SBOM_SHA256: &str = env!("CYCLONEDX_SBOM_SHA256");
+/// ```
+///
+/// [CycloneDX]: https://cyclonedx.org/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SbomInstructions {
+    embed_hash: bool,
+}
+
+impl SbomInstructions {
+    /// Specifies whether to set the `CYCLONEDX_SBOM_SHA256` environment variable to the SHA-256
+    /// hash of the SBOM, such as for logging the hash of the SBOM the binary was built with.
+    pub fn embed_hash(mut self, enabled: bool) -> Self {
+        self.embed_hash = enabled;
+        self
+    }
+
+    /// Generates the SBOM, along with the configured `cargo` build instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if running the `cargo metadata` command or writing the SBOM fails.
+    #[expect(clippy::expect_used)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        use sha2::{Digest, Sha256};
+
+        let metadata = crate::metadata::resolve_current_package();
+        let sbom = cyclonedx_sbom(&metadata);
+        let contents =
+            serde_json::to_string_pretty(&sbom).expect("Failed to serialize CycloneDX SBOM");
+
+        let out_dir = std::env::var_os("OUT_DIR").expect("`OUT_DIR` is unset");
+        let sbom_path = std::path::Path::new(&out_dir).join("sbom.cdx.json");
+        std::fs::write(&sbom_path, &contents).expect("Failed to write CycloneDX SBOM");
+
+        let mut stdout = std::io::stdout();
+        writeln!(
+            &mut stdout,
+            "cargo:rerun-if-changed={}",
+            metadata.workspace_root.join("Cargo.lock")
+        )
+        .expect("Failed to emit `rerun-if-changed` instruction");
+        writeln!(
+            &mut stdout,
+            "cargo:rustc-env=CYCLONEDX_SBOM_PATH={}",
+            sbom_path.display()
+        )
+        .expect("Failed to set `CYCLONEDX_SBOM_PATH` environment variable");
+
+        if self.embed_hash {
+            let hash = Sha256::digest(contents.as_bytes());
+            writeln!(
+                &mut stdout,
+                "cargo:rustc-env=CYCLONEDX_SBOM_SHA256={hash:x}"
+            )
+            .expect("Failed to set `CYCLONEDX_SBOM_SHA256` environment variable");
+        }
+    }
+}
+
+/// Builds the CycloneDX SBOM of the current crate from the resolved `cargo metadata` output.
+fn cyclonedx_sbom(metadata: &cargo_metadata::Metadata) -> serde_json::Value {
+    use crate::metadata::{current_package, normal_dependency_graph};
+
+    let root = current_package(metadata);
+    let dependencies = normal_dependency_graph(metadata, &root.id);
+
+    let components = metadata
+        .packages
+        .iter()
+        .filter(|package| {
+            package.id != root.id && dependencies.contains_key(package.id.repr.as_str())
+        })
+        .map(|package| cyclonedx_component(package, "library"))
+        .collect::<Vec<_>>();
+    let dependencies = dependencies
+        .into_iter()
+        .map(|(package_id, depends_on)| {
+            serde_json::json!({
+                "ref": package_id,
+                "dependsOn": depends_on,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "component": cyclonedx_component(root, "application"),
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// Builds the CycloneDX component describing the package.
+fn cyclonedx_component(
+    package: &cargo_metadata::Package,
+    component_type: &str,
+) -> serde_json::Value {
+    let name = package.name.as_str();
+    let mut component = serde_json::json!({
+        "type": component_type,
+        "bom-ref": package.id.repr,
+        "name": name,
+        "version": package.version.to_string(),
+        "purl": format!("pkg:cargo/{name}@{}", package.version),
+    });
+
+    if let Some(object) = component.as_object_mut() {
+        if let Some(description) = &package.description {
+            object.insert("description".to_owned(), description.as_str().into());
+        }
+        if let Some(license) = &package.license {
+            object.insert(
+                "licenses".to_owned(),
+                serde_json::json!([{ "expression": license }]),
+            );
+        }
+    }
+
+    component
+}
@@ -7,12 +7,18 @@
 /// The generated instructions would provide the following information:
 ///
 /// - Build date and timestamp
-/// - Cargo's target triple
-/// - Rust compiler version, commit date and commit hash
-/// - Git commit timestamp, tag (output of `git describe` command) and short commit hash
+/// - Cargo's target triple, enabled features, profile, optimization level, and whether debug
+///   information and debug assertions are enabled
+/// - Rust compiler version, commit date, commit hash, release channel, host triple and LLVM version
+/// - Git commit timestamp, tag (output of `git describe` command), branch, short commit hash,
+///   commit count and whether the working tree has uncommitted changes
 ///
 /// Refer to the documentation of the [`vergen_gix`] crate for more information on the
 /// environment variables that would be set.
+/// Unavailable information (such as the git information when building outside a git repository)
+/// falls back to the [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT] sentinel value.
+/// Use [`VergenInstructions`] instead to opt out of specific groups of instructions, or to
+/// configure the fallback behavior.
 ///
 /// # Panics
 ///
@@ -24,47 +30,596 @@
 /// // In your crate's build script (build.rs):
 /// build_info::generate_vergen_cargo_instructions();
 /// ```
-#[expect(clippy::expect_used)] // Panics are acceptable in build scripts
 pub fn generate_vergen_cargo_instructions() {
-    use vergen_gix::{BuildBuilder, CargoBuilder, Emitter, GixBuilder, RustcBuilder};
-
-    // Update the `vergen_macros` module if enabling new instructions,
-    // along with the `vergen_integration` example.
-
-    Emitter::default()
-        .add_instructions(
-            &BuildBuilder::default()
-                .build_date(true)
-                .build_timestamp(true)
-                .build()
-                .expect("Failed to generate build related build instructions"),
-        )
-        .expect("Failed to generate `cargo` related build instructions")
-        .add_instructions(
-            &CargoBuilder::default()
-                .target_triple(true)
-                .build()
-                .expect("Failed to generate `cargo` related build instructions"),
-        )
-        .expect("Failed to generate `cargo` related build instructions")
-        .add_instructions(
-            &RustcBuilder::default()
-                .semver(true)
-                .commit_hash(true)
-                .commit_date(true)
-                .build()
-                .expect("Failed to generate `rustc` related build instructions"),
-        )
-        .expect("Failed to generate `rustc` related build instructions")
-        .add_instructions(
-            &GixBuilder::default()
-                .commit_timestamp(true)
-                .describe(true, true, None)
-                .sha(true)
-                .build()
-                .expect("Failed to generate `git` related build instructions"),
-        )
-        .expect("Failed to generate `git` related build instructions")
-        .emit()
-        .expect("Failed to generate `vergen`-based `cargo` build instructions");
+    VergenInstructions::default().emit();
+}
+
+/// The prefix of the names of the environment variables set by `vergen`.
+const DEFAULT_PREFIX: &str = "VERGEN_";
+
+/// The name of the file in `OUT_DIR` the build information constants are written to.
+const CONSTANTS_FILE_NAME: &str = "build_info_generated.rs";
+
+/// The name of the file in `OUT_DIR` the build information JSON is written to by default.
+const JSON_FILE_NAME: &str = "build-info.json";
+
+/// The environment variables (without the prefix) holding boolean values.
+const BOOL_VARIABLES: &[&str] = &["GIT_DIRTY", "CARGO_DEBUG", "CARGO_DEBUG_ASSERTIONS"];
+
+/// A builder for selecting the groups of `vergen` build instructions to generate, all of which
+/// are enabled by default.
+///
+/// This allows crates built in contexts where some of the information is unavailable (such as
+/// crates published to crates.io, which are built outside a git repository) to opt out of the
+/// corresponding instructions.
+/// Note that the macros reading the environment variables of the disabled instructions (as well
+/// as the [`collect!()`][crate::collect] macro) would cause compilation errors.
+///
+/// If any of the information is unavailable when the instructions are generated (such as when
+/// building vendored source tarballs or in docker contexts without the `.git` directory), the
+/// corresponding environment variables are set to the
+/// [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT] sentinel value and a `cargo` warning is
+/// emitted, unless [`fail_on_error()`][Self::fail_on_error] is enabled.
+///
+/// # Reproducible Builds
+///
+/// If the [`SOURCE_DATE_EPOCH`][source-date-epoch] environment variable is set, the build date
+/// and timestamp (along with the git commit timestamp) are derived from it rather than from the
+/// current time, so that builds of the same source embed the same values.
+/// Alternatively, [`idempotent()`][Self::idempotent] suppresses all the values which vary between
+/// builds of the same source, with `SOURCE_DATE_EPOCH` taking precedence if both are used.
+/// The build script is re-run whenever either of the `SOURCE_DATE_EPOCH` or `VERGEN_IDEMPOTENT`
+/// environment variables changes.
+///
+/// [source-date-epoch]: https://reproducible-builds.org/docs/source-date-epoch/
+///
+/// # Example
+///
+/// ```
+/// // In your crate's build script (build.rs):
+/// build_info::VergenInstructions::default()
+///     .build(true)
+///     .git(false)
+///     .emit();
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct VergenInstructions {
+    build: bool,
+    cargo: bool,
+    rustc: bool,
+    git: bool,
+    idempotent: bool,
+    fail_on_error: bool,
+    prefix: &'static str,
+    constants: bool,
+    json: bool,
+    json_path: Option<&'static str>,
+}
+
+impl Default for VergenInstructions {
+    fn default() -> Self {
+        Self {
+            build: true,
+            cargo: true,
+            rustc: true,
+            git: true,
+            idempotent: false,
+            fail_on_error: false,
+            prefix: DEFAULT_PREFIX,
+            constants: false,
+            json: false,
+            json_path: None,
+        }
+    }
+}
+
+impl VergenInstructions {
+    /// Specifies whether to generate the build date and timestamp instructions.
+    pub fn build(mut self, enabled: bool) -> Self {
+        self.build = enabled;
+        self
+    }
+
+    /// Specifies whether to generate the cargo target triple, enabled features, profile,
+    /// optimization level, debug information and debug assertions instructions.
+    pub fn cargo(mut self, enabled: bool) -> Self {
+        self.cargo = enabled;
+        self
+    }
+
+    /// Specifies whether to generate the Rust compiler version, commit date, commit hash, release
+    /// channel, host triple and LLVM version instructions.
+    pub fn rustc(mut self, enabled: bool) -> Self {
+        self.rustc = enabled;
+        self
+    }
+
+    /// Specifies whether to generate the git commit timestamp, tag, branch, short commit hash,
+    /// commit count and dirty state instructions.
+    ///
+    /// The build script is re-run whenever `HEAD`, the checked out branch or the tags change, so
+    /// that the git information is refreshed on new commits without a `cargo clean`.
+    ///
+    /// The following repository layouts are handled specially:
+    ///
+    /// - Worktrees and submodules: the references shared with the main worktree are watched for
+    ///   changes as well.
+    /// - Detached `HEAD`s: the branch is reported as `HEAD`.
+    /// - Shallow clones: the commit count only covers the fetched commits, and is hence reported
+    ///   as unavailable, with a `cargo` warning.
+    /// - [Jujutsu][jj] repositories: the commit hash, branch, commit timestamp and dirty state are
+    ///   obtained using the `jj` command (if available), from the parent of the working copy
+    ///   commit, with the first of its bookmarks being reported as the branch (or `HEAD` if it has
+    ///   none) and the working copy being considered dirty if it is not empty.
+    ///   Since non-colocated repositories have no git working tree, the other git information is
+    ///   unavailable for them, which panics if [`fail_on_error()`][Self::fail_on_error] is enabled.
+    ///
+    /// [jj]: https://jj-vcs.github.io/jj/
+    pub fn git(mut self, enabled: bool) -> Self {
+        self.git = enabled;
+        self
+    }
+
+    /// Specifies whether to set the environment variables which vary between builds of the same
+    /// source (such as the build timestamp) to the [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT]
+    /// sentinel value, for reproducible builds.
+    ///
+    /// This is also enabled if the `VERGEN_IDEMPOTENT` environment variable is set, regardless of
+    /// this setting, so that reproducible build pipelines can enable it without code changes.
+    /// Note that the git commit timestamp is suppressed as well, unless `SOURCE_DATE_EPOCH` is set.
+    pub fn idempotent(mut self, enabled: bool) -> Self {
+        self.idempotent = enabled;
+        self
+    }
+
+    /// Specifies whether to panic if any of the information is unavailable, rather than falling
+    /// back to the [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT] sentinel value, such as for
+    /// ensuring that release builds always carry the git information.
+    pub fn fail_on_error(mut self, enabled: bool) -> Self {
+        self.fail_on_error = enabled;
+        self
+    }
+
+    /// Specifies the prefix of the names of the environment variables to set, instead of
+    /// `VERGEN_` (such as `MYAPP_` for `MYAPP_GIT_SHA` instead of `VERGEN_GIT_SHA`).
+    ///
+    /// This avoids collisions when multiple build scripts in a dependency tree use `vergen`.
+    /// The same prefix must then be passed to the macros reading the environment variables, such
+    /// as `git_sha!("MYAPP_")` or `collect!("MYAPP_")`.
+    pub fn prefix(mut self, prefix: &'static str) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Specifies whether to also write the build information as `pub const` items to the
+    /// `build_info_generated.rs` file in the `OUT_DIR` of the crate, disabled by default.
+    ///
+    /// The file declares a constant for each environment variable set (named without the prefix,
+    /// such as `GIT_SHA`), with the `GIT_DIRTY`, `CARGO_DEBUG` and `CARGO_DEBUG_ASSERTIONS`
+    /// constants being `bool`s and the `CARGO_FEATURES` constant being a `&[&str]`.
+    /// If all of the instructions are enabled, it also declares a `BUILD_INFO` constant holding
+    /// the prebuilt [`BuildInfo`][crate::BuildInfo], referring to this crate as `::build_info`.
+    ///
+    /// Unlike the environment variables, the constants can be used in `const` contexts and
+    /// pattern matches, and are type-checked like any other Rust items.
+    ///
+    /// # Example
+    ///
+    /// ```text
+    /// // In your crate:
+    /// pub mod build_info_generated {
+    ///     include!(concat!(env!("OUT_DIR"), "/build_info_generated.rs"));
+    /// }
+    ///
+    /// const GIT_SHA: &str = build_info_generated::GIT_SHA;
+    /// ```
+    pub fn constants(mut self, enabled: bool) -> Self {
+        self.constants = enabled;
+        self
+    }
+
+    /// Specifies whether to also write the build information as a JSON object to the
+    /// `build-info.json` file in the `OUT_DIR` of the crate (or to the path specified using
+    /// [`json_path()`][Self::json_path]), disabled by default.
+    ///
+    /// This allows deployment tooling to read the build information without executing the
+    /// binary.
+    /// The object has a field for each environment variable set, named in lowercase without the
+    /// prefix (such as `git_sha`), with the `git_dirty`, `cargo_debug` and `cargo_debug_assertions`
+    /// fields being booleans and the `cargo_features` field being an array of strings.
+    pub fn json(mut self, enabled: bool) -> Self {
+        self.json = enabled;
+        self
+    }
+
+    /// Specifies the path to write the build information JSON to, instead of the
+    /// `build-info.json` file in the `OUT_DIR` of the crate, such as a path in the target
+    /// directory that deployment tooling picks up.
+    /// Relative paths are relative to the directory containing the manifest of the crate.
+    ///
+    /// This also enables [`json()`][Self::json].
+    pub fn json_path(mut self, path: &'static str) -> Self {
+        self.json = true;
+        self.json_path = Some(path);
+        self
+    }
+
+    /// Generates the selected `cargo` build instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the selected `vergen` emitters fail to generate the instructions, which
+    /// only happens for unavailable information if [`fail_on_error()`][Self::fail_on_error] is
+    /// enabled, or if writing the constants or the JSON fails when [`constants()`][Self::constants]
+    /// or [`json()`][Self::json] is enabled.
+    #[expect(clippy::expect_used)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        use vergen_gix::{BuildBuilder, CargoBuilder, Emitter, GixBuilder, RustcBuilder};
+
+        // Update the `vergen_macros` module if enabling new instructions,
+        // along with the `vergen_integration` example.
+
+        let mut emitter = Emitter::default();
+        if self.idempotent {
+            emitter.idempotent();
+        }
+        if self.fail_on_error {
+            emitter.fail_on_error();
+        }
+
+        if self.build {
+            emitter
+                .add_instructions(
+                    &BuildBuilder::default()
+                        .build_date(true)
+                        .build_timestamp(true)
+                        .build()
+                        .expect("Failed to generate build related build instructions"),
+                )
+                .expect("Failed to generate build related build instructions");
+        }
+
+        if self.cargo {
+            emitter
+                .add_instructions(
+                    &CargoBuilder::default()
+                        .target_triple(true)
+                        .features(true)
+                        .debug(true)
+                        .opt_level(true)
+                        .build()
+                        .expect("Failed to generate `cargo` related build instructions"),
+                )
+                .expect("Failed to generate `cargo` related build instructions");
+        }
+
+        if self.rustc {
+            emitter
+                .add_instructions(
+                    &RustcBuilder::default()
+                        .semver(true)
+                        .commit_hash(true)
+                        .commit_date(true)
+                        .channel(true)
+                        .host_triple(true)
+                        .llvm_version(true)
+                        .build()
+                        .expect("Failed to generate `rustc` related build instructions"),
+                )
+                .expect("Failed to generate `rustc` related build instructions");
+        }
+
+        if self.git {
+            emitter
+                .add_instructions(
+                    &GixBuilder::default()
+                        .branch(true)
+                        .commit_timestamp(true)
+                        .describe(true, true, None)
+                        .sha(true)
+                        .commit_count(true)
+                        .dirty(false)
+                        .build()
+                        .expect("Failed to generate `git` related build instructions"),
+                )
+                .expect("Failed to generate `git` related build instructions");
+        }
+
+        let mut instructions = Vec::new();
+        emitter
+            .emit_to(&mut instructions)
+            .expect("Failed to generate `vergen`-based `cargo` build instructions");
+
+        // Collect the environment variables (without the prefix) to set, so that they can be
+        // set with the configured prefix and written as constants
+        let mut stdout = std::io::stdout();
+        let mut variables = Vec::new();
+        let mut other_instructions = std::collections::HashSet::new();
+        for instruction in String::from_utf8_lossy(&instructions).lines() {
+            match instruction
+                .strip_prefix("cargo:rustc-env=")
+                .and_then(|variable| variable.strip_prefix(DEFAULT_PREFIX))
+                .and_then(|variable| variable.split_once('='))
+            {
+                Some((name, value)) => variables.push((name.to_owned(), value.to_owned())),
+                None => {
+                    writeln!(&mut stdout, "{instruction}")
+                        .expect("Failed to generate `vergen`-based `cargo` build instructions");
+                    other_instructions.insert(instruction.to_owned());
+                }
+            }
+        }
+
+        if self.git {
+            let current_dir = std::env::current_dir().expect("Failed to obtain current directory");
+            let layout = crate::git::detect_repository_layout(&current_dir);
+
+            // `gix` reports the detached `HEAD` of colocated `jj` repositories (and fails outright
+            // in non-colocated ones), while the working copy commit is always considered clean
+            if layout.jujutsu {
+                let reproducible = self.idempotent
+                    || std::env::var_os("VERGEN_IDEMPOTENT").is_some()
+                    || std::env::var_os("SOURCE_DATE_EPOCH").is_some();
+                match jujutsu_variables(&current_dir) {
+                    Some(jujutsu_variables) => {
+                        for (name, value) in jujutsu_variables {
+                            if reproducible && name == "GIT_COMMIT_TIMESTAMP" {
+                                continue;
+                            }
+                            if let Some((_, existing)) =
+                                variables.iter_mut().find(|(existing, _)| existing == name)
+                            {
+                                *existing = value;
+                            }
+                        }
+                    }
+                    None => writeln!(
+                        &mut stdout,
+                        "cargo:warning=Failed to obtain the git information using `jj`, the git \
+                         information may be inaccurate"
+                    )
+                    .expect("Failed to emit `jj` warning"),
+                }
+            }
+
+            // The commit count only covers the commits fetched in shallow clones
+            if layout.shallow {
+                // `IDEMPOTENT_OUTPUT` is unavailable to the build scripts including this file
+                const UNAVAILABLE_OUTPUT: &str = "VERGEN_IDEMPOTENT_OUTPUT";
+
+                if let Some((_, count)) = variables
+                    .iter_mut()
+                    .find(|(name, _)| name == "GIT_COMMIT_COUNT")
+                {
+                    *count = UNAVAILABLE_OUTPUT.to_owned();
+                    writeln!(
+                        &mut stdout,
+                        "cargo:warning=The git commit count is unavailable in shallow clones"
+                    )
+                    .expect("Failed to emit shallow clone warning");
+                }
+            }
+
+            // `vergen` only watches `HEAD` and the loose file of the checked out branch (and
+            // nothing at all if idempotent), which misses new commits on packed branches, new tags
+            // and branches checked out in worktrees
+            for path in crate::git::git_rerun_paths(&current_dir) {
+                let instruction = format!("cargo:rerun-if-changed={}", path.display());
+                if !other_instructions.contains(&instruction) {
+                    writeln!(&mut stdout, "{instruction}")
+                        .expect("Failed to emit `rerun-if-changed` instruction");
+                }
+            }
+        }
+
+        if self.cargo {
+            variables.extend(cargo_profile_variables());
+        }
+
+        for (name, value) in &variables {
+            writeln!(&mut stdout, "cargo:rustc-env={}{name}={value}", self.prefix)
+                .expect("Failed to generate `vergen`-based `cargo` build instructions");
+        }
+
+        if self.constants {
+            let out_dir = std::env::var_os("OUT_DIR").expect("`OUT_DIR` is unset");
+            std::fs::write(
+                std::path::Path::new(&out_dir).join(CONSTANTS_FILE_NAME),
+                generate_constants(&variables),
+            )
+            .expect("Failed to write build information constants");
+        }
+
+        if self.json {
+            let json_path = match self.json_path {
+                Some(path) => std::path::PathBuf::from(path),
+                None => {
+                    std::path::Path::new(&std::env::var_os("OUT_DIR").expect("`OUT_DIR` is unset"))
+                        .join(JSON_FILE_NAME)
+                }
+            };
+            let json = serde_json::to_string_pretty(&generate_json(&variables))
+                .expect("Failed to serialize build information JSON");
+            std::fs::write(json_path, json).expect("Failed to write build information JSON");
+        }
+    }
+}
+
+/// The `jj` template printing the short commit hash, the local bookmarks and the committer
+/// timestamp of a commit, on separate lines.
+const JUJUTSU_COMMIT_TEMPLATE: &str = concat!(
+    r#"commit_id.short(7) ++ "\n" ++ "#,
+    r#"local_bookmarks.map(|b| b.name()).join(",") ++ "\n" ++ "#,
+    r#"committer.timestamp().utc().format("%Y-%m-%dT%H:%M:%S%.9fZ") ++ "\n""#,
+);
+
+/// Obtains the git environment variables (without the prefix) from `jj`, or `None` if the `jj`
+/// command is unavailable or fails.
+///
+/// The working copy commit is not a git commit yet, so the information is obtained from its
+/// parent, with the working copy being considered dirty if it is not empty.
+fn jujutsu_variables(dir: &std::path::Path) -> Option<Vec<(&'static str, String)>> {
+    let jj_log = |revision: &str, template: &str| {
+        let output = std::process::Command::new("jj")
+            .args(["--ignore-working-copy", "--no-pager", "--color", "never"])
+            .args([
+                "log",
+                "--no-graph",
+                "--limit",
+                "1",
+                "-r",
+                revision,
+                "-T",
+                template,
+            ])
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let empty = jj_log("@", "empty")?;
+    let parent = jj_log("@-", JUJUTSU_COMMIT_TEMPLATE)?;
+    parse_jujutsu_output(&empty, &parent)
+}
+
+/// Parses the output of the `jj log` commands run by [`jujutsu_variables()`] into the git
+/// environment variables (without the prefix).
+///
+/// Commits without bookmarks are reported on the `HEAD` branch, as `vergen` does for detached
+/// `HEAD`s.
+pub(crate) fn parse_jujutsu_output(
+    empty: &str,
+    parent: &str,
+) -> Option<Vec<(&'static str, String)>> {
+    let mut lines = parent.lines();
+    let sha = lines.next().filter(|sha| !sha.is_empty())?;
+    let branch = lines
+        .next()?
+        .split(',')
+        .find(|bookmark| !bookmark.is_empty())
+        .unwrap_or("HEAD");
+    let timestamp = lines.next().filter(|timestamp| !timestamp.is_empty())?;
+    let dirty = match empty.trim() {
+        "true" => false,
+        "false" => true,
+        _ => return None,
+    };
+
+    Some(vec![
+        ("GIT_SHA", sha.to_owned()),
+        ("GIT_BRANCH", branch.to_owned()),
+        ("GIT_DIRTY", dirty.to_string()),
+        ("GIT_COMMIT_TIMESTAMP", timestamp.to_owned()),
+    ])
+}
+
+/// Returns the cargo profile and debug assertions environment variables (without the prefix),
+/// which are not provided by `vergen`.
+fn cargo_profile_variables() -> [(String, String); 2] {
+    // Cargo sets the `PROFILE` environment variable for build scripts to either `debug` or
+    // `release`, and the `CARGO_CFG_DEBUG_ASSERTIONS` environment variable only if debug
+    // assertions are enabled
+    let profile = std::env::var("PROFILE").unwrap_or_else(|_| "unknown".to_owned());
+    let debug_assertions = std::env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_some();
+
+    [
+        ("CARGO_PROFILE".to_owned(), profile),
+        (
+            "CARGO_DEBUG_ASSERTIONS".to_owned(),
+            debug_assertions.to_string(),
+        ),
+    ]
+}
+
+/// Generates the Rust source declaring the environment variables (without the prefix) as
+/// constants, along with a [`BuildInfo`][crate::BuildInfo] constant if all of the information it
+/// holds is available.
+fn generate_constants(variables: &[(String, String)]) -> String {
+    use std::fmt::Write;
+
+    /// The fields of [`BuildInfo`][crate::BuildInfo], along with the constants they are read
+    /// from.
+    const BUILD_INFO_FIELDS: &[(&str, &str)] = &[
+        ("git_sha", "GIT_SHA"),
+        ("git_describe", "GIT_DESCRIBE"),
+        ("git_branch", "GIT_BRANCH"),
+        ("git_dirty", "GIT_DIRTY"),
+        ("git_commit_timestamp", "GIT_COMMIT_TIMESTAMP"),
+        ("build_date", "BUILD_DATE"),
+        ("build_timestamp", "BUILD_TIMESTAMP"),
+        ("rustc_version", "RUSTC_SEMVER"),
+        ("rustc_commit_hash", "RUSTC_COMMIT_HASH"),
+        ("rustc_commit_date", "RUSTC_COMMIT_DATE"),
+        ("target_triple", "CARGO_TARGET_TRIPLE"),
+        ("cargo_profile", "CARGO_PROFILE"),
+        ("cargo_opt_level", "CARGO_OPT_LEVEL"),
+        ("cargo_debug", "CARGO_DEBUG"),
+        ("cargo_debug_assertions", "CARGO_DEBUG_ASSERTIONS"),
+    ];
+
+    // Writing to a `String` cannot fail
+    let mut constants = String::from("// Generated by the `build_info` crate. Do not edit.\n");
+    for (name, value) in variables {
+        let _ = writeln!(constants, "\n/// The `{name}` build information.");
+        let _ = match name.as_str() {
+            name if BOOL_VARIABLES.contains(&name) => {
+                writeln!(constants, "pub const {name}: bool = {};", value == "true")
+            }
+            "CARGO_FEATURES" => writeln!(
+                constants,
+                "pub const {name}: &[&str] = &{:?};",
+                split_features(value)
+            ),
+            _ => writeln!(constants, "pub const {name}: &str = {value:?};"),
+        };
+    }
+
+    let has_variable = |constant: &str| variables.iter().any(|(name, _)| name == constant);
+    if BUILD_INFO_FIELDS
+        .iter()
+        .all(|(_, constant)| has_variable(constant))
+    {
+        let _ = writeln!(
+            constants,
+            "\n/// The build information, as a [`BuildInfo`][::build_info::BuildInfo].\n\
+             pub const BUILD_INFO: ::build_info::BuildInfo = ::build_info::BuildInfo {{"
+        );
+        for (field, constant) in BUILD_INFO_FIELDS {
+            let _ = writeln!(constants, "    {field}: {constant},");
+        }
+        let _ = writeln!(constants, "}};");
+    }
+
+    constants
+}
+
+/// Generates the JSON object holding the environment variables, with the fields named in
+/// lowercase without the prefix.
+fn generate_json(variables: &[(String, String)]) -> serde_json::Value {
+    variables
+        .iter()
+        .map(|(name, value)| {
+            let value = match name.as_str() {
+                name if BOOL_VARIABLES.contains(&name) => serde_json::Value::Bool(value == "true"),
+                "CARGO_FEATURES" => split_features(value).into(),
+                _ => value.as_str().into(),
+            };
+            (name.to_lowercase(), value)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Splits the comma-separated list of cargo features, skipping empty entries.
+fn split_features(features: &str) -> Vec<&str> {
+    features
+        .split(',')
+        .filter(|feature| !feature.is_empty())
+        .collect()
 }
@@ -1,91 +1,822 @@
 //! Macros for accessing vergen-generated environment variables.
 
+/// The sentinel value of the environment variables whose information was unavailable when the
+/// build instructions were generated (such as the git information when building outside a git
+/// repository), or which were made idempotent.
+pub const IDEMPOTENT_OUTPUT: &str = "VERGEN_IDEMPOTENT_OUTPUT";
+
 /// Returns the build date.
 ///
-/// Reads the `VERGEN_BUILD_DATE` environment variable.
+/// Reads the `VERGEN_BUILD_DATE` environment variable (or `<prefix>BUILD_DATE` if a prefix is
+/// specified).
 #[macro_export]
 macro_rules! build_date {
     () => {
-        env!("VERGEN_BUILD_DATE")
+        $crate::build_date!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "BUILD_DATE"))
     };
 }
 
 /// Returns the build timestamp.
 ///
-/// Reads the `VERGEN_BUILD_TIMESTAMP` environment variable.
+/// Reads the `VERGEN_BUILD_TIMESTAMP` environment variable (or `<prefix>BUILD_TIMESTAMP` if a
+/// prefix is specified).
 #[macro_export]
 macro_rules! build_timestamp {
     () => {
-        env!("VERGEN_BUILD_TIMESTAMP")
+        $crate::build_timestamp!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "BUILD_TIMESTAMP"))
     };
 }
 
 /// Returns the cargo target triple.
 ///
-/// Reads the `VERGEN_CARGO_TARGET_TRIPLE` environment variable.
+/// Reads the `VERGEN_CARGO_TARGET_TRIPLE` environment variable (or `<prefix>CARGO_TARGET_TRIPLE` if
+/// a prefix is specified).
 #[macro_export]
 macro_rules! cargo_target_triple {
     () => {
-        env!("VERGEN_CARGO_TARGET_TRIPLE")
+        $crate::cargo_target_triple!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "CARGO_TARGET_TRIPLE"))
+    };
+}
+
+/// Returns the cargo profile, `debug` or `release`.
+///
+/// Reads the `VERGEN_CARGO_PROFILE` environment variable (or `<prefix>CARGO_PROFILE` if a prefix is
+/// specified).
+#[macro_export]
+macro_rules! cargo_profile {
+    () => {
+        $crate::cargo_profile!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "CARGO_PROFILE"))
+    };
+}
+
+/// Returns the cargo optimization level, such as `0`, `3` or `s`.
+///
+/// Reads the `VERGEN_CARGO_OPT_LEVEL` environment variable (or `<prefix>CARGO_OPT_LEVEL` if a
+/// prefix is specified).
+#[macro_export]
+macro_rules! cargo_opt_level {
+    () => {
+        $crate::cargo_opt_level!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "CARGO_OPT_LEVEL"))
+    };
+}
+
+/// Returns `true` if the crate was built with debug information.
+///
+/// Reads the `VERGEN_CARGO_DEBUG` environment variable (or `<prefix>CARGO_DEBUG` if a prefix is
+/// specified).
+#[macro_export]
+macro_rules! cargo_debug {
+    () => {
+        $crate::cargo_debug!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        matches!(env!(concat!($prefix, "CARGO_DEBUG")), "true")
+    };
+}
+
+/// Returns `true` if the crate was built with debug assertions enabled.
+///
+/// Reads the `VERGEN_CARGO_DEBUG_ASSERTIONS` environment variable (or
+/// `<prefix>CARGO_DEBUG_ASSERTIONS` if a prefix is specified).
+#[macro_export]
+macro_rules! cargo_debug_assertions {
+    () => {
+        $crate::cargo_debug_assertions!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        matches!(env!(concat!($prefix, "CARGO_DEBUG_ASSERTIONS")), "true")
+    };
+}
+
+/// Returns the set of cargo features the crate was compiled with, as a `HashSet`.
+///
+/// The feature names are lowercase, with hyphens replaced by underscores (such as `vergen_gix`
+/// for the `vergen-gix` feature), as exposed by cargo to build scripts.
+///
+/// Reads the `VERGEN_CARGO_FEATURES` environment variable (or `<prefix>CARGO_FEATURES` if a prefix
+/// is specified).
+#[macro_export]
+macro_rules! enabled_features {
+    () => {
+        $crate::enabled_features!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "CARGO_FEATURES"))
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .collect::<std::collections::HashSet<&'static str>>()
     };
 }
 
 /// Returns the rustc semantic version.
 ///
-/// Reads the `VERGEN_RUSTC_SEMVER` environment variable.
+/// Reads the `VERGEN_RUSTC_SEMVER` environment variable (or `<prefix>RUSTC_SEMVER` if a prefix is
+/// specified).
 #[macro_export]
 macro_rules! rustc_semver {
     () => {
-        env!("VERGEN_RUSTC_SEMVER")
+        $crate::rustc_semver!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_SEMVER"))
     };
 }
 
 /// Returns the rustc commit hash.
 ///
-/// Reads the `VERGEN_RUSTC_COMMIT_HASH` environment variable.
+/// Reads the `VERGEN_RUSTC_COMMIT_HASH` environment variable (or `<prefix>RUSTC_COMMIT_HASH` if a
+/// prefix is specified).
 #[macro_export]
 macro_rules! rustc_commit_hash {
     () => {
-        env!("VERGEN_RUSTC_COMMIT_HASH")
+        $crate::rustc_commit_hash!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_COMMIT_HASH"))
     };
 }
 
 /// Returns the rustc commit date.
 ///
-/// Reads the `VERGEN_RUSTC_COMMIT_DATE` environment variable.
+/// Reads the `VERGEN_RUSTC_COMMIT_DATE` environment variable (or `<prefix>RUSTC_COMMIT_DATE` if a
+/// prefix is specified).
 #[macro_export]
 macro_rules! rustc_commit_date {
     () => {
-        env!("VERGEN_RUSTC_COMMIT_DATE")
+        $crate::rustc_commit_date!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_COMMIT_DATE"))
+    };
+}
+
+/// Returns the rustc release channel, `stable`, `beta`, `nightly` or `dev`.
+///
+/// Reads the `VERGEN_RUSTC_CHANNEL` environment variable (or `<prefix>RUSTC_CHANNEL` if a prefix is
+/// specified).
+#[macro_export]
+macro_rules! rustc_channel {
+    () => {
+        $crate::rustc_channel!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_CHANNEL"))
+    };
+}
+
+/// Returns the host triple of the rustc, such as `x86_64-unknown-linux-gnu`.
+///
+/// Reads the `VERGEN_RUSTC_HOST_TRIPLE` environment variable (or `<prefix>RUSTC_HOST_TRIPLE` if a
+/// prefix is specified).
+#[macro_export]
+macro_rules! rustc_host_triple {
+    () => {
+        $crate::rustc_host_triple!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_HOST_TRIPLE"))
+    };
+}
+
+/// Returns the LLVM version of the rustc, such as `19.1`.
+///
+/// This is the [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT] sentinel value if the rustc does not
+/// report its LLVM version.
+///
+/// Reads the `VERGEN_RUSTC_LLVM_VERSION` environment variable (or `<prefix>RUSTC_LLVM_VERSION` if a
+/// prefix is specified).
+#[macro_export]
+macro_rules! rustc_llvm_version {
+    () => {
+        $crate::rustc_llvm_version!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "RUSTC_LLVM_VERSION"))
     };
 }
 
 /// Returns the git commit timestamp.
 ///
-/// Reads the `VERGEN_GIT_COMMIT_TIMESTAMP` environment variable.
+/// Reads the `VERGEN_GIT_COMMIT_TIMESTAMP` environment variable (or `<prefix>GIT_COMMIT_TIMESTAMP`
+/// if a prefix is specified).
 #[macro_export]
 macro_rules! git_commit_timestamp {
     () => {
-        env!("VERGEN_GIT_COMMIT_TIMESTAMP")
+        $crate::git_commit_timestamp!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "GIT_COMMIT_TIMESTAMP"))
     };
 }
 
 /// Returns the git describe output.
 ///
-/// Reads the `VERGEN_GIT_DESCRIBE` environment variable.
+/// Reads the `VERGEN_GIT_DESCRIBE` environment variable (or `<prefix>GIT_DESCRIBE` if a prefix is
+/// specified).
 #[macro_export]
 macro_rules! git_describe {
     () => {
-        env!("VERGEN_GIT_DESCRIBE")
+        $crate::git_describe!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "GIT_DESCRIBE"))
+    };
+}
+
+/// Returns the git branch name, `HEAD` if built from a detached checkout.
+///
+/// Reads the `VERGEN_GIT_BRANCH` environment variable (or `<prefix>GIT_BRANCH` if a prefix is
+/// specified).
+#[macro_export]
+macro_rules! git_branch {
+    () => {
+        $crate::git_branch!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "GIT_BRANCH"))
     };
 }
 
 /// Returns the git commit SHA.
 ///
-/// Reads the `VERGEN_GIT_SHA` environment variable.
+/// Reads the `VERGEN_GIT_SHA` environment variable (or `<prefix>GIT_SHA` if a prefix is specified).
 #[macro_export]
 macro_rules! git_sha {
     () => {
-        env!("VERGEN_GIT_SHA")
+        $crate::git_sha!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "GIT_SHA"))
+    };
+}
+
+/// Returns `true` if the working tree had uncommitted changes to tracked files when the crate was
+/// built, and `false` if the crate was built from a clean tree (or if the dirty state was
+/// unavailable).
+///
+/// Reads the `VERGEN_GIT_DIRTY` environment variable (or `<prefix>GIT_DIRTY` if a prefix is
+/// specified).
+#[macro_export]
+macro_rules! git_dirty {
+    () => {
+        $crate::git_dirty!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        matches!(env!(concat!($prefix, "GIT_DIRTY")), "true")
+    };
+}
+
+/// Returns the number of commits reachable from the git commit the crate was built from (the
+/// output of the `git rev-list --count HEAD` command).
+///
+/// This is the [`IDEMPOTENT_OUTPUT`][crate::IDEMPOTENT_OUTPUT] sentinel value if the commit count
+/// was unavailable, including in shallow clones (where only the fetched commits could be counted),
+/// in which case the build script emits a warning.
+///
+/// Reads the `VERGEN_GIT_COMMIT_COUNT` environment variable (or `<prefix>GIT_COMMIT_COUNT` if a
+/// prefix is specified).
+/// Use the [`build_number!()`][crate::build_number] macro to obtain it as an integer.
+#[macro_export]
+macro_rules! git_commit_count {
+    () => {
+        $crate::git_commit_count!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        env!(concat!($prefix, "GIT_COMMIT_COUNT"))
+    };
+}
+
+/// Returns the number of commits reachable from the git commit the crate was built from as a
+/// `u64`, which increases monotonically along the history of a branch, such as for use as a build
+/// number in version schemes or for cache-busting.
+///
+/// Returns `None` if the commit count was unavailable when the build instructions were generated
+/// (such as when building outside a git repository, or in a shallow clone).
+/// Shallow clones are the default in some CI environments, and hence the clone should be
+/// unshallowed if the build number is used.
+///
+/// Reads the `VERGEN_GIT_COMMIT_COUNT` environment variable (or `<prefix>GIT_COMMIT_COUNT` if a
+/// prefix is specified).
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// if let Some(build_number) = build_info::build_number!() {
+///     println!("Build number: {build_number}");
+/// }
+/// ```
+#[macro_export]
+macro_rules! build_number {
+    () => {
+        $crate::build_number!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        $crate::git_commit_count!($prefix).parse::<u64>().ok()
+    };
+}
+
+/// Returns the build date, if available.
+///
+/// Reads the `VERGEN_BUILD_DATE` environment variable (or `<prefix>BUILD_DATE` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_build_date {
+    () => {
+        $crate::try_build_date!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "BUILD_DATE"))
+    };
+}
+
+/// Returns the build timestamp, if available.
+///
+/// Reads the `VERGEN_BUILD_TIMESTAMP` environment variable (or `<prefix>BUILD_TIMESTAMP` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_build_timestamp {
+    () => {
+        $crate::try_build_timestamp!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "BUILD_TIMESTAMP"))
+    };
+}
+
+/// Returns the cargo target triple, if available.
+///
+/// Reads the `VERGEN_CARGO_TARGET_TRIPLE` environment variable (or `<prefix>CARGO_TARGET_TRIPLE` if
+/// a prefix is specified), returning `None` if it is unset (such as if the build script was not
+/// run), rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_cargo_target_triple {
+    () => {
+        $crate::try_cargo_target_triple!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "CARGO_TARGET_TRIPLE"))
+    };
+}
+
+/// Returns the rustc semantic version, if available.
+///
+/// Reads the `VERGEN_RUSTC_SEMVER` environment variable (or `<prefix>RUSTC_SEMVER` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_semver {
+    () => {
+        $crate::try_rustc_semver!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_SEMVER"))
+    };
+}
+
+/// Returns the rustc commit hash, if available.
+///
+/// Reads the `VERGEN_RUSTC_COMMIT_HASH` environment variable (or `<prefix>RUSTC_COMMIT_HASH` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_commit_hash {
+    () => {
+        $crate::try_rustc_commit_hash!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_COMMIT_HASH"))
+    };
+}
+
+/// Returns the rustc commit date, if available.
+///
+/// Reads the `VERGEN_RUSTC_COMMIT_DATE` environment variable (or `<prefix>RUSTC_COMMIT_DATE` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_commit_date {
+    () => {
+        $crate::try_rustc_commit_date!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_COMMIT_DATE"))
+    };
+}
+
+/// Returns the rustc release channel, if available.
+///
+/// Reads the `VERGEN_RUSTC_CHANNEL` environment variable (or `<prefix>RUSTC_CHANNEL` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_channel {
+    () => {
+        $crate::try_rustc_channel!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_CHANNEL"))
+    };
+}
+
+/// Returns the host triple of the rustc, if available.
+///
+/// Reads the `VERGEN_RUSTC_HOST_TRIPLE` environment variable (or `<prefix>RUSTC_HOST_TRIPLE` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_host_triple {
+    () => {
+        $crate::try_rustc_host_triple!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_HOST_TRIPLE"))
+    };
+}
+
+/// Returns the LLVM version of the rustc, if available.
+///
+/// Reads the `VERGEN_RUSTC_LLVM_VERSION` environment variable (or `<prefix>RUSTC_LLVM_VERSION` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_rustc_llvm_version {
+    () => {
+        $crate::try_rustc_llvm_version!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "RUSTC_LLVM_VERSION"))
+    };
+}
+
+/// Returns the git commit timestamp, if available.
+///
+/// Reads the `VERGEN_GIT_COMMIT_TIMESTAMP` environment variable (or `<prefix>GIT_COMMIT_TIMESTAMP`
+/// if a prefix is specified), returning `None` if it is unset (such as if the build script was not
+/// run), rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_git_commit_timestamp {
+    () => {
+        $crate::try_git_commit_timestamp!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "GIT_COMMIT_TIMESTAMP"))
+    };
+}
+
+/// Returns the git describe output, if available.
+///
+/// Reads the `VERGEN_GIT_DESCRIBE` environment variable (or `<prefix>GIT_DESCRIBE` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_git_describe {
+    () => {
+        $crate::try_git_describe!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "GIT_DESCRIBE"))
+    };
+}
+
+/// Returns the git branch name, if available.
+///
+/// Reads the `VERGEN_GIT_BRANCH` environment variable (or `<prefix>GIT_BRANCH` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_git_branch {
+    () => {
+        $crate::try_git_branch!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "GIT_BRANCH"))
+    };
+}
+
+/// Returns the git commit SHA, if available.
+///
+/// Reads the `VERGEN_GIT_SHA` environment variable (or `<prefix>GIT_SHA` if a prefix is specified),
+/// returning `None` if it is unset (such as if the build script was not run), rather than causing a
+/// compilation error.
+#[macro_export]
+macro_rules! try_git_sha {
+    () => {
+        $crate::try_git_sha!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "GIT_SHA"))
+    };
+}
+
+/// Returns whether the working tree had uncommitted changes to tracked files when the crate was
+/// built, if available.
+///
+/// Reads the `VERGEN_GIT_DIRTY` environment variable (or `<prefix>GIT_DIRTY` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_git_dirty {
+    () => {
+        $crate::try_git_dirty!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        match option_env!(concat!($prefix, "GIT_DIRTY")) {
+            Some(value) => Some(matches!(value, "true")),
+            None => None,
+        }
+    };
+}
+
+/// Returns the number of commits reachable from the git commit the crate was built from, if
+/// available.
+///
+/// Reads the `VERGEN_GIT_COMMIT_COUNT` environment variable (or `<prefix>GIT_COMMIT_COUNT` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not
+/// run), rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_git_commit_count {
+    () => {
+        $crate::try_git_commit_count!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "GIT_COMMIT_COUNT"))
+    };
+}
+
+/// Returns the cargo profile, if available.
+///
+/// Reads the `VERGEN_CARGO_PROFILE` environment variable (or `<prefix>CARGO_PROFILE` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_cargo_profile {
+    () => {
+        $crate::try_cargo_profile!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "CARGO_PROFILE"))
+    };
+}
+
+/// Returns the cargo optimization level, if available.
+///
+/// Reads the `VERGEN_CARGO_OPT_LEVEL` environment variable (or `<prefix>CARGO_OPT_LEVEL` if a
+/// prefix is specified), returning `None` if it is unset (such as if the build script was not run),
+/// rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_cargo_opt_level {
+    () => {
+        $crate::try_cargo_opt_level!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "CARGO_OPT_LEVEL"))
+    };
+}
+
+/// Returns whether the crate was built with debug information, if available.
+///
+/// Reads the `VERGEN_CARGO_DEBUG` environment variable (or `<prefix>CARGO_DEBUG` if a prefix is
+/// specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_cargo_debug {
+    () => {
+        $crate::try_cargo_debug!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        match option_env!(concat!($prefix, "CARGO_DEBUG")) {
+            Some(value) => Some(matches!(value, "true")),
+            None => None,
+        }
+    };
+}
+
+/// Returns whether the crate was built with debug assertions enabled, if available.
+///
+/// Reads the `VERGEN_CARGO_DEBUG_ASSERTIONS` environment variable (or
+/// `<prefix>CARGO_DEBUG_ASSERTIONS` if a prefix is specified), returning `None` if it is unset
+/// (such as if the build script was not run), rather than causing a compilation error.
+#[macro_export]
+macro_rules! try_cargo_debug_assertions {
+    () => {
+        $crate::try_cargo_debug_assertions!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        match option_env!(concat!($prefix, "CARGO_DEBUG_ASSERTIONS")) {
+            Some(value) => Some(matches!(value, "true")),
+            None => None,
+        }
+    };
+}
+
+/// Returns the set of cargo features the crate was compiled with as a `HashSet`, if available.
+///
+/// Reads the `VERGEN_CARGO_FEATURES` environment variable (or `<prefix>CARGO_FEATURES` if a prefix
+/// is specified), returning `None` if it is unset (such as if the build script was not run), rather
+/// than causing a compilation error.
+#[macro_export]
+macro_rules! try_enabled_features {
+    () => {
+        $crate::try_enabled_features!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        option_env!(concat!($prefix, "CARGO_FEATURES")).map(|features| {
+            features
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect::<std::collections::HashSet<&'static str>>()
+        })
+    };
+}
+
+/// Returns all the build environment variables set by the build script helpers of this crate as a
+/// `HashMap` of names to values, such as for exposing the build environment in a diagnostics
+/// endpoint without listing each of the variables.
+///
+/// This includes the `VERGEN_*` environment variables (or `<prefix>*` if a prefix is specified),
+/// along with the `BUILD_ID`, `BUILD_HOST_*` and `BUILD_CI_*` environment variables.
+/// Only the environment variables which are set are included, so that this can be used regardless
+/// of the build script helpers called in the build script of the crate.
+///
+/// # Example
+///
+/// ```
+/// let build_env = build_info::all_build_env!();
+/// for (name, value) in &build_env {
+///     println!("{name}={value}");
+/// }
+/// ```
+#[macro_export]
+macro_rules! all_build_env {
+    () => {
+        $crate::all_build_env!("VERGEN_")
+    };
+    ($prefix:literal) => {{
+        let variables: &[(&'static str, Option<&'static str>)] = &[
+            (
+                concat!($prefix, "BUILD_DATE"),
+                option_env!(concat!($prefix, "BUILD_DATE")),
+            ),
+            (
+                concat!($prefix, "BUILD_TIMESTAMP"),
+                option_env!(concat!($prefix, "BUILD_TIMESTAMP")),
+            ),
+            (
+                concat!($prefix, "CARGO_TARGET_TRIPLE"),
+                option_env!(concat!($prefix, "CARGO_TARGET_TRIPLE")),
+            ),
+            (
+                concat!($prefix, "CARGO_FEATURES"),
+                option_env!(concat!($prefix, "CARGO_FEATURES")),
+            ),
+            (
+                concat!($prefix, "CARGO_PROFILE"),
+                option_env!(concat!($prefix, "CARGO_PROFILE")),
+            ),
+            (
+                concat!($prefix, "CARGO_OPT_LEVEL"),
+                option_env!(concat!($prefix, "CARGO_OPT_LEVEL")),
+            ),
+            (
+                concat!($prefix, "CARGO_DEBUG"),
+                option_env!(concat!($prefix, "CARGO_DEBUG")),
+            ),
+            (
+                concat!($prefix, "CARGO_DEBUG_ASSERTIONS"),
+                option_env!(concat!($prefix, "CARGO_DEBUG_ASSERTIONS")),
+            ),
+            (
+                concat!($prefix, "RUSTC_SEMVER"),
+                option_env!(concat!($prefix, "RUSTC_SEMVER")),
+            ),
+            (
+                concat!($prefix, "RUSTC_COMMIT_HASH"),
+                option_env!(concat!($prefix, "RUSTC_COMMIT_HASH")),
+            ),
+            (
+                concat!($prefix, "RUSTC_COMMIT_DATE"),
+                option_env!(concat!($prefix, "RUSTC_COMMIT_DATE")),
+            ),
+            (
+                concat!($prefix, "RUSTC_CHANNEL"),
+                option_env!(concat!($prefix, "RUSTC_CHANNEL")),
+            ),
+            (
+                concat!($prefix, "RUSTC_HOST_TRIPLE"),
+                option_env!(concat!($prefix, "RUSTC_HOST_TRIPLE")),
+            ),
+            (
+                concat!($prefix, "RUSTC_LLVM_VERSION"),
+                option_env!(concat!($prefix, "RUSTC_LLVM_VERSION")),
+            ),
+            (
+                concat!($prefix, "GIT_SHA"),
+                option_env!(concat!($prefix, "GIT_SHA")),
+            ),
+            (
+                concat!($prefix, "GIT_DESCRIBE"),
+                option_env!(concat!($prefix, "GIT_DESCRIBE")),
+            ),
+            (
+                concat!($prefix, "GIT_BRANCH"),
+                option_env!(concat!($prefix, "GIT_BRANCH")),
+            ),
+            (
+                concat!($prefix, "GIT_DIRTY"),
+                option_env!(concat!($prefix, "GIT_DIRTY")),
+            ),
+            (
+                concat!($prefix, "GIT_COMMIT_COUNT"),
+                option_env!(concat!($prefix, "GIT_COMMIT_COUNT")),
+            ),
+            (
+                concat!($prefix, "GIT_COMMIT_TIMESTAMP"),
+                option_env!(concat!($prefix, "GIT_COMMIT_TIMESTAMP")),
+            ),
+            ("BUILD_ID", option_env!("BUILD_ID")),
+            ("BUILD_HOST_OS", option_env!("BUILD_HOST_OS")),
+            ("BUILD_HOST_ARCH", option_env!("BUILD_HOST_ARCH")),
+            ("BUILD_HOST_TRIPLE", option_env!("BUILD_HOST_TRIPLE")),
+            ("BUILD_HOST_USER", option_env!("BUILD_HOST_USER")),
+            ("BUILD_HOST_NAME", option_env!("BUILD_HOST_NAME")),
+            ("BUILD_CI_SYSTEM", option_env!("BUILD_CI_SYSTEM")),
+            ("BUILD_CI_RUN_ID", option_env!("BUILD_CI_RUN_ID")),
+            ("BUILD_CI_WORKFLOW", option_env!("BUILD_CI_WORKFLOW")),
+            ("BUILD_CI_ACTOR", option_env!("BUILD_CI_ACTOR")),
+            ("BUILD_CI_RUN_URL", option_env!("BUILD_CI_RUN_URL")),
+        ];
+        variables
+            .iter()
+            .filter_map(|(name, value)| Some((*name, (*value)?)))
+            .collect::<std::collections::HashMap<&'static str, &'static str>>()
+    }};
+}
+
+/// Returns a multi-line version string of the calling crate, suitable for use as the long version
+/// of command line applications (such as with
+/// [`clap::Command::long_version()`][clap-long-version]), to standardize the `--version` output
+/// across applications.
+///
+/// The version string is a `&'static str` in the following format, with the version of the
+/// calling crate on the first line:
+///
+/// ```text
+/// 0.1.0
+/// git describe: v0.1.0-3-gc9bd26d
+/// git sha: c9bd26d
+/// build date: 2025-01-01
+/// rustc: 1.85.0
+/// ```
+///
+/// This macro requires that the `generate_vergen_cargo_instructions()` function be called in the
+/// build script of the crate where this macro is being called.
+///
+/// # Errors
+///
+/// Causes a compilation error if any of the `vergen` environment variables used is unset.
+///
+/// # Example
+///
+/// ```text
+/// // In your crate:
+/// let command = clap::Command::new("my-app").long_version(build_info::clap_long_version!());
+/// ```
+///
+/// [clap-long-version]: https://docs.rs/clap/latest/clap/struct.Command.html#method.long_version
+#[macro_export]
+macro_rules! clap_long_version {
+    () => {
+        $crate::clap_long_version!("VERGEN_")
+    };
+    ($prefix:literal) => {
+        concat!(
+            env!("CARGO_PKG_VERSION"),
+            "\ngit describe: ",
+            env!(concat!($prefix, "GIT_DESCRIBE")),
+            "\ngit sha: ",
+            env!(concat!($prefix, "GIT_SHA")),
+            "\nbuild date: ",
+            env!(concat!($prefix, "BUILD_DATE")),
+            "\nrustc: ",
+            env!(concat!($prefix, "RUSTC_SEMVER")),
+        )
     };
 }
@@ -0,0 +1,65 @@
+//! Helpers for exposing the [`BuildInfo`] over HTTP, such as at the [`VERSION_PATH`] endpoint.
+
+use std::future::{Ready, ready};
+
+use http::{Response, header};
+
+use crate::BuildInfo;
+
+/// The conventional path of the endpoint exposing the build information of a service.
+pub const VERSION_PATH: &str = "/version";
+
+impl BuildInfo {
+    /// Returns an HTTP response with the build information serialized as JSON.
+    ///
+    /// The response can be returned by handlers of any framework built on the [`http`] crate, and
+    /// can be used as is by `axum` handlers.
+    pub fn to_http_response(&self) -> Response<String> {
+        let mut response = Response::new(self.to_json());
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static("application/json"),
+        );
+        response
+    }
+
+    /// Returns a handler responding with the build information serialized as JSON, typically
+    /// routed at the [`VERSION_PATH`] endpoint.
+    ///
+    /// The handler can be passed to `axum` routing functions as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # let build_info = build_info::BuildInfo {
+    /// #     git_sha: "c9bd26d",
+    /// #     git_describe: "v0.1.0-3-gc9bd26d",
+    /// #     git_branch: "main",
+    /// #     git_dirty: false,
+    /// #     git_commit_timestamp: "2025-01-01T00:00:00.000000000Z",
+    /// #     build_date: "2025-01-01",
+    /// #     build_timestamp: "2025-01-01T00:00:00.000000000Z",
+    /// #     rustc_version: "1.85.0",
+    /// #     rustc_commit_hash: "4d91de4e48198da2e33413efdcd9cd2cc0c46688",
+    /// #     rustc_commit_date: "2025-02-17",
+    /// #     target_triple: "x86_64-unknown-linux-gnu",
+    /// #     cargo_profile: "release",
+    /// #     cargo_opt_level: "3",
+    /// #     cargo_debug: false,
+    /// #     cargo_debug_assertions: false,
+    /// # };
+    /// use build_info::VERSION_PATH;
+    ///
+    /// // let build_info = build_info::collect!();
+    /// let router: axum::Router = axum::Router::new().route(
+    ///     VERSION_PATH,
+    ///     axum::routing::get(build_info.version_handler()),
+    /// );
+    /// # let _ = router;
+    /// ```
+    pub fn version_handler(
+        self,
+    ) -> impl Fn() -> Ready<Response<String>> + Clone + Send + Sync + 'static {
+        move || ready(self.to_http_response())
+    }
+}
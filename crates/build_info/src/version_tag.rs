@@ -0,0 +1,192 @@
+/// Fail the build of the current crate in the release profile if the git commit being built is
+/// tagged with a version other than the version of the crate (`CARGO_PKG_VERSION`), such as when
+/// the version in the manifest was not bumped before tagging a release.
+///
+/// The tags pointing at the commit being built are obtained using the `git` command, with the
+/// tags such as `1.2.3`, `v1.2.3`, `<crate>-v1.2.3`, `<crate>@1.2.3` and `<crate>/v1.2.3`
+/// (where `<crate>` is the name of the crate) considered to be version tags of the crate.
+/// The check passes if the commit has no version tags (such as for commits between releases), or
+/// if the crate is not built from within a git repository (such as crates published to
+/// crates.io).
+/// Use [`VersionTagCheck`] instead to also check builds in other profiles, to only emit a
+/// warning, or to use a different tag format.
+///
+/// This function should be typically called within build scripts.
+///
+/// # Panics
+///
+/// Panics if the commit is tagged with a version other than the version of the crate.
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::assert_version_matches_tag();
+/// ```
+pub fn assert_version_matches_tag() {
+    VersionTagCheck::default().emit();
+}
+
+/// A builder for configuring the check between the version of the current crate and the version
+/// tags of the git commit being built, performed by [`assert_version_matches_tag()`].
+///
+/// # Example
+///
+/// ```no_run
+/// // In your crate's build script (build.rs):
+/// build_info::VersionTagCheck::default()
+///     .tag_prefix("release-")
+///     .all_profiles(true)
+///     .warn_only(true)
+///     .emit();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VersionTagCheck<'a> {
+    all_profiles: bool,
+    warn_only: bool,
+    tag_prefix: Option<&'a str>,
+}
+
+impl<'a> VersionTagCheck<'a> {
+    /// Specifies whether to check builds in all profiles, rather than only in the release
+    /// profile (and the custom profiles inheriting from it), disabled by default.
+    pub fn all_profiles(mut self, enabled: bool) -> Self {
+        self.all_profiles = enabled;
+        self
+    }
+
+    /// Specifies whether to only emit a `cargo` warning on a mismatch, rather than failing the
+    /// build, disabled by default.
+    pub fn warn_only(mut self, enabled: bool) -> Self {
+        self.warn_only = enabled;
+        self
+    }
+
+    /// Specifies the prefix of the version tags of the crate (such as `release-` for tags like
+    /// `release-1.2.3`), instead of the default tag formats described in
+    /// [`assert_version_matches_tag()`].
+    pub fn tag_prefix(mut self, prefix: &'a str) -> Self {
+        self.tag_prefix = Some(prefix);
+        self
+    }
+
+    /// Performs the check, along with generating the `cargo` build instructions to re-run the
+    /// build script whenever the commit being built or the tags change.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the commit is tagged with a version other than the version of the crate, unless
+    /// [`warn_only()`][Self::warn_only] is enabled.
+    #[expect(clippy::expect_used, clippy::panic)] // Panics are acceptable in build scripts
+    pub fn emit(self) {
+        use std::io::Write;
+
+        let mut stdout = std::io::stdout();
+        let current_dir = std::env::current_dir().expect("Failed to obtain current directory");
+        for path in crate::git::git_rerun_paths(&current_dir) {
+            writeln!(&mut stdout, "cargo:rerun-if-changed={}", path.display())
+                .expect("Failed to emit `rerun-if-changed` instruction");
+        }
+
+        // Cargo sets the `PROFILE` environment variable for build scripts to `release` for the
+        // release profile and the custom profiles inheriting from it
+        if !self.all_profiles && std::env::var("PROFILE").ok().as_deref() != Some("release") {
+            return;
+        }
+
+        let name = std::env::var("CARGO_PKG_NAME").expect("`CARGO_PKG_NAME` is unset");
+        let version = std::env::var("CARGO_PKG_VERSION").expect("`CARGO_PKG_VERSION` is unset");
+        let tags = head_tags(&current_dir);
+        let prefixes = match self.tag_prefix {
+            Some(prefix) => vec![prefix.to_owned()],
+            None => default_tag_prefixes(&name),
+        };
+
+        if let Err(tag_versions) = check_version_tags(&version, &tags, &prefixes) {
+            let message = format!(
+                "The version of `{name}` ({version}) does not match the version tags of the \
+                 commit being built ({})",
+                tag_versions.join(", ")
+            );
+            if self.warn_only {
+                writeln!(&mut stdout, "cargo:warning={message}")
+                    .expect("Failed to emit version tag mismatch warning");
+            } else {
+                panic!("{message}");
+            }
+        }
+    }
+}
+
+/// Returns the default prefixes of the version tags of the crate with the given name.
+pub(crate) fn default_tag_prefixes(name: &str) -> Vec<String> {
+    ["", "-", "@", "/"]
+        .into_iter()
+        .flat_map(|separator| {
+            let prefix = if separator.is_empty() {
+                String::new()
+            } else {
+                format!("{name}{separator}")
+            };
+            [format!("{prefix}v"), prefix]
+        })
+        .collect()
+}
+
+/// Checks the version of the crate against the version tags among the tags, returning the
+/// versions of the version tags if none of them match the version of the crate.
+///
+/// Tags are considered to be version tags if they consist of one of the prefixes followed by a
+/// semantic version.
+pub(crate) fn check_version_tags<'a>(
+    version: &str,
+    tags: &'a [String],
+    prefixes: &[String],
+) -> Result<(), Vec<&'a str>> {
+    let tag_versions = tags
+        .iter()
+        .filter_map(|tag| {
+            prefixes
+                .iter()
+                .filter_map(|prefix| tag.strip_prefix(prefix.as_str()))
+                .find(|tag_version| is_semantic_version(tag_version))
+        })
+        .collect::<Vec<_>>();
+
+    if tag_versions.is_empty() || tag_versions.contains(&version) {
+        Ok(())
+    } else {
+        Err(tag_versions)
+    }
+}
+
+/// Returns whether the version is a semantic version, consisting of the numeric major, minor and
+/// patch versions, optionally followed by the pre-release version and build metadata.
+fn is_semantic_version(version: &str) -> bool {
+    let core = version
+        .split_once(['-', '+'])
+        .map_or(version, |(core, _)| core);
+    let parts = core.split('.').collect::<Vec<_>>();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Returns the tags pointing at the commit checked out in `dir` using the `git` command, or an
+/// empty list if `dir` is not within a git repository or the `git` command is unavailable.
+fn head_tags(dir: &std::path::Path) -> Vec<String> {
+    std::process::Command::new("git")
+        .args(["tag", "--points-at", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(ToOwned::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,156 @@
+//! The [`WorkspaceMember`] struct, holding the metadata of a crate in the cargo workspace, and the
+//! [`WorkspaceDependencyGraph`] struct, holding the dependencies between the crates in the cargo
+//! workspace.
+
+/// The metadata of a crate in the cargo workspace, as captured by
+/// [`set_cargo_workspace_member_metadata_env()`][crate::set_cargo_workspace_member_metadata_env]
+/// in the build script of the crate.
+///
+/// This is typically obtained using the
+/// [`cargo_workspace_member_metadata!()`][crate::cargo_workspace_member_metadata] macro.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WorkspaceMember {
+    /// The name of the crate.
+    pub name: &'static str,
+
+    /// The version of the crate.
+    pub version: &'static str,
+
+    /// The name of the library target of the crate (which is the name of the crate with dashes
+    /// replaced by underscores, unless configured otherwise), or `None` if the crate has no
+    /// library target, such as binary-only crates.
+    pub lib_name: Option<&'static str>,
+
+    /// The path of the manifest of the crate, relative to the workspace root.
+    pub manifest_path: &'static str,
+
+    /// Whether the crate can be published, `false` if its `publish` field is set to `false` (or to
+    /// an empty list of registries).
+    pub publish: bool,
+
+    /// The Rust edition of the crate, such as `2021`.
+    pub edition: &'static str,
+
+    /// The features declared by the crate, excluding the `default` feature, sorted by name.
+    pub features: Vec<&'static str>,
+
+    /// The features enabled by the `default` feature of the crate, in the order they are declared.
+    pub default_features: Vec<&'static str>,
+}
+
+impl WorkspaceMember {
+    /// Parses the workspace members from the encoding used by the
+    /// `CARGO_WORKSPACE_MEMBER_METADATA` environment variable, skipping any malformed entries.
+    pub fn parse_all(encoded: &'static str) -> Vec<Self> {
+        encoded
+            .split(';')
+            .filter_map(|member| {
+                let mut fields = member.splitn(8, '|');
+                let name = fields.next()?;
+                let version = fields.next()?;
+                let lib_name = Some(fields.next()?).filter(|lib_name| !lib_name.is_empty());
+                let publish = fields.next()?.parse().ok()?;
+                let edition = fields.next()?;
+                let features = split_list(fields.next()?);
+                let default_features = split_list(fields.next()?);
+                let manifest_path = fields.next()?;
+
+                Some(Self {
+                    name,
+                    version,
+                    lib_name,
+                    manifest_path,
+                    publish,
+                    edition,
+                    features,
+                    default_features,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The dependencies between the crates in the cargo workspace, as captured by
+/// [`set_cargo_workspace_dependency_graph_env()`][crate::set_cargo_workspace_dependency_graph_env]
+/// in the build script of the crate.
+///
+/// This is typically obtained using the
+/// [`cargo_workspace_dependency_graph!()`][crate::cargo_workspace_dependency_graph] macro, and can
+/// be used for selecting the crates affected by changes or checking for layering violations.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkspaceDependencyGraph {
+    dependencies: std::collections::BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+impl WorkspaceDependencyGraph {
+    /// Parses the dependency graph from the encoding used by the
+    /// `CARGO_WORKSPACE_DEPENDENCY_GRAPH` environment variable, skipping any malformed entries.
+    pub fn parse(encoded: &'static str) -> Self {
+        let dependencies = encoded
+            .split(';')
+            .filter_map(|member| {
+                let (name, dependencies) = member.split_once(':')?;
+                Some((name, split_list(dependencies)))
+            })
+            .collect();
+
+        Self { dependencies }
+    }
+
+    /// Returns the names of the crates in the workspace, sorted by name.
+    pub fn members(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.dependencies.keys().copied()
+    }
+
+    /// Returns the crates in the workspace which the crate directly depends on, sorted by name.
+    ///
+    /// Returns an empty list if the crate is not in the workspace.
+    pub fn dependencies(&self, member: &str) -> &[&'static str] {
+        self.dependencies
+            .get(member)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the crates in the workspace which directly depend on the crate, sorted by name.
+    pub fn dependents(&self, member: &str) -> Vec<&'static str> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.contains(&member))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    /// Returns whether the crate directly or transitively depends on the other crate.
+    pub fn depends_on(&self, member: &str, dependency: &str) -> bool {
+        let mut visited = std::collections::BTreeSet::new();
+        let mut pending = self.dependencies(member).to_vec();
+        while let Some(current) = pending.pop() {
+            if current == dependency {
+                return true;
+            }
+            if visited.insert(current) {
+                pending.extend_from_slice(self.dependencies(current));
+            }
+        }
+        false
+    }
+
+    /// Returns the crates in the workspace which directly or transitively depend on the crate,
+    /// such as for selecting the crates to test when the crate changes.
+    pub fn transitive_dependents(&self, member: &str) -> std::collections::BTreeSet<&'static str> {
+        let mut dependents = std::collections::BTreeSet::new();
+        let mut pending = self.dependents(member);
+        while let Some(current) = pending.pop() {
+            if dependents.insert(current) {
+                pending.extend(self.dependents(current));
+            }
+        }
+        dependents
+    }
+}
+
+/// Splits the comma-separated list, skipping empty entries.
+fn split_list(list: &'static str) -> Vec<&'static str> {
+    list.split(',').filter(|entry| !entry.is_empty()).collect()
+}
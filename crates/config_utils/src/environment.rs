@@ -0,0 +1,145 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ConfigError;
+
+/// The environment variable specifying the deployment environment, read by
+/// [`Environment::from_env()`].
+pub const ENVIRONMENT_VAR: &str = "RUN_ENV";
+
+/// The environment the application is deployed in.
+///
+/// The environment is typically detected using [`Environment::from_env()`], and used to layer
+/// the configuration file specific to the environment (such as `config/production.toml`) using
+/// [`ConfigLoader::environment_file`][crate::ConfigLoader::environment_file], and to enable or
+/// disable behavior specific to the environment, such as exposing error details in responses
+/// outside of production.
+///
+/// The environment is parsed from and displayed as its lowercase name, such as `production`, with
+/// the common abbreviations `dev`, `integ` and `prod` accepted as well, and can also be
+/// deserialized as part of the configuration.
+///
+/// # Example
+///
+/// ```no_run
+/// use config_utils::{ConfigLoader, Environment};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct AppConfig {
+///     expose_error_details: bool,
+/// }
+///
+/// // `RUN_ENV=production` layers `config/production.toml` over `config/default.toml`
+/// let environment = Environment::from_env()?;
+/// let config: AppConfig = ConfigLoader::new()
+///     .file("config/default.toml")
+///     .environment_file("config", environment)
+///     .load()?;
+/// let expose_error_details = config.expose_error_details && !environment.is_production();
+/// println!("Running in {environment}, exposing error details: {expose_error_details}");
+/// # Ok::<(), config_utils::ConfigError>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    /// Represents local development, the default if no environment is specified.
+    #[default]
+    #[serde(alias = "dev")]
+    Development,
+
+    /// Represents the sandbox environment, used by merchants for testing their integrations.
+    Sandbox,
+
+    /// Represents the integration environment, used for testing the application along with the
+    /// services it depends on.
+    #[serde(alias = "integ")]
+    Integration,
+
+    /// Represents the production environment.
+    #[serde(alias = "prod")]
+    Production,
+}
+
+impl Environment {
+    /// Detects the environment from the [`ENVIRONMENT_VAR`] environment variable, defaulting to
+    /// [`Environment::Development`] if the environment variable is unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the environment variable does not hold a valid environment name.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_env_var(ENVIRONMENT_VAR)
+    }
+
+    /// Detects the environment from the specified environment variable, defaulting to
+    /// [`Environment::Development`] if the environment variable is unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the environment variable does not hold a valid environment name.
+    pub fn from_env_var(name: &str) -> Result<Self, ConfigError> {
+        match std::env::var(name) {
+            Ok(value) => value.parse(),
+            Err(std::env::VarError::NotPresent) => Ok(Self::default()),
+            Err(std::env::VarError::NotUnicode(value)) => Err(ConfigError::InvalidEnvironment {
+                value: value.to_string_lossy().into_owned(),
+            }),
+        }
+    }
+
+    /// Returns the lowercase name of the environment, such as `production`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Sandbox => "sandbox",
+            Self::Integration => "integration",
+            Self::Production => "production",
+        }
+    }
+
+    /// Returns `true` if the environment is [`Environment::Development`].
+    pub const fn is_development(self) -> bool {
+        matches!(self, Self::Development)
+    }
+
+    /// Returns `true` if the environment is [`Environment::Sandbox`].
+    pub const fn is_sandbox(self) -> bool {
+        matches!(self, Self::Sandbox)
+    }
+
+    /// Returns `true` if the environment is [`Environment::Integration`].
+    pub const fn is_integration(self) -> bool {
+        matches!(self, Self::Integration)
+    }
+
+    /// Returns `true` if the environment is [`Environment::Production`].
+    pub const fn is_production(self) -> bool {
+        matches!(self, Self::Production)
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Environment {
+    type Err = ConfigError;
+
+    /// Parses the environment from its name, case-insensitively, also accepting the common
+    /// abbreviations `dev`, `integ` and `prod`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Self::Development),
+            "sandbox" => Ok(Self::Sandbox),
+            "integration" | "integ" => Ok(Self::Integration),
+            "production" | "prod" => Ok(Self::Production),
+            _ => Err(ConfigError::InvalidEnvironment {
+                value: value.to_owned(),
+            }),
+        }
+    }
+}
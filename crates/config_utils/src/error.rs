@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use crate::ValidationReport;
+
+/// Errors that can occur when loading configuration.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// Represents an error serializing the defaults specified using
+    /// [`ConfigLoader::defaults`][crate::ConfigLoader::defaults].
+    #[error("Failed to serialize configuration defaults: {0}")]
+    Defaults(config::ConfigError),
+
+    /// Represents a configuration file whose format is not supported, as determined by its
+    /// extension.
+    #[error(
+        "Unsupported format of configuration file `{}`, expected a `.toml`, `.yaml` or `.yml` file",
+        path.display()
+    )]
+    UnsupportedFormat {
+        /// The path of the configuration file.
+        path: PathBuf,
+    },
+
+    /// Represents an error loading the configuration sources, such as a required configuration
+    /// file which does not exist or could not be parsed.
+    #[error("Failed to load configuration: {0}")]
+    Load(config::ConfigError),
+
+    /// Represents an invalid deployment environment name, such as in the environment variable
+    /// read by [`Environment::from_env`][crate::Environment::from_env].
+    #[error(
+        "Invalid environment `{value}`, expected one of `development`, `sandbox`, `integration` \
+         or `production`"
+    )]
+    InvalidEnvironment {
+        /// The invalid environment name.
+        value: String,
+    },
+
+    /// Represents an error deserializing the layered configuration, such as due to a missing key
+    /// or a value of the wrong type.
+    #[error("Invalid configuration at `{path}`: {source}")]
+    Deserialize {
+        /// The path of the key the error occurred at, such as `server.port`, or `.` if the error
+        /// occurred at the root of the configuration.
+        path: String,
+
+        /// The underlying deserialization error.
+        source: config::ConfigError,
+    },
+
+    /// Represents invalid or missing values found while validating the configuration, using
+    /// [`ConfigLoader::load_validated`][crate::ConfigLoader::load_validated].
+    #[error("{0}")]
+    Validation(ValidationReport),
+}
@@ -0,0 +1,621 @@
+//! `config_utils` provides utilities for loading layered application configuration.
+//!
+//! This crate provides:
+//!
+//! - A [`ConfigLoader`] for loading configuration from defaults, TOML and YAML files, and
+//!   environment variable overrides (such as `APP__SERVER__PORT` for the `port` key of the
+//!   `server` section) into any type implementing [`serde::Deserialize`], with a well-defined
+//!   order of precedence.
+//! - A [`ConfigError`] describing why the configuration could not be loaded, including the path
+//!   of the offending key (such as `server.port`) for missing or invalid values.
+//! - An [`Environment`] enum modelling the deployment environment, detected from the `RUN_ENV`
+//!   environment variable, for layering the configuration file specific to the environment and
+//!   enabling behavior specific to the environment.
+//! - A [`Validate`] trait for validating the loaded configuration, with range and regular
+//!   expression checks, reporting all the invalid and missing values in a [`ValidationReport`] at
+//!   startup rather than failing at the first.
+//! - With the `masking` feature, [`deserialize_secret()`] for deserializing
+//!   [`hyperswitch_masking::Secret`] fields whose values are held in environment variables or
+//!   files, or are encrypted and decrypted by a pluggable [`SecretResolver`] (such as for KMS or
+//!   Vault) when the configuration is loaded, and [`masked_dump()`] for logging the effective
+//!   configuration with the secrets masked, such as for audit logs.
+//!
+//! # Features
+//!
+//! - `masking` - Enables [`deserialize_secret()`], [`ConfigLoader::secret_resolver`] and
+//!   [`masked_dump()`] for
+//!   loading secrets using the [`hyperswitch_masking`](https://docs.rs/hyperswitch_masking)
+//!   crate (disabled by default)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use config_utils::ConfigLoader;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize, Serialize)]
+//! struct AppConfig {
+//!     server: ServerConfig,
+//!     database: DatabaseConfig,
+//! }
+//!
+//! #[derive(Debug, Deserialize, Serialize)]
+//! struct ServerConfig {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! #[derive(Debug, Deserialize, Serialize)]
+//! struct DatabaseConfig {
+//!     url: String,
+//!     max_connections: u32,
+//! }
+//!
+//! impl Default for AppConfig {
+//!     fn default() -> Self {
+//!         Self {
+//!             server: ServerConfig {
+//!                 host: "0.0.0.0".to_owned(),
+//!                 port: 8080,
+//!             },
+//!             database: DatabaseConfig {
+//!                 url: "postgres://localhost/app".to_owned(),
+//!                 max_connections: 10,
+//!             },
+//!         }
+//!     }
+//! }
+//!
+//! match ConfigLoader::new()
+//!     .defaults(&AppConfig::default())
+//!     .file("config/default.toml")
+//!     .optional_file("config/local.toml")
+//!     .env_prefix("APP")
+//!     .load::<AppConfig>()
+//! {
+//!     Ok(config) => println!("Loaded configuration: {config:?}"),
+//!     // Such as "Invalid configuration at `server.port`: invalid type: ..."
+//!     Err(error) => eprintln!("{error}"),
+//! }
+//! ```
+
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![doc(test(attr(deny(warnings))))]
+
+mod environment;
+mod error;
+mod loader;
+mod recording;
+#[cfg(feature = "masking")]
+mod secret;
+mod validation;
+
+#[cfg(feature = "masking")]
+pub use self::secret::{SecretResolver, SecretResolverError, deserialize_secret, masked_dump};
+pub use self::{
+    environment::{ENVIRONMENT_VAR, Environment},
+    error::ConfigError,
+    loader::ConfigLoader,
+    validation::{Validate, ValidationIssue, ValidationReport},
+};
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct AppConfig {
+        server: ServerConfig,
+        database: DatabaseConfig,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct ServerConfig {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct DatabaseConfig {
+        url: String,
+        max_connections: u32,
+    }
+
+    impl Default for AppConfig {
+        fn default() -> Self {
+            Self {
+                server: ServerConfig {
+                    host: "0.0.0.0".to_owned(),
+                    port: 8080,
+                },
+                database: DatabaseConfig {
+                    url: "postgres://localhost/app".to_owned(),
+                    max_connections: 10,
+                },
+            }
+        }
+    }
+
+    impl Validate for ServerConfig {
+        fn validate(&self, report: &mut ValidationReport) {
+            report.check_regex("host", &self.host, r"^[a-z0-9.-]+$");
+            report.check_range("port", &self.port, 1024..);
+        }
+    }
+
+    impl Validate for DatabaseConfig {
+        fn validate(&self, report: &mut ValidationReport) {
+            report.check_regex("url", &self.url, r"^postgres://");
+            report.check_range("max_connections", &self.max_connections, 1..=100);
+        }
+    }
+
+    /// Creates a directory for the configuration files of the test, removing any files left
+    /// behind by previous runs.
+    fn config_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("config-utils-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_layered_sources() {
+        let dir = config_dir("layered");
+        std::fs::write(
+            dir.join("default.toml"),
+            "[server]\nhost = \"127.0.0.1\"\nport = 3000\n\n[database]\nmax_connections = 20\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("local.yaml"), "server:\n  port: 4000\n").unwrap();
+
+        // Defaults alone
+        let config: AppConfig = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .load()
+            .unwrap();
+        assert_eq!(config, AppConfig::default());
+
+        // Later files take precedence over earlier files, and tables are merged
+        let config: AppConfig = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .file(dir.join("default.toml"))
+            .optional_file(dir.join("local.yaml"))
+            .optional_file(dir.join("missing.toml"))
+            .load()
+            .unwrap();
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 4000);
+        assert_eq!(config.database.url, "postgres://localhost/app");
+        assert_eq!(config.database.max_connections, 20);
+
+        // Environment variables take precedence over files
+        let config: AppConfig = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .file(dir.join("default.toml"))
+            .optional_file(dir.join("local.yaml"))
+            .env_prefix("APP")
+            .env_vars([
+                ("APP__SERVER__PORT", "5000"),
+                ("APP__DATABASE__MAX_CONNECTIONS", "30"),
+                ("APP__DATABASE__URL", "postgres://db/app"),
+                ("OTHER__SERVER__HOST", "ignored"),
+            ])
+            .load()
+            .unwrap();
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 5000);
+        assert_eq!(config.database.url, "postgres://db/app");
+        assert_eq!(config.database.max_connections, 30);
+
+        // Values of environment variables are not coerced to numbers or booleans for string fields
+        let config: AppConfig = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .env_prefix("APP")
+            .env_vars([("APP__SERVER__HOST", "007"), ("APP__DATABASE__URL", "1.10")])
+            .load()
+            .unwrap();
+        assert_eq!(config.server.host, "007");
+        assert_eq!(config.database.url, "1.10");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let dir = config_dir("errors");
+        std::fs::write(dir.join("invalid.toml"), "[server\n").unwrap();
+        std::fs::write(dir.join("config.json"), "{}").unwrap();
+        std::fs::write(dir.join("partial.toml"), "[server]\nhost = \"localhost\"\n").unwrap();
+
+        let error = ConfigLoader::new()
+            .file(dir.join("missing.toml"))
+            .load::<AppConfig>()
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::Load(_)), "{error}");
+
+        let error = ConfigLoader::new()
+            .file(dir.join("invalid.toml"))
+            .load::<AppConfig>()
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::Load(_)), "{error}");
+        assert!(error.to_string().contains("invalid.toml"), "{error}");
+
+        let error = ConfigLoader::new()
+            .file(dir.join("config.json"))
+            .load::<AppConfig>()
+            .unwrap_err();
+        assert!(
+            matches!(
+                error,
+                ConfigError::UnsupportedFormat { ref path } if path.ends_with("config.json")
+            ),
+            "{error}"
+        );
+
+        // Errors are reported along with the paths of the offending keys
+        let error = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .env_prefix("APP")
+            .env_vars([("APP__SERVER__PORT", "http")])
+            .load::<AppConfig>()
+            .unwrap_err();
+        assert!(
+            matches!(error, ConfigError::Deserialize { ref path, .. } if path == "server.port"),
+            "{error}"
+        );
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid configuration at `server.port`: "),
+            "{error}"
+        );
+
+        let error = ConfigLoader::new()
+            .file(dir.join("partial.toml"))
+            .load::<AppConfig>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration at `server`: missing configuration field \"server.port\""
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_environment() {
+        assert_eq!(Environment::default(), Environment::Development);
+        assert_eq!(
+            "production".parse::<Environment>().unwrap(),
+            Environment::Production
+        );
+        assert_eq!(
+            " Prod ".parse::<Environment>().unwrap(),
+            Environment::Production
+        );
+        assert_eq!(
+            "integ".parse::<Environment>().unwrap(),
+            Environment::Integration
+        );
+        assert_eq!(Environment::Sandbox.to_string(), "sandbox");
+        assert!(Environment::Production.is_production());
+        assert!(!Environment::Sandbox.is_production());
+        assert!(Environment::Development.is_development());
+
+        let error = "staging".parse::<Environment>().unwrap_err();
+        assert!(
+            matches!(error, ConfigError::InvalidEnvironment { ref value } if value == "staging"),
+            "{error}"
+        );
+
+        let env_var = format!("CONFIG_UTILS_TEST_ENV_{}", std::process::id());
+        assert_eq!(
+            Environment::from_env_var(&env_var).unwrap(),
+            Environment::Development
+        );
+        std::env::set_var(&env_var, "sandbox");
+        assert_eq!(
+            Environment::from_env_var(&env_var).unwrap(),
+            Environment::Sandbox
+        );
+        std::env::remove_var(&env_var);
+
+        // The configuration file specific to the environment takes precedence
+        let dir = config_dir("environment");
+        std::fs::write(
+            dir.join("default.toml"),
+            "environment = \"dev\"\n[server]\nhost = \"127.0.0.1\"\nport = 3000\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("production.toml"),
+            "environment = \"production\"\n[server]\nport = 80\n",
+        )
+        .unwrap();
+
+        #[derive(Debug, Deserialize)]
+        struct EnvironmentConfig {
+            environment: Environment,
+            server: ServerConfig,
+        }
+
+        let config: EnvironmentConfig = ConfigLoader::new()
+            .file(dir.join("default.toml"))
+            .environment_file(&dir, Environment::Production)
+            .load()
+            .unwrap();
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 80);
+
+        let config: EnvironmentConfig = ConfigLoader::new()
+            .file(dir.join("default.toml"))
+            .load()
+            .unwrap();
+        assert_eq!(config.environment, Environment::Development);
+
+        let error = ConfigLoader::new()
+            .file(dir.join("default.toml"))
+            .environment_file(&dir, Environment::Sandbox)
+            .load::<EnvironmentConfig>()
+            .unwrap_err();
+        assert!(error.to_string().contains("sandbox.toml"), "{error}");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_validation() {
+        #[derive(Debug, Deserialize)]
+        struct ValidatedConfig {
+            server: ServerConfig,
+            database: DatabaseConfig,
+            admin_email: Option<String>,
+        }
+
+        impl Validate for ValidatedConfig {
+            fn validate(&self, report: &mut ValidationReport) {
+                report.nested("server", &self.server);
+                report.nested("database", &self.database);
+                report.require("admin_email", &self.admin_email);
+            }
+        }
+
+        let config: ValidatedConfig = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .env_prefix("APP")
+            .env_vars([("APP__ADMIN_EMAIL", "admin@example.com")])
+            .load_validated()
+            .unwrap();
+        assert_eq!(config.admin_email.as_deref(), Some("admin@example.com"));
+
+        // All the invalid and missing values are reported together
+        let error = ConfigLoader::new()
+            .defaults(&AppConfig::default())
+            .env_prefix("APP")
+            .env_vars([
+                ("APP__SERVER__HOST", "Local Host"),
+                ("APP__SERVER__PORT", "80"),
+                ("APP__DATABASE__MAX_CONNECTIONS", "0"),
+            ])
+            .load_validated::<ValidatedConfig>()
+            .unwrap_err();
+        let ConfigError::Validation(ref report) = error else {
+            panic!("unexpected error: {error}");
+        };
+        let paths = report
+            .issues()
+            .iter()
+            .map(|issue| issue.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "server.host",
+                "server.port",
+                "database.max_connections",
+                "admin_email"
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:\n\
+             - `server.host`: must match the pattern `^[a-z0-9.-]+$`\n\
+             - `server.port`: must be at least 1024\n\
+             - `database.max_connections`: must be at least 1 and at most 100\n\
+             - `admin_email`: missing required value"
+        );
+
+        // Values missing from the configuration or of the wrong type are reported along with the
+        // issues of the other values
+        let error = ConfigLoader::new()
+            .env_prefix("APP")
+            .env_vars([
+                ("APP__SERVER__HOST", "Local Host"),
+                ("APP__SERVER__PORT", "eighty"),
+                ("APP__DATABASE__URL", "mysql://localhost/app"),
+            ])
+            .load_validated::<ValidatedConfig>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:\n\
+             - `database.max_connections`: missing required value\n\
+             - `server.port`: invalid type, expected an integer\n\
+             - `server.host`: must match the pattern `^[a-z0-9.-]+$`\n\
+             - `database.url`: must match the pattern `^postgres://`\n\
+             - `admin_email`: missing required value"
+        );
+        let error = ConfigLoader::new()
+            .load_validated::<ValidatedConfig>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:\n\
+             - `server`: missing required value\n\
+             - `database`: missing required value\n\
+             - `admin_email`: missing required value"
+        );
+
+        let mut report = ValidationReport::new();
+        report.check_range("ratio", &1.5, 0.0..1.0);
+        report.check_regex("name", "app", "[");
+        assert_eq!(report.issues().len(), 2);
+        assert_eq!(
+            report.issues()[0].message,
+            "must be at least 0 and less than 1"
+        );
+        assert!(
+            report.issues()[1]
+                .message
+                .starts_with("invalid validation pattern: "),
+            "{report}"
+        );
+    }
+
+    #[cfg(feature = "masking")]
+    #[test]
+    fn test_secrets() {
+        use hyperswitch_masking::{ExposeInterface, PeekInterface, Secret};
+
+        #[derive(Debug)]
+        struct ReversingResolver;
+
+        impl SecretResolver for ReversingResolver {
+            fn resolve(&self, encrypted: &str) -> Result<String, SecretResolverError> {
+                if encrypted.is_empty() {
+                    return Err("empty ciphertext".into());
+                }
+                Ok(encrypted.chars().rev().collect())
+            }
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct SecretsConfig {
+            #[serde(deserialize_with = "deserialize_secret")]
+            plain: Secret<String>,
+            #[serde(deserialize_with = "deserialize_secret")]
+            from_env: Secret<String>,
+            #[serde(deserialize_with = "deserialize_secret")]
+            from_file: Secret<String>,
+            #[serde(deserialize_with = "deserialize_secret")]
+            encrypted: Secret<String>,
+            #[serde(deserialize_with = "deserialize_secret")]
+            number: Secret<u32>,
+        }
+
+        #[derive(Serialize)]
+        struct SecretsDump {
+            url: String,
+            password: Secret<String>,
+        }
+
+        let dir = config_dir("secrets");
+        let env_var = format!("CONFIG_UTILS_TEST_SECRET_{}", std::process::id());
+        std::env::set_var(&env_var, "from-env");
+        std::fs::write(dir.join("secret.txt"), "from-file\n").unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            format!(
+                "plain = \"plain\"\nfrom_env = {{ env = \"{env_var}\" }}\n\
+                 from_file = {{ file = {:?} }}\nencrypted = {{ encrypted = \"detpyrcne\" }}\n\
+                 number = 42\n",
+                dir.join("secret.txt"),
+            ),
+        )
+        .unwrap();
+
+        let config: SecretsConfig = ConfigLoader::new()
+            .file(dir.join("config.toml"))
+            .secret_resolver(ReversingResolver)
+            .load()
+            .unwrap();
+        assert_eq!(config.plain.peek(), "plain");
+        assert_eq!(config.from_env.expose(), "from-env");
+        assert_eq!(config.from_file.expose(), "from-file");
+        assert_eq!(config.encrypted.expose(), "encrypted");
+        assert_eq!(config.number.expose(), 42);
+
+        // Secrets which could not be resolved fail the loading of the configuration
+        let error = ConfigLoader::new()
+            .file(dir.join("config.toml"))
+            .load::<SecretsConfig>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration at `encrypted`: encrypted secret found, but no secret resolver \
+             is configured for key `encrypted`"
+        );
+
+        // Secrets which could not be deserialized are not exposed in the errors
+        #[derive(Debug, Deserialize)]
+        struct PortConfig {
+            #[serde(deserialize_with = "deserialize_secret")]
+            port: Secret<u16>,
+        }
+
+        for port in ["\"hunter2\"", "{ encrypted = \"2retnuh\" }"] {
+            std::fs::write(dir.join("port.toml"), format!("port = {port}\n")).unwrap();
+            let error = ConfigLoader::new()
+                .file(dir.join("port.toml"))
+                .secret_resolver(ReversingResolver)
+                .load::<PortConfig>()
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "Invalid configuration at `port`: secret could not be deserialized into `u16` for \
+                 key `port`"
+            );
+            assert!(
+                !format!("{error} {error:?}").contains("hunter2"),
+                "{error:?}"
+            );
+        }
+        std::fs::write(dir.join("port.toml"), "port = { encrypted = \"0808\" }\n").unwrap();
+        let port_config: PortConfig = ConfigLoader::new()
+            .file(dir.join("port.toml"))
+            .secret_resolver(ReversingResolver)
+            .load()
+            .unwrap();
+        assert_eq!(port_config.port.expose(), 8080);
+
+        std::fs::write(
+            dir.join("invalid.toml"),
+            "plain = { env = \"CONFIG_UTILS_TEST_UNSET\" }\n",
+        )
+        .unwrap();
+        let error = ConfigLoader::new()
+            .file(dir.join("invalid.toml"))
+            .load::<SecretsConfig>()
+            .unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "Invalid configuration at `plain`: failed to read secret from environment variable \
+                 `CONFIG_UTILS_TEST_UNSET`: "
+            ),
+            "{error}"
+        );
+
+        // The effective configuration is dumped with the secrets masked
+        let dump = masked_dump(&SecretsDump {
+            url: "postgres://localhost/app".to_owned(),
+            password: config.plain,
+        })
+        .unwrap();
+        assert_eq!(
+            dump,
+            serde_json::json!({
+                "url": "postgres://localhost/app",
+                "password": "*** alloc::string::String ***",
+            })
+        );
+
+        std::env::remove_var(&env_var);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
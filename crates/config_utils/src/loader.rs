@@ -0,0 +1,267 @@
+#[cfg(feature = "masking")]
+use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use config::{Config, File, FileFormat};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{ConfigError, Environment, Validate, ValidationReport};
+
+/// The separator between the prefix and the sections of the keys of the environment variables
+/// overriding configuration values, such as in `APP__SERVER__PORT`.
+const ENV_SEPARATOR: &str = "__";
+
+/// A builder for loading configuration from multiple layered sources into any type implementing
+/// [`serde::Deserialize`].
+///
+/// The sources are layered in the following order of precedence, from lowest to highest:
+///
+/// 1. The defaults, specified using [`defaults()`][Self::defaults].
+/// 2. The configuration files, specified using [`file()`][Self::file],
+///    [`optional_file()`][Self::optional_file] and [`environment_file()`][Self::environment_file],
+///    with the files specified later taking precedence over the files specified earlier.
+/// 3. The environment variables with the prefix specified using
+///    [`env_prefix()`][Self::env_prefix].
+///
+/// Values from higher precedence sources override those from lower precedence sources key by
+/// key, so that tables (such as the `[server]` section of a TOML file) are merged rather than
+/// replaced.
+///
+/// # Example
+///
+/// ```no_run
+/// use config_utils::ConfigLoader;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Default, Deserialize, Serialize)]
+/// struct AppConfig {
+///     server: ServerConfig,
+/// }
+///
+/// #[derive(Debug, Default, Deserialize, Serialize)]
+/// struct ServerConfig {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// // `APP__SERVER__PORT=8080` overrides the `port` key of the `server` section
+/// let config: AppConfig = ConfigLoader::new()
+///     .defaults(&AppConfig::default())
+///     .file("config/default.toml")
+///     .optional_file("config/local.yaml")
+///     .env_prefix("APP")
+///     .load()?;
+/// println!("Listening on {}:{}", config.server.host, config.server.port);
+/// # Ok::<(), config_utils::ConfigError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ConfigLoader {
+    defaults: Vec<Result<Config, config::ConfigError>>,
+    files: Vec<ConfigFile>,
+    env_prefix: Option<String>,
+    env_vars: Option<HashMap<String, String>>,
+    #[cfg(feature = "masking")]
+    secret_resolver: Option<Arc<dyn crate::SecretResolver>>,
+}
+
+/// A configuration file to be loaded.
+#[derive(Debug)]
+struct ConfigFile {
+    path: PathBuf,
+    required: bool,
+}
+
+impl ConfigLoader {
+    /// Creates a new [`ConfigLoader`] without any sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the specified defaults as the source with the lowest precedence, typically the
+    /// [`Default`] value of the configuration type.
+    ///
+    /// Any error serializing the defaults is returned by [`load()`][Self::load].
+    pub fn defaults(mut self, defaults: &impl Serialize) -> Self {
+        self.defaults.push(Config::try_from(defaults));
+        self
+    }
+
+    /// Adds the configuration file at the specified path, failing [`load()`][Self::load] if the
+    /// file does not exist.
+    ///
+    /// The format of the file is determined by its extension, `.toml` for TOML files, and `.yaml`
+    /// or `.yml` for YAML files.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(ConfigFile {
+            path: path.into(),
+            required: true,
+        });
+        self
+    }
+
+    /// Adds the configuration file at the specified path, skipping it if the file does not exist,
+    /// such as for local overrides which are not present in all deployments.
+    ///
+    /// The format of the file is determined by its extension, as described in
+    /// [`file()`][Self::file].
+    pub fn optional_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(ConfigFile {
+            path: path.into(),
+            required: false,
+        });
+        self
+    }
+
+    /// Adds the configuration file specific to the deployment environment, named after the
+    /// environment in the specified directory, such as `config/production.toml` for the
+    /// directory `config` and [`Environment::Production`].
+    ///
+    /// The file is typically added after the configuration files shared by all environments, so
+    /// that it takes precedence over them, and is required to exist, as with
+    /// [`file()`][Self::file].
+    pub fn environment_file(self, dir: impl AsRef<Path>, environment: Environment) -> Self {
+        self.file(dir.as_ref().join(format!("{environment}.toml")))
+    }
+
+    /// Specifies the prefix of the environment variables overriding configuration values, as
+    /// the source with the highest precedence.
+    ///
+    /// The environment variables are mapped to keys by removing the prefix and splitting the
+    /// remainder on double underscores, with `APP__SECTION__KEY` overriding the `key` key of the
+    /// `section` section for the prefix `APP`, and `APP__DATABASE__MAX_CONNECTIONS` overriding
+    /// the `max_connections` key of the `database` section.
+    /// The keys are matched case-insensitively, and the values are retained as strings until
+    /// deserialized, so that they are converted to the types of the fields they override, with
+    /// values such as `007` or `1.10` retained as is for string fields.
+    pub fn env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Specifies the resolver for decrypting the encrypted secrets in the configuration, as
+    /// described in [`deserialize_secret()`][crate::deserialize_secret].
+    #[cfg(feature = "masking")]
+    pub fn secret_resolver(mut self, resolver: impl crate::SecretResolver + 'static) -> Self {
+        self.secret_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Specifies the environment variables to read overrides from, instead of the environment
+    /// variables of the process.
+    #[cfg(test)]
+    pub(crate) fn env_vars<'a>(
+        mut self,
+        env_vars: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        self.env_vars = Some(
+            env_vars
+                .into_iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Loads the configuration from the sources, layered in the order of precedence described in
+    /// [`ConfigLoader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the defaults could not be serialized, if a configuration file is of
+    /// an unsupported format, if a required configuration file does not exist or any
+    /// configuration file could not be parsed, or if the layered configuration could not be
+    /// deserialized into `T`, such as due to a missing key, a value of the wrong type or a secret
+    /// which could not be resolved.
+    pub fn load<T>(self) -> Result<T, ConfigError>
+    where
+        T: DeserializeOwned,
+    {
+        #[cfg(feature = "masking")]
+        let _secret_resolver_guard =
+            crate::secret::set_secret_resolver(self.secret_resolver.clone());
+        let config = self.build()?;
+
+        serde_path_to_error::deserialize(config).map_err(|error| ConfigError::Deserialize {
+            path: error.path().to_string(),
+            source: error.into_inner(),
+        })
+    }
+
+    /// Loads the configuration as described in [`load()`][Self::load], and validates it using its
+    /// [`Validate`] implementation.
+    ///
+    /// Values missing from the configuration or of the wrong type are reported together with the
+    /// issues recorded by the [`Validate`] implementation, rather than failing the loading at the
+    /// first such value.
+    /// To do so, the deserialization is restarted after each such value with a placeholder
+    /// substituted for it (such as `0` or an empty string, depending on its type), resolving the
+    /// secrets in the configuration again, and the issues of the placeholders recorded by the
+    /// [`Validate`] implementation are omitted from the report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration sources could not be loaded as described in
+    /// [`load()`][Self::load], or a [`ConfigError::Validation`] error holding the report of all
+    /// the values missing from the configuration or of the wrong type, along with all the issues
+    /// recorded by the [`Validate`] implementation, if the configuration is invalid.
+    pub fn load_validated<T>(self) -> Result<T, ConfigError>
+    where
+        T: DeserializeOwned + Validate,
+    {
+        #[cfg(feature = "masking")]
+        let _secret_resolver_guard =
+            crate::secret::set_secret_resolver(self.secret_resolver.clone());
+        let config = self.build()?;
+
+        let mut report = ValidationReport::new();
+        let Some(config) = crate::recording::deserialize::<T>(&config.cache, &mut report) else {
+            return Err(ConfigError::Validation(report));
+        };
+        let mut validation_report = ValidationReport::new();
+        config.validate(&mut validation_report);
+        report.append_unless_within_issues(validation_report);
+
+        if report.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Validation(report))
+        }
+    }
+
+    /// Builds the layered configuration from the sources.
+    fn build(self) -> Result<Config, ConfigError> {
+        let mut builder = Config::builder();
+        for defaults in self.defaults {
+            builder = builder.add_source(defaults.map_err(ConfigError::Defaults)?);
+        }
+        for file in self.files {
+            let format = file_format(&file.path)?;
+            builder =
+                builder.add_source(File::from(file.path).format(format).required(file.required));
+        }
+        if let Some(prefix) = self.env_prefix {
+            builder = builder.add_source(
+                config::Environment::with_prefix(&prefix)
+                    .prefix_separator(ENV_SEPARATOR)
+                    .separator(ENV_SEPARATOR)
+                    .source(self.env_vars),
+            );
+        }
+
+        builder.build().map_err(ConfigError::Load)
+    }
+}
+
+/// Returns the format of the configuration file at the path, determined by its extension.
+fn file_format(path: &Path) -> Result<FileFormat, ConfigError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Ok(FileFormat::Toml),
+        Some("yaml" | "yml") => Ok(FileFormat::Yaml),
+        _ => Err(ConfigError::UnsupportedFormat {
+            path: path.to_owned(),
+        }),
+    }
+}
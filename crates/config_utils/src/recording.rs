@@ -0,0 +1,547 @@
+use std::{collections::BTreeSet, fmt};
+
+use config::{Value, ValueKind};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+    value::{MapDeserializer, SeqDeserializer},
+};
+
+use crate::ValidationReport;
+
+/// The message of the issues recorded for values missing from the configuration, as recorded by
+/// [`ValidationReport::require`].
+const MISSING_VALUE_MESSAGE: &str = "missing required value";
+
+/// Deserializes the layered configuration into `T`, recording all the values missing from the
+/// configuration or of the wrong type in the report, rather than stopping at the first.
+///
+/// Each value failing the deserialization is recorded along with the path of its key, after which
+/// the deserialization is restarted with a placeholder substituted for the value (such as `0` or
+/// an empty string, depending on the type of the value), until the deserialization succeeds.
+/// The returned configuration hence holds placeholders for the values recorded in the report, and
+/// is `None` if no placeholder could be substituted for a value (such as for untagged enums).
+pub(crate) fn deserialize<T>(value: &Value, report: &mut ValidationReport) -> Option<T>
+where
+    T: DeserializeOwned,
+{
+    let mut placeholders = BTreeSet::new();
+
+    loop {
+        let result = if placeholders.contains("") {
+            T::deserialize(Placeholder).map_err(|_| Error::Placeholder)
+        } else {
+            T::deserialize(Node {
+                value,
+                path: String::new(),
+                placeholders: &placeholders,
+            })
+        };
+
+        match result.map_err(|error| error.at("")) {
+            Ok(config) => return Some(config),
+            Err(Error::At { path, message }) if !placeholders.contains(&path) => {
+                report.error(if path.is_empty() { "." } else { &path }, message);
+                placeholders.insert(path);
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+/// An error deserializing the configuration.
+#[derive(Debug)]
+enum Error {
+    /// Represents a field missing from the struct being deserialized, whose path is not known
+    /// yet.
+    MissingField(&'static str),
+
+    /// Represents an error whose path is not known yet.
+    Message(String),
+
+    /// Represents an error at the path of the key.
+    At {
+        /// The path of the key, empty for the root of the configuration.
+        path: String,
+
+        /// The description of the error.
+        message: String,
+    },
+
+    /// Represents a value for which no placeholder could be substituted.
+    Placeholder,
+}
+
+impl Error {
+    /// Attaches the path of the value being deserialized to the error, unless already attached.
+    fn at(self, path: &str) -> Self {
+        match self {
+            Self::MissingField(field) => Self::At {
+                path: child_path(path, field),
+                message: MISSING_VALUE_MESSAGE.to_owned(),
+            },
+            Self::Message(message) => Self::At {
+                path: path.to_owned(),
+                message,
+            },
+            error @ (Self::At { .. } | Self::Placeholder) => error,
+        }
+    }
+}
+
+impl From<config::ConfigError> for Error {
+    fn from(error: config::ConfigError) -> Self {
+        match error {
+            // The unexpected value is omitted, since it may be a secret
+            config::ConfigError::Type { expected, .. } => {
+                Self::Message(format!("invalid type, expected {expected}"))
+            }
+            config::ConfigError::At { error, .. } => Self::from(*error),
+            config::ConfigError::NotFound(_) => Self::Message(MISSING_VALUE_MESSAGE.to_owned()),
+            error => Self::Message(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(field) => write!(f, "missing field `{field}`"),
+            Self::Message(message) => f.write_str(message),
+            Self::At { path, message } => write!(f, "`{path}`: {message}"),
+            Self::Placeholder => f.write_str("no placeholder could be substituted for the value"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField(field)
+    }
+}
+
+/// Returns the path of the key within the table at the path.
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// A value of the layered configuration being deserialized, at the path of its key.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+    placeholders: &'a BTreeSet<String>,
+}
+
+/// Deserializes scalar values using the deserializer of the configuration value, so that they are
+/// converted to the requested types (such as strings to numbers) as done by
+/// [`ConfigLoader::load()`][crate::ConfigLoader::load].
+macro_rules! deserialize_scalar {
+    ($($method:ident($($arg:ident: $arg_type:ty),*)),* $(,)?) => {
+        $(
+            fn $method<V>(self, $($arg: $arg_type,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.value
+                    .clone()
+                    .$method($($arg,)* visitor)
+                    .map_err(|error| Error::from(error).at(&self.path))
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Node<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let result = match &self.value.kind {
+            ValueKind::Table(table) => {
+                let mut entries = table
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), Some(value)))
+                    .collect::<Vec<_>>();
+                // The placeholders of the missing fields are added as keys of the table
+                let prefix = child_path(&self.path, "");
+                entries.extend(
+                    self.placeholders
+                        .iter()
+                        .filter_map(|path| path.strip_prefix(&prefix))
+                        .filter(|key| !key.contains(['.', '[']) && !table.contains_key(*key))
+                        .map(|key| (key, None)),
+                );
+                // The entries are sorted, so that the issues are recorded in a consistent order
+                entries.sort_unstable_by_key(|(key, _)| *key);
+
+                visitor.visit_map(TableAccess {
+                    entries: entries.into_iter(),
+                    next_value: None,
+                    path: &self.path,
+                    placeholders: self.placeholders,
+                })
+            }
+            ValueKind::Array(array) => visitor.visit_seq(ArrayAccess {
+                elements: array.iter().enumerate(),
+                path: &self.path,
+                placeholders: self.placeholders,
+            }),
+            _ => self
+                .value
+                .clone()
+                .deserialize_any(visitor)
+                .map_err(Error::from),
+        };
+
+        result.map_err(|error| error.at(&self.path))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value.kind {
+            ValueKind::Nil => visitor.visit_none(),
+            _ => {
+                let path = self.path.clone();
+                visitor.visit_some(self).map_err(|error| error.at(&path))
+            }
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let path = self.path.clone();
+        visitor
+            .visit_newtype_struct(self)
+            .map_err(|error| error.at(&path))
+    }
+
+    deserialize_scalar! {
+        deserialize_bool(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_i128(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_u128(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_bytes(),
+        deserialize_byte_buf(),
+        deserialize_unit(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+        deserialize_identifier(),
+        deserialize_ignored_any(),
+    }
+
+    // Tables and arrays are deserialized regardless of the requested type, as done by the
+    // deserializer of the configuration value
+    serde::forward_to_deserialize_any! {
+        seq tuple tuple_struct map struct
+    }
+}
+
+/// The entries of a table being deserialized, with the placeholders of the missing fields as
+/// entries without values.
+struct TableAccess<'a> {
+    entries: std::vec::IntoIter<(&'a str, Option<&'a Value>)>,
+    next_value: Option<(&'a str, Option<&'a Value>)>,
+    path: &'a str,
+    placeholders: &'a BTreeSet<String>,
+}
+
+impl<'de> MapAccess<'de> for TableAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some(entry) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.next_value = Some(entry);
+        seed.deserialize(entry.0.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (key, value) = self
+            .next_value
+            .take()
+            .ok_or_else(|| Error::Message("value requested before key".to_owned()))?;
+        let path = child_path(self.path, key);
+
+        match value {
+            Some(value) if !self.placeholders.contains(&path) => seed.deserialize(Node {
+                value,
+                path,
+                placeholders: self.placeholders,
+            }),
+            _ => seed
+                .deserialize(Placeholder)
+                .map_err(|_| Error::Placeholder),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// The elements of an array being deserialized.
+struct ArrayAccess<'a> {
+    elements: std::iter::Enumerate<std::slice::Iter<'a, Value>>,
+    path: &'a str,
+    placeholders: &'a BTreeSet<String>,
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some((index, value)) = self.elements.next() else {
+            return Ok(None);
+        };
+        let path = format!("{}[{index}]", self.path);
+
+        if self.placeholders.contains(&path) {
+            seed.deserialize(Placeholder)
+                .map(Some)
+                .map_err(|_| Error::Placeholder)
+        } else {
+            seed.deserialize(Node {
+                value,
+                path,
+                placeholders: self.placeholders,
+            })
+            .map(Some)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+/// A deserializer substituting the values which could not be deserialized with placeholders of
+/// the requested types, such as `0`, empty strings, `None`, empty arrays, or structs of
+/// placeholders.
+#[derive(Clone, Copy)]
+struct Placeholder;
+
+impl<'de> IntoDeserializer<'de, Error> for Placeholder {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Deserializes placeholders of scalar types, using the specified visitor method and value.
+macro_rules! deserialize_placeholder {
+    ($($method:ident($($arg:ident: $arg_type:ty),*) => $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V>(self, $(_: $arg_type,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Placeholder {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        // The type of the value is not known
+        Err(Error::Placeholder)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::empty::<Self>()))
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqDeserializer::new(std::iter::repeat_n(self, len)))
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(std::iter::empty::<(&str, Self)>()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(MapDeserializer::new(
+            fields.iter().map(|field| (*field, self)),
+        ))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        // The first variant is substituted
+        let variant = variants.first().ok_or(Error::Placeholder)?;
+        visitor.visit_enum(PlaceholderVariant(variant))
+    }
+
+    deserialize_placeholder! {
+        deserialize_bool() => visit_bool(false),
+        deserialize_i8() => visit_i8(0),
+        deserialize_i16() => visit_i16(0),
+        deserialize_i32() => visit_i32(0),
+        deserialize_i64() => visit_i64(0),
+        deserialize_i128() => visit_i128(0),
+        deserialize_u8() => visit_u8(0),
+        deserialize_u16() => visit_u16(0),
+        deserialize_u32() => visit_u32(0),
+        deserialize_u64() => visit_u64(0),
+        deserialize_u128() => visit_u128(0),
+        deserialize_f32() => visit_f32(0.0),
+        deserialize_f64() => visit_f64(0.0),
+        deserialize_char() => visit_char('\0'),
+        deserialize_str() => visit_str(""),
+        deserialize_string() => visit_str(""),
+        deserialize_bytes() => visit_bytes(&[]),
+        deserialize_byte_buf() => visit_bytes(&[]),
+        deserialize_option() => visit_none(),
+        deserialize_unit() => visit_unit(),
+        deserialize_unit_struct(name: &'static str) => visit_unit(),
+        deserialize_ignored_any() => visit_unit(),
+    }
+
+    serde::forward_to_deserialize_any! {
+        identifier
+    }
+}
+
+/// The variant of an enum substituted as a placeholder.
+struct PlaceholderVariant(&'static str);
+
+impl<'de> EnumAccess<'de> for PlaceholderVariant {
+    type Error = Error;
+    type Variant = Placeholder;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Placeholder), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.0.into_deserializer())
+            .map(|variant| (variant, Placeholder))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Placeholder {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
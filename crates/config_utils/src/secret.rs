@@ -0,0 +1,272 @@
+use std::{cell::RefCell, fmt, path::Path, sync::Arc};
+
+use hyperswitch_masking::{Secret, masked_serialize};
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{DeserializeOwned, Error as _},
+};
+
+/// The error returned by a [`SecretResolver`].
+pub type SecretResolverError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A resolver for decrypting the encrypted secrets in the configuration, such as using a key
+/// management service (KMS) or Vault.
+///
+/// The resolver is specified using
+/// [`ConfigLoader::secret_resolver`][crate::ConfigLoader::secret_resolver], and is invoked for
+/// each encrypted secret deserialized using [`deserialize_secret()`] while the configuration is
+/// loaded.
+///
+/// # Example
+///
+/// ```
+/// use config_utils::{SecretResolver, SecretResolverError};
+///
+/// #[derive(Debug)]
+/// struct KmsResolver {
+///     key_id: String,
+/// }
+///
+/// impl SecretResolver for KmsResolver {
+///     fn resolve(&self, encrypted: &str) -> Result<String, SecretResolverError> {
+///         if encrypted.is_empty() {
+///             return Err(format!("empty ciphertext for key `{}`", self.key_id).into());
+///         }
+///         // Decrypt the value using the KMS client ...
+///         Ok(encrypted.chars().rev().collect())
+///     }
+/// }
+///
+/// let loader = config_utils::ConfigLoader::new()
+///     .file("config/default.toml")
+///     .secret_resolver(KmsResolver {
+///         key_id: "alias/app-config".to_owned(),
+///     });
+/// # drop(loader);
+/// ```
+pub trait SecretResolver: fmt::Debug + Send + Sync {
+    /// Decrypts the encrypted secret, as specified in the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret could not be decrypted, failing the loading of the
+    /// configuration.
+    fn resolve(&self, encrypted: &str) -> Result<String, SecretResolverError>;
+}
+
+thread_local! {
+    /// The resolver for the encrypted secrets in the configuration being loaded on the current
+    /// thread.
+    static SECRET_RESOLVER: RefCell<Option<Arc<dyn SecretResolver>>> = const { RefCell::new(None) };
+}
+
+/// Sets the resolver for the encrypted secrets in the configuration being loaded on the current
+/// thread, until the returned guard is dropped.
+pub(crate) fn set_secret_resolver(
+    resolver: Option<Arc<dyn SecretResolver>>,
+) -> SecretResolverGuard {
+    let previous = SECRET_RESOLVER.with(|current| current.replace(resolver));
+    SecretResolverGuard { previous }
+}
+
+/// Restores the previous resolver for the encrypted secrets when dropped.
+pub(crate) struct SecretResolverGuard {
+    previous: Option<Arc<dyn SecretResolver>>,
+}
+
+impl Drop for SecretResolverGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SECRET_RESOLVER.with(|current| current.replace(previous));
+    }
+}
+
+/// Deserializes a [`Secret`] from the configuration, for use with
+/// `#[serde(deserialize_with = "config_utils::deserialize_secret")]` on the fields of
+/// configuration types holding secrets.
+///
+/// The secret can be specified in the configuration as follows:
+///
+/// - As the value itself, such as `password = "hunter2"`.
+/// - As the name of the environment variable holding the value, such as
+///   `password = { env = "DATABASE_PASSWORD" }`.
+/// - As the path of the file holding the value, such as
+///   `password = { file = "/run/secrets/database_password" }`, with the trailing newline (if
+///   any) removed.
+/// - As the encrypted value, such as `password = { encrypted = "AQICAHh..." }`, decrypted using
+///   the [`SecretResolver`] specified using
+///   [`ConfigLoader::secret_resolver`][crate::ConfigLoader::secret_resolver].
+///
+/// Values obtained from environment variables, files or the resolver are deserialized into `T`
+/// from strings, converted to numbers and booleans as needed, as done for the other values of the
+/// configuration.
+/// The secrets are resolved when the configuration is loaded, so that the application need not
+/// decrypt them itself, with any failure resolving a secret failing the loading of the
+/// configuration along with the path of the offending key.
+///
+/// # Errors
+///
+/// Returns an error if the environment variable is unset, if the file could not be read, if no
+/// resolver is specified or the resolver fails to decrypt the value, or if the value could not be
+/// deserialized into `T`, in which case the error omits the value so as not to expose the secret.
+///
+/// # Example
+///
+/// ```no_run
+/// use config_utils::ConfigLoader;
+/// use hyperswitch_masking::{PeekInterface, Secret};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct DatabaseConfig {
+///     url: String,
+///     #[serde(deserialize_with = "config_utils::deserialize_secret")]
+///     password: Secret<String>,
+/// }
+///
+/// // With `config/default.toml` holding:
+/// // url = "postgres://localhost/app"
+/// // password = { env = "DATABASE_PASSWORD" }
+/// let config: DatabaseConfig = ConfigLoader::new().file("config/default.toml").load()?;
+/// let password: &str = config.password.peek();
+/// println!(
+///     "Connecting to {} ({} byte password)",
+///     config.url,
+///     password.len()
+/// );
+/// # Ok::<(), config_utils::ConfigError>(())
+/// ```
+pub fn deserialize_secret<'de, D, T>(deserializer: D) -> Result<Secret<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = config::Value::deserialize(deserializer)?;
+    let resolved = match secret_reference(&value) {
+        Some(SecretReference::Env(name)) => std::env::var(name).map_err(|error| {
+            D::Error::custom(format!(
+                "failed to read secret from environment variable `{name}`: {error}"
+            ))
+        })?,
+        Some(SecretReference::File(path)) => {
+            read_secret_file(Path::new(path)).map_err(|error| {
+                D::Error::custom(format!("failed to read secret from file `{path}`: {error}"))
+            })?
+        }
+        Some(SecretReference::Encrypted(encrypted)) => {
+            let resolver = SECRET_RESOLVER.with(|resolver| resolver.borrow().clone());
+            let resolver = resolver.ok_or_else(|| {
+                D::Error::custom("encrypted secret found, but no secret resolver is configured")
+            })?;
+            resolver
+                .resolve(encrypted)
+                .map_err(|error| D::Error::custom(format!("failed to decrypt secret: {error}")))?
+        }
+        None => {
+            return T::deserialize(value)
+                .map(Secret::new)
+                .map_err(|_| invalid_secret::<T, D::Error>());
+        }
+    };
+
+    T::deserialize(config::Value::new(None, resolved))
+        .map(Secret::new)
+        .map_err(|_| invalid_secret::<T, D::Error>())
+}
+
+/// Returns the error for a secret which could not be deserialized into `T`, without the details
+/// of the underlying error, since those may include the secret itself (such as the unexpected
+/// value of an invalid type error).
+fn invalid_secret<T, E>() -> E
+where
+    E: serde::de::Error,
+{
+    E::custom(format!(
+        "secret could not be deserialized into `{}`",
+        std::any::type_name::<T>()
+    ))
+}
+
+/// A reference to a secret held outside the configuration.
+enum SecretReference<'a> {
+    /// The name of the environment variable holding the secret.
+    Env(&'a str),
+
+    /// The path of the file holding the secret.
+    File(&'a str),
+
+    /// The encrypted secret, to be decrypted by the [`SecretResolver`].
+    Encrypted(&'a str),
+}
+
+/// Returns the reference to the secret if the value is a table with a single `env`, `file` or
+/// `encrypted` key holding a string.
+fn secret_reference(value: &config::Value) -> Option<SecretReference<'_>> {
+    let config::ValueKind::Table(table) = &value.kind else {
+        return None;
+    };
+    let mut entries = table.iter();
+    let (key, value) = entries.next()?;
+    if entries.next().is_some() {
+        return None;
+    }
+    let config::ValueKind::String(value) = &value.kind else {
+        return None;
+    };
+
+    match key.as_str() {
+        "env" => Some(SecretReference::Env(value)),
+        "file" => Some(SecretReference::File(value)),
+        "encrypted" => Some(SecretReference::Encrypted(value)),
+        _ => None,
+    }
+}
+
+/// Reads the secret from the file, removing the trailing newline (if any).
+fn read_secret_file(path: &Path) -> std::io::Result<String> {
+    let mut secret = std::fs::read_to_string(path)?;
+    if secret.ends_with('\n') {
+        secret.pop();
+        if secret.ends_with('\r') {
+            secret.pop();
+        }
+    }
+    Ok(secret)
+}
+
+/// Serializes the effective configuration for logging, such as in audit logs at startup, with any
+/// [`Secret`] or [`StrongSecret`][hyperswitch_masking::StrongSecret] within the configuration
+/// serialized as its masked representation rather than its inner value.
+///
+/// # Errors
+///
+/// Returns an error if the configuration could not be serialized.
+///
+/// # Example
+///
+/// ```
+/// use hyperswitch_masking::Secret;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct DatabaseConfig {
+///     url: String,
+///     password: Secret<String>,
+/// }
+///
+/// let config = DatabaseConfig {
+///     url: "postgres://localhost/app".to_owned(),
+///     password: Secret::new("hunter2".to_owned()),
+/// };
+///
+/// let dump = config_utils::masked_dump(&config)?;
+/// assert_eq!(dump["url"], "postgres://localhost/app");
+/// assert_eq!(dump["password"], "*** alloc::string::String ***");
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub fn masked_dump<T>(config: &T) -> Result<serde_json::Value, serde_json::Error>
+where
+    T: Serialize,
+{
+    masked_serialize(config)
+}
@@ -0,0 +1,219 @@
+use std::{
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+use regex::Regex;
+
+/// A configuration type whose values can be validated after the configuration is loaded, such
+/// as using [`ConfigLoader::load_validated`][crate::ConfigLoader::load_validated].
+///
+/// Implementations record all the invalid and missing values in the [`ValidationReport`] rather
+/// than stopping at the first, so that all of them can be reported at startup at once.
+///
+/// Values missing from the configuration or of the wrong type are reported along with the issues
+/// recorded by the implementation when loaded using
+/// [`ConfigLoader::load_validated`][crate::ConfigLoader::load_validated].
+///
+/// # Example
+///
+/// ```
+/// use config_utils::{Validate, ValidationReport};
+///
+/// struct AppConfig {
+///     server: ServerConfig,
+///     admin_email: Option<String>,
+/// }
+///
+/// struct ServerConfig {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// impl Validate for AppConfig {
+///     fn validate(&self, report: &mut ValidationReport) {
+///         report.nested("server", &self.server);
+///         report.require("admin_email", &self.admin_email);
+///     }
+/// }
+///
+/// impl Validate for ServerConfig {
+///     fn validate(&self, report: &mut ValidationReport) {
+///         report.check_regex("host", &self.host, r"^[a-z0-9.-]+$");
+///         report.check_range("port", &self.port, 1024..);
+///     }
+/// }
+///
+/// let config = AppConfig {
+///     server: ServerConfig {
+///         host: "localhost".to_owned(),
+///         port: 80,
+///     },
+///     admin_email: None,
+/// };
+///
+/// let mut report = ValidationReport::new();
+/// config.validate(&mut report);
+/// assert_eq!(
+///     report.to_string(),
+///     "Invalid configuration:\n\
+///      - `server.port`: must be at least 1024\n\
+///      - `admin_email`: missing required value"
+/// );
+/// ```
+pub trait Validate {
+    /// Validates the configuration, recording any invalid or missing values in the report.
+    fn validate(&self, report: &mut ValidationReport);
+}
+
+/// The invalid and missing values found while validating the configuration.
+///
+/// The issues are recorded along with the paths of the offending keys (such as `server.port`),
+/// with the keys of nested configuration types validated using
+/// [`nested()`][Self::nested] prefixed with the key of the nested type.
+/// The issues describe the expected values without including the actual values, since the values
+/// may be secrets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    prefix: Vec<String>,
+    issues: Vec<ValidationIssue>,
+}
+
+/// An invalid or missing value found while validating the configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The path of the offending key, such as `server.port`.
+    pub path: String,
+
+    /// The description of the issue, such as `must be at least 1024`.
+    pub message: String,
+}
+
+impl ValidationReport {
+    /// Creates a new [`ValidationReport`] without any issues.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no issues were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the recorded issues, in the order they were recorded.
+    pub fn issues(&self) -> &[ValidationIssue] {
+        &self.issues
+    }
+
+    /// Records an issue with the value of the key.
+    pub fn error(&mut self, key: &str, message: impl Into<String>) {
+        let path = self
+            .prefix
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(key))
+            .collect::<Vec<_>>()
+            .join(".");
+        self.issues.push(ValidationIssue {
+            path,
+            message: message.into(),
+        });
+    }
+
+    /// Validates the nested configuration type, with the keys of its issues prefixed with the
+    /// key of the nested type.
+    pub fn nested(&mut self, key: &str, value: &impl Validate) {
+        self.prefix.push(key.to_owned());
+        value.validate(self);
+        self.prefix.pop();
+    }
+
+    /// Appends the issues of the other report, except for those with keys at or within the paths
+    /// of the issues already recorded, such as the issues of the placeholders substituted for the
+    /// values which could not be deserialized.
+    pub(crate) fn append_unless_within_issues(&mut self, other: Self) {
+        let within_issues = |path: &str| {
+            self.issues.iter().any(|issue| {
+                issue.path == "."
+                    || path
+                        .strip_prefix(issue.path.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+            })
+        };
+        let issues = other
+            .issues
+            .into_iter()
+            .filter(|issue| !within_issues(&issue.path))
+            .collect::<Vec<_>>();
+        self.issues.extend(issues);
+    }
+
+    /// Records an issue if the optional value of the key is missing, for values which are
+    /// required but are declared as optional, such as values required only in some deployments.
+    pub fn require<T>(&mut self, key: &str, value: &Option<T>) {
+        if value.is_none() {
+            self.error(key, "missing required value");
+        }
+    }
+
+    /// Records an issue if the value of the key is outside the range.
+    pub fn check_range<T>(&mut self, key: &str, value: &T, range: impl RangeBounds<T>)
+    where
+        T: PartialOrd + fmt::Display,
+    {
+        if !range.contains(value) {
+            self.error(key, range_message(&range));
+        }
+    }
+
+    /// Records an issue if the value of the key does not match the regular expression, which
+    /// should be anchored using `^` and `$` to match the entire value.
+    ///
+    /// An invalid regular expression is recorded as an issue as well.
+    pub fn check_regex(&mut self, key: &str, value: &str, pattern: &str) {
+        match Regex::new(pattern) {
+            Ok(regex) if regex.is_match(value) => {}
+            Ok(_) => self.error(key, format!("must match the pattern `{pattern}`")),
+            Err(error) => self.error(key, format!("invalid validation pattern: {error}")),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Invalid configuration:")?;
+        for issue in &self.issues {
+            write!(f, "\n- {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`: {}", self.path, self.message)
+    }
+}
+
+/// Returns the description of the values within the range, such as `must be at least 1024`.
+fn range_message<T>(range: &impl RangeBounds<T>) -> String
+where
+    T: fmt::Display,
+{
+    let start = match range.start_bound() {
+        Bound::Included(start) => Some(format!("at least {start}")),
+        Bound::Excluded(start) => Some(format!("greater than {start}")),
+        Bound::Unbounded => None,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => Some(format!("at most {end}")),
+        Bound::Excluded(end) => Some(format!("less than {end}")),
+        Bound::Unbounded => None,
+    };
+
+    match (start, end) {
+        (Some(start), Some(end)) => format!("must be {start} and {end}"),
+        (Some(bound), None) | (None, Some(bound)) => format!("must be {bound}"),
+        (None, None) => "must be within the range".to_owned(),
+    }
+}
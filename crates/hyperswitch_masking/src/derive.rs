@@ -0,0 +1,61 @@
+//! Helpers used by the code generated by the [`Masked`][crate::Masked] derive macro.
+
+use core::fmt;
+
+use crate::{Strategy, WithType};
+
+/// Formats the value masked along with its type, as done by the [`WithType`] strategy.
+pub struct MaskedField<'a, T>(pub &'a T);
+
+impl<T> fmt::Debug for MaskedField<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <WithType as Strategy<T>>::fmt(self.0, f)
+    }
+}
+
+/// Serializes the `Debug` representation of the value as a string.
+#[cfg(feature = "serde")]
+pub struct DebugString<'a, T>(pub &'a T);
+
+#[cfg(feature = "serde")]
+impl<T: fmt::Debug> fmt::Debug for DebugString<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: fmt::Debug> serde::Serialize for DebugString<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&format_args!("{:?}", self.0))
+    }
+}
+
+/// Serializes the value with the secrets within it masked, as done by
+/// [`masked_serialize()`][crate::masked_serialize].
+#[cfg(feature = "serde")]
+pub struct MaskedSerialize<'a, T>(pub &'a T);
+
+#[cfg(feature = "serde")]
+impl<T> fmt::Debug for MaskedSerialize<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MaskedSerialize").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for MaskedSerialize<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error as _;
+
+        crate::masked_serialize(self.0)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
@@ -16,6 +16,8 @@ mod boxed;
 mod bytes;
 #[cfg(feature = "cassandra")]
 mod cassandra;
+#[cfg(feature = "derive")]
+mod derive;
 #[cfg(feature = "diesel")]
 mod diesel;
 mod maskable;
@@ -29,6 +31,8 @@ mod strong_secret;
 #[cfg(feature = "alloc")]
 mod vec;
 
+#[cfg(feature = "derive")]
+pub use hyperswitch_masking_derive::Masked;
 pub use zeroize::{self, DefaultIsZeroes, Zeroize as ZeroizableSecret};
 
 #[cfg(feature = "bytes")]
@@ -37,7 +41,10 @@ pub use self::{
     abs::{ExposeInterface, ExposeOptionInterface, PeekInterface, SwitchStrategy},
     maskable::{Mask, Maskable},
     secret::Secret,
-    strategy::{Strategy, WithType, WithoutType},
+    strategy::{
+        CardNumberStrategy, EmailStrategy, IbanStrategy, PhoneNumberStrategy, Strategy, WithType,
+        WithoutType,
+    },
     strong_secret::StrongSecret,
 };
 #[cfg(feature = "serde")]
@@ -45,3 +52,14 @@ pub use self::{
     secret::JsonMaskStrategy,
     serde::{Deserialize, ErasedMaskSerialize, SerializableSecret, Serialize, masked_serialize},
 };
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "serde")]
+    pub use serde;
+
+    pub use crate::derive::MaskedField;
+    #[cfg(feature = "serde")]
+    pub use crate::derive::{DebugString, MaskedSerialize};
+}
@@ -12,7 +12,7 @@ use crate::{PeekInterface, StrongSecret, strategy::Strategy};
 /// Use the [`crate::strategy::Strategy`] trait to implement a masking strategy on a zero-variant
 /// enum and pass this enum as a second generic parameter to [`Secret`] while defining it.
 /// [`Secret`] will take care of applying the masking strategy on the inner secret when being
-/// displayed.
+/// formatted using either [`fmt::Debug`] or [`fmt::Display`].
 ///
 /// ## Masking Example
 ///
@@ -35,6 +35,7 @@ use crate::{PeekInterface, StrongSecret, strategy::Strategy};
 /// let my_secret: Secret<String, MyStrategy> = Secret::new("HELLO".to_string());
 ///
 /// assert_eq!("hello", &format!("{:?}", my_secret));
+/// assert_eq!("hello", &format!("{}", my_secret));
 /// ```
 pub struct Secret<Secret, MaskingStrategy = crate::WithType>
 where
@@ -170,6 +171,15 @@ where
     }
 }
 
+impl<SecretValue, MaskingStrategy> fmt::Display for Secret<SecretValue, MaskingStrategy>
+where
+    MaskingStrategy: Strategy<SecretValue>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        MaskingStrategy::fmt(&self.inner_secret, f)
+    }
+}
+
 impl<SecretValue, MaskingStrategy> Default for Secret<SecretValue, MaskingStrategy>
 where
     SecretValue: Default,
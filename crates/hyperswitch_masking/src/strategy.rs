@@ -1,4 +1,4 @@
-use core::fmt;
+use core::fmt::{self, Write};
 
 /// Debugging trait which is specialized for handling secret values
 pub trait Strategy<T> {
@@ -28,3 +28,239 @@ impl<T> Strategy<T> for WithoutType {
         fmt.write_str("*** ***")
     }
 }
+
+/// Mask card numbers, leaving the first 6 and the last 4 digits visible (such as
+/// `424242******4242`), as permitted by PCI DSS.
+///
+/// Spaces and dashes separating the digit groups are retained as is.
+/// Values which do not look like card numbers (consisting of 13 to 19 digits) are masked entirely
+/// as done by [`WithType`].
+#[derive(Debug, Copy, Clone)]
+pub enum CardNumberStrategy {}
+
+impl<T> Strategy<T> for CardNumberStrategy
+where
+    T: AsRef<str>,
+{
+    fn fmt(value: &T, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = value.as_ref();
+        let is_valid = text
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ' ' || c == '-')
+            && (13..=19).contains(&text.chars().filter(char::is_ascii_digit).count());
+
+        if is_valid {
+            write_partially_masked(text, |c| c.is_ascii_digit(), 6, 4, fmt)
+        } else {
+            <WithType as Strategy<T>>::fmt(value, fmt)
+        }
+    }
+}
+
+/// Mask email addresses, leaving only the first character of the local part and the domain
+/// visible (such as `j***@example.com`).
+///
+/// Values which do not look like email addresses are masked entirely as done by [`WithType`].
+#[derive(Debug, Copy, Clone)]
+pub enum EmailStrategy {}
+
+impl<T> Strategy<T> for EmailStrategy
+where
+    T: AsRef<str>,
+{
+    fn fmt(value: &T, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = value.as_ref();
+        match text
+            .rsplit_once('@')
+            .and_then(|(local_part, domain)| Some((local_part.chars().next()?, domain)))
+        {
+            Some((first, domain)) if !domain.is_empty() => write!(fmt, "{first}***@{domain}"),
+            _ => <WithType as Strategy<T>>::fmt(value, fmt),
+        }
+    }
+}
+
+/// Mask phone numbers, leaving only the last 4 digits visible (such as `+** *****-*4567`).
+///
+/// The leading `+`, spaces, dashes, dots and parentheses are retained as is.
+/// Values which do not look like phone numbers (consisting of at least 7 digits) are masked
+/// entirely as done by [`WithType`].
+#[derive(Debug, Copy, Clone)]
+pub enum PhoneNumberStrategy {}
+
+impl<T> Strategy<T> for PhoneNumberStrategy
+where
+    T: AsRef<str>,
+{
+    fn fmt(value: &T, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = value.as_ref();
+        let is_valid = text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | ' ' | '-' | '.' | '(' | ')'))
+            && text.chars().filter(char::is_ascii_digit).count() >= 7;
+
+        if is_valid {
+            write_partially_masked(text, |c| c.is_ascii_digit(), 0, 4, fmt)
+        } else {
+            <WithType as Strategy<T>>::fmt(value, fmt)
+        }
+    }
+}
+
+/// Mask International Bank Account Numbers (IBANs), leaving the country code and check digits
+/// along with the last 4 characters visible (such as `DE89**************3000`).
+///
+/// Spaces separating the character groups are retained as is.
+/// Values which do not look like IBANs (consisting of 15 to 34 alphanumeric characters) are masked
+/// entirely as done by [`WithType`].
+#[derive(Debug, Copy, Clone)]
+pub enum IbanStrategy {}
+
+impl<T> Strategy<T> for IbanStrategy
+where
+    T: AsRef<str>,
+{
+    fn fmt(value: &T, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = value.as_ref();
+        let is_valid = text.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ')
+            && (15..=34).contains(&text.chars().filter(char::is_ascii_alphanumeric).count());
+
+        if is_valid {
+            write_partially_masked(text, |c| c.is_ascii_alphanumeric(), 4, 4, fmt)
+        } else {
+            <WithType as Strategy<T>>::fmt(value, fmt)
+        }
+    }
+}
+
+/// Write the value with the characters considered significant by `is_significant` replaced by
+/// `*`, except for the first `visible_prefix` and the last `visible_suffix` significant
+/// characters, and with the other characters (such as separators) retained as is.
+fn write_partially_masked(
+    value: &str,
+    is_significant: impl Fn(char) -> bool,
+    visible_prefix: usize,
+    visible_suffix: usize,
+    fmt: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let significant_count = value.chars().filter(|c| is_significant(*c)).count();
+    let masked_end = significant_count.saturating_sub(visible_suffix);
+
+    let mut index = 0;
+    for c in value.chars() {
+        if !is_significant(c) {
+            fmt.write_char(c)?;
+            continue;
+        }
+        if index < visible_prefix || index >= masked_end {
+            fmt.write_char(c)?;
+        } else {
+            fmt.write_char('*')?;
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Secret;
+
+    #[test]
+    fn test_card_number_strategy() {
+        let test_cases = [
+            ("4242424242424242", "424242******4242"),
+            ("4242 4242 4242 4242", "4242 42** **** 4242"),
+            ("4242-4242-4242-4242", "4242-42**-****-4242"),
+            ("4242424242424", "424242***2424"),
+            ("424242424242", "*** &str ***"),
+            ("4242x42424242424", "*** &str ***"),
+        ];
+
+        for (input, expected) in test_cases {
+            let secret = Secret::<_, CardNumberStrategy>::new(input);
+            assert_eq!(
+                format!("{secret:?}"),
+                expected,
+                "Failed for input: '{input}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_email_strategy() {
+        let test_cases = [
+            ("john.doe@example.com", "j***@example.com"),
+            ("ü@example.com", "ü***@example.com"),
+            ("\"a@b\"@example.com", "\"***@example.com"),
+            ("john.doe", "*** &str ***"),
+            ("@example.com", "*** &str ***"),
+            ("john.doe@", "*** &str ***"),
+        ];
+
+        for (input, expected) in test_cases {
+            let secret = Secret::<_, EmailStrategy>::new(input);
+            assert_eq!(
+                format!("{secret:?}"),
+                expected,
+                "Failed for input: '{input}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_phone_number_strategy() {
+        let test_cases = [
+            ("+49 30 1234567", "+** ** ***4567"),
+            ("(555) 123-4567", "(***) ***-4567"),
+            ("1234567", "***4567"),
+            ("123456", "*** &str ***"),
+            ("+49 30 CALL-NOW", "*** &str ***"),
+        ];
+
+        for (input, expected) in test_cases {
+            let secret = Secret::<_, PhoneNumberStrategy>::new(input);
+            assert_eq!(
+                format!("{secret:?}"),
+                expected,
+                "Failed for input: '{input}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_iban_strategy() {
+        let test_cases = [
+            ("DE89370400440532013000", "DE89**************3000"),
+            ("DE89 3704 0044 0532 0130 00", "DE89 **** **** **** **30 00"),
+            ("NO9386011117947", "NO93*******7947"),
+            ("NO938601111794", "*** &str ***"),
+            ("DE89-3704-0044-0532-0130-00", "*** &str ***"),
+        ];
+
+        for (input, expected) in test_cases {
+            let secret = Secret::<_, IbanStrategy>::new(input);
+            assert_eq!(
+                format!("{secret:?}"),
+                expected,
+                "Failed for input: '{input}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fallback_retains_type_name() {
+        let expected = "*** alloc::string::String ***";
+        let invalid = "invalid".to_owned();
+
+        let card_number = Secret::<_, CardNumberStrategy>::new(invalid.clone());
+        assert_eq!(format!("{card_number:?}"), expected);
+        let email = Secret::<_, EmailStrategy>::new(invalid.clone());
+        assert_eq!(format!("{email:?}"), expected);
+        let phone_number = Secret::<_, PhoneNumberStrategy>::new(invalid.clone());
+        assert_eq!(format!("{phone_number:?}"), expected);
+        let iban = Secret::<_, IbanStrategy>::new(invalid);
+        assert_eq!(format!("{iban:?}"), expected);
+    }
+}
@@ -139,6 +139,10 @@ fn for_string() {
         r#"Composite { secret_number: *** alloc::string::String ***, not_secret: "not secret" }"#;
     assert_eq!(got, exp);
 
+    let got = composite.secret_number.to_string();
+    let exp = "*** alloc::string::String ***";
+    assert_eq!(got, exp);
+
     // serialize
 
     #[cfg(all(feature = "alloc", feature = "serde"))]
@@ -0,0 +1,102 @@
+#![expect(missing_docs)]
+
+use hyperswitch_masking::{CardNumberStrategy, Masked, Secret, StrongSecret};
+
+#[test]
+fn named_fields() {
+    #[derive(Masked)]
+    #[cfg_attr(feature = "serde", masked(serialize))]
+    pub struct Payment {
+        #[masked(visible)]
+        id: String,
+        #[masked(visible)]
+        amount: i64,
+        card_number: Secret<String, CardNumberStrategy>,
+        cvc: StrongSecret<String>,
+        billing_address: String,
+    }
+
+    let payment = Payment {
+        id: "pay_123".to_string(),
+        amount: 100,
+        card_number: Secret::new("4242424242424242".to_string()),
+        cvc: StrongSecret::new("123".to_string()),
+        billing_address: "221B Baker Street".to_string(),
+    };
+
+    // format
+
+    let got = format!("{payment:?}");
+    let exp = r#"Payment { id: "pay_123", amount: 100, card_number: 424242******4242, cvc: *** alloc::string::String ***, billing_address: *** alloc::string::String *** }"#;
+    assert_eq!(got, exp);
+
+    // serialize
+
+    #[cfg(feature = "serde")]
+    {
+        let got = serde_json::to_string(&payment).unwrap();
+        let exp = r#"{"id":"pay_123","amount":100,"card_number":"424242******4242","cvc":"*** alloc::string::String ***","billing_address":"*** alloc::string::String ***"}"#;
+        assert_eq!(got, exp);
+    }
+}
+
+#[test]
+fn tuple_and_unit_structs() {
+    #[derive(Masked)]
+    #[cfg_attr(feature = "serde", masked(serialize))]
+    pub struct Token(#[masked(visible)] u8, Secret<String>, Vec<u8>);
+
+    #[derive(Masked)]
+    #[cfg_attr(feature = "serde", masked(serialize))]
+    pub struct Empty;
+
+    let token = Token(1, Secret::new("secret".to_string()), vec![1, 2, 3]);
+
+    // format
+
+    let got = format!("{token:?}");
+    let exp = "Token(1, *** alloc::string::String ***, *** alloc::vec::Vec<u8> ***)";
+    assert_eq!(got, exp);
+    assert_eq!(format!("{Empty:?}"), "Empty");
+
+    // serialize
+
+    #[cfg(feature = "serde")]
+    {
+        let got = serde_json::to_string(&token).unwrap();
+        let exp = r#"[1,"*** alloc::string::String ***","*** alloc::vec::Vec<u8> ***"]"#;
+        assert_eq!(got, exp);
+        assert_eq!(serde_json::to_string(&Empty).unwrap(), "null");
+    }
+}
+
+#[test]
+fn generics() {
+    #[derive(Masked)]
+    #[cfg_attr(feature = "serde", masked(serialize))]
+    pub struct Wrapper<T, U> {
+        #[masked(visible)]
+        visible: T,
+        masked: U,
+    }
+
+    let wrapper = Wrapper {
+        visible: Some(Secret::<String>::new("secret".to_string())),
+        masked: 42_u32,
+    };
+
+    // format
+
+    let got = format!("{wrapper:?}");
+    let exp = "Wrapper { visible: Some(*** alloc::string::String ***), masked: *** u32 *** }";
+    assert_eq!(got, exp);
+
+    // serialize, with the secrets within the visible fields masked as well
+
+    #[cfg(feature = "serde")]
+    {
+        let got = serde_json::to_string(&wrapper).unwrap();
+        let exp = r#"{"visible":"*** alloc::string::String ***","masked":"*** u32 ***"}"#;
+        assert_eq!(got, exp);
+    }
+}
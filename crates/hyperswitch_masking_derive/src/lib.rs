@@ -0,0 +1,299 @@
+//! Derive macros for the `hyperswitch_masking` crate.
+//!
+//! The macros are re-exported by the `hyperswitch_masking` crate when its `derive` feature is
+//! enabled, and the generated code refers to items of that crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Fields, Generics, Index, LitStr, Member, Type, ext::IdentExt,
+    parse_macro_input, parse_quote,
+};
+
+/// Derives a masked `Debug` implementation for a struct, with the fields masked unless they are
+/// explicitly allow-listed as visible, and optionally a masked `Serialize` implementation.
+///
+/// The fields are formatted as follows:
+///
+/// - Fields annotated with `#[masked(visible)]` are formatted as is.
+/// - Fields of the `Secret` and `StrongSecret` types are formatted as per their masking strategy.
+/// - Other fields are masked along with their type, like the `WithType` masking strategy.
+///
+/// Annotating the struct with `#[masked(serialize)]` additionally derives a `Serialize`
+/// implementation serializing the fields in the same manner, except that the secrets within the
+/// visible fields are masked as well, which requires the `serde` feature of the
+/// `hyperswitch_masking` crate.
+/// The masked `Serialize` implementation is intended for logging, and hence cannot be combined
+/// with a derived `Serialize` implementation.
+///
+/// Refer to the documentation of the `hyperswitch_masking` crate for examples.
+#[proc_macro_derive(Masked, attributes(masked))]
+pub fn masked_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_masked(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// How a field is formatted and serialized.
+#[derive(Clone, Copy, PartialEq)]
+enum FieldKind {
+    /// The field is allow-listed as visible.
+    Visible,
+
+    /// The field is a secret, masked as per its masking strategy.
+    Secret,
+
+    /// The field is masked along with its type.
+    Masked,
+}
+
+/// A field of the struct deriving `Masked`.
+struct MaskedField<'a> {
+    /// The field name or index, for accessing the field.
+    member: Member,
+
+    /// The field name, for named fields.
+    name: Option<LitStr>,
+
+    /// The type of the field.
+    ty: &'a Type,
+
+    /// How the field is formatted and serialized.
+    kind: FieldKind,
+}
+
+fn expand_masked(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Masked` can only be derived for structs",
+        ));
+    };
+
+    let mut serialize = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("masked"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("serialize") {
+                serialize = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported `masked` attribute, expected `serialize`"))
+            }
+        })?;
+    }
+
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let mut visible = false;
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("masked"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("visible") {
+                        visible = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported `masked` attribute, expected `visible`"))
+                    }
+                })?;
+            }
+
+            let kind = if visible {
+                FieldKind::Visible
+            } else if is_secret_type(&field.ty) {
+                FieldKind::Secret
+            } else {
+                FieldKind::Masked
+            };
+            let (member, name) = match &field.ident {
+                Some(ident) => (
+                    Member::Named(ident.clone()),
+                    Some(LitStr::new(&ident.unraw().to_string(), ident.span())),
+                ),
+                None => (Member::Unnamed(Index::from(index)), None),
+            };
+
+            Ok(MaskedField {
+                member,
+                name,
+                ty: &field.ty,
+                kind,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let debug_impl = expand_debug(input, &data.fields, &fields);
+    let serialize_impl = if serialize {
+        expand_serialize(input, &data.fields, &fields)
+    } else {
+        TokenStream2::new()
+    };
+
+    Ok(quote! {
+        #debug_impl
+        #serialize_impl
+    })
+}
+
+/// Generates the masked `Debug` implementation.
+fn expand_debug(
+    input: &DeriveInput,
+    data_fields: &Fields,
+    fields: &[MaskedField<'_>],
+) -> TokenStream2 {
+    let ident = &input.ident;
+    let ident_str = LitStr::new(&ident.unraw().to_string(), ident.span());
+    let generics = with_field_bounds(&input.generics, fields, |kind| {
+        matches!(kind, FieldKind::Visible | FieldKind::Secret).then(|| quote!(::core::fmt::Debug))
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let values = fields.iter().map(|field| {
+        let member = &field.member;
+        match field.kind {
+            FieldKind::Visible | FieldKind::Secret => quote!(&self.#member),
+            FieldKind::Masked => {
+                quote!(&::hyperswitch_masking::__private::MaskedField(&self.#member))
+            }
+        }
+    });
+    let body = match data_fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.name);
+            quote! {
+                f.debug_struct(#ident_str)
+                    #(.field(#names, #values))*
+                    .finish()
+            }
+        }
+        Fields::Unnamed(_) => quote! {
+            f.debug_tuple(#ident_str)
+                #(.field(#values))*
+                .finish()
+        },
+        Fields::Unit => quote!(f.write_str(#ident_str)),
+    };
+
+    // Not marked as `#[automatically_derived]`, since the fields read by the implementation would
+    // then be reported as never read, unlike those read by the hand-written implementations this
+    // replaces
+    quote! {
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    }
+}
+
+/// Generates the masked `Serialize` implementation.
+fn expand_serialize(
+    input: &DeriveInput,
+    data_fields: &Fields,
+    fields: &[MaskedField<'_>],
+) -> TokenStream2 {
+    let ident = &input.ident;
+    let ident_str = LitStr::new(&ident.unraw().to_string(), ident.span());
+    let generics = with_field_bounds(&input.generics, fields, |kind| match kind {
+        FieldKind::Visible => Some(quote!(::hyperswitch_masking::__private::serde::Serialize)),
+        FieldKind::Secret => Some(quote!(::core::fmt::Debug)),
+        FieldKind::Masked => None,
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let len = fields.len();
+    let values = fields.iter().map(|field| {
+        let member = &field.member;
+        match field.kind {
+            FieldKind::Visible => {
+                quote!(&::hyperswitch_masking::__private::MaskedSerialize(&self.#member))
+            }
+            FieldKind::Secret => {
+                quote!(&::hyperswitch_masking::__private::DebugString(&self.#member))
+            }
+            FieldKind::Masked => quote! {
+                &::hyperswitch_masking::__private::DebugString(
+                    &::hyperswitch_masking::__private::MaskedField(&self.#member),
+                )
+            },
+        }
+    });
+    let body = match data_fields {
+        Fields::Named(_) => {
+            let names = fields.iter().map(|field| &field.name);
+            quote! {
+                use ::hyperswitch_masking::__private::serde::ser::SerializeStruct as _;
+
+                let mut state = serializer.serialize_struct(#ident_str, #len)?;
+                #(state.serialize_field(#names, #values)?;)*
+                state.end()
+            }
+        }
+        Fields::Unnamed(_) => quote! {
+            use ::hyperswitch_masking::__private::serde::ser::SerializeTupleStruct as _;
+
+            let mut state = serializer.serialize_tuple_struct(#ident_str, #len)?;
+            #(state.serialize_field(#values)?;)*
+            state.end()
+        },
+        Fields::Unit => quote!(serializer.serialize_unit_struct(#ident_str)),
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics ::hyperswitch_masking::__private::serde::Serialize
+            for #ident #ty_generics #where_clause
+        {
+            fn serialize<__S>(&self, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+            where
+                __S: ::hyperswitch_masking::__private::serde::Serializer,
+            {
+                #body
+            }
+        }
+    }
+}
+
+/// Returns the generics of the struct, with the bounds returned by `bound` for the kind of each
+/// field added for the type of the field.
+fn with_field_bounds(
+    generics: &Generics,
+    fields: &[MaskedField<'_>],
+    bound: impl Fn(FieldKind) -> Option<TokenStream2>,
+) -> Generics {
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    for field in fields {
+        if let Some(bound) = bound(field.kind) {
+            let ty = field.ty;
+            where_clause.predicates.push(parse_quote!(#ty: #bound));
+        }
+    }
+    generics
+}
+
+/// Returns `true` if the type is a `Secret` or a `StrongSecret`, going by the last segment of the
+/// path of the type, since the type cannot be resolved by the macro.
+fn is_secret_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Secret" || segment.ident == "StrongSecret"),
+        Type::Group(group) => is_secret_type(&group.elem),
+        Type::Paren(paren) => is_secret_type(&paren.elem),
+        _ => false,
+    }
+}
@@ -3,7 +3,18 @@
 //! When the `tracing` feature is enabled, this crate provides:
 //!
 //! - A [`JsonFormattingLayer`] for customizable JSON log output.
-//! - A [`SpanStorageLayer`] layer to capture span data.
+//! - A [`HumanReadableFormattingLayer`] for customizable human-readable log output.
+//! - A [`SpanStorageLayer`] layer to capture span data, along with [`current_span_fields`] for
+//!   reading the captured fields of the current span, and [`record_on_current_span`] for
+//!   recording fields on the current span without declaring them upfront.
+//! - A [`RoutingLayer`] to dispatch records to different sinks based on the value of a field.
+//! - An [`AlertLayer`] to notify applications of events at or above a configured level.
+//! - At-rest encryption of log files, along with a [`DecryptingReader`] for reading the encrypted
+//!   log files.
+//! - Tamper-evident hash chaining of file log records, along with [`verify_hash_chain`] for
+//!   verifying the integrity of hash-chained log files.
+//! - A guaranteed-delivery audit log sink, along with the [`audit!`] macro for emitting audit
+//!   events.
 //! - A central [`build_logging_components`] function to construct logging layers and guards,
 //!   based on the specified configuration.
 //!
@@ -14,6 +25,45 @@
 //! # Features
 //!
 //! - `tracing` - Enables `tracing`-based logging infrastructure (disabled by default)
+//! - `axum` - Enables the [`axum`] module, providing integration with the
+//!   [`axum`](https://docs.rs/axum) web framework (disabled by default)
+//! - `build_info` - Enables the [`log_startup_info!`] macro for emitting a startup record carrying
+//!   the build information of the application, obtained using the
+//!   [`build_info`](https://docs.rs/build_info) crate (disabled by default)
+//! - `diesel` - Enables the [`sql::DieselInstrumentation`] for logging the queries executed by
+//!   [`diesel`](https://diesel.rs) connections (disabled by default)
+//! - `encryption` - Enables at-rest encryption of log files using AES-256-GCM, configured using
+//!   [`FileLoggingConfig::encryption`], along with the [`DecryptingReader`] for reading the
+//!   encrypted log files (disabled by default)
+//! - `hash-chain` - Enables tamper-evident hash chaining of file log records, configured using
+//!   [`FileLoggingConfig::hash_chain`], along with [`verify_hash_chain`] for verifying the
+//!   integrity of hash-chained log files (disabled by default)
+//! - `masking` - Enables [`masked`] for recording values holding
+//!   [`hyperswitch_masking`](https://docs.rs/hyperswitch_masking) secrets as fields of the records
+//!   with the secrets masked, and masks the values of fields with reserved keys in the records
+//!   logged when such fields are skipped, logging those records as errors in debug builds if the
+//!   values came from masked types (disabled by default)
+//! - `metrics` - Enables the [`LogMetricsLayer`] and [`SpanLatencyMetricsLayer`] for recording log
+//!   volume and span latency metrics using the [`metrics`](https://docs.rs/metrics) crate
+//!   (disabled by default)
+//! - `opentelemetry` - Enables including [OpenTelemetry](https://opentelemetry.io) baggage entries
+//!   as fields of the records, configured using [`LoggerConfig::baggage_keys`] (disabled by
+//!   default)
+//! - `test-support` - Enables the [`test_support`] module, providing helpers for snapshot testing
+//!   of the JSON log output, along with [`init_for_tests`] for initializing logging in tests
+//!   (disabled by default)
+//! - `tokio` - Enables [`spawn_instrumented`] and [`spawn_blocking_instrumented`] for spawning
+//!   [`tokio`](https://tokio.rs) tasks within named spans, logging panics of the spawned tasks
+//!   (disabled by default)
+//! - `tokio-console` - Enables the [`tokio-console`](https://github.com/tokio-rs/console)
+//!   instrumentation layer, configured using [`TokioConsoleConfig`] (disabled by default)
+//! - `tonic` - Enables the [`tonic`] module, providing integration with
+//!   [`tonic`](https://docs.rs/tonic) gRPC clients and servers (disabled by default)
+//! - `tower` - Enables the [`tower`] module, providing a middleware for logging HTTP requests
+//!   (disabled by default)
+//! - `wasm` - Enables support for `wasm32-unknown-unknown` targets, where the console sink writes
+//!   to the browser console using the [`BrowserConsoleWriter`], and the `hostname` and `pid`
+//!   fields are omitted (disabled by default)
 //!
 //! # Example
 //!
@@ -63,23 +113,28 @@
 //!         level: Level::INFO,
 //!         filtering_directive: Some("my_app=info,warn".to_string()),
 //!         print_filtering_directive: DirectivePrintTarget::Stdout,
+//!         file_mode: Some(0o640),
+//!         ..Default::default()
 //!     }),
 //!     console_config: Some(ConsoleLoggingConfig {
 //!         level: Level::DEBUG,
 //!         log_format: ConsoleLogFormat::HumanReadable,
 //!         filtering_directive: Some("my_app=debug,info".to_string()),
 //!         print_filtering_directive: DirectivePrintTarget::Stdout,
+//!         ..Default::default()
 //!     }),
 //!     global_filtering_directive: Some("info".to_string()),
+//!     ..Default::default()
 //! };
 //!
 //! match build_logging_components(config) {
 //!     Ok(components) => {
+//!         let storage_layer = components.filtered_storage_layer();
 //!         let _guards = components.guards; // Keep guards in scope
 //!
 //!         // Build the subscriber with all components
 //!         let mut layers = Vec::new();
-//!         layers.push(components.storage_layer.boxed());
+//!         layers.push(storage_layer.boxed());
 //!
 //!         if let Some(file_layer) = components.file_log_layer {
 //!             layers.push(file_layer);
@@ -87,6 +142,21 @@
 //!         if let Some(console_layer) = components.console_log_layer {
 //!             layers.push(console_layer);
 //!         }
+//!         if let Some(alert_layer) = components.alert_layer {
+//!             layers.push(alert_layer.boxed());
+//!         }
+//!         if let Some(sampling_layer) = components.sampling_layer {
+//!             layers.push(sampling_layer.boxed());
+//!         }
+//!         if let Some(audit_layer) = components.audit_log_layer {
+//!             layers.push(audit_layer);
+//!         }
+//!         if let Some(profiling_layer) = components.profiling_layer {
+//!             layers.push(profiling_layer);
+//!         }
+//!         if let Some(chrome_trace_layer) = components.chrome_trace_layer {
+//!             layers.push(chrome_trace_layer.boxed());
+//!         }
 //!
 //!         // Initialize the global subscriber
 //!         tracing_subscriber::registry().with(layers).init();
@@ -103,10 +173,58 @@
 #[cfg(feature = "tracing")]
 mod tracing;
 
+#[cfg(feature = "encryption")]
+pub use self::tracing::DecryptingReader;
+#[cfg(feature = "axum")]
+pub use self::tracing::axum;
+#[cfg(feature = "tracing")]
+pub use self::tracing::sql;
+#[cfg(feature = "test-support")]
+pub use self::tracing::test_support::{self, init_for_tests};
+#[cfg(feature = "tonic")]
+pub use self::tracing::tonic;
+#[cfg(feature = "tower")]
+pub use self::tracing::tower;
 #[cfg(feature = "tracing")]
 pub use self::tracing::{
-    AdditionalFieldsPlacement, ConsoleLogFormat, ConsoleLoggingConfig, DirectivePrintTarget,
-    FileLoggingConfig, JsonFormattingLayer, JsonFormattingLayerConfig, Level, LoggerConfig,
-    LoggerError, LoggingComponents, RecordType, Rotation, SpanStorageLayer,
-    build_logging_components,
+    AUDIT_KEY, AdditionalFieldsPlacement, AlertCallback, AlertConfig, AlertHook, AlertLayer,
+    AlertRecord, AuditLoggingConfig, ChromeTraceCapture, ChromeTraceConfig, ChromeTraceLayer,
+    CodeLocationFields, ConsoleLogFormat, ConsoleLoggingConfig, Directive, DirectiveCallback,
+    DirectivePrintTarget, DirectiveSink, ENCRYPTION_KEY_LENGTH, EncryptionKeyCallback,
+    EncryptionKeySource, ErrorCodeRegistry, ErrorStats, FieldOverwritePolicies,
+    FieldOverwritePolicy, FileEncryptionConfig, FileLoggingConfig, FileSinkFallback,
+    FileSinkHealth, FileWriteErrorPolicy, FoldedStackLayer, FoldedStackProfiling,
+    FoldedStackProfilingConfig, HumanReadableFormattingLayer, HumanReadableOptions,
+    JsonFormattingLayer, JsonFormattingLayerConfig, LOG_SCHEMA_VERSION, Level, LevelFilter,
+    LoggerConfig, LoggerError, LoggingComponents, PersistentKeyPropagation, PersistentKeys,
+    RecordCounts, RecordType, Rotation, RoutingLayer, SamplingConfig, SamplingLayer,
+    SpanFieldInheritance, SpanLifecycleLogging, SpanStorageLayer, SpanStorageLimits,
+    TimestampFormat, TimestampTimezone, TokioConsoleConfig, build_logging_components,
+    current_span_fields, init_from_env, parse_filtering_directive, record_all_on_current_span,
+    record_on_current_span, workspace_filtering_directive,
 };
+#[cfg(feature = "wasm")]
+pub use self::tracing::{BrowserConsoleRecordWriter, BrowserConsoleWriter};
+#[cfg(feature = "hash-chain")]
+pub use self::tracing::{
+    GENESIS_HASH, HashChainBoundary, HashChainError, HashChainSummary, verify_hash_chain,
+};
+#[cfg(feature = "metrics")]
+pub use self::tracing::{
+    LOG_BYTES_WRITTEN_TOTAL, LOG_RECORDS_TOTAL, LogMetricsLayer, SPAN_DURATION_SECONDS,
+    SpanLatencyMetricsLayer,
+};
+#[cfg(feature = "masking")]
+pub use self::tracing::{MaskedValue, masked};
+#[cfg(feature = "tokio")]
+pub use self::tracing::{spawn_blocking_instrumented, spawn_instrumented};
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "build_info")]
+    pub use build_info;
+    pub use tracing;
+
+    pub use crate::tracing::is_reserved_key;
+}
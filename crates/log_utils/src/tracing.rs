@@ -2,26 +2,113 @@
 //!
 //! This module is only available when the `tracing` feature is enabled.
 
+mod alert;
+mod audit;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "opentelemetry")]
+mod baggage;
+#[cfg(feature = "wasm")]
+mod browser_console;
+mod chrome_trace;
+#[cfg(not(target_arch = "wasm32"))]
+mod container;
+mod encryption;
+mod error_stats;
+mod fallback;
 mod formatter;
+mod from_env;
+#[cfg(feature = "hash-chain")]
+mod hash_chain;
+mod human_readable;
+#[cfg(feature = "metrics")]
+mod log_metrics;
+#[cfg(feature = "masking")]
+mod masking;
+mod memory;
+#[cfg(unix)]
+mod permissions;
+mod profiling;
+mod routing;
+mod sampling;
+mod shutdown;
+pub mod sql;
+#[cfg(feature = "build_info")]
+mod startup;
 mod storage;
+mod structured;
+#[cfg(feature = "tokio")]
+mod task;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+mod tokio_console;
+#[cfg(feature = "tonic")]
+pub mod tonic;
+#[cfg(feature = "tower")]
+pub mod tower;
 
 use std::collections::{HashMap, HashSet};
 
 use serde_json::Value;
-pub use tracing::Level;
+pub use tracing::{Level, level_filters::LevelFilter};
 pub use tracing_appender::rolling::Rotation;
-use tracing_subscriber::{EnvFilter, Layer};
+pub use tracing_subscriber::filter::Directive;
+use tracing_subscriber::{
+    EnvFilter, Layer,
+    filter::{Filtered, filter_fn},
+};
 
+#[cfg(feature = "wasm")]
+pub use self::browser_console::{BrowserConsoleRecordWriter, BrowserConsoleWriter};
+#[cfg(feature = "encryption")]
+pub use self::encryption::DecryptingReader;
+#[cfg(feature = "hash-chain")]
+pub use self::hash_chain::{
+    GENESIS_HASH, HashChainBoundary, HashChainError, HashChainSummary, verify_hash_chain,
+};
+#[cfg(feature = "metrics")]
+pub use self::log_metrics::{
+    LOG_BYTES_WRITTEN_TOTAL, LOG_RECORDS_TOTAL, LogMetricsLayer, SPAN_DURATION_SECONDS,
+    SpanLatencyMetricsLayer,
+};
+#[cfg(feature = "masking")]
+pub use self::masking::{MaskedValue, masked};
+#[cfg(feature = "tokio")]
+pub use self::task::{spawn_blocking_instrumented, spawn_instrumented};
 pub use self::{
-    formatter::{JsonFormattingLayer, JsonFormattingLayerConfig, RecordType},
-    storage::SpanStorageLayer,
+    alert::{AlertCallback, AlertConfig, AlertHook, AlertLayer, AlertRecord},
+    audit::{AUDIT_KEY, AuditLoggingConfig},
+    chrome_trace::{ChromeTraceCapture, ChromeTraceConfig, ChromeTraceLayer},
+    encryption::{
+        ENCRYPTION_KEY_LENGTH, EncryptionKeyCallback, EncryptionKeySource, FileEncryptionConfig,
+    },
+    error_stats::ErrorStats,
+    fallback::{FileSinkFallback, FileSinkHealth, FileWriteErrorPolicy},
+    formatter::{
+        JsonFormattingLayer, JsonFormattingLayerConfig, LOG_SCHEMA_VERSION, RecordType,
+        SpanLifecycleLogging, TimestampFormat, TimestampTimezone,
+    },
+    from_env::init_from_env,
+    human_readable::{HumanReadableFormattingLayer, HumanReadableOptions},
+    profiling::{FoldedStackLayer, FoldedStackProfiling, FoldedStackProfilingConfig},
+    routing::RoutingLayer,
+    sampling::{SamplingConfig, SamplingLayer},
+    shutdown::RecordCounts,
+    storage::{
+        FieldOverwritePolicies, FieldOverwritePolicy, PersistentKeyPropagation, PersistentKeys,
+        SpanFieldInheritance, SpanStorageLayer, SpanStorageLimits, current_span_fields,
+        record_all_on_current_span, record_on_current_span,
+    },
+    structured::{ErrorCodeRegistry, is_reserved_key},
+    tokio_console::TokioConsoleConfig,
 };
 
 mod keys {
-    use std::sync::LazyLock;
+    use std::sync::{Arc, LazyLock};
 
     use rustc_hash::FxHashSet;
 
+    pub(crate) const SCHEMA_VERSION: &str = "log_schema_version";
     pub(crate) const MESSAGE: &str = "message";
     pub(crate) const LEVEL: &str = "level";
     pub(crate) const TARGET: &str = "target";
@@ -30,22 +117,83 @@ mod keys {
     pub(crate) const TIME: &str = "time";
     pub(crate) const HOSTNAME: &str = "hostname";
     pub(crate) const PID: &str = "pid";
+    pub(crate) const POD_NAME: &str = "pod_name";
+    pub(crate) const NODE_NAME: &str = "node_name";
+    pub(crate) const CONTAINER_ID: &str = "container_id";
     pub(crate) const FN: &str = "fn";
     pub(crate) const FULL_NAME: &str = "full_name";
     pub(crate) const ELAPSED_MILLISECONDS: &str = "elapsed_milliseconds";
+    pub(crate) const RECORD_HASH: &str = "record_hash";
+    pub(crate) const REQUEST_SUMMARY: &str = "request_summary";
+    #[cfg(any(feature = "axum", feature = "tonic"))]
+    pub(crate) const REQUEST_ID: &str = "request_id";
+    pub(crate) const DROPPED_FIELDS: &str = "dropped_fields";
+    pub(crate) const BUSY_MILLISECONDS: &str = "busy_ms";
+    pub(crate) const IDLE_MILLISECONDS: &str = "idle_ms";
+    pub(crate) const EVENT_COUNT: &str = "event_count";
+    pub(crate) const ERROR_EVENT_COUNT: &str = "error_event_count";
+    pub(crate) const CHILD_SPAN_COUNT: &str = "child_span_count";
+    pub(crate) const RSS_BYTES: &str = "rss_bytes";
+
+    /// The keys set by the formatter for every record, which cannot be logged via events or spans.
+    pub(crate) const IMPLICIT_KEY_LIST: &[&str] = &[
+        SCHEMA_VERSION,
+        MESSAGE,
+        LEVEL,
+        TARGET,
+        LINE,
+        FILE,
+        TIME,
+        HOSTNAME,
+        PID,
+        FN,
+        FULL_NAME,
+    ];
+
+    /// The keys of the implicit fields included only if environment enrichment is enabled.
+    pub(crate) const ENVIRONMENT_ENRICHMENT_KEY_LIST: &[&str] =
+        &[POD_NAME, NODE_NAME, CONTAINER_ID];
+
+    pub(crate) static IMPLICIT_KEYS: LazyLock<FxHashSet<&'static str>> =
+        LazyLock::new(|| IMPLICIT_KEY_LIST.iter().copied().collect());
+
+    /// The keys reserved by a layer, which cannot be logged via events or spans: the
+    /// [`IMPLICIT_KEYS`], along with the keys of the optional implicit fields included by the
+    /// layer, such as the `pod_name` field if environment enrichment is enabled.
+    #[derive(Clone, Debug, Default)]
+    pub(crate) struct ReservedKeys(Arc<FxHashSet<&'static str>>);
+
+    impl ReservedKeys {
+        /// Reserves the keys of the optional implicit fields, in addition to the existing keys.
+        pub(crate) fn with_keys(self, keys: impl IntoIterator<Item = &'static str>) -> Self {
+            let mut optional_keys = Arc::unwrap_or_clone(self.0);
+            optional_keys.extend(keys);
+            Self(Arc::new(optional_keys))
+        }
 
-    pub(crate) static IMPLICIT_KEYS: LazyLock<FxHashSet<&'static str>> = LazyLock::new(|| {
-        [
-            MESSAGE, LEVEL, TARGET, LINE, FILE, TIME, HOSTNAME, PID, FN, FULL_NAME,
-        ]
-        .iter()
-        .copied()
-        .collect()
-    });
+        /// Checks whether the key is reserved.
+        pub(crate) fn contains(&self, key: &str) -> bool {
+            IMPLICIT_KEYS.contains(key) || self.0.contains(key)
+        }
+    }
 }
 
 /// Comprehensive configuration for the entire logging system.
-#[derive(Debug, Clone)]
+///
+/// The [`Default`] configuration has all the sinks and optional features disabled, so that only
+/// the fields of interest need to be specified, such as:
+///
+/// ```
+/// use log_utils::{ConsoleLoggingConfig, LoggerConfig};
+///
+/// let config = LoggerConfig {
+///     console_config: Some(ConsoleLoggingConfig::default()),
+///     global_filtering_directive: Some("info,my_app=debug".to_string()),
+///     ..Default::default()
+/// };
+/// # drop(config);
+/// ```
+#[derive(Debug, Clone, Default)]
 pub struct LoggerConfig {
     /// A map of key-value pairs that are statically defined at initialization and included
     /// at the top level of every log entry.
@@ -59,21 +207,128 @@ pub struct LoggerConfig {
     /// if they set in the current span.
     /// This is useful for capturing context that should be visible in parent spans,
     /// such as merchant IDs, user IDs, etc.
+    /// Keys can also be added at runtime through [`SpanStorageLayer::persistent_keys`].
     pub persistent_keys: HashSet<&'static str>,
 
+    /// Specifies which fields of a parent span are inherited by its child spans.
+    /// Refer to [`SpanStorageLayer::with_field_inheritance`] for more details.
+    pub span_field_inheritance: SpanFieldInheritance,
+
+    /// Specifies which ancestors of a span the values of persistent keys are propagated to, when
+    /// the span is closed.
+    pub persistent_key_propagation: PersistentKeyPropagation,
+
+    /// Limits on the values held in the storage of each span, if any.
+    /// Refer to [`SpanStorageLimits`] for more details.
+    pub span_storage_limits: Option<SpanStorageLimits>,
+
+    /// Specifies how fields that are recorded multiple times for a span are handled, if the
+    /// latest value is not to replace the existing value.
+    /// Refer to [`FieldOverwritePolicies`] for more details.
+    pub field_overwrite_policies: Option<FieldOverwritePolicies>,
+
+    /// Keys of the OpenTelemetry baggage entries to be included as top-level fields in the
+    /// records of each span and its events, which requires the `opentelemetry` feature to be
+    /// enabled.
+    /// Refer to [`SpanStorageLayer::with_baggage_keys`] for more details.
+    pub baggage_keys: HashSet<&'static str>,
+
     /// If `true`, logs all span entries and exits.
     /// If `false`, does not log span entries and only logs exits for root spans.
+    /// This can be changed at runtime using the
+    /// [`LoggingComponents::span_lifecycle_logging`] handle.
     pub log_span_lifecycles: bool,
 
+    /// If specified, the exits of spans open for longer than this threshold are logged even if
+    /// [`log_span_lifecycles`][Self::log_span_lifecycles] is `false`.
+    /// This can be changed at runtime using the
+    /// [`LoggingComponents::span_lifecycle_logging`] handle.
+    pub slow_span_threshold: Option<std::time::Duration>,
+
+    /// If `true`, the exit record of each root span carries a `request_summary` field,
+    /// aggregating the counts of events by level, the number of child spans and the slowest
+    /// child span within the root span.
+    /// Along with the persistent keys propagated to the root span, this provides a single
+    /// summary record for each request.
+    pub request_summary: bool,
+
+    /// If `true`, the exit record of each span carries `busy_ms` and `idle_ms` fields, with the
+    /// time the span was entered, and the time it was idle, respectively.
+    /// Refer to [`SpanStorageLayer::with_busy_idle_time`] for more details.
+    pub span_busy_idle_time: bool,
+
+    /// If `true`, the exit record of each span carries `event_count`, `error_event_count` and
+    /// `child_span_count` fields, with the counts of events and spans within the span.
+    /// Refer to [`SpanStorageLayer::with_span_counters`] for more details.
+    pub span_counters: bool,
+
+    /// If `true`, the exit record of each span carries the `rss_bytes` field, with the resident
+    /// set size of the process when the span was closed.
+    /// Refer to [`SpanStorageLayer::with_memory_usage`] for more details.
+    pub span_memory_usage: bool,
+
     /// Specifies how additional fields (not designated as top-level) are placed in the JSON output.
     pub additional_fields_placement: AdditionalFieldsPlacement,
 
+    /// If `true`, fields with null values (such as the `line` and `file` fields when the source
+    /// code location is unavailable), empty objects and empty arrays are omitted from the JSON
+    /// records.
+    pub omit_empty_fields: bool,
+
+    /// Specifies which JSON records include the code location fields (`file`, `line`, `fn` and
+    /// `full_name`), which may be omitted in release deployments to reduce the size of records.
+    pub code_location_fields: CodeLocationFields,
+
+    /// Specifies the format of the `time` field of the JSON records, such as an ISO 8601 string
+    /// with a lower precision than the default nanosecond precision.
+    pub timestamp_format: TimestampFormat,
+
+    /// Specifies the time zone of the `time` field of the JSON records, such as a fixed offset for
+    /// teams working in a single time zone.
+    pub timestamp_timezone: TimestampTimezone,
+
+    /// Overrides the `hostname` field of the JSON records.
+    /// If `None`, the hostname of the machine is used.
+    pub hostname: Option<String>,
+
+    /// If `true`, the JSON records carry the `pod_name` and `node_name` fields, read from the
+    /// `POD_NAME` (or `HOSTNAME`) and `NODE_NAME` environment variables respectively, as
+    /// typically exposed to containers running on Kubernetes, along with the `container_id`
+    /// field, detected from the cgroup of the process.
+    /// Fields whose values are unavailable are omitted.
+    ///
+    /// The keys of these fields are reserved only if enrichment is enabled.
+    pub environment_enrichment: bool,
+
     /// Configuration for file logging. If `None`, file logging is disabled.
     pub file_config: Option<FileLoggingConfig>,
 
     /// Configuration for console logging. If `None`, console logging is disabled.
     pub console_config: Option<ConsoleLoggingConfig>,
 
+    /// Configuration for alerting on events at or above a specified level.
+    /// If `None`, alerting is disabled.
+    pub alert_config: Option<AlertConfig>,
+
+    /// Configuration for audit logging. If `None`, audit logging is disabled.
+    pub audit_config: Option<AuditLoggingConfig>,
+
+    /// Configuration for the `tokio-console` instrumentation layer, which requires the
+    /// `tokio-console` feature to be enabled. If `None`, the layer is disabled.
+    pub tokio_console_config: Option<TokioConsoleConfig>,
+
+    /// Configuration for sampling events, with per-target sampling rates.
+    /// If `None`, all events are retained.
+    pub sampling_config: Option<SamplingConfig>,
+
+    /// Configuration for writing the timings of spans to a file in the folded stack format, for
+    /// rendering flame graphs. If `None`, span timings are not written.
+    pub profiling_config: Option<FoldedStackProfilingConfig>,
+
+    /// Configuration for capturing the entries and exits of spans in the Chrome `trace_event`
+    /// format, for visual latency analysis. If `None`, captures are not available.
+    pub chrome_trace_config: Option<ChromeTraceConfig>,
+
     /// A global [`EnvFilter`] directive (e.g., `"info,my_crate=debug"`) for filtering log events.
     /// This directive may be overridden by specific directives in
     /// [`FileLoggingConfig`] or [`ConsoleLoggingConfig`].
@@ -106,6 +361,69 @@ pub struct FileLoggingConfig {
 
     /// Specifies where to print the effective filtering directive for file logs.
     pub print_filtering_directive: DirectivePrintTarget,
+
+    /// Configuration for encrypting log files at rest, which requires the `encryption` feature to
+    /// be enabled. If `None`, log files are written in cleartext.
+    /// Encrypted log files can be read using the [`DecryptingReader`].
+    pub encryption: Option<FileEncryptionConfig>,
+
+    /// If `true`, each record carries a `record_hash` field, chaining it to the previous record
+    /// written to the file, so that modification or removal of records can be detected using
+    /// [`verify_hash_chain`], which requires the `hash-chain` feature to be enabled.
+    /// The record hash is computed before the record is encrypted, if encryption is enabled.
+    /// The `record_hash` key is reserved only if hash chaining is enabled.
+    pub hash_chain: bool,
+
+    /// Specifies what happens if the log file cannot be created, such as when the log directory
+    /// cannot be created or is not writable.
+    /// When the failure is recovered from, a `WARN` diagnostic record describing the failure is
+    /// written before the first file log record.
+    pub fallback: FileSinkFallback,
+
+    /// Specifies what happens if writing a record to the log file fails, such as when the disk
+    /// is full.
+    /// The write errors are reported by the [`LoggingComponents::file_sink_health`] handle.
+    pub write_error_policy: FileWriteErrorPolicy,
+
+    /// The permission bits (such as `0o640`) applied to the log files.
+    /// If `None`, the log files are created with the default permissions of the process.
+    /// Only supported on Unix platforms.
+    pub file_mode: Option<u32>,
+
+    /// The user ID of the owner applied to the log files, which typically requires the process
+    /// to run with elevated privileges.
+    /// If `None`, the owner is left unchanged.
+    /// Only supported on Unix platforms.
+    pub file_owner: Option<u32>,
+
+    /// The group ID applied to the log files, which requires the process to be the owner of the
+    /// files and a member of the group, or to run with elevated privileges.
+    /// If `None`, the group is left unchanged.
+    /// Only supported on Unix platforms.
+    pub file_group: Option<u32>,
+}
+
+impl Default for FileLoggingConfig {
+    /// Returns the configuration for writing daily rotated `INFO` logs to the `logs` directory,
+    /// in files named after the executable, with the other options disabled.
+    fn default() -> Self {
+        Self {
+            directory: "logs".to_string(),
+            file_name_prefix: from_env::executable_name(),
+            file_rotation: Rotation::DAILY,
+            max_log_files: None,
+            level: Level::INFO,
+            filtering_directive: None,
+            print_filtering_directive: DirectivePrintTarget::None,
+            encryption: None,
+            hash_chain: false,
+            fallback: FileSinkFallback::default(),
+            write_error_policy: FileWriteErrorPolicy::default(),
+            file_mode: None,
+            file_owner: None,
+            file_group: None,
+        }
+    }
 }
 
 /// Configuration for console logging.
@@ -117,16 +435,45 @@ pub struct ConsoleLoggingConfig {
     /// Output format for console logs.
     pub log_format: ConsoleLogFormat,
 
+    /// Options for customizing console logs when using the
+    /// [`HumanReadable`][ConsoleLogFormat::HumanReadable] or [`Compact`][ConsoleLogFormat::Compact]
+    /// formats.
+    /// Ignored for other formats.
+    pub human_readable_options: HumanReadableOptions,
+
     /// [`EnvFilter`] directive specific to console logs, overriding the global filtering directive.
     /// If `None`, the global filtering directive is used.
     pub filtering_directive: Option<String>,
 
     /// Specifies where to print the effective filtering directive for console logs.
     pub print_filtering_directive: DirectivePrintTarget,
+
+    /// If `true`, `WARN` and `ERROR` records are written to stderr, while records at lower
+    /// levels (and span records) are written to stdout.
+    /// If `false`, all records are written to stdout.
+    /// Ignored on `wasm32` targets, where records are written to the browser console.
+    pub route_warnings_to_stderr: bool,
+}
+
+impl Default for ConsoleLoggingConfig {
+    /// Returns the configuration for writing `INFO` logs to stdout in the compact JSON format.
+    fn default() -> Self {
+        Self {
+            level: Level::INFO,
+            log_format: ConsoleLogFormat::CompactJson,
+            human_readable_options: HumanReadableOptions::default(),
+            filtering_directive: None,
+            print_filtering_directive: DirectivePrintTarget::None,
+            route_warnings_to_stderr: false,
+        }
+    }
 }
 
+/// A callback invoked with the sink and the effective filtering directive for that sink.
+pub type DirectiveCallback = std::sync::Arc<dyn Fn(DirectiveSink, &str) + Send + Sync>;
+
 /// Specifies where (if at all) to print the effective filtering directive during logger setup.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone)]
 pub enum DirectivePrintTarget {
     /// Print to standard output.
     Stdout,
@@ -134,16 +481,102 @@ pub enum DirectivePrintTarget {
     /// Print to standard error.
     Stderr,
 
+    /// Invoke the callback with the sink and the effective filtering directive, allowing
+    /// applications to capture the directive programmatically (e.g., to expose it on a debug
+    /// endpoint).
+    Callback(DirectiveCallback),
+
     /// Do not print the directive.
     None,
 }
 
+impl DirectivePrintTarget {
+    /// Prints the effective filtering directive for the specified sink to this target.
+    fn print(&self, sink: DirectiveSink, directive: &str) {
+        match self {
+            #[expect(clippy::print_stdout)]
+            Self::Stdout => {
+                println!(
+                    "[INFO] {}: Using {sink} filtering directive: {directive}",
+                    env!("CARGO_PKG_NAME")
+                );
+            }
+            #[expect(clippy::print_stderr)]
+            Self::Stderr => {
+                eprintln!(
+                    "[INFO] {}: Using {sink} filtering directive: {directive}",
+                    env!("CARGO_PKG_NAME")
+                );
+            }
+            Self::Callback(callback) => callback(sink, directive),
+            Self::None => (), // Do nothing
+        }
+    }
+}
+
+impl std::fmt::Debug for DirectivePrintTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stdout => f.write_str("Stdout"),
+            Self::Stderr => f.write_str("Stderr"),
+            Self::Callback(_) => f.debug_tuple("Callback").finish_non_exhaustive(),
+            Self::None => f.write_str("None"),
+        }
+    }
+}
+
+impl PartialEq for DirectivePrintTarget {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Callback(this), Self::Callback(other)) => std::sync::Arc::ptr_eq(this, other),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for DirectivePrintTarget {}
+
+/// Identifies the sink (or the global configuration) that a filtering directive applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveSink {
+    /// The global filtering directive, applicable to all sinks.
+    Global,
+
+    /// The filtering directive specific to file logs.
+    File,
+
+    /// The filtering directive specific to console logs.
+    Console,
+}
+
+impl DirectiveSink {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::File => "file",
+            Self::Console => "console",
+        }
+    }
+}
+
+impl std::fmt::Display for DirectiveSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Defines the output format for console logging.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConsoleLogFormat {
-    /// Pretty-printed, human-readable, multi-line format.
+    /// Human-readable, multi-line text format, optionally with ANSI colors.
+    /// Refer to [`HumanReadableOptions`] for the available customizations.
     HumanReadable,
 
+    /// Compact, single-line text format of the form `LEVEL target message key=value ...`, with
+    /// long field values truncated.
+    /// Refer to [`HumanReadableOptions`] for the available customizations.
+    Compact,
+
     /// Compact, single-line JSON format.
     CompactJson,
 
@@ -152,9 +585,10 @@ pub enum ConsoleLogFormat {
 }
 
 /// Defines how additional (non-top-level, non-implicit) fields are placed in the JSON log output.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum AdditionalFieldsPlacement {
     /// Log all additional fields at the top level of the JSON object.
+    #[default]
     TopLevel,
 
     /// Nest all additional fields under the specified key.
@@ -167,25 +601,155 @@ impl AdditionalFieldsPlacement {
     }
 }
 
+/// Defines which JSON records include the code location fields (`file`, `line`, `fn` and
+/// `full_name`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodeLocationFields {
+    /// Include the code location fields in all records.
+    #[default]
+    Always,
+
+    /// Omit the code location fields from all records.
+    Never,
+
+    /// Include the code location fields only in records at the specified level or a more severe
+    /// level, such as only in `WARN` and `ERROR` records for [`Level::WARN`].
+    AtOrAbove(Level),
+}
+
+impl CodeLocationFields {
+    /// Returns `true` if the code location fields are to be included in records at the
+    /// specified level.
+    pub(crate) fn includes(self, level: Level) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // More severe levels compare as lesser levels
+            Self::AtOrAbove(min_level) => level <= min_level,
+        }
+    }
+}
+
 /// Holds the constructed logging layers and their associated worker guards.
 /// These components can be combined with other layers and a [`tracing_subscriber::Registry`]
 /// before initializing the global subscriber.
 #[expect(missing_debug_implementations)] // File and console layers are `dyn Trait` objects
 pub struct LoggingComponents {
     /// The layer responsible for storing span data.
+    ///
+    /// Refer to [`filtered_storage_layer()`][Self::filtered_storage_layer] for filtering the
+    /// layer to the levels accepted by the sinks.
     pub storage_layer: SpanStorageLayer,
 
     /// The file logging layer, if enabled and configured.
     pub file_log_layer:
         Option<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>>,
 
+    /// A handle for reporting the health of the file logging sink (such as the number of write
+    /// errors), if file logging is enabled and configured.
+    pub file_sink_health: Option<FileSinkHealth>,
+
     /// The console logging layer, if enabled and configured.
     pub console_log_layer:
         Option<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>>,
 
+    /// The layer responsible for counting the log records emitted, by level and target.
+    /// The number of bytes written by the file and console logging layers are recorded as well.
+    #[cfg(feature = "metrics")]
+    pub metrics_layer: LogMetricsLayer,
+
+    /// The layer responsible for recording span durations into histograms, by span name.
+    /// This layer relies on the [`storage_layer`][Self::storage_layer] to capture the span entry
+    /// times.
+    #[cfg(feature = "metrics")]
+    pub span_latency_metrics_layer: SpanLatencyMetricsLayer,
+
+    /// The alerting layer, if configured.
+    pub alert_layer: Option<AlertLayer>,
+
+    /// The sampling layer, if configured.
+    /// This layer must be added to the same subscriber as the sink layers, since it drops
+    /// sampled out events for all the layers of the subscriber.
+    pub sampling_layer: Option<SamplingLayer>,
+
+    /// The audit logging layer, if configured.
+    /// Audit events are written synchronously and are not associated with any worker guard.
+    pub audit_log_layer:
+        Option<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>>,
+
+    /// A handle for reporting the health of the audit logging sink (such as the number of write
+    /// errors), if audit logging is configured.
+    /// Failed audit writes are not retried, and the records are counted as dropped.
+    pub audit_sink_health: Option<FileSinkHealth>,
+
+    /// The `tokio-console` instrumentation layer, if configured.
+    /// The gRPC server serving console clients runs on a background thread.
+    #[cfg(feature = "tokio-console")]
+    pub tokio_console_layer:
+        Option<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>>,
+
+    /// A handle for changing the logging of span lifecycles (and the slow span threshold) of the
+    /// JSON file and console logging layers at runtime.
+    pub span_lifecycle_logging: SpanLifecycleLogging,
+
+    /// The layer writing the timings of spans in the folded stack format, if configured.
+    pub profiling_layer:
+        Option<Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>>,
+
+    /// A handle for enabling or disabling the writing of span timings by the
+    /// [`profiling_layer`][Self::profiling_layer] at runtime, if configured.
+    pub profiling: Option<FoldedStackProfiling>,
+
+    /// The layer capturing the entries and exits of spans in the Chrome `trace_event` format,
+    /// if configured.
+    pub chrome_trace_layer: Option<ChromeTraceLayer>,
+
+    /// A handle for starting captures of the [`chrome_trace_layer`][Self::chrome_trace_layer]
+    /// and exporting the captured trace events, if configured.
+    pub chrome_trace_capture: Option<ChromeTraceCapture>,
+
+    /// The effective filtering directives of the file and console sinks (including the default
+    /// directive derived from the configured level), along with the global filtering directive,
+    /// if configured.
+    pub filtering_directives: Vec<(DirectiveSink, String)>,
+
+    /// A handle for reading the number of records emitted by level, counted by the
+    /// [`storage_layer`][Self::storage_layer].
+    pub record_counts: RecordCounts,
+
     /// Worker guards for file and console logging layers.
     /// Logs would be written as long as these guards are in scope.
     pub guards: Vec<tracing_appender::non_blocking::WorkerGuard>,
+
+    /// The tracker of the `ERROR` records emitted over sliding windows, fed by the
+    /// [`storage_layer`][Self::storage_layer].
+    error_stats: ErrorStats,
+
+    /// The time at which the components were built.
+    built_at: std::time::Instant,
+
+    /// The most verbose level accepted by any of the sinks, or [`LevelFilter::TRACE`] if it
+    /// cannot be determined.
+    sinks_max_level: LevelFilter,
+
+    /// The counters of records dropped by the non-blocking writers of the sinks.
+    dropped_lines_counters: Vec<shutdown::DroppedLinesCounter>,
+}
+
+impl LoggingComponents {
+    /// Returns the [`storage_layer`][Self::storage_layer] with a per-layer filter restricting it
+    /// to the most verbose level accepted by any of the sinks.
+    ///
+    /// Since the sinks are filtered using per-layer filters as well, the spans and events which
+    /// no sink would accept are then disabled altogether (and are not captured by the storage
+    /// layer), unless they are enabled by other layers, such as the
+    /// [`profiling_layer`][Self::profiling_layer].
+    /// The unfiltered storage layer captures the data of all spans enabled by any layer.
+    pub fn filtered_storage_layer(
+        &self,
+    ) -> Filtered<SpanStorageLayer, LevelFilter, tracing_subscriber::Registry> {
+        self.storage_layer.clone().with_filter(self.sinks_max_level)
+    }
 }
 
 /// Errors that can occur within the logger.
@@ -203,15 +767,162 @@ pub enum LoggerError {
     #[error("Failed to initialize rolling file appender: {0}")]
     FileAppenderInitialization(#[from] tracing_appender::rolling::InitError),
 
+    /// Represents an error when opening the audit log file.
+    #[error("Failed to open audit log file: {0}")]
+    AuditFileInitialization(std::io::Error),
+
+    /// Represents an error when obtaining the log file encryption key.
+    #[error("Failed to obtain log file encryption key: {0}")]
+    EncryptionKey(String),
+
+    /// Represents an error when applying the configured permissions or ownership to the log
+    /// files.
+    #[error("Failed to apply permissions to log files: {0}")]
+    FilePermissions(std::io::Error),
+
     /// Represents an error due to an invalid filtering directive.
-    #[error("Failed to parse filtering directive: {0}")]
-    InvalidFilteringDirective(#[from] tracing_subscriber::filter::ParseError),
+    #[error("Invalid {sink} filtering directive `{directive}` at position {position}: {reason}")]
+    InvalidDirective {
+        /// The sink that the invalid filtering directive was specified for.
+        sink: DirectiveSink,
+
+        /// The complete filtering directive that failed validation.
+        directive: String,
+
+        /// The byte offset of the invalid comma-separated component within the directive.
+        position: usize,
+
+        /// The reason why the directive is invalid.
+        reason: String,
+    },
+}
+
+/// Parses and validates a comma-separated filtering directive (e.g., `"info,my_crate=debug"`),
+/// returning the individual directives it is composed of.
+///
+/// The returned directives can be used to list the effective levels for each target, since
+/// their [`Display`][std::fmt::Display] implementation produces the normalized `target=level`
+/// form of the directive.
+///
+/// # Example
+///
+/// ```
+/// use log_utils::{DirectiveSink, LoggerError, parse_filtering_directive};
+///
+/// let directives = parse_filtering_directive(DirectiveSink::Global, "info,my_app=debug").unwrap();
+/// let directives = directives
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect::<Vec<_>>();
+/// assert_eq!(directives, ["info", "my_app=debug"]);
+///
+/// let error = parse_filtering_directive(DirectiveSink::Console, "info,my_app=[").unwrap_err();
+/// assert!(matches!(
+///     error,
+///     LoggerError::InvalidDirective {
+///         sink: DirectiveSink::Console,
+///         position: 5,
+///         ..
+///     }
+/// ));
+/// ```
+///
+/// # Errors
+///
+/// Returns [`LoggerError::InvalidDirective`] if any of the comma-separated components of the
+/// directive is invalid.
+pub fn parse_filtering_directive(
+    sink: DirectiveSink,
+    directive: &str,
+) -> Result<Vec<Directive>, LoggerError> {
+    let mut directives = Vec::new();
+    let mut position = 0;
+
+    for component in directive.split(',') {
+        if !component.is_empty() {
+            let parsed =
+                component
+                    .parse::<Directive>()
+                    .map_err(|error| LoggerError::InvalidDirective {
+                        sink,
+                        directive: directive.to_owned(),
+                        position,
+                        reason: error.to_string(),
+                    })?;
+            directives.push(parsed);
+        }
+
+        position += component.len() + 1; // Account for the comma separator
+    }
+
+    Ok(directives)
+}
+
+/// Constructs a filtering directive enabling the specified level for the crates of a workspace,
+/// and the stricter `third_party_level` for all other crates, which can be used as the
+/// [`global_filtering_directive`][LoggerConfig::global_filtering_directive] of [`LoggerConfig`].
+///
+/// The crates are typically obtained using the `build_info::cargo_workspace_members!()` macro or
+/// the `build_info::framework_libs_workspace_members()` function.
+/// Hyphens in the crate names are replaced with underscores, since the targets of the records
+/// are the module paths of the crates.
+/// The `build_info::workspace_filtering_directive()` function constructs the same directive
+/// without the `third_party_level`, for crates not depending on this crate.
+///
+/// # Example
+///
+/// ```
+/// use log_utils::{Level, LevelFilter, workspace_filtering_directive};
+///
+/// let directive =
+///     workspace_filtering_directive(Level::DEBUG, LevelFilter::WARN, ["my-app", "my_lib"]);
+/// assert_eq!(directive, "warn,my_app=debug,my_lib=debug");
+/// ```
+pub fn workspace_filtering_directive<'a>(
+    level: Level,
+    third_party_level: LevelFilter,
+    crates: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let level = level.as_str().to_lowercase();
+    let mut crates = crates
+        .into_iter()
+        .map(|name| name.replace('-', "_"))
+        .collect::<Vec<_>>();
+    // Sort the crates, since the workspace members are typically obtained as a `HashSet`
+    crates.sort_unstable();
+    crates.dedup();
+
+    std::iter::once(third_party_level.to_string().to_lowercase())
+        .chain(crates.iter().map(|name| format!("{name}={level}")))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Constructs an [`EnvFilter`] from a filtering directive, using the specified level as the
+/// default directive.
+fn build_env_filter(
+    sink: DirectiveSink,
+    directive: &str,
+    default_level: Level,
+) -> Result<EnvFilter, LoggerError> {
+    EnvFilter::builder()
+        .with_default_directive(default_level.into())
+        .parse(directive)
+        .map_err(|error| LoggerError::InvalidDirective {
+            sink,
+            directive: directive.to_owned(),
+            position: 0,
+            reason: error.to_string(),
+        })
 }
 
 /// Constructs logging components based on the provided [`LoggerConfig`].
 ///
 /// This function prepares a [`SpanStorageLayer`], and optionally file and console logging layers,
 /// along with any necessary worker guards for the file and console logging layers.
+/// The [`SpanStorageLayer`] can be filtered to the most verbose level accepted by any of the
+/// configured sinks using [`LoggingComponents::filtered_storage_layer()`], so that spans and
+/// events which no layer would accept are skipped early.
 /// These components are returned in a [`LoggingComponents`] struct, allowing the caller
 /// to integrate them with a [`tracing_subscriber::Registry`] and other custom layers before
 /// initializing the global `tracing` subscriber.
@@ -264,23 +975,27 @@ pub enum LoggerError {
 ///         level: Level::INFO,
 ///         filtering_directive: Some("my_app=info,warn".to_string()),
 ///         print_filtering_directive: DirectivePrintTarget::Stdout,
+///         ..Default::default()
 ///     }),
 ///     console_config: Some(ConsoleLoggingConfig {
 ///         level: Level::DEBUG,
 ///         log_format: ConsoleLogFormat::HumanReadable,
 ///         filtering_directive: Some("my_app=debug,info".to_string()),
 ///         print_filtering_directive: DirectivePrintTarget::Stdout,
+///         ..Default::default()
 ///     }),
 ///     global_filtering_directive: Some("info".to_string()),
+///     ..Default::default()
 /// };
 ///
 /// match build_logging_components(config) {
 ///     Ok(components) => {
+///         let storage_layer = components.filtered_storage_layer();
 ///         let _guards = components.guards; // Keep guards in scope
 ///
 ///         // Build the subscriber with all components
 ///         let mut layers = Vec::new();
-///         layers.push(components.storage_layer.boxed());
+///         layers.push(storage_layer.boxed());
 ///
 ///         if let Some(file_layer) = components.file_log_layer {
 ///             layers.push(file_layer);
@@ -288,6 +1003,21 @@ pub enum LoggerError {
 ///         if let Some(console_layer) = components.console_log_layer {
 ///             layers.push(console_layer);
 ///         }
+///         if let Some(alert_layer) = components.alert_layer {
+///             layers.push(alert_layer.boxed());
+///         }
+///         if let Some(sampling_layer) = components.sampling_layer {
+///             layers.push(sampling_layer.boxed());
+///         }
+///         if let Some(audit_layer) = components.audit_log_layer {
+///             layers.push(audit_layer);
+///         }
+///         if let Some(profiling_layer) = components.profiling_layer {
+///             layers.push(profiling_layer);
+///         }
+///         if let Some(chrome_trace_layer) = components.chrome_trace_layer {
+///             layers.push(chrome_trace_layer.boxed());
+///         }
 ///
 ///         // Initialize the global subscriber
 ///         tracing_subscriber::registry().with(layers).init();
@@ -302,33 +1032,253 @@ pub enum LoggerError {
 ///
 /// Returns [`LoggerError`] if any part of the component building fails
 /// (e.g., due to invalid configuration, invalid filter directives, etc.).
+/// All filtering directives are validated before any of the components are constructed, and
+/// an invalid directive is reported as [`LoggerError::InvalidDirective`].
+///
+/// # Panics
+///
+/// Panics if [`LoggerConfig::tokio_console_config`] is specified and the application was not
+/// compiled with `RUSTFLAGS="--cfg tokio_unstable"`.
 pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponents, LoggerError> {
+    // Validate all filtering directives up front, before any files are created
+    let directives = [
+        (
+            DirectiveSink::Global,
+            config.global_filtering_directive.as_deref(),
+        ),
+        (
+            DirectiveSink::File,
+            config
+                .file_config
+                .as_ref()
+                .and_then(|file_config| file_config.filtering_directive.as_deref()),
+        ),
+        (
+            DirectiveSink::Console,
+            config
+                .console_config
+                .as_ref()
+                .and_then(|console_config| console_config.filtering_directive.as_deref()),
+        ),
+    ];
+    for (sink, directive) in directives {
+        if let Some(directive) = directive {
+            parse_filtering_directive(sink, directive)?;
+        }
+    }
+
+    #[cfg(not(feature = "tokio-console"))]
+    if config.tokio_console_config.is_some() {
+        return Err(LoggerError::Configuration(
+            "The `tokio-console` feature must be enabled to use `tokio_console_config`".to_string(),
+        ));
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    if config
+        .file_config
+        .as_ref()
+        .is_some_and(|file_config| file_config.encryption.is_some())
+    {
+        return Err(LoggerError::Configuration(
+            "The `encryption` feature must be enabled to use `FileLoggingConfig::encryption`"
+                .to_string(),
+        ));
+    }
+
+    #[cfg(not(feature = "hash-chain"))]
+    if config
+        .file_config
+        .as_ref()
+        .is_some_and(|file_config| file_config.hash_chain)
+    {
+        return Err(LoggerError::Configuration(
+            "The `hash-chain` feature must be enabled to use `FileLoggingConfig::hash_chain`"
+                .to_string(),
+        ));
+    }
+
+    #[cfg(not(feature = "opentelemetry"))]
+    if !config.baggage_keys.is_empty() {
+        return Err(LoggerError::Configuration(
+            "The `opentelemetry` feature must be enabled to use `baggage_keys`".to_string(),
+        ));
+    }
+
+    let built_at = std::time::Instant::now();
     let mut guards = Vec::new();
+    let mut dropped_lines_counters = Vec::new();
+    let mut filtering_directives = Vec::new();
+    if let Some(global_filtering_directive) = &config.global_filtering_directive {
+        filtering_directives.push((DirectiveSink::Global, global_filtering_directive.clone()));
+    }
 
-    let storage_layer = SpanStorageLayer::new(config.persistent_keys);
+    // The most verbose level accepted by any of the sinks, `None` if it cannot be determined
+    let mut sinks_max_level = Some(LevelFilter::OFF);
+    let mut accept_sink_filter = |filter: &EnvFilter| {
+        sinks_max_level = sinks_max_level
+            .zip(filter.max_level_hint())
+            .map(|(current, hint)| current.max(hint));
+    };
 
     let json_formatting_config = JsonFormattingLayerConfig {
         static_top_level_fields: config.static_top_level_fields,
-        top_level_keys: config.top_level_keys,
+        top_level_keys: config
+            .top_level_keys
+            .union(&config.baggage_keys)
+            .copied()
+            .collect(),
         log_span_lifecycles: config.log_span_lifecycles,
+        slow_span_threshold: config.slow_span_threshold,
         additional_fields_placement: config.additional_fields_placement,
+        omit_empty_fields: config.omit_empty_fields,
+        code_location_fields: config.code_location_fields,
+        timestamp_format: config.timestamp_format,
+        timestamp_timezone: config.timestamp_timezone,
+        hostname: config.hostname,
+        environment_enrichment: config.environment_enrichment,
+    };
+    // The keys of the optional implicit fields are reserved only if the fields are included
+    let mut reserved_keys = keys::ReservedKeys::default();
+    if config.environment_enrichment {
+        reserved_keys =
+            reserved_keys.with_keys(keys::ENVIRONMENT_ENRICHMENT_KEY_LIST.iter().copied());
+    }
+    // The JSON file and console logging layers share a single handle for changing the logging
+    // of span lifecycles at runtime
+    let span_lifecycle_logging =
+        SpanLifecycleLogging::new(config.log_span_lifecycles, config.slow_span_threshold);
+
+    // Audit logging
+    let mut audit_sink_health = None;
+    let audit_log_layer: Option<
+        Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>,
+    > = if let Some(audit_logging_config) = config.audit_config {
+        let writer = audit::SyncFileWriter::open(
+            &audit_logging_config.directory,
+            &audit_logging_config.file_name,
+        )
+        .map_err(LoggerError::AuditFileInitialization)?;
+        audit_sink_health = Some(writer.health());
+
+        let layer = audit::EventsOnlyLayer::new(JsonFormattingLayer::new(
+            json_formatting_config.clone(),
+            writer,
+            serde_json::ser::CompactFormatter,
+        )?)
+        .with_filter(filter_fn(audit::is_audit_event))
+        .boxed();
+
+        Some(layer)
+    } else {
+        None
     };
 
     // File logging
+    let mut file_sink_health = None;
     let file_log_layer: Option<
         Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>,
     > = if let Some(file_logging_config) = config.file_config {
+        #[cfg(not(unix))]
+        if file_logging_config.file_mode.is_some()
+            || file_logging_config.file_owner.is_some()
+            || file_logging_config.file_group.is_some()
+        {
+            return Err(LoggerError::Configuration(
+                "Log file permissions and ownership are only supported on Unix platforms"
+                    .to_string(),
+            ));
+        }
+
         let mut file_appender_builder = tracing_appender::rolling::RollingFileAppender::builder()
-            .rotation(file_logging_config.file_rotation)
-            .filename_prefix(file_logging_config.file_name_prefix);
+            .rotation(file_logging_config.file_rotation.clone())
+            .filename_prefix(&file_logging_config.file_name_prefix);
 
         if let Some(max_log_files) = file_logging_config.max_log_files {
             file_appender_builder = file_appender_builder.max_log_files(usize::from(max_log_files));
         }
 
-        let file_appender = file_appender_builder.build(&file_logging_config.directory)?;
-        let (non_blocking_appender, guard) = tracing_appender::non_blocking(file_appender);
+        #[cfg(feature = "encryption")]
+        let encryption_key = file_logging_config
+            .encryption
+            .as_ref()
+            .map(|encryption_config| encryption_config.key.resolve())
+            .transpose()
+            .map_err(LoggerError::EncryptionKey)?;
+
+        let (file_appender, diagnostic) = fallback::build_file_appender(
+            &file_appender_builder,
+            &file_logging_config.directory,
+            file_logging_config.fallback,
+        )?;
+        let health = FileSinkHealth::default();
+        let file_writer: Box<dyn std::io::Write + Send> = match file_appender {
+            Some(file_appender) => {
+                // The bytes are counted by the background worker, as they are written to the file
+                #[cfg(feature = "metrics")]
+                let file_appender =
+                    log_metrics::ByteCountingWriter::new(file_appender, DirectiveSink::File);
+                #[cfg_attr(
+                    not(any(unix, feature = "encryption", feature = "hash-chain")),
+                    expect(unused_mut)
+                )]
+                let mut file_writer: Box<dyn std::io::Write + Send> = Box::new(file_appender);
+                #[cfg(unix)]
+                {
+                    let permissions = permissions::FilePermissions {
+                        mode: file_logging_config.file_mode,
+                        owner: file_logging_config.file_owner,
+                        group: file_logging_config.file_group,
+                    };
+                    if !permissions.is_unchanged() {
+                        permissions
+                            .apply(
+                                std::path::Path::new(&file_logging_config.directory),
+                                &file_logging_config.file_name_prefix,
+                            )
+                            .map_err(LoggerError::FilePermissions)?;
+                        file_writer = Box::new(permissions::PermissionsWriter::new(
+                            file_writer,
+                            permissions,
+                            &file_logging_config.directory,
+                            &file_logging_config.file_name_prefix,
+                            file_logging_config.file_rotation.clone(),
+                        ));
+                    }
+                }
+                #[cfg(feature = "encryption")]
+                if let Some(key) = encryption_key {
+                    file_writer = Box::new(encryption::EncryptingWriter::new(file_writer, &key));
+                }
+                #[cfg(feature = "hash-chain")]
+                if file_logging_config.hash_chain {
+                    file_writer = Box::new(hash_chain::HashChainingWriter::new(file_writer));
+                }
+                Box::new(fallback::WriteErrorPolicyWriter::new(
+                    file_writer,
+                    file_logging_config.write_error_policy,
+                    health.clone(),
+                ))
+            }
+            // Falling back to the console
+            None => {
+                health.set_writing_to_console();
+                #[cfg(feature = "metrics")]
+                let stdout =
+                    log_metrics::ByteCountingWriter::new(std::io::stdout(), DirectiveSink::File);
+                #[cfg(not(feature = "metrics"))]
+                let stdout = std::io::stdout();
+                Box::new(stdout)
+            }
+        };
+        file_sink_health = Some(health);
+
+        let (non_blocking_appender, guard) = tracing_appender::non_blocking(file_writer);
         guards.push(guard);
+        dropped_lines_counters.push(shutdown::DroppedLinesCounter {
+            sink: DirectiveSink::File,
+            counter: non_blocking_appender.error_counter(),
+        });
 
         let file_filter_directive = file_logging_config
             .filtering_directive
@@ -336,35 +1286,34 @@ pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponent
             .or(config.global_filtering_directive.as_deref())
             .unwrap_or_default(); // Using an empty string causes it to use the default directive
 
-        match file_logging_config.print_filtering_directive {
-            #[expect(clippy::print_stdout)]
-            DirectivePrintTarget::Stdout => {
-                println!(
-                    "[INFO] {}: Using file filtering directive: {file_filter_directive}",
-                    env!("CARGO_PKG_NAME")
-                );
-            }
-            #[expect(clippy::print_stderr)]
-            DirectivePrintTarget::Stderr => {
-                eprintln!(
-                    "[INFO] {}: Using file filtering directive: {file_filter_directive}",
-                    env!("CARGO_PKG_NAME")
-                );
-            }
-            DirectivePrintTarget::None => (), // Do nothing
-        }
+        file_logging_config
+            .print_filtering_directive
+            .print(DirectiveSink::File, file_filter_directive);
 
-        let filter = EnvFilter::builder()
-            .with_default_directive(file_logging_config.level.into())
-            .parse(file_filter_directive)?;
+        let filter = build_env_filter(
+            DirectiveSink::File,
+            file_filter_directive,
+            file_logging_config.level,
+        )?;
+        accept_sink_filter(&filter);
+        filtering_directives.push((DirectiveSink::File, filter.to_string()));
 
-        let layer = JsonFormattingLayer::new(
+        let mut layer = JsonFormattingLayer::new(
             json_formatting_config.clone(),
             non_blocking_appender,
             serde_json::ser::CompactFormatter,
         )?
-        .with_filter(filter)
-        .boxed();
+        .with_span_lifecycle_logging(span_lifecycle_logging.clone());
+        if file_logging_config.hash_chain {
+            layer = layer.with_reserved_keys([keys::RECORD_HASH]);
+            reserved_keys = reserved_keys.with_keys([keys::RECORD_HASH]);
+        }
+        let layer = match diagnostic {
+            Some(diagnostic) => fallback::DiagnosticLayer::new(layer, diagnostic)
+                .with_filter(filter)
+                .boxed(),
+            None => layer.with_filter(filter).boxed(),
+        };
 
         Some(layer)
     } else {
@@ -375,8 +1324,50 @@ pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponent
     let console_log_layer: Option<
         Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>,
     > = if let Some(console_logging_config) = config.console_config {
-        let (non_blocking_stdout, guard) = tracing_appender::non_blocking(std::io::stdout());
-        guards.push(guard);
+        // Records are written to the browser console on `wasm32` targets, where neither
+        // stdout nor background threads are available
+        #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+        let console_writer = browser_console::BrowserConsoleWriter::new();
+        #[cfg(all(target_arch = "wasm32", feature = "wasm", feature = "metrics"))]
+        let console_writer =
+            log_metrics::ByteCountingWriter::new(console_writer, DirectiveSink::Console);
+        #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+        let console_writer = {
+            use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+
+            // The bytes are counted by the background workers, as they are written
+            #[cfg(feature = "metrics")]
+            let (stdout, stderr) = (
+                log_metrics::ByteCountingWriter::new(std::io::stdout(), DirectiveSink::Console),
+                log_metrics::ByteCountingWriter::new(std::io::stderr(), DirectiveSink::Console),
+            );
+            #[cfg(not(feature = "metrics"))]
+            let (stdout, stderr) = (std::io::stdout(), std::io::stderr());
+
+            let (non_blocking_stdout, guard) = tracing_appender::non_blocking(stdout);
+            guards.push(guard);
+            dropped_lines_counters.push(shutdown::DroppedLinesCounter {
+                sink: DirectiveSink::Console,
+                counter: non_blocking_stdout.error_counter(),
+            });
+
+            if console_logging_config.route_warnings_to_stderr {
+                let (non_blocking_stderr, guard) = tracing_appender::non_blocking(stderr);
+                guards.push(guard);
+                dropped_lines_counters.push(shutdown::DroppedLinesCounter {
+                    sink: DirectiveSink::Console,
+                    counter: non_blocking_stderr.error_counter(),
+                });
+
+                BoxMakeWriter::new(
+                    non_blocking_stderr
+                        .with_max_level(Level::WARN)
+                        .or_else(non_blocking_stdout),
+                )
+            } else {
+                BoxMakeWriter::new(non_blocking_stdout)
+            }
+        };
 
         let console_filter_directive = console_logging_config
             .filtering_directive
@@ -384,44 +1375,44 @@ pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponent
             .or(config.global_filtering_directive.as_deref())
             .unwrap_or_default(); // Using an empty string causes it to use the default directive
 
-        match console_logging_config.print_filtering_directive {
-            #[expect(clippy::print_stdout)]
-            DirectivePrintTarget::Stdout => {
-                println!(
-                    "[INFO] {}: Using console filtering directive: {console_filter_directive}",
-                    env!("CARGO_PKG_NAME")
-                );
-            }
-            #[expect(clippy::print_stderr)]
-            DirectivePrintTarget::Stderr => {
-                eprintln!(
-                    "[INFO] {}: Using console filtering directive: {console_filter_directive}",
-                    env!("CARGO_PKG_NAME")
-                );
-            }
-            DirectivePrintTarget::None => (), // Do nothing
-        }
+        console_logging_config
+            .print_filtering_directive
+            .print(DirectiveSink::Console, console_filter_directive);
 
-        let filter = EnvFilter::builder()
-            .with_default_directive(console_logging_config.level.into())
-            .parse(console_filter_directive)?;
+        let filter = build_env_filter(
+            DirectiveSink::Console,
+            console_filter_directive,
+            console_logging_config.level,
+        )?;
+        accept_sink_filter(&filter);
+        filtering_directives.push((DirectiveSink::Console, filter.to_string()));
 
         match console_logging_config.log_format {
             ConsoleLogFormat::HumanReadable => {
-                let human_readable_layer = tracing_subscriber::fmt::layer()
-                    .with_timer(tracing_subscriber::fmt::time::time())
-                    .pretty()
-                    .with_writer(non_blocking_stdout)
-                    .with_filter(filter)
-                    .boxed();
+                let human_readable_layer = HumanReadableFormattingLayer::new(
+                    console_logging_config.human_readable_options,
+                    console_writer,
+                )
+                .with_filter(filter)
+                .boxed();
                 Some(human_readable_layer)
             }
+            ConsoleLogFormat::Compact => {
+                let compact_layer = HumanReadableFormattingLayer::compact(
+                    console_logging_config.human_readable_options,
+                    console_writer,
+                )
+                .with_filter(filter)
+                .boxed();
+                Some(compact_layer)
+            }
             ConsoleLogFormat::CompactJson => {
                 let json_layer = JsonFormattingLayer::new(
                     json_formatting_config,
-                    non_blocking_stdout,
+                    console_writer,
                     serde_json::ser::CompactFormatter,
                 )?
+                .with_span_lifecycle_logging(span_lifecycle_logging.clone())
                 .with_filter(filter)
                 .boxed();
                 Some(json_layer)
@@ -429,9 +1420,10 @@ pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponent
             ConsoleLogFormat::PrettyJson => {
                 let pretty_json_layer = JsonFormattingLayer::new(
                     json_formatting_config,
-                    non_blocking_stdout,
+                    console_writer,
                     serde_json::ser::PrettyFormatter::new(),
                 )?
+                .with_span_lifecycle_logging(span_lifecycle_logging.clone())
                 .with_filter(filter)
                 .boxed();
                 Some(pretty_json_layer)
@@ -441,11 +1433,106 @@ pub fn build_logging_components(config: LoggerConfig) -> Result<LoggingComponent
         None
     };
 
+    // Audit events are written irrespective of the levels of the other sinks, and require the
+    // data of spans at all levels to be captured.
+    // Similarly, the Tokio runtime instrumentation is emitted at the `TRACE` level.
+    if audit_log_layer.is_some() || config.tokio_console_config.is_some() {
+        sinks_max_level = None;
+    }
+    // Alerts include the data of the spans the alerting events belong to
+    if let Some(alert_config) = &config.alert_config {
+        sinks_max_level = sinks_max_level.map(|max_level| max_level.max(alert_config.level.into()));
+    }
+
+    let record_counts = RecordCounts::default();
+    let error_stats = ErrorStats::default();
+    let mut storage_layer = SpanStorageLayer::new(config.persistent_keys)
+        .with_field_inheritance(config.span_field_inheritance)
+        .with_persistent_key_propagation(config.persistent_key_propagation)
+        .with_storage_limits(config.span_storage_limits.unwrap_or_default())
+        .with_reserved_keys(reserved_keys)
+        .with_record_counts(record_counts.clone())
+        .with_error_stats(error_stats.clone());
+    if let Some(field_overwrite_policies) = config.field_overwrite_policies {
+        storage_layer = storage_layer.with_field_overwrite_policies(field_overwrite_policies);
+    }
+    if config.request_summary {
+        storage_layer = storage_layer.with_request_summary();
+    }
+    if config.span_busy_idle_time {
+        storage_layer = storage_layer.with_busy_idle_time();
+    }
+    if config.span_counters {
+        storage_layer = storage_layer.with_span_counters();
+    }
+    if config.span_memory_usage {
+        storage_layer = storage_layer.with_memory_usage();
+    }
+    #[cfg(feature = "opentelemetry")]
+    if !config.baggage_keys.is_empty() {
+        storage_layer = storage_layer.with_baggage_keys(config.baggage_keys);
+    }
+
+    // Profiling
+    let mut profiling = None;
+    let profiling_layer: Option<
+        Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync + 'static>,
+    > = if let Some(profiling_config) = config.profiling_config {
+        let profile_appender = tracing_appender::rolling::RollingFileAppender::builder()
+            .rotation(Rotation::NEVER)
+            .filename_prefix(&profiling_config.file_name)
+            .build(&profiling_config.directory)?;
+        let (non_blocking_appender, guard) = tracing_appender::non_blocking(profile_appender);
+        guards.push(guard);
+
+        let handle = FoldedStackProfiling::new(profiling_config.enabled);
+        profiling = Some(handle.clone());
+
+        Some(
+            FoldedStackLayer::new(non_blocking_appender)
+                .with_profiling(handle)
+                .boxed(),
+        )
+    } else {
+        None
+    };
+
+    let chrome_trace_layer = config.chrome_trace_config.map(ChromeTraceLayer::new);
+    let chrome_trace_capture = chrome_trace_layer.as_ref().map(ChromeTraceLayer::capture);
+
+    let alert_layer = config.alert_config.map(AlertLayer::new);
+    let sampling_layer = config.sampling_config.map(SamplingLayer::new).transpose()?;
+
+    #[cfg(feature = "tokio-console")]
+    let tokio_console_layer = config.tokio_console_config.map(tokio_console::build_layer);
+
     Ok(LoggingComponents {
         storage_layer,
+        #[cfg(feature = "metrics")]
+        metrics_layer: LogMetricsLayer::new(),
+        #[cfg(feature = "metrics")]
+        span_latency_metrics_layer: SpanLatencyMetricsLayer::new(),
         file_log_layer,
+        file_sink_health,
         console_log_layer,
+        alert_layer,
+        sampling_layer,
+        audit_log_layer,
+        audit_sink_health,
+        #[cfg(feature = "tokio-console")]
+        tokio_console_layer,
+        span_lifecycle_logging,
+        profiling_layer,
+        profiling,
+        chrome_trace_layer,
+        chrome_trace_capture,
+        filtering_directives,
+        record_counts,
         guards,
+        error_stats,
+        built_at,
+        sinks_max_level: sinks_max_level.unwrap_or(LevelFilter::TRACE),
+        dropped_lines_counters,
     })
 }
 
@@ -513,6 +1600,7 @@ mod tests {
             top_level_keys: HashSet::new(),
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
         };
 
         let layer = JsonFormattingLayer::new(
@@ -547,8 +1635,280 @@ mod tests {
         assert!(log_entry["pid"].is_number());
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     #[test]
-    fn test_top_level_keys_promotion() {
+    fn test_container_id_detection() {
+        let id = "3f4b2e1a9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f";
+
+        let cgroups = [
+            format!("12:memory:/docker/{id}\n11:cpu:/docker/{id}"),
+            format!("0::/system.slice/docker-{id}.scope"),
+            format!(
+                "1:name=systemd:/kubepods/burstable/pod7a8b9c0d-1e2f-3a4b-5c6d-7e8f9a0b1c2d/{id}"
+            ),
+            format!(
+                "0::/kubepods.slice/kubepods-burstable.slice/\
+                 kubepods-burstable-pod7a8b9c0d_1e2f_3a4b_5c6d_7e8f9a0b1c2d.slice/\
+                 cri-containerd-{id}.scope"
+            ),
+            format!("0::/machine.slice/libpod-{id}.scope/container"),
+        ];
+        for cgroup in cgroups {
+            assert_eq!(
+                container::container_id_from_cgroup(&cgroup).as_deref(),
+                Some(id),
+                "{cgroup}"
+            );
+        }
+        assert_eq!(container::container_id_from_cgroup("0::/"), None);
+        assert_eq!(
+            container::container_id_from_cgroup("0::/user.slice/user-1000.slice/session-2.scope"),
+            None
+        );
+
+        let mountinfo = format!(
+            "1030 1021 0:52 / / rw,relatime - overlay overlay rw,\
+             lowerdir=/var/lib/docker/overlay2/l/ABCDEF:/var/lib/docker/overlay2/{}/diff\n\
+             1041 1030 8:1 /var/lib/docker/containers/{id}/hostname /etc/hostname rw,relatime \
+             - ext4 /dev/sda1 rw",
+            "a".repeat(64)
+        );
+        assert_eq!(
+            container::container_id_from_mountinfo(&mountinfo).as_deref(),
+            Some(id)
+        );
+        let podman_mountinfo = format!(
+            "612 600 0:44 /containers/storage/overlay-containers/{id}/userdata/hostname \
+             /etc/hostname rw - tmpfs tmpfs rw"
+        );
+        assert_eq!(
+            container::container_id_from_mountinfo(&podman_mountinfo).as_deref(),
+            Some(id)
+        );
+        assert_eq!(
+            container::container_id_from_mountinfo("22 1 8:1 / / rw - ext4 /dev/sda1 rw"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hostname_override_and_environment_enrichment() {
+        let test_writer = TestWriter::new();
+
+        // No other test enables environment enrichment, and hence reads these variables
+        std::env::set_var("POD_NAME", "my-app-7d4b9c-x2x5z");
+        std::env::set_var("NODE_NAME", "node-1");
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            hostname: Some("my-app.example.com".to_string()),
+            environment_enrichment: true,
+            ..Default::default()
+        };
+
+        let layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let schema = layer.json_schema();
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Test message");
+        });
+
+        let output = test_writer.get_output();
+        let log_entry: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(log_entry["hostname"], "my-app.example.com");
+        assert_eq!(log_entry["pod_name"], "my-app-7d4b9c-x2x5z");
+        assert_eq!(log_entry["node_name"], "node-1");
+
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&json!("pod_name")));
+        assert!(required.contains(&json!("node_name")));
+
+        // Enriched fields are reserved only if enrichment is enabled
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::from([("pod_name".to_string(), json!("pod"))]),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            environment_enrichment: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            JsonFormattingLayer::new(
+                config.clone(),
+                TestWriter::new(),
+                serde_json::ser::CompactFormatter
+            ),
+            Err(LoggerError::Configuration(_))
+        ));
+
+        let test_writer = TestWriter::new();
+        let config = JsonFormattingLayerConfig {
+            environment_enrichment: false,
+            ..config
+        };
+        let layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", node_name = "node");
+            let _guard = span.enter();
+            info!(container_id = "container", "Test message");
+        });
+
+        let output = test_writer.get_output();
+        let log_entry: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(log_entry["pod_name"], "pod");
+        assert_eq!(log_entry["node_name"], "node");
+        assert_eq!(log_entry["container_id"], "container");
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::from([("service".to_string(), json!("payments"))]),
+            top_level_keys: HashSet::from(["request_id"]),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
+            ..Default::default()
+        };
+        let test_writer = TestWriter::new();
+        let layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let schema = layer.json_schema();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            info!(request_id = "req_1", amount = 100, "Payment created");
+        });
+        let record: Value = serde_json::from_str(test_writer.get_output().trim()).unwrap();
+        assert_eq!(record["log_schema_version"], LOG_SCHEMA_VERSION);
+
+        // Every required property is present in the record, with the expected constant values
+        let properties = schema["properties"].as_object().unwrap();
+        for key in schema["required"].as_array().unwrap() {
+            let key = key.as_str().unwrap();
+            assert!(record.get(key).is_some(), "record is missing `{key}`");
+            if let Some(value) = properties[key].get("const") {
+                assert_eq!(&record[key], value);
+            }
+        }
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("service"))
+        );
+        assert!(properties.contains_key("request_id"));
+        assert_eq!(properties["extra"]["type"], "object");
+        assert_eq!(record["extra"]["amount"], 100);
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn test_init_for_tests() {
+        let logs = test_support::init_for_tests();
+
+        span!(TracingLevel::INFO, "checkout", request_id = "req_1").in_scope(|| {
+            info!(amount = 100, "Payment created");
+            tracing::trace!("Not captured at the default level");
+        });
+
+        let records = logs.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["message"], "[CHECKOUT - EVENT] Payment created");
+        assert_eq!(records[0]["amount"], 100);
+        assert_eq!(records[0]["request_id"], "req_1");
+        assert!(logs.contains_message("[CHECKOUT - END]"));
+
+        logs.clear();
+        assert!(logs.records().is_empty());
+
+        // A subsequent initialization does not fail, and captures the records independently
+        let nested_logs = test_support::init_for_tests();
+        info!("Refund created");
+        assert!(nested_logs.contains_message("Refund created"));
+        drop(nested_logs);
+        assert!(logs.records().is_empty());
+
+        // The global initialization is idempotent
+        test_support::init_global_for_tests();
+        test_support::init_global_for_tests();
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn test_golden_file_helpers() {
+        use test_support::{
+            FIXED_HOSTNAME, FIXED_PID, FIXED_TIME, assert_matches_golden, render_json_records,
+        };
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let render = |message: &'static str| {
+            render_json_records(config.clone(), [], || {
+                let span = span!(TracingLevel::INFO, "checkout");
+                let _guard = span.enter();
+                std::thread::sleep(std::time::Duration::from_millis(2));
+                info!(amount = 100, "{message}");
+            })
+            .unwrap()
+        };
+
+        let records = render("Payment created");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["time"], FIXED_TIME);
+        assert_eq!(records[0]["pid"], FIXED_PID);
+        assert_eq!(records[0]["hostname"], FIXED_HOSTNAME);
+        assert_eq!(records[1]["message"], "[CHECKOUT - END]");
+        assert_eq!(records[1]["elapsed_milliseconds"], 0);
+        assert_eq!(records, render("Payment created"));
+
+        let golden_path = std::env::temp_dir()
+            .join("log_utils_golden_test")
+            .join("checkout.json");
+        let _ = std::fs::remove_file(&golden_path);
+
+        // The golden file is created when missing, and matched subsequently
+        assert_matches_golden(&records, &golden_path);
+        assert!(golden_path.exists());
+        assert_matches_golden(&records, &golden_path);
+
+        let changed = render("Payment initiated");
+        let result = std::panic::catch_unwind(|| assert_matches_golden(&changed, &golden_path));
+        assert!(result.is_err());
+
+        let _ = std::fs::remove_file(&golden_path);
+    }
+
+    #[test]
+    fn test_top_level_keys_promotion() {
         let test_writer = TestWriter::new();
         let top_level_keys = HashSet::from(["user_id", "request_id"]);
 
@@ -557,6 +1917,7 @@ mod tests {
             top_level_keys,
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
         };
 
         let layer = JsonFormattingLayer::new(
@@ -598,6 +1959,7 @@ mod tests {
             top_level_keys: HashSet::from(["user_id"]),
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
+            ..Default::default()
         };
 
         let layer = JsonFormattingLayer::new(
@@ -643,6 +2005,7 @@ mod tests {
             top_level_keys: HashSet::from(["user_id", "session_id", "operation"]),
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
         };
 
         let formatting_layer = JsonFormattingLayer::new(
@@ -668,31 +2031,3265 @@ mod tests {
             let inner_span = span!(TracingLevel::INFO, "inner", operation = "process");
             let _inner_guard = inner_span.enter();
 
-            info!("Processing data");
-        });
+            info!("Processing data");
+        });
+
+        let output = test_writer.get_output();
+        let lines: Vec<&str> = output.trim().split('\n').collect();
+        let log_entry: Value = serde_json::from_str(lines[0]).unwrap();
+
+        // Verify persistent keys from parent span are available
+        assert_eq!(log_entry["user_id"], "123");
+        assert_eq!(log_entry["session_id"], "session-456");
+        assert_eq!(log_entry["operation"], "process");
+    }
+
+    #[test]
+    fn test_span_field_inheritance() {
+        let render = |field_inheritance: SpanFieldInheritance| {
+            let test_writer = TestWriter::new();
+            let storage_layer =
+                SpanStorageLayer::new(["user_id"]).with_field_inheritance(field_inheritance);
+
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(storage_layer)
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                let outer_span = span!(
+                    TracingLevel::INFO,
+                    "outer",
+                    user_id = "123",
+                    payload = "large",
+                    route = "/payments"
+                );
+                let _outer_guard = outer_span.enter();
+
+                let inner_span = span!(TracingLevel::INFO, "inner", operation = "process");
+                let _inner_guard = inner_span.enter();
+
+                info!("Processing data");
+            });
+
+            let output = test_writer.get_output();
+            let record: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+            let inherited: Vec<&str> = ["user_id", "payload", "route"]
+                .into_iter()
+                .filter(|key| record.get(key).is_some())
+                .collect();
+            assert_eq!(record["operation"], "process");
+            inherited
+        };
+
+        assert_eq!(
+            render(SpanFieldInheritance::All),
+            ["user_id", "payload", "route"]
+        );
+        assert!(render(SpanFieldInheritance::None).is_empty());
+        assert_eq!(render(SpanFieldInheritance::PersistentKeys), ["user_id"]);
+        assert_eq!(
+            render(SpanFieldInheritance::Only(HashSet::from(["route"]))),
+            ["route"]
+        );
+    }
+
+    #[test]
+    fn test_runtime_persistent_keys() {
+        let test_writer = TestWriter::new();
+        let storage_layer =
+            SpanStorageLayer::with_persistent_keys(PersistentKeys::new([String::from("user_id")]));
+        let persistent_keys = storage_layer.persistent_keys();
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent_span = span!(TracingLevel::INFO, "parent");
+            let _parent_guard = parent_span.enter();
+
+            span!(
+                TracingLevel::INFO,
+                "child",
+                user_id = "123",
+                tenant_id = "t_1"
+            )
+            .in_scope(|| {});
+            info!("Before adding key");
+
+            // Add a key at runtime, such as one derived from tenant configuration
+            assert!(persistent_keys.insert("tenant_id"));
+            span!(TracingLevel::INFO, "child", tenant_id = "t_2").in_scope(|| {});
+            info!("After adding key");
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[0]["user_id"], "123");
+        assert!(records[0].get("tenant_id").is_none());
+        assert_eq!(records[1]["tenant_id"], "t_2");
+        assert_eq!(
+            persistent_keys.keys(),
+            HashSet::from([String::from("user_id"), String::from("tenant_id")])
+        );
+    }
+
+    #[test]
+    fn test_span_storage_limits() {
+        let render = |storage_limits: SpanStorageLimits| {
+            let test_writer = TestWriter::new();
+            let storage_layer =
+                SpanStorageLayer::new(HashSet::new()).with_storage_limits(storage_limits);
+
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(storage_layer)
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                let stream_span = span!(
+                    TracingLevel::INFO,
+                    "stream",
+                    connection_id = "conn_1",
+                    last_chunk = tracing::field::Empty,
+                    last_offset = tracing::field::Empty,
+                    last_error = tracing::field::Empty
+                );
+                let _guard = stream_span.enter();
+
+                stream_span.record("last_chunk", "chunk_1");
+                // Updating an existing field is allowed even when the field count limit is reached
+                stream_span.record("last_chunk", "chunk_2");
+                stream_span.record("last_offset", 1024);
+                stream_span.record("last_error", "timeout");
+
+                info!("Chunk received");
+            });
+
+            let output = test_writer.get_output();
+            serde_json::from_str::<Value>(output.lines().next().unwrap()).unwrap()
+        };
+
+        let record = render(SpanStorageLimits {
+            max_fields: Some(2),
+            max_total_bytes: None,
+        });
+        assert_eq!(record["connection_id"], "conn_1");
+        assert_eq!(record["last_chunk"], "chunk_2");
+        assert!(record.get("last_offset").is_none());
+        assert!(record.get("last_error").is_none());
+        assert_eq!(record["dropped_fields"], 2);
+
+        // `connection_id` and `last_chunk` take 21 and 19 bytes respectively
+        let record = render(SpanStorageLimits {
+            max_fields: None,
+            max_total_bytes: Some(50),
+        });
+        assert_eq!(record["last_chunk"], "chunk_2");
+        assert!(record.get("last_offset").is_none());
+        assert!(record.get("last_error").is_none());
+        assert_eq!(record["dropped_fields"], 2);
+
+        let record = render(SpanStorageLimits::default());
+        assert_eq!(record["last_offset"], 1024);
+        assert_eq!(record["last_error"], "timeout");
+        assert!(record.get("dropped_fields").is_none());
+    }
+
+    #[test]
+    fn test_field_overwrite_policies() {
+        let test_writer = TestWriter::new();
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_field_overwrite_policies(
+            FieldOverwritePolicies {
+                default_policy: FieldOverwritePolicy::FirstWins,
+                key_policies: HashMap::from([
+                    (
+                        String::from("status"),
+                        FieldOverwritePolicy::CollectIntoArray,
+                    ),
+                    (String::from("attempt"), FieldOverwritePolicy::LastWins),
+                ]),
+            },
+        );
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent_span = span!(
+                TracingLevel::INFO,
+                "parent",
+                merchant_id = "m_1",
+                status = "created",
+                attempt = 1
+            );
+            let _parent_guard = parent_span.enter();
+            parent_span.record("merchant_id", "m_2");
+            parent_span.record("status", "processing");
+            parent_span.record("attempt", 2);
+
+            let child_span = span!(
+                TracingLevel::INFO,
+                "child",
+                merchant_id = "m_3",
+                status = "done"
+            );
+            let _child_guard = child_span.enter();
+
+            info!("Payment processed");
+        });
+
+        let output = test_writer.get_output();
+        let record: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(record["merchant_id"], "m_1");
+        assert_eq!(record["status"], json!(["created", "processing", "done"]));
+        assert_eq!(record["attempt"], 2);
+    }
+
+    #[test]
+    fn test_current_span_fields() {
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(["payment_id"]))
+            .with(
+                JsonFormattingLayer::new(
+                    JsonFormattingLayerConfig {
+                        static_top_level_fields: HashMap::new(),
+                        top_level_keys: HashSet::new(),
+                        log_span_lifecycles: false,
+                        additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                        ..Default::default()
+                    },
+                    io::sink,
+                    serde_json::ser::CompactFormatter,
+                )
+                .unwrap(),
+            );
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(current_span_fields().is_empty());
+
+            let request_span = span!(TracingLevel::INFO, "request", request_id = "req_123");
+            let _request_guard = request_span.enter();
+            span!(TracingLevel::INFO, "payment", payment_id = "pay_123").in_scope(|| {
+                assert_eq!(
+                    current_span_fields(),
+                    HashMap::from([
+                        (String::from("request_id"), json!("req_123")),
+                        (String::from("payment_id"), json!("pay_123")),
+                    ])
+                );
+            });
+
+            // The persistent key is propagated from the closed child span
+            let fields = current_span_fields();
+            assert_eq!(fields["request_id"], "req_123");
+            assert_eq!(fields["payment_id"], "pay_123");
+        });
+
+        // Without a subscriber, there is no current span
+        assert!(current_span_fields().is_empty());
+    }
+
+    #[test]
+    fn test_record_on_current_span() {
+        let test_writer = TestWriter::new();
+        let storage_layer = SpanStorageLayer::new(["merchant_id"]);
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!record_on_current_span("merchant_id", "m_1"));
+
+            let request_span = span!(TracingLevel::INFO, "request");
+            let _request_guard = request_span.enter();
+
+            span!(TracingLevel::INFO, "authenticate").in_scope(|| {
+                // The fields need not be declared when the span is created
+                assert!(record_on_current_span("merchant_id", "m_123"));
+                assert!(record_all_on_current_span([
+                    ("profile_id", json!("pro_123")),
+                    ("retry_count", json!(2)),
+                ]));
+                info!("Authenticated");
+            });
+
+            info!("Request completed");
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[0]["merchant_id"], "m_123");
+        assert_eq!(records[0]["profile_id"], "pro_123");
+        assert_eq!(records[0]["retry_count"], 2);
+
+        // Only the persistent key is propagated to the parent span
+        assert_eq!(records[1]["merchant_id"], "m_123");
+        assert!(records[1].get("profile_id").is_none());
+    }
+
+    #[cfg(feature = "masking")]
+    #[test]
+    fn test_masked_fields() {
+        use hyperswitch_masking::Secret;
+
+        #[derive(serde::Serialize)]
+        struct PaymentRequest {
+            amount: i64,
+            card_number: Secret<String>,
+        }
+
+        let test_writer = TestWriter::new();
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new([]))
+            .with(formatting_layer);
+
+        let request = PaymentRequest {
+            amount: 100,
+            card_number: Secret::new("4242424242424242".to_owned()),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            span!(TracingLevel::INFO, "payment").in_scope(|| {
+                assert!(record_on_current_span("request", masked(&request)));
+                info!(
+                    card_number = ?request.card_number,
+                    request_string = %masked(&request),
+                    "Payment request received"
+                );
+                record_on_current_span("level", "4242424242424242");
+                record_on_current_span("message", masked(&request));
+            });
+        });
+
+        let output = test_writer.get_output();
+        assert!(!output.contains("4242424242424242"));
+
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let masked_request = json!({
+            "amount": 100,
+            "card_number": "*** alloc::string::String ***",
+        });
+        assert_eq!(records[0]["request"], masked_request);
+        assert_eq!(records[0]["card_number"], "*** alloc::string::String ***");
+        assert_eq!(
+            serde_json::from_str::<Value>(records[0]["request_string"].as_str().unwrap()).unwrap(),
+            masked_request
+        );
+
+        // Fields with reserved keys are masked, and logged as errors in debug builds if their
+        // values came from masked types
+        assert_eq!(records[1]["level"], "WARN");
+        assert_eq!(
+            records[1]["message"],
+            "[PAYMENT - EVENT] Attempting to record a reserved key `level` (value: \"4**14**2\"). \
+             Skipping."
+        );
+        let expected_level = if cfg!(debug_assertions) {
+            "ERROR"
+        } else {
+            "WARN"
+        };
+        assert_eq!(records[2]["level"], expected_level);
+        assert!(
+            records[2]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("[PAYMENT - EVENT] Attempting to record a reserved key `message`"),
+            "{}",
+            records[2]
+        );
+    }
+
+    #[test]
+    fn test_persistent_key_propagation() {
+        let render = |persistent_key_propagation: PersistentKeyPropagation| {
+            let test_writer = TestWriter::new();
+            let storage_layer = SpanStorageLayer::new(["payment_id"])
+                .with_persistent_key_propagation(persistent_key_propagation);
+
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(storage_layer)
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                let root_span = span!(TracingLevel::INFO, "root");
+                let _root_guard = root_span.enter();
+
+                // The intermediate span remains open while the leaf span is closed
+                let intermediate_span = span!(TracingLevel::INFO, "intermediate");
+                let leaf_span = span!(
+                    parent: &intermediate_span,
+                    TracingLevel::INFO,
+                    "leaf",
+                    payment_id = "pay_123"
+                );
+                drop(leaf_span);
+
+                info!("Request completed");
+            });
+
+            let output = test_writer.get_output();
+            let record: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+            record.get("payment_id").cloned()
+        };
+
+        assert_eq!(render(PersistentKeyPropagation::Parent), None);
+        assert_eq!(
+            render(PersistentKeyPropagation::AllAncestors),
+            Some(Value::from("pay_123"))
+        );
+    }
+
+    #[test]
+    fn test_span_lifecycle_logging() {
+        let test_writer = TestWriter::new();
+
+        // Need storage layer to capture elapsed time
+        let storage_layer = SpanStorageLayer::new(HashSet::new());
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: true, // Enable span lifecycle logging
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = span!(TracingLevel::INFO, "test_span", operation = "test");
+            let _guard = span.enter();
+            info!("Inside span");
+            // Span ends when _guard is dropped
+        });
+
+        let output = test_writer.get_output();
+        let lines: Vec<&str> = output
+            .trim()
+            .split('\n')
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        // Should have: span start, event, span end
+        assert_eq!(lines.len(), 3);
+
+        // Parse each log entry
+        let start_entry: Value = serde_json::from_str(lines[0]).unwrap();
+        let event_entry: Value = serde_json::from_str(lines[1]).unwrap();
+        let end_entry: Value = serde_json::from_str(lines[2]).unwrap();
+
+        // Verify span start
+        assert_eq!(
+            start_entry["message"].as_str().unwrap(),
+            "[TEST_SPAN - START]"
+        );
+
+        // Verify event (includes span name prefix when inside a span)
+        assert_eq!(
+            event_entry["message"].as_str().unwrap(),
+            "[TEST_SPAN - EVENT] Inside span"
+        );
+
+        // Verify span end
+        assert_eq!(end_entry["message"].as_str().unwrap(), "[TEST_SPAN - END]");
+        assert!(end_entry["elapsed_milliseconds"].is_number());
+    }
+
+    #[test]
+    fn test_request_summary() {
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(["merchant_id"]).with_request_summary();
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request_span = span!(TracingLevel::INFO, "request");
+            let _request_guard = request_span.enter();
+            info!("Request received");
+
+            {
+                let auth_span = span!(TracingLevel::INFO, "authenticate", merchant_id = "m_1");
+                let _auth_guard = auth_span.enter();
+                info!("Authenticated");
+            }
+            {
+                let db_span = span!(TracingLevel::INFO, "database_query");
+                let _db_guard = db_span.enter();
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                tracing::warn!("Slow query");
+                tracing::error!("Query failed");
+            }
+        });
+
+        let output = test_writer.get_output();
+        let lines: Vec<&str> = output.trim().lines().collect();
+        let summary_record: Value = serde_json::from_str(lines[lines.len() - 1]).unwrap();
+
+        assert_eq!(summary_record["message"], "[REQUEST - END]");
+        assert_eq!(summary_record["merchant_id"], "m_1");
+        assert_eq!(
+            summary_record["request_summary"]["event_counts"],
+            json!({ "ERROR": 1, "INFO": 2, "WARN": 1 })
+        );
+        assert_eq!(summary_record["request_summary"]["child_span_count"], 2);
+        assert_eq!(
+            summary_record["request_summary"]["slowest_child_span"]["name"],
+            "database_query"
+        );
+        assert!(
+            summary_record["request_summary"]["slowest_child_span"]["elapsed_milliseconds"]
+                .as_u64()
+                .unwrap()
+                >= 20
+        );
+
+        // The summary is not recorded for child spans
+        let event_record: Value = serde_json::from_str(lines[0]).unwrap();
+        assert!(event_record.get("request_summary").is_none());
+    }
+
+    #[test]
+    fn test_span_busy_idle_time() {
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_busy_idle_time();
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request_span = span!(TracingLevel::INFO, "request");
+
+            // Busy across two entries, and idle in between
+            request_span.in_scope(|| std::thread::sleep(std::time::Duration::from_millis(20)));
+            std::thread::sleep(std::time::Duration::from_millis(40));
+            request_span.in_scope(|| std::thread::sleep(std::time::Duration::from_millis(20)));
+        });
+
+        let output = test_writer.get_output();
+        let record: Value = serde_json::from_str(output.trim().lines().last().unwrap()).unwrap();
+
+        assert_eq!(record["message"], "[REQUEST - END]");
+        let busy = record["busy_ms"].as_u64().unwrap();
+        let idle = record["idle_ms"].as_u64().unwrap();
+        assert!((40..80).contains(&busy), "busy time: {busy}");
+        assert!(idle >= 40, "idle time: {idle}");
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn test_baggage_keys() {
+        use opentelemetry::{Context, KeyValue, baggage::BaggageExt};
+
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_baggage_keys(["channel"]);
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::from(["channel"]),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            // Baggage extracted from the incoming request, as set by an upstream service
+            let _context_guard = Context::current_with_baggage([
+                KeyValue::new("channel", "mobile"),
+                KeyValue::new("internal_token", "secret"),
+            ])
+            .attach();
+
+            let request_span = span!(TracingLevel::INFO, "request");
+            let _request_guard = request_span.enter();
+            info!("Request received");
+        });
+
+        let output = test_writer.get_output();
+        let record: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(record["channel"], "mobile");
+        assert!(record.get("internal_token").is_none());
+        assert!(record["extra"].get("internal_token").is_none());
+    }
+
+    #[cfg(feature = "tower")]
+    #[test]
+    fn test_tower_request_logging() {
+        use std::{
+            convert::Infallible,
+            future::{Future, Ready, ready},
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        use http::{Request, Response, StatusCode};
+        use tower_layer::Layer as _;
+        use tower_service::Service;
+
+        use super::tower::RequestLoggingLayer;
+
+        #[derive(Clone, Debug)]
+        struct PaymentsService;
+
+        impl Service<Request<()>> for PaymentsService {
+            type Response = Response<()>;
+            type Error = Infallible;
+            type Future = Ready<Result<Self::Response, Self::Error>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, _request: Request<()>) -> Self::Future {
+                let span = span!(TracingLevel::INFO, "create_payment", payment_id = "pay_123");
+                span.in_scope(|| info!("Payment created"));
+                let mut response = Response::new(());
+                *response.status_mut() = StatusCode::CREATED;
+                ready(Ok(response))
+            }
+        }
+
+        let test_writer = TestWriter::new();
+        let formatting_layer = JsonFormattingLayer::new(
+            JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            },
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(["payment_id"]))
+            .with(formatting_layer);
+
+        let mut service = RequestLoggingLayer::new().layer(PaymentsService);
+        let response_request_ids = tracing::subscriber::with_default(subscriber, || {
+            let requests = [
+                Request::post("/payments").body(()).unwrap(),
+                Request::post("/payments")
+                    .header("x-request-id", "req_123")
+                    .body(())
+                    .unwrap(),
+            ];
+            requests
+                .into_iter()
+                .map(|request| {
+                    let mut future = pin!(service.call(request));
+                    let Poll::Ready(Ok(response)) = future
+                        .as_mut()
+                        .poll(&mut Context::from_waker(Waker::noop()))
+                    else {
+                        panic!("response future should be ready");
+                    };
+                    assert_eq!(response.status(), StatusCode::CREATED);
+                    response.headers()["x-request-id"]
+                        .to_str()
+                        .unwrap()
+                        .to_owned()
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 4);
+
+        // A request ID is generated for the first request
+        let generated_request_id = uuid::Uuid::parse_str(&response_request_ids[0]).unwrap();
+        assert_eq!(generated_request_id.get_version_num(), 4);
+        assert_eq!(
+            records[0]["message"],
+            "[CREATE_PAYMENT - EVENT] Payment created"
+        );
+        assert_eq!(records[0]["request_id"], response_request_ids[0]);
+
+        let access_record = &records[1];
+        assert_eq!(access_record["message"], "[HTTP_REQUEST - END]");
+        assert_eq!(access_record["request_id"], response_request_ids[0]);
+        assert_eq!(access_record["method"], "POST");
+        assert_eq!(access_record["path"], "/payments");
+        assert_eq!(access_record["status"], 201);
+        assert!(access_record["latency_ms"].is_u64());
+        assert_eq!(access_record["payment_id"], "pay_123");
+
+        // The request ID of the second request is read from its header
+        assert_eq!(response_request_ids[1], "req_123");
+        assert_eq!(records[3]["request_id"], "req_123");
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn test_axum_log_context_extractor() {
+        use std::{
+            convert::Infallible,
+            future::{Future, Ready, ready},
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        use axum_core::extract::FromRequestParts;
+        use http::{Request, Response};
+        use tower_layer::Layer as _;
+        use tower_service::Service;
+
+        use super::axum::{LogContext, RequestLoggingLayer};
+
+        /// Polls a future which is expected to be ready immediately.
+        fn poll_ready<F: Future>(future: F) -> F::Output {
+            let Poll::Ready(output) = pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+            else {
+                panic!("future should be ready");
+            };
+            output
+        }
+
+        /// A service returning the log context extracted from the request.
+        #[derive(Clone, Debug)]
+        struct LogContextService;
+
+        impl Service<Request<()>> for LogContextService {
+            type Response = Response<LogContext>;
+            type Error = Infallible;
+            type Future = Ready<Result<Self::Response, Self::Error>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, request: Request<()>) -> Self::Future {
+                let (mut parts, ()) = request.into_parts();
+                let log_context =
+                    poll_ready(LogContext::from_request_parts(&mut parts, &())).unwrap();
+                ready(Ok(Response::new(log_context)))
+            }
+        }
+
+        let subscriber = tracing_subscriber::registry().with(SpanStorageLayer::new(HashSet::new()));
+        let mut service = RequestLoggingLayer::new().layer(LogContextService);
+        let log_context = tracing::subscriber::with_default(subscriber, || {
+            let request = Request::get("/payments/pay_123")
+                .header("x-request-id", "req_123")
+                .body(())
+                .unwrap();
+            poll_ready(service.call(request)).unwrap().into_body()
+        });
+
+        assert_eq!(log_context.request_id(), Some("req_123"));
+        assert_eq!(log_context.get("method"), Some(&json!("GET")));
+        assert_eq!(log_context.get("path"), Some(&json!("/payments/pay_123")));
+
+        // Without the layers, the log context is empty
+        let (mut parts, ()) = Request::new(()).into_parts();
+        let log_context = poll_ready(LogContext::from_request_parts(&mut parts, &())).unwrap();
+        assert_eq!(log_context.request_id(), None);
+        assert!(log_context.fields().is_empty());
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_tonic_request_logging_and_propagation() {
+        use std::{
+            collections::VecDeque,
+            convert::Infallible,
+            future::{Future, Ready, ready},
+            pin::{Pin, pin},
+            sync::Mutex,
+            task::{Context, Poll, Waker},
+        };
+
+        use ::tonic::service::Interceptor;
+        use http::{HeaderMap, Request, Response};
+        use http_body::{Body, Frame};
+        use tower_layer::Layer as _;
+        use tower_service::Service;
+
+        use super::tonic::{GrpcClientInterceptor, GrpcServerLayer};
+
+        /// A response body yielding the specified frames.
+        #[derive(Debug)]
+        struct FramesBody(VecDeque<Frame<&'static [u8]>>);
+
+        impl Body for FramesBody {
+            type Data = &'static [u8];
+            type Error = Infallible;
+
+            fn poll_frame(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+                Poll::Ready(self.get_mut().0.pop_front().map(Ok))
+            }
+        }
+
+        /// A gRPC service which calls another service, and responds with the specified status.
+        #[derive(Clone, Debug)]
+        struct PaymentsService {
+            outgoing_metadata: Arc<Mutex<Vec<HashMap<String, String>>>>,
+        }
+
+        impl Service<Request<()>> for PaymentsService {
+            type Response = Response<FramesBody>;
+            type Error = Infallible;
+            type Future = Ready<Result<Self::Response, Self::Error>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, request: Request<()>) -> Self::Future {
+                info!("Creating payment");
+
+                let outgoing_request = GrpcClientInterceptor::new()
+                    .with_propagated_fields(["merchant_id"])
+                    .call(::tonic::Request::new(()))
+                    .unwrap();
+                self.outgoing_metadata.lock().unwrap().push(
+                    outgoing_request
+                        .metadata()
+                        .clone()
+                        .into_headers()
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_str().unwrap().to_owned()))
+                        .collect(),
+                );
+
+                let status = request.headers()["test-status"].clone();
+                let mut trailers = HeaderMap::new();
+                trailers.insert("grpc-status", status.clone());
+                let response = if status == "0" {
+                    Response::new(FramesBody(VecDeque::from([
+                        Frame::data(b"payment".as_slice()),
+                        Frame::trailers(trailers),
+                    ])))
+                } else {
+                    // Trailers-only response
+                    let mut response = Response::new(FramesBody(VecDeque::new()));
+                    *response.headers_mut() = trailers;
+                    response
+                };
+                ready(Ok(response))
+            }
+        }
+
+        let test_writer = TestWriter::new();
+        let formatting_layer = JsonFormattingLayer::new(
+            JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            },
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(formatting_layer);
+
+        let outgoing_metadata = Arc::new(Mutex::new(Vec::new()));
+        let mut service = GrpcServerLayer::new()
+            .with_propagated_fields(["merchant_id"])
+            .layer(PaymentsService {
+                outgoing_metadata: Arc::clone(&outgoing_metadata),
+            });
+        tracing::subscriber::with_default(subscriber, || {
+            for status in ["0", "5"] {
+                let request = Request::post("/payments.Payments/Create")
+                    .header("x-request-id", "req_123")
+                    .header("merchant_id", "m_1")
+                    .header("test-status", status)
+                    .body(())
+                    .unwrap();
+                let mut waker_context = Context::from_waker(Waker::noop());
+                let Poll::Ready(Ok(response)) =
+                    pin!(service.call(request)).poll(&mut waker_context)
+                else {
+                    panic!("response future should be ready");
+                };
+
+                let mut body = pin!(response.into_body());
+                while let Poll::Ready(Some(_)) = body.as_mut().poll_frame(&mut waker_context) {}
+            }
+        });
+
+        // The request ID and the propagated fields are injected into outgoing requests
+        let outgoing_metadata = outgoing_metadata.lock().unwrap();
+        assert_eq!(outgoing_metadata[0]["x-request-id"], "req_123");
+        assert_eq!(outgoing_metadata[0]["merchant_id"], "m_1");
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            records[0]["message"],
+            "[GRPC_REQUEST - EVENT] Creating payment"
+        );
+        assert_eq!(records[0]["merchant_id"], "m_1");
+
+        let access_record = &records[1];
+        assert_eq!(access_record["message"], "[GRPC_REQUEST - END]");
+        assert_eq!(access_record["request_id"], "req_123");
+        assert_eq!(access_record["rpc_service"], "payments.Payments");
+        assert_eq!(access_record["rpc_method"], "Create");
+        assert_eq!(access_record["grpc_status"], 0);
+        assert!(access_record["latency_ms"].is_u64());
+
+        assert_eq!(records[3]["grpc_status"], 5);
+    }
+
+    #[cfg(all(feature = "tonic", feature = "opentelemetry"))]
+    #[test]
+    fn test_tonic_trace_context_propagation() {
+        use std::{
+            convert::Infallible,
+            future::{Future, Ready, ready},
+            pin::pin,
+            sync::Mutex,
+            task::{Context as TaskContext, Poll, Waker},
+        };
+
+        use ::tonic::service::Interceptor;
+        use http::{Request, Response};
+        use opentelemetry::{
+            Context,
+            propagation::{Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter},
+        };
+        use tower_layer::Layer as _;
+        use tower_service::Service;
+
+        use super::tonic::{GrpcClientInterceptor, GrpcServerLayer};
+
+        /// The `traceparent` entry carried by the OpenTelemetry context.
+        #[derive(Debug)]
+        struct TraceParent(String);
+
+        /// A propagator of the `traceparent` entry.
+        #[derive(Debug)]
+        struct TraceParentPropagator(Vec<String>);
+
+        impl TextMapPropagator for TraceParentPropagator {
+            fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+                if let Some(TraceParent(value)) = cx.get() {
+                    injector.set("traceparent", value.clone());
+                }
+            }
+
+            fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+                match extractor.get("traceparent") {
+                    Some(value) => cx.with_value(TraceParent(value.to_owned())),
+                    None => cx.clone(),
+                }
+            }
+
+            fn fields(&self) -> FieldIter<'_> {
+                FieldIter::new(&self.0)
+            }
+        }
+
+        /// A gRPC service which calls another service.
+        #[derive(Clone, Debug)]
+        struct PaymentsService {
+            outgoing_traceparent: Arc<Mutex<Option<String>>>,
+        }
+
+        impl Service<Request<()>> for PaymentsService {
+            type Response = Response<()>;
+            type Error = Infallible;
+            type Future = Ready<Result<Self::Response, Self::Error>>;
+
+            fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, _request: Request<()>) -> Self::Future {
+                let outgoing_request = GrpcClientInterceptor::new()
+                    .call(::tonic::Request::new(()))
+                    .unwrap();
+                *self.outgoing_traceparent.lock().unwrap() = outgoing_request
+                    .metadata()
+                    .get("traceparent")
+                    .map(|value| value.to_str().unwrap().to_owned());
+                ready(Ok(Response::new(())))
+            }
+        }
+
+        opentelemetry::global::set_text_map_propagator(TraceParentPropagator(vec![
+            "traceparent".to_string(),
+        ]));
+
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let request = Request::post("/payments.Payments/Create")
+            .header("traceparent", traceparent)
+            .body(())
+            .unwrap();
+        let outgoing_traceparent = Arc::new(Mutex::new(None));
+        let mut service = GrpcServerLayer::new().layer(PaymentsService {
+            outgoing_traceparent: Arc::clone(&outgoing_traceparent),
+        });
+        let Poll::Ready(Ok(_)) =
+            pin!(service.call(request)).poll(&mut TaskContext::from_waker(Waker::noop()))
+        else {
+            panic!("response future should be ready");
+        };
+
+        // The trace context extracted from the incoming request is injected into outgoing requests
+        assert_eq!(
+            outgoing_traceparent.lock().unwrap().as_deref(),
+            Some(traceparent)
+        );
+    }
+
+    #[test]
+    fn test_span_counters() {
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_span_counters();
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: true,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request_span = span!(TracingLevel::INFO, "request");
+            let _request_guard = request_span.enter();
+            info!("Request received");
+
+            span!(TracingLevel::INFO, "database_query").in_scope(|| {
+                span!(TracingLevel::INFO, "connect").in_scope(|| {});
+                tracing::warn!("Slow query");
+                tracing::error!("Query failed");
+            });
+        });
+
+        let output = test_writer.get_output();
+        let end_records: HashMap<String, Value> = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .filter_map(|record| {
+                let message = record["message"].as_str()?.to_owned();
+                message.ends_with("- END]").then_some((message, record))
+            })
+            .collect();
+
+        let request_record = &end_records["[REQUEST - END]"];
+        assert_eq!(request_record["event_count"], 3);
+        assert_eq!(request_record["error_event_count"], 1);
+        assert_eq!(request_record["child_span_count"], 2);
+
+        let query_record = &end_records["[DATABASE_QUERY - END]"];
+        assert_eq!(query_record["event_count"], 2);
+        assert_eq!(query_record["error_event_count"], 1);
+        assert_eq!(query_record["child_span_count"], 1);
+
+        let connect_record = &end_records["[CONNECT - END]"];
+        assert_eq!(connect_record["event_count"], 0);
+        assert_eq!(connect_record["child_span_count"], 0);
+    }
+
+    #[test]
+    fn test_span_memory_usage() {
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_memory_usage();
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            span!(TracingLevel::INFO, "request").in_scope(|| {
+                info!("Request received");
+            });
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let [event_record, end_record] = records.as_slice() else {
+            panic!("Expected an event record and a span exit record");
+        };
+        assert!(event_record.get("rss_bytes").is_none());
+        assert_eq!(end_record["message"], "[REQUEST - END]");
+        if cfg!(target_os = "linux") {
+            assert!(end_record["rss_bytes"].as_u64().unwrap() > 0);
+        } else {
+            assert!(end_record.get("rss_bytes").is_none());
+        }
+    }
+
+    #[test]
+    fn test_omit_empty_fields() {
+        let render = |omit_empty_fields: bool| -> (Value, Value) {
+            let test_writer = TestWriter::new();
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::from([
+                    ("service".to_string(), json!("payments")),
+                    ("region".to_string(), Value::Null),
+                    ("labels".to_string(), json!({})),
+                ]),
+                top_level_keys: HashSet::from(["tags"]),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
+                omit_empty_fields,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+            let schema = formatting_layer.json_schema();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(SpanStorageLayer::new(HashSet::new()))
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                span!(TracingLevel::INFO, "request").in_scope(|| {
+                    record_all_on_current_span([("tags", json!([])), ("customer_id", Value::Null)]);
+                    info!("Payment created");
+                });
+            });
+
+            let output = test_writer.get_output();
+            let record = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+            (record, schema)
+        };
+
+        let (record, _) = render(false);
+        assert_eq!(record["region"], Value::Null);
+        assert_eq!(record["labels"], json!({}));
+        assert_eq!(record["tags"], json!([]));
+        assert_eq!(record["extra"]["customer_id"], Value::Null);
+
+        let (record, schema) = render(true);
+        let record = record.as_object().unwrap();
+        assert_eq!(record["service"], "payments");
+        assert!(record.contains_key("line"));
+        assert!(record.contains_key("file"));
+        for key in ["region", "labels", "tags", "extra"] {
+            assert!(!record.contains_key(key), "`{key}` was not omitted");
+        }
+
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&json!("service")));
+        for key in ["region", "labels", "line", "file"] {
+            assert!(!required.contains(&json!(key)), "`{key}` is required");
+        }
+        assert_eq!(schema["properties"]["line"]["type"], "integer");
+    }
+
+    #[test]
+    fn test_code_location_fields() {
+        let render = |code_location_fields: CodeLocationFields| -> (Vec<Value>, Value) {
+            let test_writer = TestWriter::new();
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                code_location_fields,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+            let schema = formatting_layer.json_schema();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(SpanStorageLayer::new(HashSet::new()))
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                info!("Payment created");
+                tracing::warn!("Payment retried");
+                tracing::error!("Payment failed");
+            });
+
+            let records = test_writer
+                .get_output()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            (records, schema)
+        };
+        let has_code_location = |record: &Value| {
+            ["file", "line", "fn", "full_name"]
+                .iter()
+                .all(|key| record.get(key).is_some())
+        };
+        let has_no_code_location = |record: &Value| {
+            ["file", "line", "fn", "full_name"]
+                .iter()
+                .all(|key| record.get(key).is_none())
+        };
+
+        let (records, schema) = render(CodeLocationFields::Always);
+        assert!(records.iter().all(has_code_location));
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("line"))
+        );
+
+        let (records, schema) = render(CodeLocationFields::Never);
+        assert!(records.iter().all(has_no_code_location));
+        assert!(schema["properties"].get("line").is_none());
+
+        let (records, schema) = render(CodeLocationFields::AtOrAbove(Level::WARN));
+        assert!(has_no_code_location(&records[0]));
+        assert!(has_code_location(&records[1]));
+        assert!(has_code_location(&records[2]));
+        assert!(schema["properties"].get("line").is_some());
+        assert!(
+            !schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("line"))
+        );
+    }
+
+    #[test]
+    fn test_timestamp_format() {
+        let render = |timestamp_format: TimestampFormat| -> (Value, Value) {
+            let test_writer = TestWriter::new();
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                timestamp_format,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+            let schema = formatting_layer.json_schema();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(SpanStorageLayer::new(HashSet::new()))
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                info!("Payment created");
+            });
+
+            let record: Value = serde_json::from_str(test_writer.get_output().trim()).unwrap();
+            (record["time"].clone(), schema["properties"]["time"].clone())
+        };
+        let fraction_digits = |time: &Value| {
+            let time = time.as_str().unwrap();
+            assert!(time.ends_with('Z'));
+            time.split_once('.')
+                .map_or(0, |(_, fraction)| fraction.len() - 1)
+        };
+
+        let (time, schema) = render(TimestampFormat::Seconds);
+        assert_eq!(fraction_digits(&time), 0);
+        assert_eq!(schema["type"], "string");
+        let (time, _) = render(TimestampFormat::Milliseconds);
+        assert_eq!(fraction_digits(&time), 3);
+        let (time, _) = render(TimestampFormat::Microseconds);
+        assert_eq!(fraction_digits(&time), 6);
+        let (time, _) = render(TimestampFormat::Nanoseconds);
+        assert_eq!(fraction_digits(&time), 9);
+
+        let (time, schema) = render(TimestampFormat::EpochMilliseconds);
+        assert!(time.as_u64().unwrap() > 1_700_000_000_000);
+        assert_eq!(schema["type"], "integer");
+    }
+
+    #[test]
+    fn test_timestamp_timezone() {
+        let render = |timestamp_timezone: TimestampTimezone| -> String {
+            let test_writer = TestWriter::new();
+            let config = JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                timestamp_format: TimestampFormat::Seconds,
+                timestamp_timezone,
+                ..Default::default()
+            };
+            let formatting_layer = JsonFormattingLayer::new(
+                config,
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+
+            let subscriber = tracing_subscriber::registry()
+                .with(SpanStorageLayer::new(HashSet::new()))
+                .with(formatting_layer);
+
+            tracing::subscriber::with_default(subscriber, || {
+                info!("Payment created");
+            });
+
+            let record: Value = serde_json::from_str(test_writer.get_output().trim()).unwrap();
+            record["time"].as_str().unwrap().to_owned()
+        };
+        let utc_time = render(TimestampTimezone::Utc);
+        assert!(utc_time.ends_with('Z'));
+
+        let ist = time::UtcOffset::from_hms(5, 30, 0).unwrap();
+        let ist_time = render(TimestampTimezone::FixedOffset(ist));
+        assert!(ist_time.ends_with("+05:30"));
+
+        // The local offset may be unavailable in the multi-threaded test harness, in which case
+        // UTC is used instead
+        let local_offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
+        let local_time = render(TimestampTimezone::Local);
+        if local_offset.is_utc() {
+            assert!(local_time.ends_with('Z'));
+        } else {
+            assert!(local_time.ends_with(&format!(
+                "{:+03}:{:02}",
+                local_offset.whole_hours(),
+                local_offset.minutes_past_hour().abs()
+            )));
+        }
+    }
+
+    #[test]
+    fn test_chrome_trace_layer() {
+        let layer = ChromeTraceLayer::new(ChromeTraceConfig { max_events: 3 });
+        let capture = layer.capture();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        let trace_events = |capture: &ChromeTraceCapture| {
+            let mut trace = Vec::new();
+            capture.write_trace(&mut trace).unwrap();
+            let trace: Value = serde_json::from_slice(&trace).unwrap();
+            trace["traceEvents"].as_array().unwrap().clone()
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            // Nothing is recorded before a capture is started
+            span!(TracingLevel::INFO, "startup").in_scope(|| {});
+            assert!(!capture.is_capturing());
+
+            capture.start(std::time::Duration::from_secs(60));
+            assert!(capture.is_capturing());
+            span!(TracingLevel::INFO, "request").in_scope(|| {
+                span!(TracingLevel::INFO, "db_query").in_scope(|| {});
+            });
+        });
+
+        // The capture ends once the maximum number of events is reached
+        assert!(!capture.is_capturing());
+        let trace_events = trace_events(&capture);
+        let phases = trace_events
+            .iter()
+            .map(|event| {
+                (
+                    event["name"].as_str().unwrap(),
+                    event["ph"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            [("request", "B"), ("db_query", "B"), ("db_query", "E")]
+        );
+        assert!(trace_events.iter().all(|event| event["ts"].is_number()
+            && event["pid"].is_number()
+            && event["tid"].is_number()));
+    }
+
+    #[test]
+    fn test_folded_stack_layer() {
+        let test_writer = TestWriter::new();
+        let layer = FoldedStackLayer::new(test_writer.clone());
+        let profiling = layer.profiling();
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        let busy_wait = |duration: std::time::Duration| {
+            let start = std::time::Instant::now();
+            while start.elapsed() < duration {}
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request = span!(TracingLevel::INFO, "request");
+            request.in_scope(|| {
+                busy_wait(std::time::Duration::from_millis(2));
+                span!(TracingLevel::INFO, "db_query")
+                    .in_scope(|| busy_wait(std::time::Duration::from_millis(2)));
+                busy_wait(std::time::Duration::from_millis(2));
+            });
+
+            profiling.set_enabled(false);
+            request.in_scope(|| busy_wait(std::time::Duration::from_millis(2)));
+        });
+
+        let output = test_writer.get_output();
+        let samples = output
+            .lines()
+            .map(|line| {
+                let (stack, micros) = line.rsplit_once(' ').unwrap();
+                (stack, micros.parse::<u128>().unwrap())
+            })
+            .collect::<Vec<_>>();
+        let stacks = samples.iter().map(|(stack, _)| *stack).collect::<Vec<_>>();
+        assert_eq!(stacks, ["request", "request;db_query", "request"]);
+        assert!(samples.iter().all(|(_, micros)| *micros >= 2_000));
+    }
+
+    #[test]
+    fn test_routing_layer() {
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let json_layer = |writer: TestWriter| {
+            JsonFormattingLayer::new(config.clone(), writer, serde_json::ser::CompactFormatter)
+                .unwrap()
+        };
+        let messages = |writer: &TestWriter| -> Vec<String> {
+            writer
+                .get_output()
+                .lines()
+                .map(|line| {
+                    let record: Value = serde_json::from_str(line).unwrap();
+                    record["message"].as_str().unwrap().to_owned()
+                })
+                .collect()
+        };
+
+        let (acme_writer, globex_writer, default_writer) =
+            (TestWriter::new(), TestWriter::new(), TestWriter::new());
+        let routing_layer = RoutingLayer::new("tenant")
+            .with_route("acme", json_layer(acme_writer.clone()))
+            .with_route("globex", json_layer(globex_writer.clone()))
+            .with_default_route(json_layer(default_writer.clone()));
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(routing_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            span!(TracingLevel::INFO, "request", tenant = "acme").in_scope(|| {
+                info!("Payment created");
+                info!(tenant = "globex", "Payment forwarded");
+            });
+            info!(tenant = "initech", "Payment refunded");
+            info!("Server started");
+        });
+
+        assert_eq!(
+            messages(&acme_writer),
+            ["[REQUEST - EVENT] Payment created", "[REQUEST - END]"]
+        );
+        assert_eq!(
+            messages(&globex_writer),
+            ["[REQUEST - EVENT] Payment forwarded"]
+        );
+        assert_eq!(
+            messages(&default_writer),
+            ["Payment refunded", "Server started"]
+        );
+    }
+
+    #[test]
+    fn test_span_lifecycle_logging_toggle() {
+        let test_writer = TestWriter::new();
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let span_lifecycle_logging = formatting_layer.span_lifecycle_logging();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(formatting_layer);
+
+        let messages = || -> Vec<String> {
+            let output = test_writer.get_output();
+            test_writer.buffer.lock().unwrap().clear();
+            output
+                .lines()
+                .map(|line| {
+                    let record: Value = serde_json::from_str(line).unwrap();
+                    record["message"].as_str().unwrap().to_owned()
+                })
+                .collect()
+        };
+        let run_request = || {
+            span!(TracingLevel::INFO, "request").in_scope(|| {
+                span!(TracingLevel::INFO, "fast_query").in_scope(|| {});
+                span!(TracingLevel::INFO, "slow_query")
+                    .in_scope(|| std::thread::sleep(std::time::Duration::from_millis(20)));
+            });
+        };
+
+        tracing::subscriber::with_default(subscriber, || {
+            run_request();
+            assert_eq!(messages(), ["[REQUEST - END]"]);
+
+            span_lifecycle_logging.set_log_span_lifecycles(true);
+            run_request();
+            assert_eq!(
+                messages(),
+                [
+                    "[REQUEST - START]",
+                    "[FAST_QUERY - START]",
+                    "[FAST_QUERY - END]",
+                    "[SLOW_QUERY - START]",
+                    "[SLOW_QUERY - END]",
+                    "[REQUEST - END]",
+                ]
+            );
+
+            span_lifecycle_logging.set_log_span_lifecycles(false);
+            span_lifecycle_logging
+                .set_slow_span_threshold(Some(std::time::Duration::from_millis(10)));
+            run_request();
+            assert_eq!(messages(), ["[SLOW_QUERY - END]", "[REQUEST - END]"]);
+        });
+
+        assert_eq!(
+            span_lifecycle_logging.slow_span_threshold(),
+            Some(std::time::Duration::from_millis(10))
+        );
+        span_lifecycle_logging.set_slow_span_threshold(None);
+        assert_eq!(span_lifecycle_logging.slow_span_threshold(), None);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_spawn_instrumented() {
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new());
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            runtime.block_on(async {
+                let request_span = span!(TracingLevel::INFO, "request", request_id = "req_1");
+                let _request_guard = request_span.enter();
+
+                spawn_instrumented("refresh_cache", async {
+                    info!("Refreshing cache");
+                })
+                .await
+                .unwrap();
+
+                let result = spawn_instrumented("sync_refunds", async {
+                    panic!("Refund sync failed");
+                })
+                .await;
+                assert!(result.unwrap_err().is_panic());
+
+                let result = spawn_blocking_instrumented("compress_archive", || {
+                    panic!("{} bytes left", 10);
+                })
+                .await;
+                assert!(result.unwrap_err().is_panic());
+            });
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let refresh_record = records
+            .iter()
+            .find(|record| record["message"] == "[TASK - EVENT] Refreshing cache")
+            .unwrap();
+        assert_eq!(refresh_record["task_name"], "refresh_cache");
+        assert_eq!(refresh_record["request_id"], "req_1");
+
+        let panic_records: Vec<&Value> = records
+            .iter()
+            .filter(|record| record["message"] == "[TASK - EVENT] Task panicked")
+            .collect();
+        assert_eq!(panic_records.len(), 2);
+        assert_eq!(panic_records[0]["level"], "ERROR");
+        assert_eq!(panic_records[0]["task_name"], "sync_refunds");
+        assert_eq!(panic_records[0]["panic"], "Refund sync failed");
+        assert_eq!(panic_records[1]["task_name"], "compress_archive");
+        assert_eq!(panic_records[1]["panic"], "10 bytes left");
+    }
+
+    #[test]
+    fn test_sql_query_logging() {
+        assert_eq!(
+            sql::sanitize_sql(
+                "SELECT * FROM \"payments\"\n  WHERE merchant_id = 'm_''1' AND amount > 100.5 \
+                 AND id = $1 AND col2 = 2"
+            ),
+            "SELECT * FROM \"payments\" WHERE merchant_id = ? AND amount > ? AND id = $1 AND \
+             col2 = ?"
+        );
+        assert_eq!(
+            sql::sanitize_sql(
+                "UPDATE cards SET hash = 0xDEAD, rate = 1e5, note = E'it\\'s ''a'' \\\\' \
+                 WHERE body = $$it's$$ AND name = $tag$a $$ b$tag$ AND id = $2"
+            ),
+            "UPDATE cards SET hash = ?, rate = ?, note = ? WHERE body = ? AND name = ? AND id = $2"
+        );
+
+        let test_writer = TestWriter::new();
+
+        let storage_layer = SpanStorageLayer::new(HashSet::new());
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: true,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+
+        let subscriber = tracing_subscriber::registry()
+            .with(storage_layer)
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let sql_query_logging =
+                sql::SqlQueryLogging::new().with_slow_query_threshold(std::time::Duration::ZERO);
+
+            let query = sql_query_logging
+                .start_query("UPDATE payments SET status = 'charged' WHERE amount = 100");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            query.finish(Some(2));
+
+            let query = sql::SqlQueryLogging::new().start_query("DELETE FROM refunds");
+            query.fail(&"relation \"refunds\" does not exist");
+        });
+
+        let output = test_writer.get_output();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let end_records: Vec<&Value> = records
+            .iter()
+            .filter(|record| record["message"] == "[SQL_QUERY - END]")
+            .collect();
+        assert_eq!(end_records.len(), 2);
+        assert_eq!(
+            end_records[0]["db_statement"],
+            "UPDATE payments SET status = ? WHERE amount = ?"
+        );
+        assert_eq!(end_records[0]["rows"], 2);
+        assert!(end_records[0]["duration_ms"].as_u64().unwrap() >= 1);
+        assert_eq!(end_records[1]["db_statement"], "DELETE FROM refunds");
+        assert_eq!(
+            end_records[1]["error"],
+            "relation \"refunds\" does not exist"
+        );
+
+        let slow_query_records: Vec<&Value> = records
+            .iter()
+            .filter(|record| record["message"] == "Slow query")
+            .collect();
+        assert_eq!(slow_query_records.len(), 1);
+        assert_eq!(slow_query_records[0]["level"], "WARN");
+        assert_eq!(slow_query_records[0]["slow_query_threshold_ms"], 0);
+    }
+
+    #[test]
+    fn test_human_readable_formatting_layer_output() {
+        let test_writer = TestWriter::new();
+
+        let options = HumanReadableOptions {
+            ansi_colors: false,
+            inline_keys: vec!["request_id"],
+            field_order: vec!["zeta"],
+            span_context: true,
+            ..Default::default()
+        };
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(HumanReadableFormattingLayer::new(
+                options,
+                test_writer.clone(),
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let outer_span = span!(TracingLevel::INFO, "outer", request_id = "req_123");
+            let _outer_guard = outer_span.enter();
+
+            let inner_span = span!(TracingLevel::INFO, "inner", alpha = 1);
+            let _inner_guard = inner_span.enter();
+
+            info!(zeta = true, "Processing data");
+        });
+
+        let output = test_writer.get_output();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+
+        // Verify level, span context, target, message and inline fields
+        assert!(lines[0].ends_with(
+            " INFO outer:inner: log_utils::tracing::tests: Processing data request_id=req_123"
+        ));
+        assert!(!lines[0].contains('\u{1b}'));
+
+        // Verify that ordered fields precede the alphabetically ordered remaining fields
+        assert_eq!(lines[1], "    zeta: true");
+        assert_eq!(lines[2], "    alpha: 1");
+        assert!(lines[3].starts_with("    at "));
+    }
+
+    #[test]
+    fn test_human_readable_formatting_layer_ansi_colors() {
+        let test_writer = TestWriter::new();
+
+        let subscriber = tracing_subscriber::registry().with(HumanReadableFormattingLayer::new(
+            HumanReadableOptions::default(),
+            test_writer.clone(),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("Something failed");
+        });
+
+        let output = test_writer.get_output();
+        assert!(output.contains(&format!(
+            "{}",
+            nu_ansi_term::Color::Red.bold().paint("ERROR")
+        )));
+    }
+
+    #[test]
+    fn test_human_readable_block_fields() {
+        let test_writer = TestWriter::new();
+
+        let options = HumanReadableOptions {
+            ansi_colors: false,
+            block_threshold: 16,
+            ..Default::default()
+        };
+
+        let subscriber = tracing_subscriber::registry().with(HumanReadableFormattingLayer::new(
+            options,
+            test_writer.clone(),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(
+                error = "connection refused",
+                payload = r#"{"amount":100,"currency":"USD"}"#,
+                status = 500,
+                "Request failed"
+            );
+        });
+
+        let output = test_writer.get_output();
+        let lines: Vec<&str> = output.lines().skip(1).collect();
+
+        assert_eq!(
+            lines[..7],
+            [
+                "    error:",
+                "        connection refused",
+                "    payload:",
+                "        {",
+                "          \"amount\": 100,",
+                "          \"currency\": \"USD\"",
+                "        }",
+            ]
+        );
+        assert_eq!(lines[7], "    status: 500");
+    }
+
+    #[test]
+    fn test_compact_formatting_layer_output() {
+        let test_writer = TestWriter::new();
+
+        let options = HumanReadableOptions {
+            ansi_colors: false,
+            inline_keys: vec!["request_id"],
+            compact_max_field_length: 8,
+            ..Default::default()
+        };
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(HumanReadableFormattingLayer::compact(
+                options,
+                test_writer.clone(),
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = span!(TracingLevel::INFO, "handler", request_id = "req_123");
+            let _guard = span.enter();
+
+            tracing::warn!(payload = "a very long payload", count = 3, "Slow request");
+        });
+
+        let output = test_writer.get_output();
+        assert_eq!(
+            output,
+            " WARN log_utils::tracing::tests Slow request request_id=req_123 count=3 \
+             payload=a very l…\n"
+        );
+    }
+
+    #[test]
+    fn test_formatting_layers_route_records_by_level() {
+        use tracing_subscriber::fmt::writer::MakeWriterExt;
+
+        let warn_writer = TestWriter::new();
+        let info_writer = TestWriter::new();
+        let routing_writer = || {
+            warn_writer
+                .clone()
+                .with_max_level(TracingLevel::WARN)
+                .or_else(info_writer.clone())
+        };
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+        let json_layer =
+            JsonFormattingLayer::new(config, routing_writer(), serde_json::ser::CompactFormatter)
+                .unwrap();
+        let human_readable_layer = HumanReadableFormattingLayer::compact(
+            HumanReadableOptions::default(),
+            routing_writer(),
+        );
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(json_layer)
+            .with(human_readable_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = span!(TracingLevel::INFO, "handler");
+            let _guard = span.enter();
+
+            info!("Request received");
+            tracing::warn!("Slow request");
+            tracing::error!("Request failed");
+        });
+
+        let warn_output = warn_writer.get_output();
+        let info_output = info_writer.get_output();
+        assert_eq!(warn_output.lines().count(), 4);
+        assert!(warn_output.contains("Slow request"));
+        assert!(warn_output.contains("Request failed"));
+        assert!(!warn_output.contains("Request received"));
+
+        // The event and the root span exit record
+        assert_eq!(info_output.lines().count(), 3);
+        assert!(info_output.contains("Request received"));
+        assert!(info_output.contains("[HANDLER - END]"));
+    }
+
+    #[test]
+    fn test_sampling_layer_samples_events_by_target() {
+        let test_writer = TestWriter::new();
+
+        let sampling_layer = SamplingLayer::new(SamplingConfig {
+            default_rate: 0.0,
+            target_rates: HashMap::from([
+                ("my_app".to_string(), 0.5),
+                ("my_app::payments".to_string(), 1.0),
+                ("my_app::db".to_string(), 0.0),
+            ]),
+        })
+        .unwrap();
+        let subscriber = tracing_subscriber::registry().with(sampling_layer).with(
+            HumanReadableFormattingLayer::compact(
+                HumanReadableOptions {
+                    ansi_colors: false,
+                    ..Default::default()
+                },
+                test_writer.clone(),
+            ),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..1000 {
+                info!(target: "my_app::payments", "Payment created");
+                info!(target: "my_app::payments_v2", "Payment created");
+                info!(target: "my_app::db::pool", "Connection acquired");
+                info!(target: "other_app", "Request received");
+            }
+            info!(target: "other_app", audit = true, "Refund issued");
+        });
+
+        let output = test_writer.get_output();
+        let count = |target: &str| {
+            output
+                .lines()
+                .filter(|line| line.split(' ').nth(2) == Some(target))
+                .count()
+        };
+        assert_eq!(count("my_app::payments"), 1000);
+        assert_eq!(count("my_app::db::pool"), 0);
+        assert_eq!(count("other_app"), 1); // Audit events are never sampled out
+
+        // Matches `my_app`, rather than `my_app::payments`
+        let sampled = count("my_app::payments_v2");
+        assert!((300..700).contains(&sampled), "{sampled}");
+
+        assert!(matches!(
+            SamplingLayer::new(SamplingConfig {
+                default_rate: 1.5,
+                target_rates: HashMap::new(),
+            }),
+            Err(LoggerError::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_alert_layer_notifies_events_at_or_above_level() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let alert_layer = AlertLayer::new(AlertConfig {
+            level: Level::WARN,
+            hook: AlertHook::Channel(sender),
+        });
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(alert_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = span!(TracingLevel::INFO, "payment", payment_id = "pay_123");
+            let _guard = span.enter();
+
+            info!("Not alerted");
+            tracing::warn!(attempt = 2, "Retrying payment");
+            tracing::error!(error = "timeout", "Payment failed");
+        });
+
+        let records: Vec<AlertRecord> = receiver.try_iter().collect();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].level, Level::WARN);
+        assert_eq!(records[0].message.as_deref(), Some("Retrying payment"));
+        assert_eq!(records[0].span_name, Some("payment"));
+        assert_eq!(records[0].fields["attempt"], json!(2));
+        assert_eq!(records[0].fields["payment_id"], json!("pay_123"));
+
+        assert_eq!(records[1].level, Level::ERROR);
+        assert_eq!(records[1].fields["error"], json!("timeout"));
+    }
+
+    #[test]
+    fn test_audit_events_are_written_synchronously() {
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir().join("log_utils_audit_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::from([("service".to_string(), json!("payments"))]),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: true,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: None,
+            audit_config: Some(AuditLoggingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name: "audit.log".to_string(),
+            }),
+            global_filtering_directive: Some("error".to_string()),
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+        assert!(components.guards.is_empty());
+        assert_eq!(
+            Layer::<tracing_subscriber::Registry>::max_level_hint(&components.storage_layer),
+            None
+        );
+
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.audit_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = span!(TracingLevel::DEBUG, "refund", merchant_id = "merchant_1");
+            let _guard = span.enter();
+
+            info!("Not audited");
+            crate::audit!(payment_id = "pay_123", "Refund initiated");
+            tracing::debug!(audit = "manual", "Refund approved");
+        });
+
+        // Audit events are written synchronously, without requiring any guards to be dropped
+        let content = fs::read_to_string(temp_dir.join("audit.log")).unwrap();
+        let records: Vec<Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["message"], "[REFUND - EVENT] Refund initiated");
+        assert_eq!(records[0]["payment_id"], "pay_123");
+        assert_eq!(records[0][AUDIT_KEY], true);
+        assert_eq!(records[0]["merchant_id"], "merchant_1");
+        assert_eq!(records[0]["service"], "payments");
+        assert_eq!(records[1]["message"], "[REFUND - EVENT] Refund approved");
+        assert_eq!(records[1][AUDIT_KEY], "manual");
+
+        let health = components.audit_sink_health.unwrap();
+        assert_eq!(health.write_errors(), 0);
+        assert!(!health.is_degraded());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_audit_write_errors_are_counted() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: None,
+            // Every write to `/dev/full` fails with `ENOSPC`
+            audit_config: Some(AuditLoggingConfig {
+                directory: "/dev".to_string(),
+                file_name: "full".to_string(),
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+        let health = components.audit_sink_health.clone().unwrap();
+
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.audit_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Not audited");
+            crate::audit!(payment_id = "pay_123", "Refund initiated");
+            crate::audit!(payment_id = "pay_456", "Refund initiated");
+        });
+
+        assert_eq!(health.write_errors(), 2);
+        assert_eq!(health.dropped_records(), 2);
+        assert!(health.is_degraded());
+        assert!(!health.is_writing_to_console());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encrypted_file_logging() {
+        use std::{
+            fs,
+            io::{Read, Write},
+        };
+
+        let temp_dir = std::env::temp_dir().join("log_utils_encryption_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let key = [7; ENCRYPTION_KEY_LENGTH];
+        let file_config = |key_source| FileLoggingConfig {
+            directory: temp_dir.to_string_lossy().to_string(),
+            file_name_prefix: "encrypted".to_string(),
+            file_rotation: Rotation::NEVER,
+            max_log_files: None,
+            level: Level::INFO,
+            filtering_directive: None,
+            print_filtering_directive: DirectivePrintTarget::None,
+            encryption: Some(FileEncryptionConfig { key: key_source }),
+            ..Default::default()
+        };
+        let config = |key_source| LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(file_config(key_source)),
+            console_config: None,
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let error =
+            build_logging_components(config(EncryptionKeySource::Callback(Arc::new(|| {
+                Err("key service unavailable".to_string())
+            }))));
+        assert!(matches!(error, Err(LoggerError::EncryptionKey(_))));
+
+        let callback: EncryptionKeyCallback = Arc::new(move || Ok(key));
+        let components =
+            build_logging_components(config(EncryptionKeySource::Callback(callback))).unwrap();
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.file_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!(card_last4 = "4242", "First record");
+            info!("Second record");
+        });
+        drop(components.guards);
+
+        let contents = fs::read(temp_dir.join("encrypted")).unwrap();
+        assert!(!String::from_utf8_lossy(&contents).contains("record"));
+
+        let mut decrypted = String::new();
+        DecryptingReader::new(contents.as_slice(), &key)
+            .read_to_string(&mut decrypted)
+            .unwrap();
+        let records: Vec<Value> = decrypted
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["message"], "First record");
+        assert_eq!(records[0]["card_last4"], "4242");
+        assert_eq!(records[1]["message"], "Second record");
+
+        let error = DecryptingReader::new(contents.as_slice(), &[0; ENCRYPTION_KEY_LENGTH])
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = DecryptingReader::new(&contents[..contents.len() - 1], &key)
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Frames longer than the largest frame written are rejected without being read
+        let error = DecryptingReader::new(&u32::MAX.to_be_bytes()[..], &key)
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut writer = encryption::EncryptingWriter::new(Vec::new(), &key);
+        let error = writer
+            .write(&vec![b'a'; encryption::MAX_RECORD_LENGTH + 1])
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_record_hash_reserved_only_with_hash_chain() {
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+
+        for hash_chain in [false, true] {
+            let test_writer = TestWriter::new();
+            let mut layer = JsonFormattingLayer::new(
+                config.clone(),
+                test_writer.clone(),
+                serde_json::ser::CompactFormatter,
+            )
+            .unwrap();
+            if hash_chain {
+                layer = layer.with_reserved_keys([keys::RECORD_HASH]);
+            }
+            let subscriber = tracing_subscriber::registry()
+                .with(SpanStorageLayer::new(HashSet::new()))
+                .with(layer);
+            tracing::subscriber::with_default(subscriber, || {
+                info!(record_hash = "abc", "Test message");
+            });
+
+            let record: Value = serde_json::from_str(test_writer.get_output().trim()).unwrap();
+            assert_eq!(record.get("record_hash").is_some(), !hash_chain);
+        }
+    }
+
+    #[cfg(feature = "hash-chain")]
+    #[test]
+    fn test_hash_chained_file_logging() {
+        use std::{fs, io::Cursor};
+
+        let temp_dir = std::env::temp_dir().join("log_utils_hash_chain_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(FileLoggingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name_prefix: "chained".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: None,
+                level: Level::INFO,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                hash_chain: true,
+                ..Default::default()
+            }),
+            console_config: None,
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.file_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!(amount = 100, "Payment created");
+            info!(amount = 100, "Payment authorized");
+            info!(amount = 100, "Payment captured");
+        });
+        drop(components.guards);
+
+        let contents = fs::read_to_string(temp_dir.join("chained")).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+
+        let first_record: Value = serde_json::from_str(lines[0]).unwrap();
+        let first_hash = first_record["record_hash"].as_str().unwrap();
+        assert_eq!(first_hash.len(), 64);
+
+        let summary = verify_hash_chain(Cursor::new(&contents), None, false).unwrap();
+        assert_eq!(summary.records, 3);
+        assert_eq!(
+            summary.chains,
+            [HashChainBoundary {
+                first_line: 1,
+                last_line: 3,
+                previous_hash: GENESIS_HASH.to_owned(),
+                last_hash: summary.last_hash.clone(),
+            }]
+        );
+        let last_record: Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(summary.last_hash, last_record["record_hash"]);
+
+        // Modified record
+        let modified = contents.replacen("\"amount\":100", "\"amount\":1000", 2);
+        assert!(matches!(
+            verify_hash_chain(Cursor::new(&modified), None, false),
+            Err(HashChainError::HashMismatch { line: 1 })
+        ));
+
+        // Removed record
+        let removed = [lines[0], lines[2]].join("\n");
+        assert!(matches!(
+            verify_hash_chain(Cursor::new(&removed), None, false),
+            Err(HashChainError::HashMismatch { line: 2 })
+        ));
+
+        // Truncated start, which verifies only when continuing from the removed record
+        let truncated = lines[1..].join("\n");
+        assert!(matches!(
+            verify_hash_chain(Cursor::new(&truncated), None, false),
+            Err(HashChainError::HashMismatch { line: 1 })
+        ));
+        let summary = verify_hash_chain(Cursor::new(&truncated), Some(first_hash), false).unwrap();
+        assert_eq!(summary.records, 2);
+        assert_eq!(summary.chains.len(), 1);
+        assert_eq!(summary.chains[0].previous_hash, first_hash);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[cfg(feature = "hash-chain")]
+    #[test]
+    fn test_hash_chain_restarts() {
+        use std::{fs, io::Cursor};
+
+        let temp_dir = std::env::temp_dir().join("log_utils_hash_chain_restart_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(FileLoggingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name_prefix: "chained".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: None,
+                level: Level::INFO,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                hash_chain: true,
+                ..Default::default()
+            }),
+            console_config: None,
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        // Each run of the application starts a new chain in the same file
+        for run in 1..=3 {
+            let components = build_logging_components(config.clone()).unwrap();
+            let mut layers = vec![components.storage_layer.boxed()];
+            layers.extend(components.file_log_layer);
+            let subscriber = tracing_subscriber::registry().with(layers);
+
+            tracing::subscriber::with_default(subscriber, || {
+                info!(run, "Application started");
+                info!(run, "Application stopped");
+            });
+            drop(components.guards);
+        }
+
+        let contents = fs::read_to_string(temp_dir.join("chained")).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 6);
+
+        // Restarts are rejected unless allowed
+        assert!(matches!(
+            verify_hash_chain(Cursor::new(&contents), None, false),
+            Err(HashChainError::UnexpectedRestart { line: 3 })
+        ));
+        let summary = verify_hash_chain(Cursor::new(&contents), None, true).unwrap();
+        assert_eq!(summary.records, 6);
+        let boundaries: Vec<_> = summary
+            .chains
+            .iter()
+            .map(|chain| (chain.first_line, chain.last_line))
+            .collect();
+        assert_eq!(boundaries, [(1, 2), (3, 4), (5, 6)]);
+        // The last hashes of the runs, as stored externally
+        let anchors: Vec<_> = summary
+            .chains
+            .iter()
+            .map(|chain| chain.last_hash.clone())
+            .collect();
+
+        // Removed middle chain, which is detected against the anchors if restarts are allowed
+        let removed = [lines[0], lines[1], lines[4], lines[5]].join("\n");
+        assert!(matches!(
+            verify_hash_chain(Cursor::new(&removed), None, false),
+            Err(HashChainError::UnexpectedRestart { line: 3 })
+        ));
+        let summary = verify_hash_chain(Cursor::new(&removed), None, true).unwrap();
+        let last_hashes: Vec<_> = summary
+            .chains
+            .iter()
+            .map(|chain| chain.last_hash.clone())
+            .collect();
+        assert_eq!(last_hashes, [anchors[0].clone(), anchors[2].clone()]);
+        assert_ne!(last_hashes, anchors);
+
+        // Truncated end of the first chain, which is detected against the anchors as well
+        let truncated = [lines[0], lines[2], lines[3]].join("\n");
+        let summary = verify_hash_chain(Cursor::new(&truncated), None, true).unwrap();
+        assert_ne!(summary.chains[0].last_hash, anchors[0]);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_log_file_permissions() {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let temp_dir = std::env::temp_dir().join("log_utils_file_permissions_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        // Existing log files with the prefix are updated, while other files are left unchanged
+        let rotated_file = temp_dir.join("restricted.2025-01-01");
+        let other_file = temp_dir.join("other");
+        for file in [&rotated_file, &other_file] {
+            fs::write(file, "").unwrap();
+            fs::set_permissions(file, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(FileLoggingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name_prefix: "restricted".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: None,
+                level: Level::INFO,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                file_mode: Some(0o640),
+                ..Default::default()
+            }),
+            console_config: None,
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.file_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Payment created");
+        });
+        drop(components.guards);
+
+        let mode =
+            |file: &std::path::Path| fs::metadata(file).unwrap().permissions().mode() & 0o777;
+        let log_file = temp_dir.join("restricted");
+        assert!(!fs::read_to_string(&log_file).unwrap().is_empty());
+        assert_eq!(mode(&log_file), 0o640);
+        assert_eq!(mode(&rotated_file), 0o640);
+        assert_eq!(mode(&other_file), 0o644);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rotated_log_files_created_with_permissions() {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        use time::{OffsetDateTime, macros::datetime};
+
+        let temp_dir = std::env::temp_dir().join("log_utils_rotated_file_permissions_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let time = datetime!(2025-01-01 13:45:30 UTC);
+        let file_name = |rotation, prefix| permissions::log_file_name(&rotation, prefix, time);
+        assert_eq!(
+            file_name(Rotation::MINUTELY, "app").as_deref(),
+            Some("app.2025-01-01-13-45")
+        );
+        assert_eq!(
+            file_name(Rotation::HOURLY, "app").as_deref(),
+            Some("app.2025-01-01-13")
+        );
+        assert_eq!(
+            file_name(Rotation::DAILY, "").as_deref(),
+            Some("2025-01-01")
+        );
+        assert_eq!(
+            file_name(Rotation::WEEKLY, "app").as_deref(),
+            Some("app.2024-12-29")
+        );
+        assert_eq!(file_name(Rotation::NEVER, "app"), None);
+
+        // The file names match the files created by the rolling file appender
+        let before = OffsetDateTime::now_utc();
+        drop(
+            tracing_appender::rolling::RollingFileAppender::builder()
+                .rotation(Rotation::MINUTELY)
+                .filename_prefix("app")
+                .build(&temp_dir)
+                .unwrap(),
+        );
+        let after = OffsetDateTime::now_utc();
+        assert!([before, after].into_iter().any(|time| {
+            let file_name = permissions::log_file_name(&Rotation::MINUTELY, "app", time);
+            temp_dir.join(file_name.unwrap()).exists()
+        }));
+
+        // Log files are created with the permissions before any records are written to them
+        let permissions = permissions::FilePermissions {
+            mode: Some(0o600),
+            owner: None,
+            group: None,
+        };
+        let mode =
+            |file: &std::path::Path| fs::metadata(file).unwrap().permissions().mode() & 0o777;
+        let rotated_file = temp_dir.join("app.2025-01-01-13-45");
+        permissions.create_file(&rotated_file).unwrap();
+        assert_eq!(mode(&rotated_file), 0o600);
+        assert!(fs::read_to_string(&rotated_file).unwrap().is_empty());
+
+        // Only the files named as log files are updated if the prefix is empty
+        let dated_file = temp_dir.join("2025-01-01");
+        let other_file = temp_dir.join("other");
+        for file in [&dated_file, &other_file] {
+            fs::write(file, "").unwrap();
+            fs::set_permissions(file, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        permissions.apply(&temp_dir, "").unwrap();
+        assert_eq!(mode(&dated_file), 0o600);
+        assert_eq!(mode(&other_file), 0o644);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_file_sink_fallback() {
+        use std::{fs, thread, time::Duration};
+
+        let temp_dir = std::env::temp_dir().join("log_utils_file_sink_fallback_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        // A file in place of the log directory prevents the log file from being created
+        let log_dir = temp_dir.join("logs");
+        fs::write(&log_dir, "").unwrap();
+
+        let config = |fallback| LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(FileLoggingConfig {
+                directory: log_dir.to_string_lossy().to_string(),
+                file_name_prefix: "fallback".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: None,
+                level: Level::INFO,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                fallback,
+                ..Default::default()
+            }),
+            console_config: None,
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            build_logging_components(config(FileSinkFallback::Fail)),
+            Err(LoggerError::FileAppenderInitialization(_))
+        ));
+
+        let components = build_logging_components(config(FileSinkFallback::Console)).unwrap();
+        assert!(components.file_log_layer.is_some());
+        let health = components.file_sink_health.unwrap();
+        assert!(health.is_writing_to_console());
+        assert!(health.is_degraded());
+
+        let retry = FileSinkFallback::Retry {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+        };
+        assert!(matches!(
+            build_logging_components(config(retry)),
+            Err(LoggerError::FileAppenderInitialization(_))
+        ));
+
+        // Unblock the log directory while retrying
+        let unblock = {
+            let log_dir = log_dir.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                fs::remove_file(log_dir).unwrap();
+            })
+        };
+        let retry = FileSinkFallback::Retry {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+        };
+        let components = build_logging_components(config(retry)).unwrap();
+        unblock.join().unwrap();
+
+        let mut layers = vec![components.storage_layer.boxed()];
+        layers.extend(components.file_log_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Payment created");
+            info!("Payment captured");
+        });
+        drop(components.guards);
+
+        let contents = fs::read_to_string(log_dir.join("fallback")).unwrap();
+        let records: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["level"], "WARN");
+        assert_eq!(records[0]["message"], "Created log file after retrying");
+        assert_eq!(records[0]["failed_attempts"], 1);
+        assert!(records[0]["error"].is_string());
+        assert_eq!(records[1]["message"], "Payment created");
+        assert_eq!(records[2]["message"], "Payment captured");
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_file_write_error_policy() {
+        use std::time::Duration;
+
+        use fallback::WriteErrorPolicyWriter;
+
+        /// A writer partially writing the specified number of writes, and then failing the
+        /// specified number of writes, before succeeding.
+        struct FailingWriter {
+            partial_writes: usize,
+            failures: usize,
+            buffer: Arc<Mutex<Vec<u8>>>,
+        }
+
+        impl Write for FailingWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.partial_writes > 0 {
+                    self.partial_writes -= 1;
+                    let written = buf.len().min(3);
+                    self.buffer
+                        .lock()
+                        .map_err(|_| io::Error::other("Mutex poisoned"))?
+                        .extend_from_slice(&buf[..written]);
+                    return Ok(written);
+                }
+                if self.failures > 0 {
+                    self.failures -= 1;
+                    return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+                }
+                self.buffer
+                    .lock()
+                    .map_err(|_| io::Error::other("Mutex poisoned"))?
+                    .extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let writer = |partial_writes, failures, policy| {
+            let buffer = Arc::new(Mutex::new(Vec::new()));
+            let health = FileSinkHealth::default();
+            let inner = FailingWriter {
+                partial_writes,
+                failures,
+                buffer: buffer.clone(),
+            };
+            let writer = WriteErrorPolicyWriter::new(inner, policy, health.clone());
+            (writer, buffer, health)
+        };
+
+        // Dropped records are counted
+        let (mut dropping_writer, buffer, health) = writer(0, 2, FileWriteErrorPolicy::Drop);
+        for record in [b"first\n", b"secnd\n", b"third\n"] {
+            assert_eq!(dropping_writer.write(record).unwrap(), record.len());
+        }
+        assert_eq!(buffer.lock().unwrap().as_slice(), b"third\n");
+        assert_eq!(health.write_errors(), 2);
+        assert_eq!(health.dropped_records(), 2);
+        assert!(health.is_degraded());
+        assert!(!health.is_writing_to_console());
+
+        // Records are retried until written
+        let policy = FileWriteErrorPolicy::Block {
+            retry_interval: Duration::from_millis(1),
+        };
+        let (mut blocking_writer, buffer, health) = writer(0, 3, policy);
+        blocking_writer.write_all(b"record\n").unwrap();
+        assert_eq!(buffer.lock().unwrap().as_slice(), b"record\n");
+        assert_eq!(health.write_errors(), 3);
+        assert_eq!(health.dropped_records(), 0);
+
+        // Only the remainder of partially written records is retried
+        let (mut blocking_writer, buffer, health) = writer(2, 2, policy);
+        blocking_writer.write_all(b"record\n").unwrap();
+        assert_eq!(buffer.lock().unwrap().as_slice(), b"record\n");
+        assert_eq!(health.write_errors(), 2);
+
+        // Subsequent records are written to the console
+        let (mut console_writer, buffer, health) = writer(0, 1, FileWriteErrorPolicy::Console);
+        console_writer.write_all(b"first\n").unwrap();
+        console_writer.write_all(b"second\n").unwrap();
+        assert!(buffer.lock().unwrap().is_empty());
+        assert_eq!(health.write_errors(), 1);
+        assert_eq!(health.dropped_records(), 1);
+        assert!(health.is_writing_to_console());
+
+        // Healthy sink
+        let (mut healthy_writer, _, health) = writer(0, 0, FileWriteErrorPolicy::Drop);
+        healthy_writer.write_all(b"record\n").unwrap();
+        assert!(!health.is_degraded());
+    }
+
+    #[cfg(not(feature = "tokio-console"))]
+    #[test]
+    fn test_tokio_console_config_requires_feature() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: None,
+            tokio_console_config: Some(TokioConsoleConfig::default()),
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            build_logging_components(config),
+            Err(LoggerError::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_reserved_key_validation() {
+        let static_fields = HashMap::from([("message".to_string(), json!("should_fail"))]);
+
+        let config = JsonFormattingLayerConfig {
+            static_top_level_fields: static_fields,
+            top_level_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
+        };
+
+        let result =
+            JsonFormattingLayer::new(config, TestWriter::new(), serde_json::ser::CompactFormatter);
+
+        // Should fail because "message" is a reserved key
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Configuration error: A reserved key `message` was included in \
+             `static_top_level_fields` in the log formatting layer"
+        );
+    }
+
+    #[test]
+    fn test_invalid_filter_directive() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: Some("invalid[filter".to_string()), // Invalid syntax
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let result = build_logging_components(config);
+
+        assert!(matches!(
+            result,
+            Err(LoggerError::InvalidDirective {
+                sink: DirectiveSink::Console,
+                position: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_directive_print_target_callback() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let captured_clone = Arc::clone(&captured);
+        let callback: DirectiveCallback = Arc::new(move |sink, directive: &str| {
+            captured_clone
+                .lock()
+                .unwrap()
+                .push((sink, directive.to_string()));
+        });
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::Callback(callback),
+                ..Default::default()
+            }),
+            global_filtering_directive: Some("info,my_app=debug".to_string()),
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+
+        assert_eq!(
+            *captured.lock().unwrap(),
+            [(DirectiveSink::Console, "info,my_app=debug".to_string())]
+        );
+
+        let [
+            (global_sink, global_directive),
+            (console_sink, console_directive),
+        ] = components.filtering_directives.as_slice()
+        else {
+            panic!("Expected the global and console filtering directives");
+        };
+        assert_eq!(
+            (*global_sink, global_directive.as_str()),
+            (DirectiveSink::Global, "info,my_app=debug")
+        );
+        assert_eq!(*console_sink, DirectiveSink::Console);
+        assert!(console_directive.contains("my_app=debug"));
+    }
+
+    #[test]
+    fn test_shutdown_summary() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+        let components = build_logging_components(config).unwrap();
+        let record_counts = components.record_counts.clone();
+
+        let test_writer = TestWriter::new();
+        let formatting_layer = JsonFormattingLayer::new(
+            JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            },
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(components.storage_layer.clone())
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!("Payment created");
+            tracing::warn!("Payment retried");
+            tracing::error!("Payment failed");
+            assert_eq!(record_counts.count(Level::WARN), 1);
+            assert_eq!(record_counts.total(), 3);
+
+            components.shutdown();
+        });
+
+        let output = test_writer.get_output();
+        let summary: Value = serde_json::from_str(output.lines().last().unwrap()).unwrap();
+        assert_eq!(summary["message"], "Logging shut down");
+        assert_eq!(summary["target"], "log_utils");
+        assert_eq!(summary["info_records"], 1);
+        assert_eq!(summary["warn_records"], 1);
+        assert_eq!(summary["error_records"], 1);
+        assert!(summary["uptime_ms"].is_u64());
+        assert_eq!(summary["console_dropped_lines"], 0);
+        // Absent sinks are omitted from the summary
+        assert!(summary.get("file_dropped_lines").is_none());
+    }
+
+    #[test]
+    fn test_unused_global_filter_directive_is_validated() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: Some("info".to_string()),
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: Some("warn,my_app=verbose".to_string()),
+            ..Default::default()
+        };
+
+        let Err(LoggerError::InvalidDirective {
+            sink,
+            directive,
+            position,
+            ..
+        }) = build_logging_components(config)
+        else {
+            panic!("Expected an invalid directive error");
+        };
+
+        assert_eq!(sink, DirectiveSink::Global);
+        assert_eq!(directive, "warn,my_app=verbose");
+        assert_eq!(position, 5);
+    }
+
+    #[test]
+    fn test_build_logging_components_with_all_options() {
+        use std::num::NonZeroUsize;
+
+        let static_fields = HashMap::from([("service".to_string(), json!("test_service"))]);
+
+        let config = LoggerConfig {
+            static_top_level_fields: static_fields,
+            top_level_keys: HashSet::from(["user_id"]),
+            persistent_keys: HashSet::from(["session_id"]),
+            log_span_lifecycles: true,
+            additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
+            file_config: Some(FileLoggingConfig {
+                directory: std::env::temp_dir().to_string_lossy().to_string(),
+                file_name_prefix: "test_log".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: NonZeroUsize::new(1),
+                level: Level::DEBUG,
+                filtering_directive: Some("debug".to_string()),
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: Some("info".to_string()),
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: Some("warn".to_string()),
+            ..Default::default()
+        };
+
+        let result = build_logging_components(config);
+        assert!(result.is_ok());
+
+        let components = result.unwrap();
+        assert!(components.file_log_layer.is_some());
+        assert!(components.console_log_layer.is_some());
+        assert_eq!(components.guards.len(), 2); // One for file, one for console
+    }
+
+    #[test]
+    fn test_build_logging_components_routing_warnings_to_stderr() {
+        let config = LoggerConfig {
+            console_config: Some(ConsoleLoggingConfig {
+                route_warnings_to_stderr: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
 
-        let output = test_writer.get_output();
-        let lines: Vec<&str> = output.trim().split('\n').collect();
-        let log_entry: Value = serde_json::from_str(lines[0]).unwrap();
+        let components = build_logging_components(config).unwrap();
+        assert!(components.console_log_layer.is_some());
+        assert_eq!(components.guards.len(), 2); // One each for stdout and stderr
+    }
 
-        // Verify persistent keys from parent span are available
-        assert_eq!(log_entry["user_id"], "123");
-        assert_eq!(log_entry["session_id"], "session-456");
-        assert_eq!(log_entry["operation"], "process");
+    #[test]
+    fn test_storage_layer_max_level_follows_sink_filters() {
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::INFO,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: Some("info".to_string()),
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
+        };
+
+        let components = build_logging_components(config).unwrap();
+        assert_eq!(
+            Layer::<tracing_subscriber::Registry>::max_level_hint(
+                &components.filtered_storage_layer()
+            ),
+            Some(LevelFilter::INFO)
+        );
+        // The storage layer does not restrict the levels of other layers
+        assert_eq!(
+            Layer::<tracing_subscriber::Registry>::max_level_hint(&components.storage_layer),
+            None
+        );
     }
 
     #[test]
-    fn test_span_lifecycle_logging() {
+    fn test_records_above_storage_layer_max_level_are_skipped() {
         let test_writer = TestWriter::new();
 
-        // Need storage layer to capture elapsed time
-        let storage_layer = SpanStorageLayer::new(HashSet::new());
+        let storage_layer = SpanStorageLayer::new(HashSet::new()).with_filter(LevelFilter::INFO);
 
         let config = JsonFormattingLayerConfig {
             static_top_level_fields: HashMap::new(),
             top_level_keys: HashSet::new(),
-            log_span_lifecycles: true, // Enable span lifecycle logging
+            log_span_lifecycles: true,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            ..Default::default()
         };
 
         let formatting_layer = JsonFormattingLayer::new(
@@ -704,13 +5301,13 @@ mod tests {
 
         let subscriber = tracing_subscriber::registry()
             .with(storage_layer)
-            .with(formatting_layer);
+            .with(formatting_layer.with_filter(LevelFilter::INFO));
 
         tracing::subscriber::with_default(subscriber, || {
-            let span = span!(TracingLevel::INFO, "test_span", operation = "test");
+            let span = span!(TracingLevel::DEBUG, "debug_span");
             let _guard = span.enter();
-            info!("Inside span");
-            // Span ends when _guard is dropped
+            tracing::debug!("Debug message");
+            info!("Info message");
         });
 
         let output = test_writer.get_output();
@@ -720,117 +5317,205 @@ mod tests {
             .filter(|l| !l.is_empty())
             .collect();
 
-        // Should have: span start, event, span end
-        assert_eq!(lines.len(), 3);
-
-        // Parse each log entry
-        let start_entry: Value = serde_json::from_str(lines[0]).unwrap();
-        let event_entry: Value = serde_json::from_str(lines[1]).unwrap();
-        let end_entry: Value = serde_json::from_str(lines[2]).unwrap();
-
-        // Verify span start
-        assert_eq!(
-            start_entry["message"].as_str().unwrap(),
-            "[TEST_SPAN - START]"
-        );
-
-        // Verify event (includes span name prefix when inside a span)
-        assert_eq!(
-            event_entry["message"].as_str().unwrap(),
-            "[TEST_SPAN - EVENT] Inside span"
-        );
-
-        // Verify span end
-        assert_eq!(end_entry["message"].as_str().unwrap(), "[TEST_SPAN - END]");
-        assert!(end_entry["elapsed_milliseconds"].is_number());
+        assert_eq!(lines.len(), 1);
+        let log_entry: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(log_entry["message"], "Info message");
     }
 
     #[test]
-    fn test_reserved_key_validation() {
-        let static_fields = HashMap::from([("message".to_string(), json!("should_fail"))]);
+    fn test_storage_layer_filter_does_not_disable_other_layers() {
+        use std::fs;
 
-        let config = JsonFormattingLayerConfig {
-            static_top_level_fields: static_fields,
+        let temp_dir = std::env::temp_dir().join("log_utils_storage_layer_filter_test");
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        let config = LoggerConfig {
+            static_top_level_fields: HashMap::new(),
             top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: Some(FileLoggingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name_prefix: "sink".to_string(),
+                file_rotation: Rotation::NEVER,
+                max_log_files: None,
+                level: Level::INFO,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            console_config: None,
+            profiling_config: Some(FoldedStackProfilingConfig {
+                directory: temp_dir.to_string_lossy().to_string(),
+                file_name: "profile".to_string(),
+                enabled: true,
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
         };
 
-        let result =
-            JsonFormattingLayer::new(config, TestWriter::new(), serde_json::ser::CompactFormatter);
+        let components = build_logging_components(config).unwrap();
+        let mut layers = vec![components.filtered_storage_layer().boxed()];
+        layers.extend(components.file_log_layer);
+        layers.extend(components.profiling_layer);
+        let subscriber = tracing_subscriber::registry().with(layers);
 
-        // Should fail because "message" is a reserved key
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err().to_string(),
-            "Configuration error: A reserved key `message` was included in \
-             `static_top_level_fields` in the log formatting layer"
-        );
+        tracing::subscriber::with_default(subscriber, || {
+            let request_span = tracing::info_span!("request", payment_id = "pay_1");
+            let _request_guard = request_span.enter();
+            let debug_span = tracing::debug_span!("debug_span", attempt = 1);
+            let _debug_guard = debug_span.enter();
+            tracing::debug!("Debug message");
+            info!("Info message");
+        });
+        drop(components.guards);
+
+        // The profiling layer receives the `DEBUG` span, which no sink accepts
+        let profile = fs::read_to_string(temp_dir.join("profile")).unwrap();
+        assert!(profile.contains("request;debug_span "), "{profile}");
+
+        // The file sink receives only the `INFO` records, with the fields of the `INFO` span
+        let contents = fs::read_to_string(temp_dir.join("sink")).unwrap();
+        let records: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(records.iter().all(|record| record["level"] == "INFO"));
+        let event = records
+            .iter()
+            .find(|record| {
+                record["message"]
+                    .as_str()
+                    .unwrap()
+                    .ends_with("Info message")
+            })
+            .unwrap();
+        assert_eq!(event["payment_id"], "pay_1");
+        assert!(event.get("attempt").is_none());
+
+        let _ = fs::remove_dir_all(&temp_dir);
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_invalid_filter_directive() {
-        let config = LoggerConfig {
+    fn test_log_metrics_layer_counts_records_and_bytes() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let test_writer = TestWriter::new();
+
+        let config = JsonFormattingLayerConfig {
             static_top_level_fields: HashMap::new(),
             top_level_keys: HashSet::new(),
-            persistent_keys: HashSet::new(),
             log_span_lifecycles: false,
             additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
-            file_config: None,
-            console_config: Some(ConsoleLoggingConfig {
-                level: Level::INFO,
-                log_format: ConsoleLogFormat::CompactJson,
-                filtering_directive: Some("invalid[filter".to_string()), // Invalid syntax
-                print_filtering_directive: DirectivePrintTarget::None,
-            }),
-            global_filtering_directive: None,
+            ..Default::default()
         };
 
-        let result = build_logging_components(config);
+        let formatting_layer = JsonFormattingLayer::new(
+            config,
+            log_metrics::ByteCountingWriter::new(test_writer.clone(), DirectiveSink::Console),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
 
-        assert!(matches!(
-            result,
-            Err(LoggerError::InvalidFilteringDirective(_))
-        ));
+        let subscriber = tracing_subscriber::registry()
+            .with(LogMetricsLayer::new())
+            .with(formatting_layer);
+
+        metrics::with_local_recorder(&recorder, || {
+            tracing::subscriber::with_default(subscriber, || {
+                info!("First message");
+                info!("Second message");
+                tracing::error!("Error message");
+            });
+
+            // Bytes failing to be written are not counted
+            let mut full: &mut [u8] = &mut [];
+            let mut writer =
+                log_metrics::ByteCountingWriter::new(&mut full, DirectiveSink::Console);
+            assert!(writer.write_all(b"Dropped message").is_err());
+        });
+
+        let counters = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels = key
+                    .labels()
+                    .map(|label| format!("{}={}", label.key(), label.value()))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let DebugValue::Counter(value) = value else {
+                    panic!("Expected a counter");
+                };
+                (format!("{}{{{labels}}}", key.name()), value)
+            })
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            counters["log_records_total{level=INFO,target=log_utils::tracing::tests}"],
+            2
+        );
+        assert_eq!(
+            counters["log_records_total{level=ERROR,target=log_utils::tracing::tests}"],
+            1
+        );
+        assert_eq!(
+            counters["log_bytes_written_total{sink=console}"],
+            u64::try_from(test_writer.get_output().len()).unwrap()
+        );
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_build_logging_components_with_all_options() {
-        use std::num::NonZeroUsize;
+    fn test_span_latency_metrics_layer_records_durations() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
 
-        let static_fields = HashMap::from([("service".to_string(), json!("test_service"))]);
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
 
-        let config = LoggerConfig {
-            static_top_level_fields: static_fields,
-            top_level_keys: HashSet::from(["user_id"]),
-            persistent_keys: HashSet::from(["session_id"]),
-            log_span_lifecycles: true,
-            additional_fields_placement: AdditionalFieldsPlacement::Nested("extra".to_string()),
-            file_config: Some(FileLoggingConfig {
-                directory: std::env::temp_dir().to_string_lossy().to_string(),
-                file_name_prefix: "test_log".to_string(),
-                file_rotation: Rotation::NEVER,
-                max_log_files: NonZeroUsize::new(1),
-                level: Level::DEBUG,
-                filtering_directive: Some("debug".to_string()),
-                print_filtering_directive: DirectivePrintTarget::None,
-            }),
-            console_config: Some(ConsoleLoggingConfig {
-                level: Level::INFO,
-                log_format: ConsoleLogFormat::CompactJson,
-                filtering_directive: Some("info".to_string()),
-                print_filtering_directive: DirectivePrintTarget::None,
-            }),
-            global_filtering_directive: Some("warn".to_string()),
-        };
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new(HashSet::new()))
+            .with(SpanLatencyMetricsLayer::new());
+
+        metrics::with_local_recorder(&recorder, || {
+            tracing::subscriber::with_default(subscriber, || {
+                for _ in 0..2 {
+                    let span = span!(TracingLevel::INFO, "handle_request");
+                    let _guard = span.enter();
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                }
 
-        let result = build_logging_components(config);
-        assert!(result.is_ok());
+                // Spans which were never entered are not recorded
+                let _span = span!(TracingLevel::INFO, "never_entered");
+            });
+        });
 
-        let components = result.unwrap();
-        assert!(components.file_log_layer.is_some());
-        assert!(components.console_log_layer.is_some());
-        assert_eq!(components.guards.len(), 2); // One for file, one for console
+        let snapshot = snapshotter.snapshot().into_vec();
+        assert_eq!(snapshot.len(), 1);
+
+        let (key, _, _, value) = &snapshot[0];
+        assert_eq!(key.key().name(), SPAN_DURATION_SECONDS);
+        assert!(
+            key.key()
+                .labels()
+                .any(|label| label.key() == "span" && label.value() == "handle_request")
+        );
+
+        let DebugValue::Histogram(durations) = value else {
+            panic!("Expected a histogram");
+        };
+        assert_eq!(durations.len(), 2);
+        assert!(
+            durations
+                .iter()
+                .all(|duration| duration.into_inner() >= 0.005)
+        );
     }
 
     #[test]
@@ -860,9 +5545,11 @@ mod tests {
                 level: Level::DEBUG,
                 filtering_directive: Some("debug".to_string()),
                 print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
             }),
             console_config: None, // Only test file logging
             global_filtering_directive: Some("info".to_string()),
+            ..Default::default()
         };
 
         let result = build_logging_components(config);
@@ -976,4 +5663,188 @@ mod tests {
         // Clean up
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_logger_config_from_vars() {
+        let vars = HashMap::from([
+            ("LOG_FORMAT", "human"),
+            ("LOG_LEVEL", "info,my_app=debug"),
+            ("LOG_DIR", "/var/log/my_sidecar"),
+            ("LOG_STATIC_FIELDS", r#"{"service": "my_sidecar"}"#),
+        ]);
+        let config =
+            from_env::logger_config_from_vars(|name| vars.get(name).map(ToString::to_string))
+                .unwrap();
+
+        assert_eq!(
+            config.static_top_level_fields,
+            HashMap::from([("service".to_owned(), Value::from("my_sidecar"))])
+        );
+        assert_eq!(
+            config.global_filtering_directive.as_deref(),
+            Some("info,my_app=debug")
+        );
+        let console_config = config.console_config.unwrap();
+        assert_eq!(console_config.log_format, ConsoleLogFormat::HumanReadable);
+        assert_eq!(console_config.level, Level::INFO);
+        assert_eq!(config.file_config.unwrap().directory, "/var/log/my_sidecar");
+
+        // Defaults
+        let config = from_env::logger_config_from_vars(|_| None).unwrap();
+        let console_config = config.console_config.unwrap();
+        assert_eq!(console_config.log_format, ConsoleLogFormat::CompactJson);
+        assert_eq!(console_config.level, Level::INFO);
+        assert!(config.file_config.is_none());
+        assert!(config.global_filtering_directive.is_none());
+
+        // A level
+        let config = from_env::logger_config_from_vars(|name| {
+            (name == "LOG_LEVEL").then(|| "debug".to_owned())
+        })
+        .unwrap();
+        assert_eq!(config.console_config.unwrap().level, Level::DEBUG);
+        assert!(config.global_filtering_directive.is_none());
+
+        // Invalid values
+        assert!(matches!(
+            from_env::logger_config_from_vars(
+                |name| (name == "LOG_FORMAT").then(|| "xml".to_owned())
+            ),
+            Err(LoggerError::Configuration(_))
+        ));
+        assert!(matches!(
+            from_env::logger_config_from_vars(
+                |name| (name == "LOG_STATIC_FIELDS").then(|| "[1, 2]".to_owned())
+            ),
+            Err(LoggerError::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_workspace_filtering_directive() {
+        let members = HashSet::from(["log_utils", "build_info", "hyperswitch-masking"]);
+        let directive = workspace_filtering_directive(Level::TRACE, LevelFilter::WARN, members);
+        assert_eq!(
+            directive,
+            "warn,build_info=trace,hyperswitch_masking=trace,log_utils=trace"
+        );
+        assert!(parse_filtering_directive(DirectiveSink::Global, &directive).is_ok());
+
+        let directive = workspace_filtering_directive(Level::INFO, LevelFilter::OFF, []);
+        assert_eq!(directive, "off");
+    }
+
+    #[test]
+    fn test_log_error() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("Payment declined")]
+        struct PaymentError(#[source] io::Error);
+
+        const ERROR_CODES: ErrorCodeRegistry = ErrorCodeRegistry::new(&["PAY_001", "PAY_002"]);
+
+        let test_writer = TestWriter::new();
+        let formatting_layer = JsonFormattingLayer::new(
+            JsonFormattingLayerConfig {
+                static_top_level_fields: HashMap::new(),
+                top_level_keys: HashSet::new(),
+                log_span_lifecycles: false,
+                additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+                ..Default::default()
+            },
+            test_writer.clone(),
+            serde_json::ser::CompactFormatter,
+        )
+        .unwrap();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new([]))
+            .with(formatting_layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let error = PaymentError(io::Error::other("connection reset"));
+            let payment_id = "pay_123";
+            let attempt = 2;
+            crate::log_error!(
+                error,
+                code = "PAY_001",
+                payment_id = %payment_id,
+                attempt,
+                "Payment {payment_id} failed"
+            );
+            crate::log_error!(error, "Payment failed");
+        });
+
+        let output = test_writer.get_output();
+        let records = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records[0]["level"], "ERROR");
+        assert_eq!(records[0]["message"], "Payment pay_123 failed");
+        assert_eq!(records[0]["code"], "PAY_001");
+        assert_eq!(records[0]["payment_id"], "pay_123");
+        assert_eq!(records[0]["attempt"], 2);
+        assert_eq!(
+            records[0]["error"],
+            json!({"message": "Payment declined", "sources": ["connection reset"]})
+        );
+        assert!(records[1].get("code").is_none());
+        assert_eq!(records[1]["error"]["message"], "Payment declined");
+
+        assert!(ERROR_CODES.contains("PAY_002"));
+        assert!(!ERROR_CODES.contains("PAY"));
+        assert!(is_reserved_key("level"));
+        assert!(!is_reserved_key("payment_id"));
+    }
+
+    #[test]
+    fn test_error_stats() {
+        let error_stats = ErrorStats::new(std::time::Duration::from_millis(2500));
+        assert_eq!(error_stats.retention(), std::time::Duration::from_secs(3));
+
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new([]).with_error_stats(error_stats.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("Payment failed");
+            tracing::error!("Refund failed");
+            tracing::warn!("Payment retried");
+        });
+
+        assert_eq!(error_stats.count(std::time::Duration::from_secs(3)), 2);
+        assert_eq!(error_stats.count(std::time::Duration::from_secs(3600)), 2);
+        // Windows are capped at the retention
+        assert!(
+            (error_stats.rate(std::time::Duration::from_secs(3600)) - 2.0 / 3.0).abs()
+                < f64::EPSILON
+        );
+
+        // Clones observe the same counts
+        let components = build_logging_components(LoggerConfig {
+            static_top_level_fields: HashMap::new(),
+            top_level_keys: HashSet::new(),
+            persistent_keys: HashSet::new(),
+            log_span_lifecycles: false,
+            additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+            file_config: None,
+            console_config: Some(ConsoleLoggingConfig {
+                level: Level::WARN,
+                log_format: ConsoleLogFormat::CompactJson,
+                filtering_directive: None,
+                print_filtering_directive: DirectivePrintTarget::None,
+                ..Default::default()
+            }),
+            global_filtering_directive: None,
+            ..Default::default()
+        })
+        .unwrap();
+        let error_stats = components.error_stats();
+        let subscriber = tracing_subscriber::registry().with(components.storage_layer.clone());
+        tracing::subscriber::with_default(subscriber, || tracing::error!("Payment failed"));
+        assert_eq!(
+            components
+                .error_stats()
+                .count(std::time::Duration::from_secs(60)),
+            1
+        );
+        assert_eq!(error_stats.count(std::time::Duration::from_secs(60)), 1);
+    }
 }
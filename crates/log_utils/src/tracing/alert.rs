@@ -0,0 +1,147 @@
+//! Provides a [`tracing_subscriber::Layer`] ([`AlertLayer`]) for notifying applications of events
+//! at or above a configured level, such as errors.
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use serde_json::Value;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use super::storage::Storage;
+
+/// A callback invoked with each [`AlertRecord`].
+///
+/// The callback is invoked synchronously on the thread emitting the event, and hence should not
+/// block.
+/// Any long-running work (such as notifying a paging integration) should be offloaded, by
+/// spawning an asynchronous task, for example.
+/// The callback must not emit events at or above the alert level, to avoid infinite recursion.
+pub type AlertCallback = Arc<dyn Fn(AlertRecord) + Send + Sync>;
+
+/// Configuration for alerting on events at or above a specified level.
+#[derive(Clone, Debug)]
+pub struct AlertConfig {
+    /// Minimum level of events that trigger an alert.
+    pub level: Level,
+
+    /// The hook to be notified when an alert is triggered.
+    pub hook: AlertHook,
+}
+
+/// Specifies how applications are notified of alerts.
+#[derive(Clone)]
+pub enum AlertHook {
+    /// Invoke the callback with the alert record.
+    Callback(AlertCallback),
+
+    /// Send the alert record over the channel.
+    /// Alerts are discarded if the receiving end of the channel has been dropped.
+    Channel(std::sync::mpsc::Sender<AlertRecord>),
+}
+
+impl fmt::Debug for AlertHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Callback(_) => f.debug_tuple("Callback").finish_non_exhaustive(),
+            Self::Channel(sender) => f.debug_tuple("Channel").field(sender).finish(),
+        }
+    }
+}
+
+/// The structured record of an event that triggered an alert.
+#[derive(Clone, Debug)]
+pub struct AlertRecord {
+    /// The level of the event.
+    pub level: Level,
+
+    /// The target of the event.
+    pub target: &'static str,
+
+    /// The message of the event, if any.
+    pub message: Option<String>,
+
+    /// The name of the span that the event occurred within, if any.
+    pub span_name: Option<&'static str>,
+
+    /// The fields of the event, along with the fields of the span that the event occurred within.
+    pub fields: HashMap<String, Value>,
+
+    /// The source code file where the event was emitted, if available.
+    pub file: Option<&'static str>,
+
+    /// The line number in the source code file where the event was emitted, if available.
+    pub line: Option<u32>,
+}
+
+/// A [`tracing_subscriber::Layer`] that notifies the configured [`AlertHook`] of events at or above
+/// the configured level, carrying the structured record of the event.
+///
+/// This can be used to integrate with paging services or to implement in-process error budgets,
+/// without having to parse log files.
+#[derive(Debug)]
+pub struct AlertLayer {
+    level: Level,
+    hook: AlertHook,
+}
+
+impl AlertLayer {
+    /// Creates a new [`AlertLayer`] with the specified configuration.
+    pub fn new(config: AlertConfig) -> Self {
+        Self {
+            level: config.level,
+            hook: config.hook,
+        }
+    }
+}
+
+impl<S> Layer<S> for AlertLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > self.level {
+            return;
+        }
+
+        let mut storage = Storage::default();
+        event.record(&mut storage);
+
+        // Collect span fields followed by event fields, so that event fields take precedence
+        let span = ctx.event_span(event);
+        let mut fields = HashMap::new();
+        if let Some(span) = &span {
+            if let Some(span_storage) = span.extensions().get::<Storage<'_>>() {
+                fields.extend(
+                    span_storage
+                        .values()
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.clone())),
+                );
+            }
+        }
+        fields.extend(
+            storage
+                .values()
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone())),
+        );
+
+        let record = AlertRecord {
+            level: *metadata.level(),
+            target: metadata.target(),
+            message: storage.message().map(ToOwned::to_owned),
+            span_name: span.as_ref().map(|span| span.name()),
+            fields,
+            file: metadata.file(),
+            line: metadata.line(),
+        };
+
+        match &self.hook {
+            AlertHook::Callback(callback) => callback(record),
+            AlertHook::Channel(sender) => {
+                let _ = sender.send(record);
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Provides a guaranteed-delivery audit logging sink, to which selected events are written
+//! synchronously and persisted to disk before the logging call returns.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
+
+use tracing::{Event, Metadata, Subscriber};
+use tracing_subscriber::{Layer, fmt::MakeWriter, layer::Context};
+
+use super::fallback::FileSinkHealth;
+
+/// The field that marks an event as an audit event.
+///
+/// Any event including this field (regardless of its value) is written to the audit log, in
+/// addition to the other logging sinks.
+/// The [`audit!`][crate::audit] macro can be used to emit events including this field.
+pub const AUDIT_KEY: &str = "audit";
+
+/// Configuration for audit logging.
+///
+/// Audit events are written synchronously to the audit log file, and the file contents are
+/// flushed to disk before the logging call returns.
+/// Audit events are never dropped by the non-blocking writers used by the other logging sinks,
+/// and are written irrespective of the levels and filtering directives configured for the other
+/// sinks.
+#[derive(Debug, Clone)]
+pub struct AuditLoggingConfig {
+    /// Directory where the audit log file will be stored.
+    pub directory: String,
+
+    /// Name of the audit log file. Records are appended to the file if it already exists.
+    pub file_name: String,
+}
+
+/// Checks whether the metadata describes an audit event.
+///
+/// Spans are accepted as well, so that the span data is available to audit events.
+pub(crate) fn is_audit_event(metadata: &Metadata<'_>) -> bool {
+    metadata.is_span() || metadata.fields().field(AUDIT_KEY).is_some()
+}
+
+/// A [`MakeWriter`] that writes to a file synchronously, syncing the file contents to disk after
+/// every write.
+///
+/// Failed writes are not retried, and are counted in the [`FileSinkHealth`] of the audit sink,
+/// since the formatting layers have no means of reporting them.
+#[derive(Debug)]
+pub(crate) struct SyncFileWriter {
+    file: Mutex<File>,
+    health: FileSinkHealth,
+}
+
+impl SyncFileWriter {
+    /// Opens the file in the specified directory for appending, creating the directory and the
+    /// file if they do not exist.
+    pub(crate) fn open(directory: &str, file_name: &str) -> io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Path::new(directory).join(file_name))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+            health: FileSinkHealth::default(),
+        })
+    }
+
+    /// Returns the handle for reporting the health of the audit sink.
+    pub(crate) fn health(&self) -> FileSinkHealth {
+        self.health.clone()
+    }
+}
+
+impl<'a> MakeWriter<'a> for SyncFileWriter {
+    type Writer = SyncFileWriterGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SyncFileWriterGuard {
+            file: self
+                .file
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+            health: &self.health,
+        }
+    }
+}
+
+/// The writer produced by [`SyncFileWriter`], holding exclusive access to the file.
+#[derive(Debug)]
+pub(crate) struct SyncFileWriterGuard<'a> {
+    file: MutexGuard<'a, File>,
+    health: &'a FileSinkHealth,
+}
+
+impl SyncFileWriterGuard<'_> {
+    /// Counts the failure of a write (or of syncing the written record to disk), returning the
+    /// error.
+    fn record_error(&self, error: io::Error, dropped_record: bool) -> io::Error {
+        self.health.record_write_error();
+        if dropped_record {
+            self.health.record_dropped_record();
+        }
+        error
+    }
+}
+
+impl Write for SyncFileWriterGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self
+            .file
+            .write(buf)
+            .map_err(|error| self.record_error(error, true))?;
+        self.file
+            .sync_data()
+            .map_err(|error| self.record_error(error, false))?;
+        Ok(written)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.file
+            .write_all(buf)
+            .map_err(|error| self.record_error(error, true))?;
+        self.file
+            .sync_data()
+            .map_err(|error| self.record_error(error, false))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file
+            .sync_data()
+            .map_err(|error| self.record_error(error, false))
+    }
+}
+
+/// A [`tracing_subscriber::Layer`] wrapper that only forwards events to the wrapped layer,
+/// suppressing any span lifecycle records it would otherwise produce.
+#[derive(Debug)]
+pub(crate) struct EventsOnlyLayer<L> {
+    inner: L,
+}
+
+impl<L> EventsOnlyLayer<L> {
+    pub(crate) fn new(inner: L) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, L> Layer<S> for EventsOnlyLayer<L>
+where
+    S: Subscriber,
+    L: Layer<S>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.inner.on_event(event, ctx);
+    }
+}
+
+/// Emits an audit event at the `INFO` level, which would be written to the audit log (if
+/// configured) in addition to the other logging sinks.
+///
+/// This macro accepts the same arguments as the [`tracing::info!`] macro, and marks the event
+/// as an audit event by including the [`AUDIT_KEY`] field.
+///
+/// # Example
+///
+/// ```
+/// log_utils::audit!(payment_id = "pay_123", amount = 100, "Refund initiated");
+/// ```
+#[macro_export]
+macro_rules! audit {
+    ($($arg:tt)+) => {
+        $crate::__private::tracing::info!(audit = true, $($arg)+)
+    };
+}
@@ -0,0 +1,84 @@
+//! Provides integration with [`axum`](https://docs.rs/axum), following the key conventions of
+//! this crate.
+//!
+//! The [`RequestLoggingLayer`] can be added to an `axum::Router` as is, to handle each request
+//! within a root span carrying the `request_id` field, whose exit record serves as the access log
+//! record of the request (and includes the persistent keys propagated from the spans within the
+//! request).
+//! The [`LogContext`] extractor provides handlers with the log context of the current request.
+//!
+//! This module is only available when the `axum` feature is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use log_utils::axum::LogContext;
+//!
+//! async fn create_payment(log_context: LogContext) -> String {
+//!     // Return the request ID to the client, for correlation with the logs
+//!     log_context.request_id().unwrap_or_default().to_owned()
+//! }
+//! # let _ = create_payment;
+//! ```
+
+use std::{collections::HashMap, convert::Infallible};
+
+use axum_core::extract::FromRequestParts;
+use http::request::Parts;
+use serde_json::Value;
+
+pub use super::tower::{RequestId, RequestLoggingLayer};
+use super::{keys, storage::current_span_fields};
+
+/// An extractor for the log context of the current request: the request ID, and the fields
+/// captured by the [`SpanStorageLayer`][crate::SpanStorageLayer] for the current span.
+///
+/// The request ID is set by the [`RequestLoggingLayer`], and the fields include the fields of the
+/// request span created by the layer (as well as any fields recorded on the span afterwards),
+/// such as the `request_id`, `method` and `path` fields.
+/// Extraction never fails, and the context is empty if the layers are not installed.
+#[derive(Clone, Debug, Default)]
+pub struct LogContext {
+    request_id: Option<String>,
+    fields: HashMap<String, Value>,
+}
+
+impl LogContext {
+    /// Returns the ID of the current request, if any.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns the value of the field of the current span with the specified key, if any.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.get(key)
+    }
+
+    /// Returns the fields of the current span.
+    pub fn fields(&self) -> &HashMap<String, Value> {
+        &self.fields
+    }
+}
+
+impl<S> FromRequestParts<S> for LogContext
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let fields = current_span_fields();
+        let request_id = parts
+            .extensions
+            .get::<RequestId>()
+            .map(|request_id| request_id.as_str().to_owned())
+            .or_else(|| {
+                fields
+                    .get(keys::REQUEST_ID)
+                    .and_then(Value::as_str)
+                    .map(str::to_owned)
+            });
+
+        Ok(Self { request_id, fields })
+    }
+}
@@ -0,0 +1,24 @@
+//! Provides the propagation of [OpenTelemetry baggage](https://opentelemetry.io/docs/concepts/signals/baggage/)
+//! entries as log fields, so that metadata set by upstream services is included in the logs.
+
+use std::collections::HashSet;
+
+use opentelemetry::{Context, baggage::BaggageExt};
+
+use super::storage::Storage;
+
+/// Records the entries of the baggage of the current OpenTelemetry context with the specified
+/// keys into the storage, with the baggage keys as the field names.
+pub(crate) fn record_baggage(keys: &HashSet<&'static str>, storage: &mut Storage<'static>) {
+    let context = Context::current();
+    let baggage = context.baggage();
+    if baggage.is_empty() {
+        return;
+    }
+
+    for key in keys {
+        if let Some(value) = baggage.get(key) {
+            storage.record_value(key, serde_json::Value::from(value.as_str()));
+        }
+    }
+}
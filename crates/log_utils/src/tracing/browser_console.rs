@@ -0,0 +1,84 @@
+//! Provides a [`MakeWriter`] ([`BrowserConsoleWriter`]) for writing log records to the browser
+//! console, for use on `wasm32-unknown-unknown` targets.
+
+use std::io;
+
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+use wasm_bindgen::JsValue;
+
+/// A [`MakeWriter`] that writes each log record to the browser console.
+///
+/// Records are written using the console method corresponding to their level (such as
+/// `console.error()` for `ERROR` records) when the level is known, and using `console.log()`
+/// otherwise.
+/// Each record is written with a single console call, once the record has been completely
+/// written.
+///
+/// This writer is only available when the `wasm` feature is enabled, and is used by the console
+/// sink constructed by [`build_logging_components`][super::build_logging_components] on `wasm32`
+/// targets.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BrowserConsoleWriter;
+
+impl BrowserConsoleWriter {
+    /// Creates a new [`BrowserConsoleWriter`].
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<'a> MakeWriter<'a> for BrowserConsoleWriter {
+    type Writer = BrowserConsoleRecordWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        BrowserConsoleRecordWriter {
+            level: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        BrowserConsoleRecordWriter {
+            level: Some(*meta.level()),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// The writer produced by [`BrowserConsoleWriter`], which buffers a record and writes it to the
+/// browser console when dropped.
+#[derive(Debug)]
+pub struct BrowserConsoleRecordWriter {
+    level: Option<Level>,
+    buffer: Vec<u8>,
+}
+
+impl io::Write for BrowserConsoleRecordWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for BrowserConsoleRecordWriter {
+    fn drop(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let record = String::from_utf8_lossy(&self.buffer);
+        let record = JsValue::from_str(record.trim_end());
+        match self.level {
+            Some(Level::ERROR) => web_sys::console::error_1(&record),
+            Some(Level::WARN) => web_sys::console::warn_1(&record),
+            Some(Level::INFO) => web_sys::console::info_1(&record),
+            Some(_) => web_sys::console::debug_1(&record),
+            None => web_sys::console::log_1(&record),
+        }
+    }
+}
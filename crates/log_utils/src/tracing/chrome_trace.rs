@@ -0,0 +1,238 @@
+//! Provides a [`tracing_subscriber::Layer`] ([`ChromeTraceLayer`]) for capturing the entries and
+//! exits of spans within a bounded window, and exporting them in the Chrome `trace_event` format.
+
+use std::{
+    cell::Cell,
+    io,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tracing::{Subscriber, span::Id};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+/// Configuration for capturing the entries and exits of spans in the Chrome `trace_event`
+/// format.
+#[derive(Clone, Copy, Debug)]
+pub struct ChromeTraceConfig {
+    /// The maximum number of trace events retained in a capture.
+    /// Once reached, the capture ends early, bounding the memory used by a capture.
+    pub max_events: usize,
+}
+
+/// A trace event in the Chrome `trace_event` format.
+#[derive(Debug, Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    /// The phase of the event, `B` for span entries and `E` for span exits.
+    ph: &'static str,
+    /// The time of the event in microseconds, relative to the start of the capture.
+    ts: f64,
+    pid: u32,
+    tid: u64,
+}
+
+/// The trace events of a capture, in the format expected by trace viewers.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+#[derive(Debug, Default)]
+struct CaptureState {
+    started_at: Option<Instant>,
+    deadline: Option<Instant>,
+    events: Vec<TraceEvent>,
+}
+
+/// A handle for starting a capture of a [`ChromeTraceLayer`] and exporting the captured trace
+/// events, such as from an administrative endpoint of a production service.
+///
+/// The handle can be cloned cheaply, and all clones control the same capture.
+#[derive(Clone, Debug)]
+pub struct ChromeTraceCapture {
+    capturing: Arc<AtomicBool>,
+    max_events: usize,
+    state: Arc<Mutex<CaptureState>>,
+}
+
+impl ChromeTraceCapture {
+    /// Starts capturing the entries and exits of spans for the specified window, discarding the
+    /// trace events of any previous capture.
+    pub fn start(&self, window: Duration) {
+        let now = Instant::now();
+        let mut state = self.lock_state();
+        *state = CaptureState {
+            started_at: Some(now),
+            deadline: now.checked_add(window),
+            events: Vec::new(),
+        };
+        self.capturing.store(true, Ordering::Relaxed);
+    }
+
+    /// Ends the current capture early, retaining the captured trace events.
+    pub fn stop(&self) {
+        self.capturing.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a capture is in progress.
+    ///
+    /// A capture whose window has elapsed is considered to have ended.
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.load(Ordering::Relaxed)
+            && self
+                .lock_state()
+                .deadline
+                .is_none_or(|deadline| Instant::now() < deadline)
+    }
+
+    /// Writes the trace events of the current (or last) capture as Chrome `trace_event` JSON,
+    /// which can be loaded into [Perfetto](https://ui.perfetto.dev) or `chrome://tracing`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails.
+    pub fn write_trace(&self, writer: impl io::Write) -> io::Result<()> {
+        let state = self.lock_state();
+        let trace = Trace {
+            trace_events: &state.events,
+            display_time_unit: "ms",
+        };
+        serde_json::to_writer(writer, &trace).map_err(io::Error::from)
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, CaptureState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records the trace event, if a capture is in progress.
+    fn record(&self, name: &'static str, cat: &'static str, ph: &'static str) {
+        // Avoid locking the state when no capture is in progress
+        if !self.capturing.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut state = self.lock_state();
+        let Some(started_at) = state.started_at else {
+            return;
+        };
+        if state.deadline.is_some_and(|deadline| now >= deadline)
+            || state.events.len() >= self.max_events
+        {
+            self.capturing.store(false, Ordering::Relaxed);
+            return;
+        }
+
+        state.events.push(TraceEvent {
+            name,
+            cat,
+            ph,
+            ts: now.duration_since(started_at).as_secs_f64() * 1_000_000.0,
+            pid: process_id(),
+            tid: thread_id(),
+        });
+    }
+}
+
+/// Returns the ID of the current process, `0` on `wasm32` targets.
+fn process_id() -> u32 {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::process::id();
+    #[cfg(target_arch = "wasm32")]
+    return 0;
+}
+
+/// Returns a numeric ID of the current thread, assigned on first use, since the IDs of
+/// [`std::thread::ThreadId`] are not available as integers.
+fn thread_id() -> u64 {
+    static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+    }
+
+    THREAD_ID.with(|thread_id| {
+        if thread_id.get() == 0 {
+            thread_id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        thread_id.get()
+    })
+}
+
+/// A [`tracing_subscriber::Layer`] that captures the entries and exits of spans within a bounded
+/// window, started using the [`ChromeTraceCapture`] handle.
+///
+/// The layer records nothing when no capture is in progress.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use log_utils::{ChromeTraceConfig, ChromeTraceLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let layer = ChromeTraceLayer::new(ChromeTraceConfig {
+///     max_events: 100_000,
+/// });
+/// let capture = layer.capture();
+/// let subscriber = tracing_subscriber::registry().with(layer);
+///
+/// tracing::subscriber::with_default(subscriber, || {
+///     capture.start(Duration::from_secs(30));
+///     tracing::info_span!("request").in_scope(|| {});
+/// });
+///
+/// let mut trace = Vec::new();
+/// capture.write_trace(&mut trace)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ChromeTraceLayer {
+    capture: ChromeTraceCapture,
+}
+
+impl ChromeTraceLayer {
+    /// Creates a new [`ChromeTraceLayer`] with the specified configuration, without a capture
+    /// in progress.
+    pub fn new(config: ChromeTraceConfig) -> Self {
+        Self {
+            capture: ChromeTraceCapture {
+                capturing: Arc::new(AtomicBool::new(false)),
+                max_events: config.max_events,
+                state: Arc::new(Mutex::new(CaptureState::default())),
+            },
+        }
+    }
+
+    /// Returns the handle for starting captures and exporting the captured trace events.
+    pub fn capture(&self) -> ChromeTraceCapture {
+        self.capture.clone()
+    }
+}
+
+impl<S> Layer<S> for ChromeTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let metadata = span.metadata();
+            self.capture.record(metadata.name(), metadata.target(), "B");
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let metadata = span.metadata();
+            self.capture.record(metadata.name(), metadata.target(), "E");
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Provides detection of the ID of the container the process is running in, from the cgroup and
+//! mount information of the process exposed by Linux.
+
+use std::sync::LazyLock;
+
+/// The length of a container ID, as used by Docker, containerd and CRI-O.
+const CONTAINER_ID_LENGTH: usize = 64;
+
+/// The ID of the container the process is running in, detected once per process.
+static CONTAINER_ID: LazyLock<Option<String>> = LazyLock::new(|| {
+    std::fs::read_to_string("/proc/self/cgroup")
+        .ok()
+        .and_then(|cgroup| container_id_from_cgroup(&cgroup))
+        .or_else(|| {
+            std::fs::read_to_string("/proc/self/mountinfo")
+                .ok()
+                .and_then(|mountinfo| container_id_from_mountinfo(&mountinfo))
+        })
+});
+
+/// Returns the ID of the container the process is running in, if the process is running in a
+/// container and the ID could be detected.
+pub(crate) fn container_id() -> Option<String> {
+    CONTAINER_ID.clone()
+}
+
+/// Extracts the container ID from the contents of `/proc/self/cgroup`.
+///
+/// The cgroup paths of containers carry the container ID as a path segment (such as
+/// `/docker/<id>` or `/kubepods/burstable/pod<uid>/<id>`), optionally with a prefix and suffix
+/// (such as `/system.slice/docker-<id>.scope` or `.../cri-containerd-<id>.scope`).
+/// With cgroup v2 and a private cgroup namespace, the path is `/` and no ID can be extracted.
+pub(crate) fn container_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        // Each line is of the form `hierarchy-ID:controller-list:cgroup-path`
+        let path = line.splitn(3, ':').nth(2)?;
+        path.rsplit('/').find_map(|segment| {
+            segment
+                .split(['-', '.'])
+                .find(|token| is_container_id(token))
+                .map(str::to_owned)
+        })
+    })
+}
+
+/// Extracts the container ID from the contents of `/proc/self/mountinfo`.
+///
+/// Container runtimes bind mount files such as `/etc/hostname` from a per-container directory
+/// (such as `/var/lib/docker/containers/<id>/hostname`, or
+/// `.../overlay-containers/<id>/userdata/hostname` for Podman).
+pub(crate) fn container_id_from_mountinfo(mountinfo: &str) -> Option<String> {
+    mountinfo.lines().find_map(|line| {
+        line.split(' ').find_map(|field| {
+            field.split("containers/").skip(1).find_map(|rest| {
+                rest.split('/')
+                    .next()
+                    .filter(|segment| is_container_id(segment))
+                    .map(str::to_owned)
+            })
+        })
+    })
+}
+
+/// Returns `true` if the token is a container ID, that is, a lowercase hex-encoded SHA-256 hash.
+fn is_container_id(token: &str) -> bool {
+    token.len() == CONTAINER_ID_LENGTH
+        && token
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
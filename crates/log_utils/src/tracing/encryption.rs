@@ -0,0 +1,270 @@
+//! Provides at-rest encryption of log files using AES-256-GCM, along with a reader
+//! ([`DecryptingReader`]) for decrypting the encrypted log files.
+//!
+//! Each record written to an encrypted log file is encrypted independently and stored as a
+//! length-prefixed frame, laid out as follows:
+//!
+//! | Field      | Size (bytes)     | Description                                            |
+//! | ---------- | ---------------- | ------------------------------------------------------ |
+//! | Length     | 4                | Big-endian length of the nonce and ciphertext combined |
+//! | Nonce      | 12               | Randomly generated nonce used for encrypting the frame |
+//! | Ciphertext | Length - 12      | Encrypted record, followed by the 16-byte tag          |
+//!
+//! Records longer than [`MAX_RECORD_LENGTH`] are not written, so that readers can reject frames
+//! longer than the largest frame written without allocating memory for them.
+
+#[cfg(feature = "encryption")]
+use std::io::{self, Read, Write};
+use std::{fmt, sync::Arc};
+
+#[cfg(feature = "encryption")]
+use aes_gcm::{
+    Aes256Gcm, Key, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+
+/// Length of the AES-256-GCM encryption key, in bytes.
+pub const ENCRYPTION_KEY_LENGTH: usize = 32;
+
+/// Length of the nonce stored at the start of each encrypted frame, in bytes.
+#[cfg(feature = "encryption")]
+const NONCE_LENGTH: usize = 12;
+
+/// Length of the big-endian length prefix of each encrypted frame, in bytes.
+#[cfg(feature = "encryption")]
+const LENGTH_PREFIX_LENGTH: usize = 4;
+
+/// Length of the authentication tag following the ciphertext of each encrypted frame, in bytes.
+#[cfg(feature = "encryption")]
+const TAG_LENGTH: usize = 16;
+
+/// Maximum length of a record written to an encrypted log file, in bytes.
+#[cfg(feature = "encryption")]
+pub(crate) const MAX_RECORD_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Maximum length of an encrypted frame (excluding the length prefix), in bytes.
+#[cfg(feature = "encryption")]
+const MAX_FRAME_LENGTH: usize = NONCE_LENGTH + MAX_RECORD_LENGTH + TAG_LENGTH;
+
+/// A callback invoked to fetch the encryption key, such as from a key management service.
+///
+/// The callback is invoked once, when the logging components are being built.
+pub type EncryptionKeyCallback =
+    Arc<dyn Fn() -> Result<[u8; ENCRYPTION_KEY_LENGTH], String> + Send + Sync>;
+
+/// Configuration for encrypting log files at rest.
+#[derive(Clone, Debug)]
+pub struct FileEncryptionConfig {
+    /// The source of the AES-256-GCM encryption key.
+    pub key: EncryptionKeySource,
+}
+
+/// Specifies how the encryption key is obtained.
+#[derive(Clone)]
+pub enum EncryptionKeySource {
+    /// Use the supplied key.
+    Key([u8; ENCRYPTION_KEY_LENGTH]),
+
+    /// Fetch the key by invoking the callback.
+    Callback(EncryptionKeyCallback),
+}
+
+impl fmt::Debug for EncryptionKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(_) => f.write_str("Key(*** redacted ***)"),
+            Self::Callback(_) => f.debug_tuple("Callback").finish_non_exhaustive(),
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl EncryptionKeySource {
+    /// Obtains the encryption key, invoking the callback if required.
+    pub(crate) fn resolve(&self) -> Result<[u8; ENCRYPTION_KEY_LENGTH], String> {
+        match self {
+            Self::Key(key) => Ok(*key),
+            Self::Callback(callback) => callback(),
+        }
+    }
+}
+
+/// A writer that encrypts each write as a separate length-prefixed frame before writing it to the
+/// wrapped writer.
+///
+/// The formatting layers write each record using a single write, and hence each record is stored
+/// in its own frame.
+#[cfg(feature = "encryption")]
+pub(crate) struct EncryptingWriter<W> {
+    inner: W,
+    cipher: Aes256Gcm,
+}
+
+#[cfg(feature = "encryption")]
+impl<W> EncryptingWriter<W> {
+    /// Wraps the writer, encrypting the data written to it with the specified key.
+    pub(crate) fn new(inner: W, key: &[u8; ENCRYPTION_KEY_LENGTH]) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if buf.len() > MAX_RECORD_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "log record too large",
+            ));
+        }
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, buf)
+            .map_err(|_| io::Error::other("failed to encrypt log record"))?;
+
+        let frame_length = u32::try_from(NONCE_LENGTH + ciphertext.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "log record too large"))?;
+
+        // Write the entire frame at once, so that it is not split across rotated files
+        let mut frame = Vec::with_capacity(LENGTH_PREFIX_LENGTH + NONCE_LENGTH + ciphertext.len());
+        frame.extend_from_slice(&frame_length.to_be_bytes());
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&ciphertext);
+        self.inner.write_all(&frame)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader that decrypts log files written with encryption enabled, producing the original
+/// records.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::{fs::File, io::BufRead};
+///
+/// use log_utils::DecryptingReader;
+///
+/// let key = [0u8; 32];
+/// let file = File::open("/var/log/my_app/my_app.log.2025-01-01")?;
+/// let reader = std::io::BufReader::new(DecryptingReader::new(file, &key));
+///
+/// for line in reader.lines() {
+///     println!("{}", line?);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Reads fail with an [`io::ErrorKind::InvalidData`] error if a frame could not be decrypted
+/// (due to an incorrect key or a tampered file, for example) or is longer than the largest frame
+/// written, or with an [`io::ErrorKind::UnexpectedEof`] error if the file ends with a truncated
+/// frame.
+#[cfg(feature = "encryption")]
+pub struct DecryptingReader<R> {
+    inner: R,
+    cipher: Aes256Gcm,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+#[cfg(feature = "encryption")]
+impl<R> fmt::Debug for DecryptingReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecryptingReader").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<R: Read> DecryptingReader<R> {
+    /// Creates a new [`DecryptingReader`] reading encrypted frames from the specified reader.
+    pub fn new(inner: R, key: &[u8; ENCRYPTION_KEY_LENGTH]) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Reads and decrypts the next frame, returning `None` at the end of the input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame could not be read or decrypted.
+    pub fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut length_prefix = [0; LENGTH_PREFIX_LENGTH];
+        let read = loop {
+            match self.inner.read(&mut length_prefix) {
+                Ok(read) => break read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        self.inner
+            .read_exact(length_prefix.get_mut(read..).unwrap_or_default())?;
+
+        let frame_length = usize::try_from(u32::from_be_bytes(length_prefix))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "frame too large"))?;
+        if frame_length < NONCE_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "encrypted frame shorter than nonce",
+            ));
+        }
+        // The length is untrusted, and hence is validated before allocating the frame
+        if frame_length > MAX_FRAME_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "encrypted frame too large",
+            ));
+        }
+
+        let mut frame = vec![0; frame_length];
+        self.inner.read_exact(&mut frame)?;
+
+        let (nonce, ciphertext) = frame.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map(Some)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "failed to decrypt log record"))
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.buffer.len() {
+            match self.next_record()? {
+                Some(record) => {
+                    self.buffer = record;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let mut remaining = self.buffer.get(self.position..).unwrap_or_default();
+        let count = remaining.read(buf)?;
+        self.position += count;
+
+        Ok(count)
+    }
+}
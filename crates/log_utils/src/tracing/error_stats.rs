@@ -0,0 +1,182 @@
+//! Provides [`ErrorStats`], an in-process tracker of the `ERROR` records emitted over sliding
+//! windows.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use super::LoggingComponents;
+
+/// The default duration for which the counts of `ERROR` records are retained.
+const DEFAULT_RETENTION: Duration = Duration::from_secs(15 * 60);
+
+/// A handle for reading the number and rate of `ERROR` records emitted over sliding windows,
+/// counted by the [`SpanStorageLayer`][crate::SpanStorageLayer].
+///
+/// This allows readiness probes and circuit breakers to react to a storm of errors without
+/// scraping the logs.
+/// The records are counted in buckets of one second, and the counts are retained for a fixed
+/// duration, which bounds the windows that can be queried.
+///
+/// Similar to [`RecordCounts`][crate::RecordCounts], `ERROR` records are only counted if the
+/// storage layer is not restricted from the `ERROR` level.
+///
+/// The handle can be cloned cheaply, and all clones observe the same counts.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use log_utils::{ErrorStats, SpanStorageLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let error_stats = ErrorStats::new(Duration::from_secs(60));
+/// let storage_layer = SpanStorageLayer::new([]).with_error_stats(error_stats.clone());
+/// let subscriber = tracing_subscriber::registry().with(storage_layer);
+///
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::error!("Payment failed");
+/// });
+///
+/// assert_eq!(error_stats.count(Duration::from_secs(10)), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ErrorStats {
+    /// The instant relative to which the seconds of the buckets are measured.
+    started_at: Instant,
+    /// The buckets of counts, indexed by the second modulo the number of buckets.
+    /// Each bucket packs the second it counts in the upper 32 bits, and the count in the lower
+    /// 32 bits, so that a bucket can be reset and incremented atomically.
+    buckets: Arc<[AtomicU64]>,
+}
+
+impl Default for ErrorStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETENTION)
+    }
+}
+
+impl ErrorStats {
+    /// Creates a new [`ErrorStats`] retaining the counts for the specified duration (rounded up
+    /// to whole seconds, and at least a second), which is the longest window that can be
+    /// queried.
+    pub fn new(retention: Duration) -> Self {
+        let bucket_count = retention
+            .as_secs()
+            .saturating_add(u64::from(retention.subsec_nanos() > 0))
+            .max(1);
+        let bucket_count = usize::try_from(bucket_count).unwrap_or(usize::MAX);
+
+        Self {
+            started_at: Instant::now(),
+            buckets: (0..bucket_count).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Returns the longest window that can be queried.
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(u64::try_from(self.buckets.len()).unwrap_or(u64::MAX))
+    }
+
+    /// Returns the number of `ERROR` records emitted within the window ending now.
+    ///
+    /// The window is rounded up to whole seconds, and capped at the
+    /// [`retention`][Self::retention], with the current (partial) second counted as a whole.
+    pub fn count(&self, window: Duration) -> u64 {
+        let now = self.current_second();
+        let window_seconds = window
+            .as_secs()
+            .saturating_add(u64::from(window.subsec_nanos() > 0))
+            .min(u64::try_from(self.buckets.len()).unwrap_or(u64::MAX));
+        let oldest = now.saturating_sub(window_seconds.saturating_sub(1));
+
+        self.buckets
+            .iter()
+            .map(|bucket| unpack(bucket.load(Ordering::Relaxed)))
+            .filter(|(second, _)| (oldest..=now).contains(second))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns the average number of `ERROR` records emitted per second within the window
+    /// ending now, with the window rounded and capped as in [`count()`][Self::count].
+    pub fn rate(&self, window: Duration) -> f64 {
+        let window_seconds = window
+            .as_secs_f64()
+            .ceil()
+            .clamp(1.0, self.retention().as_secs_f64());
+        #[expect(clippy::as_conversions)]
+        let count = self.count(window) as f64;
+
+        count / window_seconds
+    }
+
+    /// Counts an `ERROR` record emitted now.
+    pub(crate) fn increment(&self) {
+        let now = self.current_second();
+        let Some(bucket) = u64::try_from(self.buckets.len())
+            .ok()
+            .and_then(|bucket_count| usize::try_from(now % bucket_count).ok())
+            .and_then(|index| self.buckets.get(index))
+        else {
+            return;
+        };
+
+        // The update always succeeds, since the closure always returns `Some`
+        let _ = bucket.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |packed| {
+            let (second, count) = unpack(packed);
+            // Reset buckets last used for an earlier second
+            let count = if second == now {
+                count.saturating_add(1)
+            } else {
+                1
+            };
+            Some(pack(now, count))
+        });
+    }
+
+    /// Returns the number of whole seconds elapsed since the tracker was created.
+    fn current_second(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+}
+
+impl LoggingComponents {
+    /// Returns a handle for reading the number and rate of `ERROR` records emitted over sliding
+    /// windows of up to 15 minutes, counted by the [`storage_layer`][Self::storage_layer].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn config() -> log_utils::LoggerConfig { unimplemented!() }
+    /// use std::time::Duration;
+    ///
+    /// let components = log_utils::build_logging_components(config())?;
+    /// let error_stats = components.error_stats();
+    ///
+    /// // Such as in a readiness probe
+    /// if error_stats.rate(Duration::from_secs(60)) > 10.0 {
+    ///     tracing::warn!("Not ready due to a high error rate");
+    /// }
+    /// # Ok::<(), log_utils::LoggerError>(())
+    /// ```
+    pub fn error_stats(&self) -> ErrorStats {
+        self.error_stats.clone()
+    }
+}
+
+/// Packs the second and the count of a bucket into a single value, truncating the second to 32
+/// bits and saturating the count at 32 bits.
+fn pack(second: u64, count: u64) -> u64 {
+    ((second & u64::from(u32::MAX)) << 32) | count.min(u64::from(u32::MAX))
+}
+
+/// Unpacks the second and the count of a bucket.
+fn unpack(packed: u64) -> (u64, u64) {
+    (packed >> 32, packed & u64::from(u32::MAX))
+}
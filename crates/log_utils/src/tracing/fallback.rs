@@ -0,0 +1,433 @@
+//! Provides the policies applied when the file logging sink cannot be created or written to,
+//! along with a [`tracing_subscriber::Layer`] wrapper that emits a diagnostic record describing a
+//! creation failure, and a handle ([`FileSinkHealth`]) for reporting degraded file logging.
+
+use std::{
+    io::{self, Write},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tracing::{
+    Event, Level, Metadata, Subscriber,
+    callsite::{DefaultCallsite, Identifier},
+    field::{FieldSet, Value},
+    metadata::Kind,
+    span,
+};
+use tracing_appender::rolling::{Builder, InitError, RollingFileAppender};
+use tracing_subscriber::{Layer, layer::Context};
+
+/// Specifies what happens when the log file cannot be created, such as when the log directory
+/// cannot be created or is not writable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileSinkFallback {
+    /// Fail building the logging components with
+    /// [`LoggerError::FileAppenderInitialization`][super::LoggerError::FileAppenderInitialization].
+    #[default]
+    Fail,
+
+    /// Write the file logs to the console (stdout) instead, with the same format and filters.
+    /// Encryption and hash chaining are not applied to the console output.
+    Console,
+
+    /// Retry creating the log file, waiting between attempts with exponential backoff, and fail
+    /// if the log file could not be created after all retries.
+    /// Building the logging components blocks while retrying.
+    Retry {
+        /// The maximum number of retries, in addition to the initial attempt.
+        max_retries: u32,
+
+        /// The duration to wait before the first retry, which is doubled for each subsequent
+        /// retry.
+        initial_backoff: Duration,
+    },
+}
+
+/// Specifies what happens when writing a record to the log file fails, such as when the disk is
+/// full (`ENOSPC`) or on I/O errors (`EIO`).
+///
+/// Failed writes are counted in the [`FileSinkHealth`] of the file sink, regardless of the policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileWriteErrorPolicy {
+    /// Drop the record, counting it as dropped.
+    #[default]
+    Drop,
+
+    /// Retry writing the record until it succeeds, waiting for the specified interval between
+    /// attempts.
+    /// Records are written by a background worker, and hence the application is not blocked
+    /// while retrying, but records emitted while retrying are dropped once the worker's queue is
+    /// full.
+    Block {
+        /// The duration to wait between attempts.
+        retry_interval: Duration,
+    },
+
+    /// Drop the record, and write all subsequent records to an emergency console (stdout) sink
+    /// instead of the log file.
+    /// Encryption and hash chaining are not applied to the console output.
+    Console,
+}
+
+/// A handle for reporting the health of a file based logging sink (the file logging sink or the
+/// audit logging sink), such as from health checks.
+///
+/// The handle can be cloned cheaply, and all clones observe the same counts.
+#[derive(Clone, Debug, Default)]
+pub struct FileSinkHealth {
+    inner: Arc<FileSinkHealthInner>,
+}
+
+#[derive(Debug, Default)]
+struct FileSinkHealthInner {
+    write_errors: AtomicU64,
+    dropped_records: AtomicU64,
+    writing_to_console: AtomicBool,
+}
+
+impl FileSinkHealth {
+    /// Returns the number of failed attempts to write a record to the log file.
+    pub fn write_errors(&self) -> u64 {
+        self.inner.write_errors.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of records dropped due to write errors.
+    pub fn dropped_records(&self) -> u64 {
+        self.inner.dropped_records.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the file logs are being written to the console instead of the log file,
+    /// either because the log file could not be created, or due to a write error.
+    pub fn is_writing_to_console(&self) -> bool {
+        self.inner.writing_to_console.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if file logging is degraded, that is, if any write to the log file has
+    /// failed, or if the file logs are being written to the console instead.
+    pub fn is_degraded(&self) -> bool {
+        self.write_errors() > 0 || self.is_writing_to_console()
+    }
+
+    /// Counts a failed attempt to write a record to the log file.
+    pub(crate) fn record_write_error(&self) {
+        self.inner.write_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a record dropped due to a write error.
+    pub(crate) fn record_dropped_record(&self) {
+        self.inner.dropped_records.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marks the file logs as being written to the console instead of the log file.
+    pub(crate) fn set_writing_to_console(&self) {
+        self.inner.writing_to_console.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A writer that applies the [`FileWriteErrorPolicy`] to errors returned by the wrapped writer,
+/// counting them in the [`FileSinkHealth`].
+///
+/// The formatting layers write each record using a single write, and hence each write is
+/// treated as a separate record.
+pub(crate) struct WriteErrorPolicyWriter<W> {
+    inner: W,
+    policy: FileWriteErrorPolicy,
+    health: FileSinkHealth,
+    console: Option<io::Stdout>,
+}
+
+impl<W> WriteErrorPolicyWriter<W> {
+    pub(crate) fn new(inner: W, policy: FileWriteErrorPolicy, health: FileSinkHealth) -> Self {
+        Self {
+            inner,
+            policy,
+            health,
+            console: None,
+        }
+    }
+}
+
+impl<W: Write> Write for WriteErrorPolicyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(console) = self.console.as_mut() {
+            console.write_all(buf)?;
+            return Ok(buf.len());
+        }
+
+        // Only the remainder of a partially written record is retried, so that the part of the
+        // record already written is not duplicated
+        let mut remaining = buf;
+        while !remaining.is_empty() {
+            match self.inner.write(remaining) {
+                Ok(0) => {}
+                Ok(written) => {
+                    remaining = remaining.get(written..).unwrap_or_default();
+                    continue;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => {}
+            }
+            self.health.record_write_error();
+
+            match self.policy {
+                FileWriteErrorPolicy::Drop => {
+                    self.health.record_dropped_record();
+                    return Ok(buf.len());
+                }
+                FileWriteErrorPolicy::Block { retry_interval } => {
+                    std::thread::sleep(retry_interval);
+                }
+                FileWriteErrorPolicy::Console => {
+                    self.health.record_dropped_record();
+                    self.health.set_writing_to_console();
+                    self.console = Some(io::stdout());
+                    return Ok(buf.len());
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.console.as_mut() {
+            Some(console) => console.flush(),
+            None => self.inner.flush(),
+        }
+    }
+}
+
+/// Describes a failure to create the file logging sink, which was recovered from using the
+/// configured [`FileSinkFallback`].
+#[derive(Debug)]
+pub(crate) struct FileSinkDiagnostic {
+    /// The directory in which the log file was to be created.
+    pub(crate) directory: String,
+
+    /// The error encountered when creating the log file.
+    pub(crate) error: String,
+
+    /// The number of failed attempts to create the log file.
+    pub(crate) failed_attempts: u32,
+
+    /// Whether the file logs are written to the console instead.
+    pub(crate) console_fallback: bool,
+}
+
+impl FileSinkDiagnostic {
+    fn message(&self) -> &'static str {
+        if self.console_fallback {
+            "Failed to create log file, writing file logs to the console instead"
+        } else {
+            "Created log file after retrying"
+        }
+    }
+}
+
+/// Creates the rolling file appender in the specified directory, applying the fallback policy if
+/// the appender cannot be created.
+///
+/// Returns `None` as the appender if the file logs are to be written to the console instead, along
+/// with a diagnostic describing the failure, if a failure was recovered from.
+pub(crate) fn build_file_appender(
+    builder: &Builder,
+    directory: &str,
+    fallback: FileSinkFallback,
+) -> Result<(Option<RollingFileAppender>, Option<FileSinkDiagnostic>), InitError> {
+    let error = match builder.build(directory) {
+        Ok(appender) => return Ok((Some(appender), None)),
+        Err(error) => error,
+    };
+
+    match fallback {
+        FileSinkFallback::Fail => Err(error),
+        FileSinkFallback::Console => Ok((
+            None,
+            Some(FileSinkDiagnostic {
+                directory: directory.to_owned(),
+                error: error.to_string(),
+                failed_attempts: 1,
+                console_fallback: true,
+            }),
+        )),
+        FileSinkFallback::Retry {
+            max_retries,
+            initial_backoff,
+        } => {
+            let mut error = error;
+            let mut backoff = initial_backoff;
+            for failed_attempts in 1..=max_retries {
+                std::thread::sleep(backoff);
+                backoff = backoff.saturating_mul(2);
+
+                match builder.build(directory) {
+                    Ok(appender) => {
+                        return Ok((
+                            Some(appender),
+                            Some(FileSinkDiagnostic {
+                                directory: directory.to_owned(),
+                                error: error.to_string(),
+                                failed_attempts,
+                                console_fallback: false,
+                            }),
+                        ));
+                    }
+                    Err(retry_error) => error = retry_error,
+                }
+            }
+
+            Err(error)
+        }
+    }
+}
+
+/// The callsite of the diagnostic records.
+///
+/// The diagnostic records are constructed manually rather than using the `tracing` macros, since
+/// events emitted using the macros while the subscriber is processing another event are dropped.
+static DIAGNOSTIC_CALLSITE: DefaultCallsite = DefaultCallsite::new(&DIAGNOSTIC_METADATA);
+
+static DIAGNOSTIC_METADATA: Metadata<'static> = Metadata::new(
+    "file sink diagnostic",
+    env!("CARGO_PKG_NAME"),
+    Level::WARN,
+    Some(file!()),
+    Some(line!()),
+    Some(module_path!()),
+    FieldSet::new(
+        &["message", "directory", "error", "failed_attempts"],
+        Identifier(&DIAGNOSTIC_CALLSITE),
+    ),
+    Kind::EVENT,
+);
+
+/// A [`tracing_subscriber::Layer`] wrapper that passes a diagnostic record describing a file
+/// sink failure to the wrapped layer, just before the wrapped layer processes its first event.
+///
+/// The diagnostic record is only passed to the wrapped layer, so that it is written to the sink
+/// it describes.
+#[derive(Debug)]
+pub(crate) struct DiagnosticLayer<L> {
+    inner: L,
+    diagnostic: Mutex<Option<FileSinkDiagnostic>>,
+}
+
+impl<L> DiagnosticLayer<L> {
+    pub(crate) fn new(inner: L, diagnostic: FileSinkDiagnostic) -> Self {
+        Self {
+            inner,
+            diagnostic: Mutex::new(Some(diagnostic)),
+        }
+    }
+
+    /// Passes the diagnostic record to the wrapped layer, if it has not been passed already.
+    fn emit_diagnostic<S>(&self, ctx: &Context<'_, S>)
+    where
+        S: Subscriber,
+        L: Layer<S>,
+    {
+        let Some(diagnostic) = self
+            .diagnostic
+            .lock()
+            .ok()
+            .and_then(|mut diagnostic| diagnostic.take())
+        else {
+            return;
+        };
+
+        let fields = DIAGNOSTIC_METADATA.fields();
+        let (Some(message), Some(directory), Some(error), Some(failed_attempts)) = (
+            fields.field("message"),
+            fields.field("directory"),
+            fields.field("error"),
+            fields.field("failed_attempts"),
+        ) else {
+            return;
+        };
+
+        let message_value: &dyn Value = &diagnostic.message();
+        let directory_value: &dyn Value = &diagnostic.directory.as_str();
+        let error_value: &dyn Value = &diagnostic.error.as_str();
+        let failed_attempts_value: &dyn Value = &diagnostic.failed_attempts;
+        let values = [
+            (&message, Some(message_value)),
+            (&directory, Some(directory_value)),
+            (&error, Some(error_value)),
+            (&failed_attempts, Some(failed_attempts_value)),
+        ];
+        let values = fields.value_set(&values);
+
+        self.inner
+            .on_event(&Event::new(&DIAGNOSTIC_METADATA, &values), ctx.clone());
+    }
+}
+
+impl<S, L> Layer<S> for DiagnosticLayer<L>
+where
+    S: Subscriber,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &tracing::Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(
+        &self,
+        metadata: &'static Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn max_level_hint(&self) -> Option<tracing::level_filters::LevelFilter> {
+        self.inner.max_level_hint()
+    }
+
+    fn on_record(&self, span: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(span, values, ctx);
+    }
+
+    fn on_follows_from(&self, span: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.emit_diagnostic(&ctx);
+        self.inner.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+}
@@ -5,12 +5,23 @@ use std::{
     collections::{HashMap, HashSet},
     fmt,
     io::Write,
-    sync::Arc,
+    num::NonZeroU8,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
 };
 
 use serde::ser::{SerializeMap, Serializer};
 use serde_json::{Value, ser::Formatter};
-use time::format_description::well_known::Iso8601;
+use time::{
+    OffsetDateTime, UtcOffset,
+    format_description::well_known::{
+        Iso8601,
+        iso8601::{Config, EncodedConfig, TimePrecision},
+    },
+};
 use tracing::{Event, Metadata, Subscriber, span::Id};
 use tracing_subscriber::{
     Layer,
@@ -19,7 +30,20 @@ use tracing_subscriber::{
     registry::{LookupSpan, SpanRef},
 };
 
-use super::{AdditionalFieldsPlacement, LoggerError, storage::Storage};
+use super::{
+    AdditionalFieldsPlacement, CodeLocationFields, LoggerError, keys::ReservedKeys,
+    storage::Storage,
+};
+
+/// The version of the log record schema, included in every record as the `log_schema_version`
+/// field.
+///
+/// The `log_schema_version` key is reserved along with the other implicit keys, and hence fields
+/// with the same name are skipped from log records.
+///
+/// This version is incremented whenever the implicit fields included in log records are changed
+/// in an incompatible manner, such as when a field is renamed, removed, or changes type.
+pub const LOG_SCHEMA_VERSION: u32 = 1;
 
 /// Configuration for creating a [`JsonFormattingLayer`].
 ///
@@ -28,7 +52,10 @@ use super::{AdditionalFieldsPlacement, LoggerError, storage::Storage};
 /// - Keys from event or span data that should be promoted to the top level.
 /// - Behavior for logging span lifecycles (entries and exits).
 /// - Placement of additional (non-top-level) fields.
-#[derive(Clone, Debug)]
+///
+/// The [`Default`] configuration has no static or promoted fields, and the optional features
+/// disabled.
+#[derive(Clone, Debug, Default)]
 pub struct JsonFormattingLayerConfig {
     /// A map of key-value pairs that are statically defined at initialization and included at the
     /// top level of every log entry.
@@ -42,8 +69,224 @@ pub struct JsonFormattingLayerConfig {
     /// If `false`, does not log span entries and only logs exits for root spans.
     pub log_span_lifecycles: bool,
 
+    /// If specified, the exits of spans open for longer than this threshold are logged even if
+    /// [`log_span_lifecycles`][Self::log_span_lifecycles] is `false`.
+    /// The elapsed time of spans is captured by the [`SpanStorageLayer`][crate::SpanStorageLayer].
+    pub slow_span_threshold: Option<Duration>,
+
     /// Specifies how additional fields (not designated as top-level) are placed in the JSON output.
     pub additional_fields_placement: AdditionalFieldsPlacement,
+
+    /// If `true`, fields with null values (such as the `line` and `file` fields when the source
+    /// code location is unavailable), empty objects and empty arrays are omitted from the
+    /// records, instead of being serialized as `null`, `{}` and `[]` respectively.
+    pub omit_empty_fields: bool,
+
+    /// Specifies which records include the code location fields (`file`, `line`, `fn` and
+    /// `full_name`).
+    pub code_location_fields: CodeLocationFields,
+
+    /// Specifies the format of the `time` field.
+    pub timestamp_format: TimestampFormat,
+
+    /// Specifies the time zone of the `time` field.
+    pub timestamp_timezone: TimestampTimezone,
+
+    /// Overrides the `hostname` field.
+    /// If `None`, the hostname of the machine is used.
+    pub hostname: Option<String>,
+
+    /// If `true`, includes the `pod_name` and `node_name` fields, read from the `POD_NAME` (or
+    /// `HOSTNAME`) and `NODE_NAME` environment variables respectively, along with the
+    /// `container_id` field, detected from the cgroup of the process.
+    /// Fields whose values are unavailable are omitted.
+    ///
+    /// The keys of these fields are reserved only if enrichment is enabled, and can otherwise be
+    /// logged via events or spans.
+    pub environment_enrichment: bool,
+}
+
+/// A handle for changing the logging of span lifecycles at runtime, without rebuilding the
+/// subscriber, such as for temporarily logging the entries and exits of all spans during an
+/// investigation.
+///
+/// The handle can be cloned cheaply, and all clones (along with the layers sharing the handle)
+/// observe the same settings.
+#[derive(Clone, Debug)]
+pub struct SpanLifecycleLogging {
+    inner: Arc<SpanLifecycleLoggingInner>,
+}
+
+#[derive(Debug)]
+struct SpanLifecycleLoggingInner {
+    log_span_lifecycles: AtomicBool,
+    /// The slow span threshold in milliseconds, [`u64::MAX`] if not specified.
+    slow_span_threshold_ms: AtomicU64,
+}
+
+impl SpanLifecycleLogging {
+    /// Creates a new [`SpanLifecycleLogging`] with the specified settings.
+    pub fn new(log_span_lifecycles: bool, slow_span_threshold: Option<Duration>) -> Self {
+        let span_lifecycle_logging = Self {
+            inner: Arc::new(SpanLifecycleLoggingInner {
+                log_span_lifecycles: AtomicBool::new(log_span_lifecycles),
+                slow_span_threshold_ms: AtomicU64::new(u64::MAX),
+            }),
+        };
+        span_lifecycle_logging.set_slow_span_threshold(slow_span_threshold);
+        span_lifecycle_logging
+    }
+
+    /// Returns `true` if all span entries and exits are logged.
+    pub fn log_span_lifecycles(&self) -> bool {
+        self.inner.log_span_lifecycles.load(Ordering::Relaxed)
+    }
+
+    /// Specifies whether all span entries and exits are logged, or only the exits of root spans
+    /// (and slow spans).
+    pub fn set_log_span_lifecycles(&self, log_span_lifecycles: bool) {
+        self.inner
+            .log_span_lifecycles
+            .store(log_span_lifecycles, Ordering::Relaxed);
+    }
+
+    /// Returns the threshold beyond which the exits of spans are logged, if specified.
+    pub fn slow_span_threshold(&self) -> Option<Duration> {
+        match self.inner.slow_span_threshold_ms.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            threshold_ms => Some(Duration::from_millis(threshold_ms)),
+        }
+    }
+
+    /// Specifies the threshold beyond which the exits of spans are logged, even if span
+    /// lifecycles are not logged.
+    pub fn set_slow_span_threshold(&self, slow_span_threshold: Option<Duration>) {
+        let threshold_ms = slow_span_threshold.map_or(u64::MAX, |threshold| {
+            u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX)
+        });
+        self.inner
+            .slow_span_threshold_ms
+            .store(threshold_ms, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the exit of the span is to be logged, given whether it is a root span
+    /// and its elapsed time in milliseconds, if captured.
+    fn should_log_exit(&self, is_root: bool, elapsed_milliseconds: Option<u64>) -> bool {
+        if self.log_span_lifecycles() || is_root {
+            return true;
+        }
+
+        let threshold_ms = self.inner.slow_span_threshold_ms.load(Ordering::Relaxed);
+        threshold_ms != u64::MAX
+            && elapsed_milliseconds.is_some_and(|elapsed| elapsed > threshold_ms)
+    }
+}
+
+/// The ISO 8601 format with whole seconds.
+const ISO8601_SECONDS: EncodedConfig = Config::DEFAULT
+    .set_time_precision(TimePrecision::Second {
+        decimal_digits: None,
+    })
+    .encode();
+
+/// The ISO 8601 format with millisecond precision.
+const ISO8601_MILLISECONDS: EncodedConfig = Config::DEFAULT
+    .set_time_precision(TimePrecision::Second {
+        decimal_digits: NonZeroU8::new(3),
+    })
+    .encode();
+
+/// The ISO 8601 format with microsecond precision.
+const ISO8601_MICROSECONDS: EncodedConfig = Config::DEFAULT
+    .set_time_precision(TimePrecision::Second {
+        decimal_digits: NonZeroU8::new(6),
+    })
+    .encode();
+
+/// Specifies the format of the `time` field of the JSON records.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// An ISO 8601 string with whole seconds, such as `2025-06-11T01:04:44Z`.
+    Seconds,
+
+    /// An ISO 8601 string with millisecond precision, such as `2025-06-11T01:04:44.123Z`.
+    Milliseconds,
+
+    /// An ISO 8601 string with microsecond precision, such as `2025-06-11T01:04:44.123456Z`.
+    Microseconds,
+
+    /// An ISO 8601 string with nanosecond precision, such as `2025-06-11T01:04:44.123456789Z`.
+    #[default]
+    Nanoseconds,
+
+    /// The number of milliseconds elapsed since the Unix epoch, as an integer, such as
+    /// `1749603884123`.
+    EpochMilliseconds,
+}
+
+impl TimestampFormat {
+    /// Serializes the current time as the `time` field, in this format and at the specified
+    /// offset from UTC.
+    fn serialize_now(
+        self,
+        offset: UtcOffset,
+        map_serializer: &mut impl SerializeMap<Error = serde_json::Error>,
+    ) -> Result<(), serde_json::Error> {
+        let now = OffsetDateTime::now_utc().to_offset(offset);
+        let time = match self {
+            Self::Seconds => now.format(&Iso8601::<ISO8601_SECONDS>),
+            Self::Milliseconds => now.format(&Iso8601::<ISO8601_MILLISECONDS>),
+            Self::Microseconds => now.format(&Iso8601::<ISO8601_MICROSECONDS>),
+            Self::Nanoseconds => now.format(&Iso8601::DEFAULT),
+            Self::EpochMilliseconds => {
+                let epoch_milliseconds = now.unix_timestamp_nanos() / 1_000_000;
+                return map_serializer.serialize_entry(super::keys::TIME, &epoch_milliseconds);
+            }
+        };
+
+        match time {
+            Ok(time) => map_serializer.serialize_entry(super::keys::TIME, &time),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Specifies the time zone of the `time` field of the JSON records.
+///
+/// This does not affect the [`TimestampFormat::EpochMilliseconds`] format, which is independent
+/// of the time zone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampTimezone {
+    /// UTC, such as `2025-06-11T01:04:44.123456789Z`.
+    #[default]
+    Utc,
+
+    /// The specified fixed offset from UTC, such as `2025-06-11T06:34:44.123456789+05:30` for
+    /// Indian Standard Time.
+    FixedOffset(UtcOffset),
+
+    /// The offset of the system local time zone, such as
+    /// `2025-06-11T06:34:44.123456789+05:30`.
+    ///
+    /// The offset is determined once, when the layer is created, and hence is not updated on
+    /// daylight saving time transitions.
+    /// If the offset cannot be determined, UTC is used instead.
+    /// On Unix platforms, the offset can only be determined soundly while the process is
+    /// single-threaded, and hence the layer must be created before spawning any threads (such as
+    /// before starting an asynchronous runtime) for the local offset to be used.
+    Local,
+}
+
+impl TimestampTimezone {
+    /// Returns the offset from UTC of this time zone, falling back to UTC if the offset of the
+    /// system local time zone cannot be determined.
+    fn offset(self) -> UtcOffset {
+        match self {
+            Self::Utc => UtcOffset::UTC,
+            Self::FixedOffset(offset) => offset,
+            Self::Local => UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        }
+    }
 }
 
 /// Describes the type of a tracing record.
@@ -89,12 +332,21 @@ where
 {
     dst_writer: W,
     formatter: F,
-    pid: u32,
-    hostname: String,
+    pid: Option<u32>,
+    hostname: Option<String>,
+    pod_name: Option<String>,
+    node_name: Option<String>,
+    container_id: Option<String>,
+    fixed_time: Option<String>,
+    reserved_keys: ReservedKeys,
     static_top_level_fields: HashMap<String, Value>,
     top_level_keys: Arc<HashSet<&'static str>>,
-    log_span_lifecycles: bool,
+    span_lifecycle_logging: SpanLifecycleLogging,
     additional_fields_placement: AdditionalFieldsPlacement,
+    omit_empty_fields: bool,
+    code_location_fields: CodeLocationFields,
+    timestamp_format: TimestampFormat,
+    timestamp_offset: UtcOffset,
 }
 
 impl<W, F> JsonFormattingLayer<W, F>
@@ -109,11 +361,31 @@ where
         dst_writer: W,
         formatter: F,
     ) -> Result<Self, LoggerError> {
-        let pid = std::process::id();
-        let hostname = gethostname::gethostname().to_string_lossy().into_owned();
+        // The process ID, hostname and container ID are not available on `wasm32` targets
+        #[cfg(not(target_arch = "wasm32"))]
+        let (pid, hostname, container_id) = (
+            Some(std::process::id()),
+            Some(gethostname::gethostname().to_string_lossy().into_owned()),
+            super::container::container_id(),
+        );
+        #[cfg(target_arch = "wasm32")]
+        let (pid, hostname, container_id) = (None, None, None);
+        let hostname = config.hostname.or(hostname);
+
+        let (pod_name, node_name, container_id, reserved_keys) = if config.environment_enrichment {
+            (
+                env_var("POD_NAME").or_else(|| env_var("HOSTNAME")),
+                env_var("NODE_NAME"),
+                container_id,
+                ReservedKeys::default()
+                    .with_keys(super::keys::ENVIRONMENT_ENRICHMENT_KEY_LIST.iter().copied()),
+            )
+        } else {
+            (None, None, None, ReservedKeys::default())
+        };
 
         for key in config.static_top_level_fields.keys() {
-            if super::keys::IMPLICIT_KEYS.contains(key.as_str()) {
+            if reserved_keys.contains(key) {
                 return Err(LoggerError::Configuration(format!(
                     "A reserved key `{key}` was included in `static_top_level_fields` in the \
                      log formatting layer"
@@ -126,10 +398,269 @@ where
             formatter,
             pid,
             hostname,
+            pod_name,
+            node_name,
+            container_id,
+            fixed_time: None,
+            reserved_keys,
             static_top_level_fields: config.static_top_level_fields,
             top_level_keys: Arc::new(config.top_level_keys),
-            log_span_lifecycles: config.log_span_lifecycles,
+            span_lifecycle_logging: SpanLifecycleLogging::new(
+                config.log_span_lifecycles,
+                config.slow_span_threshold,
+            ),
             additional_fields_placement: config.additional_fields_placement,
+            omit_empty_fields: config.omit_empty_fields,
+            code_location_fields: config.code_location_fields,
+            timestamp_format: config.timestamp_format,
+            timestamp_offset: config.timestamp_timezone.offset(),
+        })
+    }
+
+    /// Uses the specified handle for changing the logging of span lifecycles at runtime, instead
+    /// of the settings specified in the [`JsonFormattingLayerConfig`].
+    ///
+    /// This allows sharing a single handle across multiple layers.
+    pub fn with_span_lifecycle_logging(
+        mut self,
+        span_lifecycle_logging: SpanLifecycleLogging,
+    ) -> Self {
+        self.span_lifecycle_logging = span_lifecycle_logging;
+        self
+    }
+
+    /// Returns the handle for changing the logging of span lifecycles at runtime.
+    pub fn span_lifecycle_logging(&self) -> SpanLifecycleLogging {
+        self.span_lifecycle_logging.clone()
+    }
+
+    /// Reserves the keys of the implicit fields added to the records after they are formatted,
+    /// such as the `record_hash` field added by hash chaining.
+    pub(crate) fn with_reserved_keys(
+        mut self,
+        keys: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        self.reserved_keys = self.reserved_keys.with_keys(keys);
+        self
+    }
+
+    /// Uses the specified values for the process ID, hostname and time in all records, and omits
+    /// the container ID, to render deterministic records for snapshot testing.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn with_fixed_metadata(mut self, pid: u32, hostname: &str, time: &str) -> Self {
+        self.pid = Some(pid);
+        self.hostname = Some(hostname.to_owned());
+        self.container_id = None;
+        self.fixed_time = Some(time.to_owned());
+        self
+    }
+
+    /// Generates a [JSON Schema](https://json-schema.org/) (draft 2020-12) describing the log
+    /// records produced by this layer.
+    ///
+    /// The schema describes the implicit fields included in every record, the static top-level
+    /// fields (along with their values), the top-level keys, and the key under which additional
+    /// fields are nested, if configured.
+    /// Since records may include additional fields from events and spans, the schema allows
+    /// additional properties.
+    ///
+    /// This can be used by downstream ingestion pipelines to validate records, and to detect
+    /// changes to the log format across versions.
+    pub fn json_schema(&self) -> Value {
+        use serde_json::json;
+
+        use super::keys;
+
+        // The source code location is omitted when unavailable if empty fields are omitted,
+        // instead of being null
+        let (line_type, file_type) = if self.omit_empty_fields {
+            (json!("integer"), json!("string"))
+        } else {
+            (json!(["integer", "null"]), json!(["string", "null"]))
+        };
+
+        let time_schema = if self.timestamp_format == TimestampFormat::EpochMilliseconds {
+            json!({
+                "description": "The time at which the record was emitted, in milliseconds since the Unix epoch.",
+                "type": "integer",
+                "minimum": 0,
+            })
+        } else {
+            json!({
+                "description": "The time at which the record was emitted, along with its offset from UTC.",
+                "type": "string",
+                "format": "date-time",
+            })
+        };
+
+        let mut properties = serde_json::Map::from_iter([
+            (
+                keys::SCHEMA_VERSION.to_owned(),
+                json!({
+                    "description": "The version of the log record schema.",
+                    "const": LOG_SCHEMA_VERSION,
+                }),
+            ),
+            (
+                keys::MESSAGE.to_owned(),
+                json!({
+                    "description": "The log message, prefixed with the span name and record type, if any.",
+                    "type": "string",
+                }),
+            ),
+            (
+                keys::HOSTNAME.to_owned(),
+                json!({
+                    "description": "The hostname of the machine emitting the record.",
+                    "type": "string",
+                }),
+            ),
+            (
+                keys::PID.to_owned(),
+                json!({
+                    "description": "The ID of the process emitting the record.",
+                    "type": "integer",
+                    "minimum": 0,
+                }),
+            ),
+            (
+                keys::LEVEL.to_owned(),
+                json!({
+                    "description": "The level of the record.",
+                    "enum": ["TRACE", "DEBUG", "INFO", "WARN", "ERROR"],
+                }),
+            ),
+            (
+                keys::TARGET.to_owned(),
+                json!({
+                    "description": "The target of the record, typically the module path.",
+                    "type": "string",
+                }),
+            ),
+            (
+                keys::LINE.to_owned(),
+                json!({
+                    "description": "The line number in the source code file emitting the record.",
+                    "type": line_type,
+                    "minimum": 0,
+                }),
+            ),
+            (
+                keys::FILE.to_owned(),
+                json!({
+                    "description": "The source code file emitting the record.",
+                    "type": file_type,
+                }),
+            ),
+            (
+                keys::FN.to_owned(),
+                json!({
+                    "description": "The name of the span the record belongs to.",
+                    "type": "string",
+                }),
+            ),
+            (
+                keys::FULL_NAME.to_owned(),
+                json!({
+                    "description": "The target and the name of the span the record belongs to.",
+                    "type": "string",
+                }),
+            ),
+            (keys::TIME.to_owned(), time_schema),
+        ]);
+        if self.hostname.is_none() {
+            properties.remove(keys::HOSTNAME);
+        }
+        if self.pod_name.is_some() {
+            properties.insert(
+                keys::POD_NAME.to_owned(),
+                json!({
+                    "description": "The name of the pod emitting the record.",
+                    "type": "string",
+                }),
+            );
+        }
+        if self.node_name.is_some() {
+            properties.insert(
+                keys::NODE_NAME.to_owned(),
+                json!({
+                    "description": "The name of the node running the pod emitting the record.",
+                    "type": "string",
+                }),
+            );
+        }
+        if self.container_id.is_some() {
+            properties.insert(
+                keys::CONTAINER_ID.to_owned(),
+                json!({
+                    "description": "The ID of the container emitting the record.",
+                    "type": "string",
+                }),
+            );
+        }
+        if self.pid.is_none() {
+            properties.remove(keys::PID);
+        }
+        let code_location_keys = [keys::LINE, keys::FILE, keys::FN, keys::FULL_NAME];
+        if self.code_location_fields == CodeLocationFields::Never {
+            for key in code_location_keys {
+                properties.remove(key);
+            }
+        }
+        let mut required: Vec<String> = properties
+            .keys()
+            .filter(|key| {
+                let key = key.as_str();
+                // The code location fields are included only in some of the records
+                let is_partial_code_location = self.code_location_fields
+                    != CodeLocationFields::Always
+                    && code_location_keys.contains(&key);
+                let is_omitted_location =
+                    self.omit_empty_fields && (key == keys::LINE || key == keys::FILE);
+                !is_partial_code_location && !is_omitted_location
+            })
+            .cloned()
+            .collect();
+
+        for (key, value) in &self.static_top_level_fields {
+            if self.is_omitted(value) {
+                continue;
+            }
+            properties.insert(
+                key.clone(),
+                json!({
+                    "description": "A static top-level field.",
+                    "const": value,
+                }),
+            );
+            required.push(key.clone());
+        }
+
+        for key in self.top_level_keys.iter() {
+            properties.entry(*key).or_insert_with(
+                || json!({ "description": "A top-level key from event or span data." }),
+            );
+        }
+
+        if let AdditionalFieldsPlacement::Nested(field_name) = &self.additional_fields_placement {
+            properties.insert(
+                field_name.clone(),
+                json!({
+                    "description": "Additional fields from event or span data.",
+                    "type": "object",
+                }),
+            );
+        }
+
+        required.sort_unstable();
+
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Log record",
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": true,
         })
     }
 
@@ -143,21 +674,44 @@ where
     ) -> Result<(), LoggerError> {
         use super::keys;
 
+        map_serializer.serialize_entry(keys::SCHEMA_VERSION, &LOG_SCHEMA_VERSION)?;
         map_serializer.serialize_entry(keys::MESSAGE, message)?;
-        map_serializer.serialize_entry(keys::HOSTNAME, &self.hostname)?;
-        map_serializer.serialize_entry(keys::PID, &self.pid)?;
+        if let Some(hostname) = &self.hostname {
+            map_serializer.serialize_entry(keys::HOSTNAME, hostname)?;
+        }
+        if let Some(pid) = &self.pid {
+            map_serializer.serialize_entry(keys::PID, pid)?;
+        }
+        if let Some(pod_name) = &self.pod_name {
+            map_serializer.serialize_entry(keys::POD_NAME, pod_name)?;
+        }
+        if let Some(node_name) = &self.node_name {
+            map_serializer.serialize_entry(keys::NODE_NAME, node_name)?;
+        }
+        if let Some(container_id) = &self.container_id {
+            map_serializer.serialize_entry(keys::CONTAINER_ID, container_id)?;
+        }
         map_serializer.serialize_entry(keys::LEVEL, &format_args!("{}", metadata.level()))?;
         map_serializer.serialize_entry(keys::TARGET, metadata.target())?;
-        map_serializer.serialize_entry(keys::LINE, &metadata.line())?;
-        map_serializer.serialize_entry(keys::FILE, &metadata.file())?;
-        map_serializer.serialize_entry(keys::FN, name)?;
-        map_serializer.serialize_entry(
-            keys::FULL_NAME,
-            &format_args!("{}::{}", metadata.target(), name),
-        )?;
+        if self.code_location_fields.includes(*metadata.level()) {
+            if !self.omit_empty_fields || metadata.line().is_some() {
+                map_serializer.serialize_entry(keys::LINE, &metadata.line())?;
+            }
+            if !self.omit_empty_fields || metadata.file().is_some() {
+                map_serializer.serialize_entry(keys::FILE, &metadata.file())?;
+            }
+            map_serializer.serialize_entry(keys::FN, name)?;
+            map_serializer.serialize_entry(
+                keys::FULL_NAME,
+                &format_args!("{}::{}", metadata.target(), name),
+            )?;
+        }
 
-        if let Ok(time) = time::UtcDateTime::now().format(&Iso8601::DEFAULT) {
-            map_serializer.serialize_entry(keys::TIME, &time)?;
+        if let Some(time) = &self.fixed_time {
+            map_serializer.serialize_entry(keys::TIME, time)?;
+        } else {
+            self.timestamp_format
+                .serialize_now(self.timestamp_offset, map_serializer)?;
         }
 
         Ok(())
@@ -181,7 +735,9 @@ where
 
         // Serialize static top-level fields
         for (key, value) in self.static_top_level_fields.iter() {
-            map_serializer.serialize_entry(key, value)?;
+            if !self.is_omitted(value) {
+                map_serializer.serialize_entry(key, value)?;
+            }
         }
 
         let mut explicit_entries_set: HashSet<&str> = HashSet::default();
@@ -195,10 +751,18 @@ where
         if let Some(storage) = storage {
             // Serialize event fields
             for (key, value) in storage.values() {
-                if super::keys::IMPLICIT_KEYS.contains(*key) {
-                    tracing::warn!(
-                        "Attempting to log a reserved key `{key}` (value: `{value:?}`) via event. \
-                         Skipping."
+                if self.is_omitted(value) {
+                    // Omitted fields are considered explicit entries nonetheless, so that they
+                    // are not replaced by the span fields with the same keys
+                    explicit_entries_set.insert(*key);
+                } else if self.reserved_keys.contains(key) {
+                    super::structured::log_skipped_reserved_key(
+                        value,
+                        format_args!(
+                            "Attempting to log a reserved key `{key}` (value: `{}`) via event. \
+                             Skipping.",
+                            super::structured::ReservedKeyValue(value)
+                        ),
                     );
                 } else if self.top_level_keys.contains(*key) {
                     map_serializer.serialize_entry(key, value)?;
@@ -223,12 +787,16 @@ where
                 for (key, value) in visitor
                     .values()
                     .iter()
-                    .filter(|(k, _v)| !explicit_entries_set.contains(*k))
+                    .filter(|(k, v)| !explicit_entries_set.contains(*k) && !self.is_omitted(v))
                 {
-                    if super::keys::IMPLICIT_KEYS.contains(*key) {
-                        tracing::warn!(
-                            "Attempting to log a reserved key `{key}` (value: `{value:?}`) via span. \
-                             Skipping."
+                    if self.reserved_keys.contains(key) {
+                        super::structured::log_skipped_reserved_key(
+                            value,
+                            format_args!(
+                                "Attempting to log a reserved key `{key}` (value: `{}`) via span. \
+                                 Skipping.",
+                                super::structured::ReservedKeyValue(value)
+                            ),
                         );
                     } else if self.top_level_keys.contains(*key) {
                         map_serializer.serialize_entry(key, value)?;
@@ -255,13 +823,29 @@ where
         Ok(())
     }
 
+    /// Returns `true` if the field with the specified value is to be omitted from the records.
+    fn is_omitted(&self, value: &Value) -> bool {
+        self.omit_empty_fields
+            && match value {
+                Value::Null => true,
+                Value::Array(array) => array.is_empty(),
+                Value::Object(object) => object.is_empty(),
+                Value::Bool(_) | Value::Number(_) | Value::String(_) => false,
+            }
+    }
+
     /// Flush memory buffer into an output stream with a trailing newline.
+    /// The output stream is obtained for the metadata of the record, allowing writers to route
+    /// records based on their level.
     ///
     /// Should be done by a single `write_all` call to avoid fragmentation of log because of
     /// multithreading.
-    fn flush(&self, mut buffer: Vec<u8>) -> Result<(), std::io::Error> {
+    ///
+    /// The layer callbacks cannot propagate the returned errors, and hence the writers of the file
+    /// and audit sinks count them in their [`FileSinkHealth`][super::FileSinkHealth] instead.
+    fn flush(&self, mut buffer: Vec<u8>, metadata: &Metadata<'_>) -> Result<(), std::io::Error> {
         buffer.write_all(b"\n")?;
-        self.dst_writer.make_writer().write_all(&buffer)
+        self.dst_writer.make_writer_for(metadata).write_all(&buffer)
     }
 
     /// Serialize entries of a span.
@@ -378,19 +962,19 @@ where
 
         let result = self.event_serialize(span.as_ref(), event);
         if let Ok(serialized) = result {
-            let _ = self.flush(serialized);
+            let _ = self.flush(serialized, event.metadata());
         }
     }
 
     fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
-        if self.log_span_lifecycles {
+        if self.span_lifecycle_logging.log_span_lifecycles() {
             #[expect(clippy::expect_used)]
             let span = ctx
                 .span(id)
                 .expect("span with specified id does not exist in `on_enter()`");
 
             if let Ok(serialized) = self.span_serialize(&span, RecordType::EnterSpan) {
-                let _ = self.flush(serialized);
+                let _ = self.flush(serialized, span.metadata());
             }
         }
     }
@@ -401,16 +985,25 @@ where
             .span(&id)
             .expect("span with specified id does not exist in `on_close()`");
 
-        let should_log_exit = if self.log_span_lifecycles {
-            true // Log all exits if full lifecycle is enabled
-        } else {
-            span.parent().is_none() // Only log root span exits otherwise
-        };
-
-        if should_log_exit {
+        let elapsed_milliseconds = span.extensions().get::<Storage<'_>>().and_then(|storage| {
+            storage
+                .values()
+                .get(super::keys::ELAPSED_MILLISECONDS)
+                .and_then(Value::as_u64)
+        });
+
+        if self
+            .span_lifecycle_logging
+            .should_log_exit(span.parent().is_none(), elapsed_milliseconds)
+        {
             if let Ok(serialized) = self.span_serialize(&span, RecordType::ExitSpan) {
-                let _ = self.flush(serialized);
+                let _ = self.flush(serialized, span.metadata());
             }
         }
     }
 }
+
+/// Returns the value of the environment variable, if it is set and non-empty.
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
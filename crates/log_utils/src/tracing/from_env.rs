@@ -0,0 +1,183 @@
+//! Provides [`init_from_env()`] for initializing logging entirely from environment variables.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+use super::{
+    AdditionalFieldsPlacement, CodeLocationFields, ConsoleLogFormat, ConsoleLoggingConfig,
+    DirectivePrintTarget, FileLoggingConfig, FileSinkFallback, FileWriteErrorPolicy,
+    HumanReadableOptions, Level, LoggerConfig, LoggerError, LoggingComponents,
+    PersistentKeyPropagation, Rotation, SpanFieldInheritance, TimestampFormat, TimestampTimezone,
+    build_logging_components,
+};
+
+/// The environment variable specifying the format of the console logs.
+const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+/// The environment variable specifying the level or the filtering directive of the logs.
+const LOG_LEVEL_ENV: &str = "LOG_LEVEL";
+
+/// The environment variable specifying the directory of the log files.
+const LOG_DIR_ENV: &str = "LOG_DIR";
+
+/// The environment variable specifying the static top-level fields of the JSON records.
+const LOG_STATIC_FIELDS_ENV: &str = "LOG_STATIC_FIELDS";
+
+/// Builds a [`LoggerConfig`] from the environment variables, and installs the global subscriber
+/// using the components built from it, giving small tools and sidecars reasonable logging in a
+/// single line.
+///
+/// The following environment variables are read:
+///
+/// - `LOG_FORMAT`: the format of the console logs, one of `json` (the default), `pretty_json`,
+///   `human` and `compact`.
+/// - `LOG_LEVEL`: either a level (such as `debug`, defaulting to `info`), or a filtering
+///   directive (such as `info,my_app=debug`), applied to all the sinks.
+/// - `LOG_DIR`: the directory to write daily rotated JSON log files to, in addition to the
+///   console. If unset, logs are written to the console alone.
+///   The log files are named after the executable.
+/// - `LOG_STATIC_FIELDS`: a JSON object with the static top-level fields included in every
+///   JSON record, such as `{"service": "my_sidecar"}`.
+///
+/// The returned components no longer hold the layers, which are installed in the global
+/// subscriber, but must be retained for the duration of the program, since dropping them stops
+/// writing the logs.
+/// Call [`LoggingComponents::shutdown()`] on exit to log a summary and flush the buffered logs.
+///
+/// # Errors
+///
+/// Returns [`LoggerError::Configuration`] if any of the environment variables is invalid, or if
+/// a global subscriber has already been installed, along with any error returned by
+/// [`build_logging_components`].
+///
+/// # Example
+///
+/// ```no_run
+/// let logging = log_utils::init_from_env()?;
+///
+/// tracing::info!("Sidecar started");
+///
+/// logging.shutdown();
+/// # Ok::<(), log_utils::LoggerError>(())
+/// ```
+pub fn init_from_env() -> Result<LoggingComponents, LoggerError> {
+    let config = logger_config_from_vars(|name| std::env::var(name).ok())?;
+    let mut components = build_logging_components(config)?;
+
+    let mut layers = vec![components.filtered_storage_layer().boxed()];
+    layers.extend(components.file_log_layer.take());
+    layers.extend(components.console_log_layer.take());
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()
+        .map_err(|error| {
+            LoggerError::Configuration(format!("Failed to install the global subscriber: {error}"))
+        })?;
+
+    Ok(components)
+}
+
+/// Builds a [`LoggerConfig`] from the variables returned by the specified function.
+pub(crate) fn logger_config_from_vars(
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<LoggerConfig, LoggerError> {
+    let log_format = match var(LOG_FORMAT_ENV).as_deref() {
+        None | Some("json") => ConsoleLogFormat::CompactJson,
+        Some("pretty_json") => ConsoleLogFormat::PrettyJson,
+        Some("human") => ConsoleLogFormat::HumanReadable,
+        Some("compact") => ConsoleLogFormat::Compact,
+        Some(format) => {
+            return Err(LoggerError::Configuration(format!(
+                "Invalid `{LOG_FORMAT_ENV}` value `{format}`, expected one of `json`, \
+                 `pretty_json`, `human` and `compact`"
+            )));
+        }
+    };
+
+    // A level is used as the level of the sinks, and anything else as a filtering directive
+    let (level, global_filtering_directive) = match var(LOG_LEVEL_ENV) {
+        None => (Level::INFO, None),
+        Some(level_or_directive) => match level_or_directive.parse::<Level>() {
+            Ok(level) => (level, None),
+            Err(_) => (Level::INFO, Some(level_or_directive)),
+        },
+    };
+
+    let static_top_level_fields = match var(LOG_STATIC_FIELDS_ENV) {
+        None => HashMap::new(),
+        Some(static_fields) => serde_json::from_str::<HashMap<String, Value>>(&static_fields)
+            .map_err(|error| {
+                LoggerError::Configuration(format!(
+                    "Invalid `{LOG_STATIC_FIELDS_ENV}` value, expected a JSON object: {error}"
+                ))
+            })?,
+    };
+
+    let file_config = var(LOG_DIR_ENV).map(|directory| FileLoggingConfig {
+        directory,
+        file_name_prefix: executable_name(),
+        file_rotation: Rotation::DAILY,
+        max_log_files: None,
+        level,
+        filtering_directive: None,
+        print_filtering_directive: DirectivePrintTarget::None,
+        encryption: None,
+        hash_chain: false,
+        fallback: FileSinkFallback::Console,
+        write_error_policy: FileWriteErrorPolicy::Drop,
+        file_mode: None,
+        file_owner: None,
+        file_group: None,
+    });
+
+    Ok(LoggerConfig {
+        static_top_level_fields,
+        top_level_keys: HashSet::new(),
+        persistent_keys: HashSet::new(),
+        span_field_inheritance: SpanFieldInheritance::All,
+        persistent_key_propagation: PersistentKeyPropagation::Parent,
+        span_storage_limits: None,
+        field_overwrite_policies: None,
+        baggage_keys: HashSet::new(),
+        log_span_lifecycles: false,
+        slow_span_threshold: None,
+        request_summary: false,
+        span_busy_idle_time: false,
+        span_counters: false,
+        span_memory_usage: false,
+        additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+        omit_empty_fields: false,
+        code_location_fields: CodeLocationFields::Always,
+        timestamp_format: TimestampFormat::Nanoseconds,
+        timestamp_timezone: TimestampTimezone::Utc,
+        hostname: None,
+        environment_enrichment: false,
+        file_config,
+        console_config: Some(ConsoleLoggingConfig {
+            level,
+            log_format,
+            human_readable_options: HumanReadableOptions::default(),
+            filtering_directive: None,
+            print_filtering_directive: DirectivePrintTarget::None,
+            route_warnings_to_stderr: false,
+        }),
+        alert_config: None,
+        audit_config: None,
+        tokio_console_config: None,
+        sampling_config: None,
+        profiling_config: None,
+        chrome_trace_config: None,
+        global_filtering_directive,
+    })
+}
+
+/// Returns the name of the current executable (without its extension), `app` if unavailable.
+pub(super) fn executable_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "app".to_owned())
+}
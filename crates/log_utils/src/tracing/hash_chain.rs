@@ -0,0 +1,264 @@
+//! Provides tamper-evident hash chaining of log records, along with a utility
+//! ([`verify_hash_chain`]) for verifying the integrity of hash-chained log files.
+//!
+//! Each record carries a `record_hash` field, holding the hex-encoded SHA-256 hash of the
+//! previous record's hash followed by the record's content (the record excluding the
+//! `record_hash` field).
+//! The first record of a chain uses [`GENESIS_HASH`] as the previous record's hash.
+//! A new chain is started each time the logging components are built, such as when the
+//! application is restarted.
+
+use std::io::{self, BufRead, Write};
+
+use sha2::{Digest, Sha256};
+
+use super::keys;
+
+/// The hash used as the previous record's hash for the first record of a chain.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Errors that can occur while verifying a hash chain.
+#[derive(Debug, thiserror::Error)]
+pub enum HashChainError {
+    /// Represents an error when reading the log records.
+    #[error("Failed to read log records: {0}")]
+    Io(#[from] io::Error),
+
+    /// Represents a record that does not carry a record hash.
+    #[error("Record on line {line} does not carry a record hash")]
+    MissingHash {
+        /// The line number of the record, starting at 1.
+        line: usize,
+    },
+
+    /// Represents a record whose hash does not match its content or the previous record's hash,
+    /// indicating that the record was modified, or that records preceding it were removed.
+    #[error("Record on line {line} does not match its record hash")]
+    HashMismatch {
+        /// The line number of the record, starting at 1.
+        line: usize,
+    },
+
+    /// Represents a record starting a new chain when restarts are not allowed, indicating that
+    /// the records preceding it were removed (such as an entire chain, or the end of the
+    /// previous chain), unless the application was restarted.
+    #[error("Record on line {line} unexpectedly starts a new hash chain")]
+    UnexpectedRestart {
+        /// The line number of the record, starting at 1.
+        line: usize,
+    },
+}
+
+/// The summary of a successfully verified hash chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashChainSummary {
+    /// The number of records verified.
+    pub records: usize,
+
+    /// The chains within the verified records, in the order they appear.
+    ///
+    /// Since a removed chain (or the removed end of a chain) cannot be detected from the records
+    /// alone, the boundaries of the chains can be compared against externally stored hashes,
+    /// such as the last hash of each run of the application.
+    pub chains: Vec<HashChainBoundary>,
+
+    /// The hash of the last record verified, or the starting hash if no records were verified.
+    ///
+    /// This can be compared against an externally stored hash to detect removal of records at
+    /// the end of the chain, or used as the starting hash when verifying the next rotated file.
+    pub last_hash: String,
+}
+
+/// The boundaries of a chain within the verified records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashChainBoundary {
+    /// The line number of the first record of the chain, starting at 1.
+    pub first_line: usize,
+
+    /// The line number of the last record of the chain, starting at 1.
+    pub last_line: usize,
+
+    /// The hash preceding the first record of the chain, which is [`GENESIS_HASH`] if the chain
+    /// was started within the records, or the `previous_hash` specified for verification if the
+    /// chain continues from a previous file.
+    pub previous_hash: String,
+
+    /// The hash of the last record of the chain.
+    pub last_hash: String,
+}
+
+/// Verifies the hash chain of the newline-delimited JSON records read from the reader.
+///
+/// If the records continue a chain from a previous file (such as after the log file was
+/// rotated), the hash of the last record of the previous file must be specified as the
+/// `previous_hash`.
+/// Otherwise, the first record is expected to start a new chain.
+///
+/// A new chain is started each time the logging components are built, such as when the
+/// application is restarted.
+/// Such restarts are accepted only if `allow_restarts` is `true`, since the removal of the
+/// records preceding a restart cannot be detected from the records alone; the boundaries of
+/// the chains are then returned in the [`HashChainSummary`], to be verified against externally
+/// stored hashes.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::{fs::File, io::BufReader};
+///
+/// use log_utils::verify_hash_chain;
+///
+/// let file = File::open("/var/log/my_app/my_app.log")?;
+/// let summary = verify_hash_chain(BufReader::new(file), None, true)?;
+/// assert!(summary.records > 0);
+/// for chain in &summary.chains {
+///     println!(
+///         "Chain on lines {}-{} ends with {}",
+///         chain.first_line, chain.last_line, chain.last_hash
+///     );
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`HashChainError::HashMismatch`] if a record was modified, or if records were
+/// removed from the chain, [`HashChainError::UnexpectedRestart`] if a record starts a new
+/// chain when restarts are not allowed, and [`HashChainError::MissingHash`] if a record does
+/// not carry a record hash.
+pub fn verify_hash_chain(
+    reader: impl BufRead,
+    previous_hash: Option<&str>,
+    allow_restarts: bool,
+) -> Result<HashChainSummary, HashChainError> {
+    let mut last_hash = previous_hash.unwrap_or(GENESIS_HASH).to_owned();
+    let mut records = 0;
+    let mut chains: Vec<HashChainBoundary> = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (content, record_hash) =
+            split_record_hash(&line).ok_or(HashChainError::MissingHash { line: line_number })?;
+
+        if record_hash == compute_record_hash(&last_hash, &content) {
+            // The first record either starts a new chain, or continues the previous file's chain
+            if chains.is_empty() {
+                chains.push(HashChainBoundary::new(line_number, &last_hash));
+            }
+        } else if record_hash == compute_record_hash(GENESIS_HASH, &content) {
+            if !allow_restarts {
+                return Err(HashChainError::UnexpectedRestart { line: line_number });
+            }
+            chains.push(HashChainBoundary::new(line_number, GENESIS_HASH));
+        } else {
+            return Err(HashChainError::HashMismatch { line: line_number });
+        }
+        if let Some(chain) = chains.last_mut() {
+            chain.last_line = line_number;
+            record_hash.clone_into(&mut chain.last_hash);
+        }
+
+        last_hash = record_hash.to_owned();
+        records += 1;
+    }
+
+    Ok(HashChainSummary {
+        records,
+        chains,
+        last_hash,
+    })
+}
+
+impl HashChainBoundary {
+    /// Creates the boundaries of a chain starting at the line, following the previous hash.
+    fn new(first_line: usize, previous_hash: &str) -> Self {
+        Self {
+            first_line,
+            last_line: first_line,
+            previous_hash: previous_hash.to_owned(),
+            last_hash: previous_hash.to_owned(),
+        }
+    }
+}
+
+/// Computes the hex-encoded hash of a record, given the previous record's hash.
+fn compute_record_hash(previous_hash: &str, content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(content);
+    hex::encode(hasher.finalize())
+}
+
+/// The prefix of the serialized record hash field, which is appended as the last field of the
+/// record.
+fn record_hash_prefix() -> String {
+    format!(",\"{}\":\"", keys::RECORD_HASH)
+}
+
+/// Splits a record into its content (excluding the record hash field) and the record hash.
+fn split_record_hash(line: &str) -> Option<(Vec<u8>, &str)> {
+    let (content, record_hash) = line
+        .strip_suffix("\"}")?
+        .rsplit_once(record_hash_prefix().as_str())?;
+
+    let mut content = content.as_bytes().to_vec();
+    content.push(b'}');
+
+    Some((content, record_hash))
+}
+
+/// A writer that appends the `record_hash` field to each JSON record written to it, before
+/// writing the record to the wrapped writer.
+///
+/// The formatting layers write each record using a single write, and hence each write is
+/// treated as a separate record.
+/// Writes that are not JSON objects are passed through unchanged, and fail verification.
+pub(crate) struct HashChainingWriter<W> {
+    inner: W,
+    previous_hash: String,
+    record_hash_prefix: String,
+}
+
+impl<W> HashChainingWriter<W> {
+    /// Wraps the writer, starting a new chain.
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            previous_hash: GENESIS_HASH.to_owned(),
+            record_hash_prefix: record_hash_prefix(),
+        }
+    }
+}
+
+impl<W: Write> Write for HashChainingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let content = buf.strip_suffix(b"\n").unwrap_or(buf);
+        let Some(object) = content.strip_suffix(b"}") else {
+            self.inner.write_all(buf)?;
+            return Ok(buf.len());
+        };
+
+        let record_hash = compute_record_hash(&self.previous_hash, content);
+
+        let mut record = Vec::with_capacity(
+            object.len() + self.record_hash_prefix.len() + record_hash.len() + 3,
+        );
+        record.extend_from_slice(object);
+        record.extend_from_slice(self.record_hash_prefix.as_bytes());
+        record.extend_from_slice(record_hash.as_bytes());
+        record.extend_from_slice(b"\"}\n");
+        self.inner.write_all(&record)?;
+
+        self.previous_hash = record_hash;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
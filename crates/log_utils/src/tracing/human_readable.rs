@@ -0,0 +1,428 @@
+//! Provides a customizable [`tracing_subscriber::Layer`] ([`HumanReadableFormattingLayer`]) for
+//! formatting log events into a human-readable text representation, either spanning multiple
+//! lines or compacted into a single line.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    io::Write as _,
+};
+
+use nu_ansi_term::{Color, Style};
+use serde_json::Value;
+use time::format_description::well_known::Iso8601;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    Layer,
+    fmt::MakeWriter,
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+use super::storage::Storage;
+
+/// Options for customizing the output of the [`HumanReadableFormattingLayer`].
+#[derive(Clone, Debug)]
+pub struct HumanReadableOptions {
+    /// If `true`, log levels, span names and field names are highlighted using ANSI colors.
+    pub ansi_colors: bool,
+
+    /// Keys whose values are displayed inline on the first line of a log entry (in the specified
+    /// order), if they appear in an event or span's data.
+    /// This could include resource identifiers, for example.
+    pub inline_keys: Vec<&'static str>,
+
+    /// Keys of fields which are displayed before the others (in the specified order), if they
+    /// appear in an event or span's data.
+    /// All other fields are displayed after these, in alphabetical order.
+    pub field_order: Vec<&'static str>,
+
+    /// If `true`, the names of the spans that an event occurred within are prefixed to the event
+    /// message, starting from the root span.
+    /// Not applicable to the compact format.
+    pub span_context: bool,
+
+    /// The maximum number of characters of a field value displayed in the compact format.
+    /// Longer values are truncated and suffixed with an ellipsis.
+    pub compact_max_field_length: usize,
+
+    /// Keys of fields (such as errors or stack traces) whose values are always rendered as an
+    /// indented multi-line block below the field name, rather than on the same line.
+    /// Not applicable to the compact format.
+    pub block_keys: Vec<&'static str>,
+
+    /// Field values longer than the specified number of characters are rendered as an indented
+    /// multi-line block below the field name, rather than on the same line.
+    /// Objects, arrays, and strings containing serialized JSON objects or arrays are
+    /// pretty-printed when rendered as a block.
+    /// Not applicable to the compact format.
+    pub block_threshold: usize,
+}
+
+impl Default for HumanReadableOptions {
+    fn default() -> Self {
+        Self {
+            ansi_colors: true,
+            inline_keys: Vec::new(),
+            field_order: Vec::new(),
+            span_context: true,
+            compact_max_field_length: 64,
+            block_keys: vec!["error", "stack"],
+            block_threshold: 120,
+        }
+    }
+}
+
+/// Key-value pairs of fields, in the order they are to be displayed.
+type OrderedFields<'a> = Vec<(&'a str, &'a Value)>;
+
+/// Describes the layout of log entries produced by a [`HumanReadableFormattingLayer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layout {
+    /// Multi-line log entries including the timestamp, span context and source code location.
+    Full,
+
+    /// Single-line log entries including only the level, target, message and fields.
+    Compact,
+}
+
+/// A [`tracing_subscriber::Layer`] that formats tracing events into a human-readable text
+/// representation, intended for consumption in local development environments.
+///
+/// When created using [`new()`][Self::new], each log entry consists of a line containing the
+/// timestamp, level, span context, target, message and any inline fields, followed by one line for
+/// each of the remaining event and span fields, and a line containing the source code location of
+/// the event.
+///
+/// When created using [`compact()`][Self::compact], each log entry is a single line of the form
+/// `LEVEL target message key=value ...`, with long field values truncated.
+///
+/// The output can be customized using [`HumanReadableOptions`].
+///
+/// It requires a [`MakeWriter`] to determine the output destination.
+#[derive(Debug)]
+pub struct HumanReadableFormattingLayer<W>
+where
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    dst_writer: W,
+    options: HumanReadableOptions,
+    layout: Layout,
+}
+
+impl<W> HumanReadableFormattingLayer<W>
+where
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    /// Creates a new [`HumanReadableFormattingLayer`] producing multi-line log entries, with the
+    /// specified options and writer.
+    pub fn new(options: HumanReadableOptions, dst_writer: W) -> Self {
+        Self {
+            dst_writer,
+            options,
+            layout: Layout::Full,
+        }
+    }
+
+    /// Creates a new [`HumanReadableFormattingLayer`] producing single-line log entries, with the
+    /// specified options and writer.
+    pub fn compact(options: HumanReadableOptions, dst_writer: W) -> Self {
+        Self {
+            dst_writer,
+            options,
+            layout: Layout::Compact,
+        }
+    }
+
+    /// Writes the text to the buffer, applying the style if ANSI colors are enabled.
+    fn write_styled(&self, buffer: &mut String, style: Style, text: &str) -> fmt::Result {
+        if self.options.ansi_colors {
+            write!(buffer, "{}", style.paint(text))
+        } else {
+            buffer.write_str(text)
+        }
+    }
+
+    /// Obtain the style used for highlighting the specified level.
+    fn level_style(level: Level) -> Style {
+        match level {
+            Level::ERROR => Color::Red.bold(),
+            Level::WARN => Color::Yellow.bold(),
+            Level::INFO => Color::Green.bold(),
+            Level::DEBUG => Color::Blue.bold(),
+            Level::TRACE => Color::Purple.bold(),
+        }
+    }
+
+    /// Writes a field value to the buffer, without quoting string values.
+    fn write_value(buffer: &mut String, value: &Value) -> fmt::Result {
+        match value {
+            Value::String(string) => buffer.write_str(string),
+            other => write!(buffer, "{other}"),
+        }
+    }
+
+    /// Writes a field value to the buffer as an indented multi-line block, pretty-printing JSON
+    /// objects and arrays.
+    fn write_block_value(buffer: &mut String, value: &Value) -> fmt::Result {
+        const INDENT: &str = "        ";
+
+        let parsed_value;
+        let value = match value {
+            Value::String(string) if string.starts_with(['{', '[']) => {
+                match serde_json::from_str::<Value>(string) {
+                    Ok(parsed @ (Value::Object(_) | Value::Array(_))) => {
+                        parsed_value = parsed;
+                        &parsed_value
+                    }
+                    _ => value,
+                }
+            }
+            _ => value,
+        };
+
+        let formatted = match value {
+            Value::Object(_) | Value::Array(_) => {
+                serde_json::to_string_pretty(value).map_err(|_| fmt::Error)?
+            }
+            other => {
+                let mut formatted = String::new();
+                Self::write_value(&mut formatted, other)?;
+                formatted
+            }
+        };
+
+        for line in formatted.lines() {
+            buffer.write_char('\n')?;
+            buffer.write_str(INDENT)?;
+            buffer.write_str(line)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a field is to be rendered as a multi-line block.
+    fn is_block_field(&self, key: &str, value: &Value) -> bool {
+        if self.options.block_keys.contains(&key) {
+            return true;
+        }
+
+        match value {
+            Value::String(string) => {
+                string.contains('\n') || string.chars().nth(self.options.block_threshold).is_some()
+            }
+            Value::Object(_) | Value::Array(_) => value
+                .to_string()
+                .chars()
+                .nth(self.options.block_threshold)
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Writes a field value to the buffer, truncating it to the maximum field length configured
+    /// for the compact format.
+    fn write_truncated_value(&self, buffer: &mut String, value: &Value) -> fmt::Result {
+        let mut formatted = String::new();
+        Self::write_value(&mut formatted, value)?;
+
+        let max_length = self.options.compact_max_field_length;
+        match formatted.char_indices().nth(max_length) {
+            Some((index, _)) => {
+                buffer.write_str(formatted.get(..index).unwrap_or_default())?;
+                buffer.write_char('…')
+            }
+            None => buffer.write_str(&formatted),
+        }
+    }
+
+    /// Orders the fields such that inline fields come first, followed by the fields in the
+    /// configured field order, and the remaining fields in alphabetical order.
+    ///
+    /// Returns the inline fields and the remaining fields separately.
+    fn order_fields<'a>(
+        &self,
+        mut fields: BTreeMap<&'a str, &'a Value>,
+    ) -> (OrderedFields<'a>, OrderedFields<'a>) {
+        let inline_fields = self
+            .options
+            .inline_keys
+            .iter()
+            .filter_map(|key| fields.remove_entry(key))
+            .collect();
+
+        let mut ordered_fields = self
+            .options
+            .field_order
+            .iter()
+            .filter_map(|key| fields.remove_entry(key))
+            .collect::<Vec<_>>();
+        ordered_fields.extend(fields);
+
+        (inline_fields, ordered_fields)
+    }
+
+    /// Format an event and the data of its parent span.
+    fn format_event<S>(
+        &self,
+        span: Option<&SpanRef<'_, S>>,
+        event: &Event<'_>,
+    ) -> Result<String, fmt::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut storage = Storage::default();
+        event.record(&mut storage);
+
+        // Collect span fields followed by event fields, so that event fields take precedence
+        let extensions = span.map(SpanRef::extensions);
+        let mut fields: BTreeMap<&str, &Value> = extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get::<Storage<'_>>())
+            .map(|span_storage| {
+                span_storage
+                    .values()
+                    .iter()
+                    .map(|(key, value)| (*key, value))
+                    .collect()
+            })
+            .unwrap_or_default();
+        fields.extend(storage.values().iter().map(|(key, value)| (*key, value)));
+
+        match self.layout {
+            Layout::Full => self.format_full(span, event, &storage, fields),
+            Layout::Compact => self.format_compact(event, &storage, fields),
+        }
+    }
+
+    /// Format an event into a single line of the form `LEVEL target message key=value ...`.
+    fn format_compact(
+        &self,
+        event: &Event<'_>,
+        storage: &Storage<'_>,
+        fields: BTreeMap<&str, &Value>,
+    ) -> Result<String, fmt::Error> {
+        let mut buffer = String::new();
+        let metadata = event.metadata();
+
+        let level = *metadata.level();
+        self.write_styled(
+            &mut buffer,
+            Self::level_style(level),
+            &format!("{level:>5}"),
+        )?;
+        buffer.write_char(' ')?;
+        self.write_styled(&mut buffer, Style::new().dimmed(), metadata.target())?;
+
+        if let Some(message) = storage.message() {
+            buffer.write_char(' ')?;
+            buffer.write_str(message)?;
+        }
+
+        let (inline_fields, ordered_fields) = self.order_fields(fields);
+        for (key, value) in inline_fields.into_iter().chain(ordered_fields) {
+            buffer.write_char(' ')?;
+            self.write_styled(&mut buffer, Style::new().italic(), key)?;
+            buffer.write_char('=')?;
+            self.write_truncated_value(&mut buffer, value)?;
+        }
+
+        buffer.write_char('\n')?;
+        Ok(buffer)
+    }
+
+    /// Format an event into multiple lines, including the timestamp, span context and source code
+    /// location.
+    fn format_full<S>(
+        &self,
+        span: Option<&SpanRef<'_, S>>,
+        event: &Event<'_>,
+        storage: &Storage<'_>,
+        fields: BTreeMap<&str, &Value>,
+    ) -> Result<String, fmt::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut buffer = String::new();
+        let metadata = event.metadata();
+
+        if let Ok(time) = time::UtcDateTime::now().format(&Iso8601::DEFAULT) {
+            self.write_styled(&mut buffer, Style::new().dimmed(), &time)?;
+            buffer.write_char(' ')?;
+        }
+
+        let level = *metadata.level();
+        self.write_styled(
+            &mut buffer,
+            Self::level_style(level),
+            &format!("{level:>5}"),
+        )?;
+        buffer.write_char(' ')?;
+
+        if self.options.span_context {
+            if let Some(span) = span {
+                for span in span.scope().from_root() {
+                    self.write_styled(&mut buffer, Style::new().bold(), span.name())?;
+                    buffer.write_char(':')?;
+                }
+                buffer.write_char(' ')?;
+            }
+        }
+
+        self.write_styled(&mut buffer, Style::new().dimmed(), metadata.target())?;
+        buffer.write_str(": ")?;
+        buffer.write_str(storage.message().unwrap_or_default())?;
+
+        let (inline_fields, ordered_fields) = self.order_fields(fields);
+        for (key, value) in inline_fields {
+            buffer.write_char(' ')?;
+            self.write_styled(&mut buffer, Style::new().italic(), key)?;
+            buffer.write_char('=')?;
+            Self::write_value(&mut buffer, value)?;
+        }
+
+        for (key, value) in ordered_fields {
+            buffer.write_str("\n    ")?;
+            self.write_styled(&mut buffer, Style::new().bold(), key)?;
+            if self.is_block_field(key, value) {
+                buffer.write_char(':')?;
+                Self::write_block_value(&mut buffer, value)?;
+            } else {
+                buffer.write_str(": ")?;
+                Self::write_value(&mut buffer, value)?;
+            }
+        }
+
+        if let (Some(file), Some(line)) = (metadata.file(), metadata.line()) {
+            buffer.write_str("\n    ")?;
+            self.write_styled(
+                &mut buffer,
+                Style::new().dimmed(),
+                &format!("at {file}:{line}"),
+            )?;
+        }
+
+        buffer.write_char('\n')?;
+        Ok(buffer)
+    }
+}
+
+impl<S, W> Layer<S> for HumanReadableFormattingLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        // Obtain the parent span for the event
+        let span = ctx.event_span(event);
+
+        if let Ok(formatted) = self.format_event(span.as_ref(), event) {
+            // Written using a single `write_all` call to avoid fragmentation of logs because of
+            // multithreading
+            let _ = self
+                .dst_writer
+                .make_writer_for(event.metadata())
+                .write_all(formatted.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Provides [`tracing_subscriber::Layer`]s for recording log volume metrics ([`LogMetricsLayer`])
+//! and span latency metrics ([`SpanLatencyMetricsLayer`]) using the [`metrics`] crate, along with
+//! a writer wrapper for recording the number of bytes written by each logging sink.
+//!
+//! The metrics are recorded using the globally installed [`metrics::Recorder`], allowing them to
+//! be exported to Prometheus, OpenTelemetry or any other backend supported by the `metrics`
+//! ecosystem.
+
+use std::{io, time::Instant};
+
+use tracing::{Event, Metadata, Subscriber, span::Id};
+use tracing_subscriber::{Layer, fmt::MakeWriter, layer::Context, registry::LookupSpan};
+
+use super::DirectiveSink;
+
+/// Name of the counter tracking the number of log records emitted, labelled by `level` and
+/// `target`.
+pub const LOG_RECORDS_TOTAL: &str = "log_records_total";
+
+/// Name of the counter tracking the number of bytes written by each logging sink, labelled by
+/// `sink`.
+/// Records dropped by the non-blocking writers or failing to be written are not counted.
+pub const LOG_BYTES_WRITTEN_TOTAL: &str = "log_bytes_written_total";
+
+/// Name of the histogram tracking the duration of spans in seconds, labelled by `span` (the span
+/// name) and `target`.
+pub const SPAN_DURATION_SECONDS: &str = "span_duration_seconds";
+
+/// A [`tracing_subscriber::Layer`] that counts the log records emitted, by level and target.
+///
+/// The counts are recorded in the [`LOG_RECORDS_TOTAL`] counter, which may be used for alerting
+/// on spikes in error rates or detecting runaway log volume, for example.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogMetricsLayer;
+
+impl LogMetricsLayer {
+    /// Creates a new [`LogMetricsLayer`].
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogMetricsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        metrics::counter!(
+            LOG_RECORDS_TOTAL,
+            "level" => metadata.level().as_str(),
+            "target" => metadata.target(),
+        )
+        .increment(1);
+    }
+}
+
+/// A [`tracing_subscriber::Layer`] that records the duration of spans, keyed by span name and
+/// target.
+///
+/// The durations are recorded in the [`SPAN_DURATION_SECONDS`] histogram, measured from the
+/// first time a span was entered until it was closed.
+/// The span entry time is captured by the [`SpanStorageLayer`][super::SpanStorageLayer], which
+/// must be installed for this layer to record any durations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpanLatencyMetricsLayer;
+
+impl SpanLatencyMetricsLayer {
+    /// Creates a new [`SpanLatencyMetricsLayer`].
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for SpanLatencyMetricsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(elapsed) = span.extensions().get::<Instant>().map(Instant::elapsed) else {
+            return; // The span was never entered, or the storage layer is not installed
+        };
+
+        let metadata = span.metadata();
+        metrics::histogram!(
+            SPAN_DURATION_SECONDS,
+            "span" => metadata.name(),
+            "target" => metadata.target(),
+        )
+        .record(elapsed.as_secs_f64());
+    }
+}
+
+/// A writer wrapper that records the number of bytes written by a logging sink in the
+/// [`LOG_BYTES_WRITTEN_TOTAL`] counter.
+///
+/// For sinks using non-blocking writers, this wraps the writer used by the background worker, so
+/// that only the bytes actually written are counted, excluding the records dropped by the
+/// non-blocking writer or failing to be written.
+/// For sinks writing synchronously, this may wrap the [`MakeWriter`] of the sink instead.
+#[derive(Debug)]
+pub(crate) struct ByteCountingWriter<W> {
+    inner: W,
+    sink: DirectiveSink,
+}
+
+impl<W> ByteCountingWriter<W> {
+    /// Wraps the writer, attributing the bytes written to the specified sink.
+    pub(crate) fn new(inner: W, sink: DirectiveSink) -> Self {
+        Self { inner, sink }
+    }
+}
+
+impl<W: io::Write> io::Write for ByteCountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        metrics::counter!(LOG_BYTES_WRITTEN_TOTAL, "sink" => self.sink.as_str())
+            .increment(u64::try_from(written).unwrap_or(u64::MAX));
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a, W> MakeWriter<'a> for ByteCountingWriter<W>
+where
+    W: MakeWriter<'a>,
+{
+    type Writer = ByteCountingWriter<W::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ByteCountingWriter::new(self.inner.make_writer(), self.sink)
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        ByteCountingWriter::new(self.inner.make_writer_for(meta), self.sink)
+    }
+}
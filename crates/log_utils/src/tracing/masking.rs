@@ -0,0 +1,75 @@
+//! Integration with the [`hyperswitch_masking`] crate, for recording values holding secrets as
+//! fields of the records without exposing the secrets.
+
+use std::fmt;
+
+use hyperswitch_masking::masked_serialize;
+use serde::Serialize;
+
+/// The placeholder recorded for values which could not be serialized.
+const UNSERIALIZABLE_VALUE: &str = "*** unserializable value ***";
+
+/// The masked representation of a value, obtained using [`masked()`], recordable as a field of
+/// the records.
+///
+/// The value can be recorded on the current span using
+/// [`record_on_current_span`][crate::record_on_current_span], retaining its structure in the
+/// records of the [`JsonFormattingLayer`][crate::JsonFormattingLayer], or as a field of events and
+/// spans using the `%` sigil, in which case it is recorded as a JSON string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaskedValue(serde_json::Value);
+
+impl From<MaskedValue> for serde_json::Value {
+    fn from(value: MaskedValue) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for MaskedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Serializes the value for recording as a field of the records, with any
+/// [`Secret`][hyperswitch_masking::Secret] or [`StrongSecret`][hyperswitch_masking::StrongSecret]
+/// within the value serialized as its masked representation rather than its inner value.
+///
+/// This allows recording values such as request bodies holding secrets, whose `Serialize`
+/// implementations expose the secrets by design.
+/// Secrets recorded directly as fields of events and spans (using the `?` or `%` sigils) need no
+/// such handling, since they are always formatted as their masked representation.
+/// Values which could not be serialized are replaced by a placeholder.
+///
+/// # Example
+///
+/// ```
+/// use hyperswitch_masking::Secret;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct PaymentRequest {
+///     amount: i64,
+///     card_number: Secret<String>,
+/// }
+///
+/// let request = PaymentRequest {
+///     amount: 100,
+///     card_number: Secret::new("4242424242424242".to_owned()),
+/// };
+///
+/// let value = serde_json::Value::from(log_utils::masked(&request));
+/// assert_eq!(value["amount"], 100);
+/// assert_eq!(value["card_number"], "*** alloc::string::String ***");
+///
+/// log_utils::record_on_current_span("request", log_utils::masked(&request));
+/// tracing::info!(request = %log_utils::masked(&request), "Payment request received");
+/// ```
+pub fn masked<T>(value: &T) -> MaskedValue
+where
+    T: Serialize + ?Sized,
+{
+    MaskedValue(
+        masked_serialize(&value).unwrap_or_else(|_| serde_json::Value::from(UNSERIALIZABLE_VALUE)),
+    )
+}
@@ -0,0 +1,64 @@
+//! Provides cheap sampling of the resident set size (RSS) of the process, cached across calls.
+
+use std::{
+    sync::{
+        LazyLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// The minimum interval between two samples of the resident set size.
+const SAMPLING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The instant relative to which the sampling times are recorded.
+static EPOCH: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+/// The time of the last sample in milliseconds since [`EPOCH`], [`u64::MAX`] if never sampled.
+static SAMPLED_AT_MS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// The resident set size in bytes as of the last sample, `0` if unavailable.
+static RESIDENT_SET_SIZE: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the resident set size of the process in bytes, if available on the platform.
+///
+/// The resident set size is sampled at most once per [`SAMPLING_INTERVAL`], and the last sample
+/// is returned otherwise, so that this can be called on every span close.
+pub(crate) fn resident_set_size() -> Option<u64> {
+    let now_ms = u64::try_from(EPOCH.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let sampled_at_ms = SAMPLED_AT_MS.load(Ordering::Relaxed);
+    let interval_ms = u64::try_from(SAMPLING_INTERVAL.as_millis()).unwrap_or(u64::MAX);
+
+    if sampled_at_ms == u64::MAX || now_ms.saturating_sub(sampled_at_ms) >= interval_ms {
+        // Concurrent callers may sample at the same time, which is harmless
+        SAMPLED_AT_MS.store(now_ms, Ordering::Relaxed);
+        RESIDENT_SET_SIZE.store(sample_resident_set_size().unwrap_or(0), Ordering::Relaxed);
+    }
+
+    match RESIDENT_SET_SIZE.load(Ordering::Relaxed) {
+        0 => None,
+        resident_set_size => Some(resident_set_size),
+    }
+}
+
+/// Reads the resident set size of the process from `/proc/self/status`.
+#[cfg(target_os = "linux")]
+fn sample_resident_set_size() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    kilobytes.checked_mul(1024)
+}
+
+/// The resident set size is not available on platforms other than Linux.
+#[cfg(not(target_os = "linux"))]
+fn sample_resident_set_size() -> Option<u64> {
+    None
+}
@@ -0,0 +1,212 @@
+//! Provides the application of the configured permissions and ownership to log files, on Unix
+//! platforms.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use time::{Duration, OffsetDateTime, Time, macros::format_description};
+use tracing_appender::rolling::Rotation;
+
+/// The permissions and ownership to apply to log files.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FilePermissions {
+    pub(crate) mode: Option<u32>,
+    pub(crate) owner: Option<u32>,
+    pub(crate) group: Option<u32>,
+}
+
+impl FilePermissions {
+    /// Returns `true` if neither the permissions nor the ownership are to be changed.
+    pub(crate) fn is_unchanged(&self) -> bool {
+        self.mode.is_none() && self.owner.is_none() && self.group.is_none()
+    }
+
+    /// Applies the permissions and ownership to the log files with the specified prefix in the
+    /// directory.
+    pub(crate) fn apply(&self, directory: &Path, file_name_prefix: &str) -> io::Result<()> {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if !is_log_file(file_name, file_name_prefix) || !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            self.apply_to_file(&entry.path())?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the log file with the permissions and ownership applied, if it does not exist, so
+    /// that records are never written to the file with the default permissions.
+    pub(crate) fn create_file(&self, path: &Path) -> io::Result<()> {
+        match OpenOptions::new().append(true).create_new(true).open(path) {
+            Ok(_) => self.apply_to_file(path),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Applies the permissions and ownership to the file.
+    fn apply_to_file(&self, path: &Path) -> io::Result<()> {
+        if let Some(mode) = self.mode {
+            let mut permissions = std::fs::metadata(path)?.permissions();
+            if permissions.mode() & 0o7777 != mode {
+                permissions.set_mode(mode);
+                std::fs::set_permissions(path, permissions)?;
+            }
+        }
+        if self.owner.is_some() || self.group.is_some() {
+            std::os::unix::fs::chown(path, self.owner, self.group)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `true` if the file name belongs to a log file created by the rolling file appender
+/// with the specified prefix, named either as the prefix itself (if the files are not rotated),
+/// or as the date of the file, preceded by the prefix and a dot (if specified).
+fn is_log_file(file_name: &str, file_name_prefix: &str) -> bool {
+    if file_name_prefix.is_empty() {
+        return is_log_file_date(file_name);
+    }
+
+    file_name
+        .strip_prefix(file_name_prefix)
+        .is_some_and(|suffix| {
+            suffix.is_empty() || suffix.strip_prefix('.').is_some_and(is_log_file_date)
+        })
+}
+
+/// Returns `true` if the text is a date formatted by the rolling file appender, such as
+/// `2025-01-01`, `2025-01-01-13` or `2025-01-01-13-45`.
+fn is_log_file_date(text: &str) -> bool {
+    let lengths: Vec<usize> = text.split('-').map(str::len).collect();
+    matches!(
+        lengths.as_slice(),
+        [4, 2, 2] | [4, 2, 2, 2] | [4, 2, 2, 2, 2]
+    ) && text
+        .bytes()
+        .all(|byte| byte.is_ascii_digit() || byte == b'-')
+}
+
+/// Returns the name of the log file the rolling file appender writes to at the specified time,
+/// or `None` if the files are not rotated.
+pub(crate) fn log_file_name(
+    rotation: &Rotation,
+    file_name_prefix: &str,
+    now: OffsetDateTime,
+) -> Option<String> {
+    let midnight = now.replace_time(Time::MIDNIGHT);
+    let date = if *rotation == Rotation::MINUTELY {
+        now.format(format_description!("[year]-[month]-[day]-[hour]-[minute]"))
+    } else if *rotation == Rotation::HOURLY {
+        now.format(format_description!("[year]-[month]-[day]-[hour]"))
+    } else if *rotation == Rotation::DAILY {
+        midnight.format(format_description!("[year]-[month]-[day]"))
+    } else if *rotation == Rotation::WEEKLY {
+        // Weekly files are named after the preceding Sunday
+        let days_since_sunday = midnight.weekday().number_days_from_sunday();
+        (midnight - Duration::days(days_since_sunday.into()))
+            .format(format_description!("[year]-[month]-[day]"))
+    } else {
+        return None;
+    };
+    let date = date.ok()?;
+
+    if file_name_prefix.is_empty() {
+        Some(date)
+    } else {
+        Some(format!("{file_name_prefix}.{date}"))
+    }
+}
+
+/// A writer that applies the permissions and ownership to the log files created by the wrapped
+/// rolling file appender.
+///
+/// The rolling file appender rotates files at minute boundaries at the earliest, and hence on
+/// the first write of each minute, the log file the appender would write to is created with the
+/// permissions and ownership applied before the write, so that the appender appends to it
+/// rather than creating it with the default permissions.
+/// The log files are checked once more after the write, in case the appender rotated the file
+/// while the write was in progress.
+/// Errors applying the permissions or ownership to rotated files are ignored.
+pub(crate) struct PermissionsWriter<W> {
+    inner: W,
+    permissions: FilePermissions,
+    directory: PathBuf,
+    file_name_prefix: String,
+    rotation: Rotation,
+    last_checked_minute: u64,
+}
+
+impl<W> PermissionsWriter<W> {
+    /// Wraps the writer, assuming that the permissions and ownership were applied to the existing
+    /// log files.
+    pub(crate) fn new(
+        inner: W,
+        permissions: FilePermissions,
+        directory: impl Into<PathBuf>,
+        file_name_prefix: impl Into<String>,
+        rotation: Rotation,
+    ) -> Self {
+        Self {
+            inner,
+            permissions,
+            directory: directory.into(),
+            file_name_prefix: file_name_prefix.into(),
+            rotation,
+            last_checked_minute: current_minute(),
+        }
+    }
+}
+
+impl<W: Write> Write for PermissionsWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let minute = current_minute();
+        let is_new_minute = minute != self.last_checked_minute;
+        if is_new_minute {
+            self.last_checked_minute = minute;
+            if let Some(file_name) = log_file_name(
+                &self.rotation,
+                &self.file_name_prefix,
+                OffsetDateTime::now_utc(),
+            ) {
+                let _ = self
+                    .permissions
+                    .create_file(&self.directory.join(file_name));
+            }
+        }
+
+        let written = self.inner.write(buf)?;
+
+        if is_new_minute {
+            let _ = self
+                .permissions
+                .apply(&self.directory, &self.file_name_prefix);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Returns the number of minutes elapsed since the Unix epoch.
+fn current_minute() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 60)
+        .unwrap_or_default()
+}
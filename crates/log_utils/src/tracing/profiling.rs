@@ -0,0 +1,197 @@
+//! Provides a [`tracing_subscriber::Layer`] ([`FoldedStackLayer`]) for writing the timings of
+//! spans in the folded stack format, which can be rendered as flame graphs.
+
+use std::{
+    cell::Cell,
+    fmt::Write as _,
+    io::Write as _,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use tracing::{Subscriber, span::Id};
+use tracing_subscriber::{
+    Layer,
+    fmt::MakeWriter,
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+/// Configuration for writing the timings of spans in the folded stack format.
+///
+/// The profile file can be rendered as a flame graph using tools such as
+/// [`inferno`](https://github.com/jonhoo/inferno) or
+/// [`flamegraph.pl`](https://github.com/brendangregg/FlameGraph), showing where the time of
+/// requests is spent without attaching a profiler.
+#[derive(Debug, Clone)]
+pub struct FoldedStackProfilingConfig {
+    /// Directory where the profile file will be stored.
+    pub directory: String,
+
+    /// Name of the profile file. Samples are appended to the file if it already exists.
+    pub file_name: String,
+
+    /// If `true`, the timings of spans are written from startup.
+    /// Writing the timings can be enabled or disabled at runtime using the
+    /// [`FoldedStackProfiling`] handle.
+    pub enabled: bool,
+}
+
+/// A handle for enabling or disabling the writing of span timings by a [`FoldedStackLayer`] at
+/// runtime, such as for capturing a profile of a production service for a few minutes.
+///
+/// The handle can be cloned cheaply, and all clones (along with the layers sharing the handle)
+/// observe the same setting.
+#[derive(Clone, Debug)]
+pub struct FoldedStackProfiling {
+    enabled: Arc<AtomicBool>,
+}
+
+impl FoldedStackProfiling {
+    /// Creates a new [`FoldedStackProfiling`] with the specified setting.
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+        }
+    }
+
+    /// Returns `true` if the timings of spans are being written.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Specifies whether the timings of spans are written.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    /// The time of the last span entry or exit on the current thread.
+    static LAST_TRANSITION: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Returns the time elapsed since the last span entry or exit on the current thread, if any,
+/// and records the current time as the time of the last transition.
+fn elapsed_since_last_transition() -> Option<Duration> {
+    let now = Instant::now();
+    LAST_TRANSITION
+        .replace(Some(now))
+        .map(|last_transition| now.duration_since(last_transition))
+}
+
+/// A [`tracing_subscriber::Layer`] that writes the timings of spans in the folded stack format,
+/// one sample per line, such as `request;db_query 1234`.
+///
+/// Each sample consists of the names of the spans from the root span to the innermost span
+/// separated by semicolons, followed by the number of microseconds spent in the innermost span
+/// (excluding the time spent in its child spans) on the current thread.
+/// The time a span is idle (such as when an asynchronous task is waiting) is not included.
+///
+/// # Example
+///
+/// ```
+/// use log_utils::FoldedStackLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let layer = FoldedStackLayer::new(std::io::stderr);
+/// let profiling = layer.profiling();
+/// let subscriber = tracing_subscriber::registry().with(layer);
+///
+/// tracing::subscriber::with_default(subscriber, || {
+///     let _span = tracing::info_span!("request").entered();
+///     // Stop writing samples, such as at the end of a capture window
+///     profiling.set_enabled(false);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct FoldedStackLayer<W> {
+    make_writer: W,
+    profiling: FoldedStackProfiling,
+}
+
+impl<W> FoldedStackLayer<W>
+where
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    /// Creates a new [`FoldedStackLayer`] writing samples to the specified writer, enabled
+    /// initially.
+    pub fn new(make_writer: W) -> Self {
+        Self {
+            make_writer,
+            profiling: FoldedStackProfiling::new(true),
+        }
+    }
+
+    /// Uses the specified handle for enabling or disabling the writing of samples at runtime.
+    ///
+    /// This allows sharing a single handle across multiple layers.
+    pub fn with_profiling(mut self, profiling: FoldedStackProfiling) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    /// Returns the handle for enabling or disabling the writing of samples at runtime.
+    pub fn profiling(&self) -> FoldedStackProfiling {
+        self.profiling.clone()
+    }
+
+    /// Writes a sample attributing the elapsed time to the stack of spans ending with the
+    /// specified span.
+    fn write_sample<S>(&self, span: &SpanRef<'_, S>, elapsed: Duration)
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let micros = elapsed.as_micros();
+        if micros == 0 {
+            return;
+        }
+
+        let mut sample = String::new();
+        for (index, span) in span.scope().from_root().enumerate() {
+            if index > 0 {
+                sample.push(';');
+            }
+            sample.push_str(span.name());
+        }
+        // Writing to a `String` is infallible
+        let _ = writeln!(sample, " {micros}");
+
+        // Failing to write a sample must not affect the application
+        let _ = self.make_writer.make_writer().write_all(sample.as_bytes());
+    }
+}
+
+impl<S, W> Layer<S> for FoldedStackLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        // The time of the transition is recorded even when disabled, so that the first sample
+        // after enabling does not include the time elapsed while disabled
+        let elapsed = elapsed_since_last_transition();
+        if !self.profiling.is_enabled() {
+            return;
+        }
+
+        // The time elapsed before entering the span is attributed to its parent
+        if let Some((parent, elapsed)) = ctx.span(id).and_then(|span| span.parent()).zip(elapsed) {
+            self.write_sample(&parent, elapsed);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let elapsed = elapsed_since_last_transition();
+        if !self.profiling.is_enabled() {
+            return;
+        }
+
+        if let Some((span, elapsed)) = ctx.span(id).zip(elapsed) {
+            self.write_sample(&span, elapsed);
+        }
+    }
+}
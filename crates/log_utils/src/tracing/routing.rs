@@ -0,0 +1,258 @@
+//! Provides a [`tracing_subscriber::Layer`] ([`RoutingLayer`]) for dispatching records to
+//! different sinks based on the value of a field.
+
+use std::{collections::HashMap, fmt};
+
+use serde_json::Value;
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+};
+use tracing_subscriber::{
+    Layer,
+    filter::LevelFilter,
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+use super::storage::Storage;
+
+/// A route of a [`RoutingLayer`].
+type Route<S> = Box<dyn Layer<S> + Send + Sync + 'static>;
+
+/// A [`tracing_subscriber::Layer`] that dispatches records to different layers (such as
+/// [`JsonFormattingLayer`][crate::JsonFormattingLayer]s writing to different files) based on the
+/// value of a field, such as giving each tenant of a multi-tenant service an isolated log stream.
+///
+/// The value of the field is read from the fields of the event, or from the fields of the span
+/// (captured by the [`SpanStorageLayer`][crate::SpanStorageLayer]) for events lacking the field
+/// and for span records.
+/// Records whose value does not match any of the routes (or which lack the field) are dispatched
+/// to the default route, if configured, and dropped otherwise.
+///
+/// The records are dispatched to exactly one route, and hence the routes must not use per-layer
+/// filters; any filter is to be applied to the [`RoutingLayer`] itself instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::collections::{HashMap, HashSet};
+///
+/// use log_utils::{
+///     AdditionalFieldsPlacement, JsonFormattingLayer, JsonFormattingLayerConfig, RoutingLayer,
+///     SpanStorageLayer,
+/// };
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let config = JsonFormattingLayerConfig {
+///     static_top_level_fields: HashMap::new(),
+///     top_level_keys: HashSet::new(),
+///     log_span_lifecycles: false,
+///     additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+///     ..Default::default()
+/// };
+/// let json_layer = |writer| {
+///     JsonFormattingLayer::new(config.clone(), writer, serde_json::ser::CompactFormatter)
+/// };
+///
+/// let routing_layer = RoutingLayer::new("tenant")
+///     .with_route(
+///         "acme",
+///         json_layer(tracing_appender::rolling::daily("logs", "acme.log"))?,
+///     )
+///     .with_default_route(json_layer(tracing_appender::rolling::daily(
+///         "logs", "app.log",
+///     ))?);
+///
+/// let subscriber = tracing_subscriber::registry()
+///     .with(SpanStorageLayer::new(HashSet::new()))
+///     .with(routing_layer);
+/// # drop(subscriber);
+/// # Ok::<(), log_utils::LoggerError>(())
+/// ```
+pub struct RoutingLayer<S> {
+    key: &'static str,
+    routes: HashMap<String, Route<S>>,
+    default_route: Option<Route<S>>,
+}
+
+impl<S> RoutingLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    /// Creates a new [`RoutingLayer`] which routes records by the value of the field with the
+    /// specified key, without any routes.
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            routes: HashMap::new(),
+            default_route: None,
+        }
+    }
+
+    /// Adds a route dispatching the records whose field has the specified value to the layer.
+    ///
+    /// Non-string values are matched by their JSON representation, such as `42` or `true`.
+    pub fn with_route(
+        mut self,
+        value: impl Into<String>,
+        layer: impl Layer<S> + Send + Sync + 'static,
+    ) -> Self {
+        self.routes.insert(value.into(), Box::new(layer));
+        self
+    }
+
+    /// Specifies the layer to dispatch the records not matching any of the routes to.
+    pub fn with_default_route(mut self, layer: impl Layer<S> + Send + Sync + 'static) -> Self {
+        self.default_route = Some(Box::new(layer));
+        self
+    }
+
+    /// Returns all the routes, including the default route.
+    fn all_routes(&self) -> impl Iterator<Item = &Route<S>> {
+        self.routes.values().chain(self.default_route.as_ref())
+    }
+
+    /// Returns the route for the specified value of the field, if any.
+    fn route(&self, value: Option<String>) -> Option<&Route<S>> {
+        value
+            .and_then(|value| self.routes.get(&value))
+            .or(self.default_route.as_ref())
+    }
+
+    /// Returns the value of the field captured in the storage of the span, if any.
+    fn span_value(&self, span: Option<&SpanRef<'_, S>>) -> Option<String> {
+        let extensions = span?.extensions();
+        let value = extensions.get::<Storage<'_>>()?.values().get(self.key)?;
+        Some(match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+    }
+}
+
+impl<S> fmt::Debug for RoutingLayer<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoutingLayer")
+            .field("key", &self.key)
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("default_route", &self.default_route.is_some())
+            .finish()
+    }
+}
+
+impl<S> Layer<S> for RoutingLayer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_layer(&mut self, subscriber: &mut S) {
+        for route in self.routes.values_mut().chain(self.default_route.as_mut()) {
+            route.on_layer(subscriber);
+        }
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // Return the highest interest of any of the routes
+        let mut interest = Interest::never();
+        for route in self.all_routes() {
+            let route_interest = route.register_callsite(metadata);
+            if (interest.is_sometimes() && route_interest.is_always())
+                || (interest.is_never() && !route_interest.is_never())
+            {
+                interest = route_interest;
+            }
+        }
+
+        interest
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.all_routes()
+            .any(|route| route.enabled(metadata, ctx.clone()))
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        let mut max_level = LevelFilter::OFF;
+        for route in self.all_routes() {
+            max_level = max_level.max(route.max_level_hint()?);
+        }
+
+        Some(max_level)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        for route in self.all_routes() {
+            route.on_new_span(attrs, id, ctx.clone());
+        }
+    }
+
+    fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        for route in self.all_routes() {
+            route.on_record(span, values, ctx.clone());
+        }
+    }
+
+    fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+        for route in self.all_routes() {
+            route.on_follows_from(span, follows, ctx.clone());
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldValueVisitor {
+            key: self.key,
+            value: None,
+        };
+        event.record(&mut visitor);
+        let value = visitor
+            .value
+            .or_else(|| self.span_value(ctx.event_span(event).as_ref()));
+
+        if let Some(route) = self.route(value) {
+            route.on_event(event, ctx);
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let value = self.span_value(ctx.span(id).as_ref());
+        if let Some(route) = self.route(value) {
+            route.on_enter(id, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let value = self.span_value(ctx.span(id).as_ref());
+        if let Some(route) = self.route(value) {
+            route.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let value = self.span_value(ctx.span(&id).as_ref());
+        if let Some(route) = self.route(value) {
+            route.on_close(id, ctx);
+        }
+    }
+}
+
+/// A visitor extracting the value of the field with the specified key from an event.
+struct FieldValueVisitor {
+    key: &'static str,
+    value: Option<String>,
+}
+
+impl Visit for FieldValueVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == self.key {
+            self.value = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == self.key {
+            self.value = Some(format!("{value:?}"));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Provides a [`tracing_subscriber::Layer`] ([`SamplingLayer`]) for sampling events, with
+//! per-target sampling rates.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+};
+
+use tracing::{Event, Subscriber};
+use tracing_subscriber::{Layer, layer::Context};
+
+use super::{LoggerError, audit};
+
+/// Configuration for sampling events, where each event is retained with the probability
+/// configured for its target.
+///
+/// Sampled out events are dropped for all sinks and layers, and hence do not count towards
+/// metrics or trigger alerts either.
+/// Spans and audit events are never sampled out.
+#[derive(Clone, Debug)]
+pub struct SamplingConfig {
+    /// The probability (between `0.0` and `1.0`) of retaining events whose target does not
+    /// match any of the [`target_rates`][Self::target_rates].
+    pub default_rate: f64,
+
+    /// The probabilities (between `0.0` and `1.0`) of retaining events by target, such as
+    /// `my_app::db` => `0.01` and `my_app::payments` => `1.0`.
+    /// A target matches the events whose target is the same module or one of its submodules,
+    /// with the longest matching target taking precedence.
+    pub target_rates: HashMap<String, f64>,
+}
+
+/// A [`tracing_subscriber::Layer`] that samples events according to the configured
+/// [`SamplingConfig`].
+///
+/// Since the sampling decision is made once per event, an event is either written to all sinks
+/// or to none of them.
+#[derive(Debug)]
+pub struct SamplingLayer {
+    default_rate: f64,
+    /// Targets along with their rates, ordered by decreasing target length.
+    target_rates: Vec<(String, f64)>,
+}
+
+impl SamplingLayer {
+    /// Creates a new [`SamplingLayer`] with the specified configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LoggerError::Configuration`] if any of the rates are not between `0.0` and
+    /// `1.0`.
+    pub fn new(config: SamplingConfig) -> Result<Self, LoggerError> {
+        let rates = std::iter::once(("default", config.default_rate)).chain(
+            config
+                .target_rates
+                .iter()
+                .map(|(target, rate)| (target.as_str(), *rate)),
+        );
+        for (target, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(LoggerError::Configuration(format!(
+                    "The {target} sampling rate `{rate}` must be between 0.0 and 1.0"
+                )));
+            }
+        }
+
+        let mut target_rates: Vec<(String, f64)> = config.target_rates.into_iter().collect();
+        target_rates.sort_unstable_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+
+        Ok(Self {
+            default_rate: config.default_rate,
+            target_rates,
+        })
+    }
+
+    /// Returns the sampling rate for the specified event target.
+    fn rate(&self, target: &str) -> f64 {
+        self.target_rates
+            .iter()
+            .find(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default_rate, |(_, rate)| *rate)
+    }
+}
+
+impl<S> Layer<S> for SamplingLayer
+where
+    S: Subscriber,
+{
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        if audit::is_audit_event(metadata) {
+            return true;
+        }
+
+        let rate = self.rate(metadata.target());
+        if rate >= 1.0 {
+            true
+        } else if rate <= 0.0 {
+            false
+        } else {
+            random_unit() < rate
+        }
+    }
+}
+
+thread_local! {
+    /// The state of the per-thread pseudo-random number generator, seeded randomly.
+    static RNG_STATE: Cell<u64> = Cell::new(RandomState::new().hash_one(0_u8) | 1);
+}
+
+/// Returns a pseudo-random number in the range `[0.0, 1.0)`, using the xorshift64* generator.
+///
+/// The numbers are not suitable for cryptographic purposes, but suffice for sampling.
+fn random_unit() -> f64 {
+    RNG_STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+
+        // Use the upper 53 bits, which fit exactly in the mantissa of an `f64`
+        let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        #[expect(clippy::as_conversions)]
+        let (bits, scale) = (bits as f64, (1_u64 << 53) as f64);
+        bits / scale
+    })
+}
@@ -0,0 +1,143 @@
+//! Provides the counters summarized by [`LoggingComponents::shutdown()`], along with the
+//! shutdown itself.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use tracing::Level;
+use tracing_appender::non_blocking::ErrorCounter;
+
+use super::{DirectiveSink, FileSinkHealth, LoggingComponents};
+
+/// A handle for reading the number of records emitted by level, counted by the
+/// [`SpanStorageLayer`][crate::SpanStorageLayer].
+///
+/// Events at levels which the storage layer is restricted from are not counted, such as levels
+/// not accepted by any of the sinks configured using
+/// [`build_logging_components`][crate::build_logging_components].
+///
+/// The handle can be cloned cheaply, and all clones observe the same counts.
+#[derive(Clone, Debug, Default)]
+pub struct RecordCounts {
+    /// The counts of records, indexed by [`level_index`].
+    counts: Arc<[AtomicU64; 5]>,
+}
+
+/// Returns the index of the count of records at the level.
+fn level_index(level: Level) -> usize {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+impl RecordCounts {
+    /// Returns the number of records emitted at the level.
+    pub fn count(&self, level: Level) -> u64 {
+        self.counts
+            .get(level_index(level))
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// Returns the total number of records emitted, across all levels.
+    pub fn total(&self) -> u64 {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Counts a record emitted at the level.
+    pub(crate) fn increment(&self, level: Level) {
+        if let Some(count) = self.counts.get(level_index(level)) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The counters of records dropped by the non-blocking writer of a sink, since its buffer was
+/// full.
+#[derive(Clone, Debug)]
+pub(crate) struct DroppedLinesCounter {
+    pub(crate) sink: DirectiveSink,
+    pub(crate) counter: ErrorCounter,
+}
+
+impl LoggingComponents {
+    /// Emits a final `INFO` record summarizing the lifetime of the logging components, and then
+    /// drops the worker guards, flushing the buffered records to the sinks.
+    ///
+    /// The record is emitted with the `log_utils` target, and carries the following fields:
+    ///
+    /// - `uptime_ms`: the time elapsed since the components were built, approximating the uptime
+    ///   of the process.
+    /// - `error_records`, `warn_records`, `info_records`, `debug_records` and `trace_records`:
+    ///   the number of records emitted by level, as counted by the
+    ///   [`storage_layer`][Self::storage_layer].
+    /// - `file_dropped_records` and `file_write_errors`: the records dropped due to write errors
+    ///   and the failed writes of the file sink, if file logging is enabled.
+    /// - `audit_dropped_records` and `audit_write_errors`: the records dropped due to write errors
+    ///   and the failed writes of the audit sink, if audit logging is configured.
+    /// - `file_dropped_lines` and `console_dropped_lines`: the records dropped by the
+    ///   non-blocking writers of the file and console sinks respectively since their buffers were
+    ///   full, if the sinks are enabled.
+    ///
+    /// The layers are typically moved out of the components when building the subscriber, and
+    /// hence the optional layers can be taken using [`Option::take()`] and the storage layer can
+    /// be cloned (such as using [`filtered_storage_layer()`][Self::filtered_storage_layer]), so
+    /// that the components can be retained until shutdown.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn config() -> log_utils::LoggerConfig { unimplemented!() }
+    /// use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+    ///
+    /// let mut components = log_utils::build_logging_components(config())?;
+    /// let mut layers = vec![components.filtered_storage_layer().boxed()];
+    /// layers.extend(components.console_log_layer.take());
+    /// tracing_subscriber::registry().with(layers).init();
+    ///
+    /// // Run the application ...
+    ///
+    /// components.shutdown();
+    /// # Ok::<(), log_utils::LoggerError>(())
+    /// ```
+    pub fn shutdown(self) {
+        let dropped_lines = |sink: DirectiveSink| {
+            self.dropped_lines_counters
+                .iter()
+                .filter(|counter| counter.sink == sink)
+                .map(|counter| u64::try_from(counter.counter.dropped_lines()).unwrap_or(u64::MAX))
+                .reduce(u64::saturating_add)
+        };
+        let uptime_ms = u64::try_from(self.built_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let file_sink_health = self.file_sink_health.as_ref();
+        let audit_sink_health = self.audit_sink_health.as_ref();
+
+        tracing::info!(
+            target: "log_utils",
+            uptime_ms,
+            error_records = self.record_counts.count(Level::ERROR),
+            warn_records = self.record_counts.count(Level::WARN),
+            info_records = self.record_counts.count(Level::INFO),
+            debug_records = self.record_counts.count(Level::DEBUG),
+            trace_records = self.record_counts.count(Level::TRACE),
+            file_dropped_records = file_sink_health.map(FileSinkHealth::dropped_records),
+            file_write_errors = file_sink_health.map(FileSinkHealth::write_errors),
+            audit_dropped_records = audit_sink_health.map(FileSinkHealth::dropped_records),
+            audit_write_errors = audit_sink_health.map(FileSinkHealth::write_errors),
+            file_dropped_lines = dropped_lines(DirectiveSink::File),
+            console_dropped_lines = dropped_lines(DirectiveSink::Console),
+            "Logging shut down"
+        );
+
+        // Flush the buffered records, including the summary record
+        drop(self.guards);
+    }
+}
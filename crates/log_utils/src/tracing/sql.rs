@@ -0,0 +1,286 @@
+//! Provides the logging of SQL query executions as spans, following the storage and formatting
+//! conventions of this crate.
+//!
+//! Each query execution is logged as a span named `sql_query`, a child of the current span,
+//! carrying the sanitized SQL statement (with literals replaced by `?`, so that sensitive values
+//! are not logged) in the `db_statement` field, as well as the `rows` and `duration_ms` fields
+//! once the query completes (or the `error` field if the query fails).
+//! Queries exceeding the configured slow query threshold are additionally logged as `WARN`
+//! events.
+//! Since the spans are not root spans, their exit records are only logged if the logging of
+//! span lifecycles is enabled.
+//! The spans are created with the `log_utils::tracing::sql` target.
+//!
+//! Queries can be instrumented using [`SqlQueryLogging::start_query`], such as with the number
+//! of rows reported by `sqlx`, or by installing the [`DieselInstrumentation`] on `diesel`
+//! connections (with the `diesel` feature enabled).
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use log_utils::sql::SqlQueryLogging;
+//!
+//! let sql_query_logging =
+//!     SqlQueryLogging::new().with_slow_query_threshold(Duration::from_millis(500));
+//!
+//! let query = sql_query_logging.start_query("SELECT * FROM payments WHERE merchant_id = 'm_1'");
+//! // Execute the query within `query.span()`
+//! query.finish(Some(1));
+//! ```
+
+use std::{
+    fmt,
+    iter::Peekable,
+    str::CharIndices,
+    time::{Duration, Instant},
+};
+
+use tracing::{Span, field::Empty};
+
+/// Configuration for logging SQL query executions as spans.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SqlQueryLogging {
+    slow_query_threshold: Option<Duration>,
+}
+
+impl SqlQueryLogging {
+    /// Creates a new [`SqlQueryLogging`], without a slow query threshold.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies the duration beyond which queries are additionally logged as `WARN` events.
+    pub fn with_slow_query_threshold(mut self, slow_query_threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(slow_query_threshold);
+        self
+    }
+
+    /// Starts logging the execution of the SQL query, returning the [`QuerySpan`] to be finished
+    /// once the query completes.
+    pub fn start_query(&self, sql: &str) -> QuerySpan {
+        let span = tracing::info_span!(
+            "sql_query",
+            db_statement = sanitize_sql(sql),
+            rows = Empty,
+            duration_ms = Empty,
+            error = Empty,
+        );
+
+        QuerySpan {
+            span,
+            started_at: Instant::now(),
+            slow_query_threshold: self.slow_query_threshold,
+        }
+    }
+}
+
+/// The span of an SQL query execution, started using [`SqlQueryLogging::start_query`].
+///
+/// The span is closed when the [`QuerySpan`] is finished (or dropped).
+#[derive(Debug)]
+pub struct QuerySpan {
+    span: Span,
+    started_at: Instant,
+    slow_query_threshold: Option<Duration>,
+}
+
+impl QuerySpan {
+    /// Returns the span of the query, within which the query is to be executed (such as using
+    /// `tracing::Instrument::instrument()` for asynchronous queries).
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Finishes logging the query as successful, with the number of rows returned or affected
+    /// by the query, if known.
+    pub fn finish(self, rows: Option<u64>) {
+        if let Some(rows) = rows {
+            self.span.record("rows", rows);
+        }
+        self.record_duration();
+    }
+
+    /// Finishes logging the query as failed, with the specified error.
+    pub fn fail(self, error: &dyn fmt::Display) {
+        self.span.record("error", tracing::field::display(error));
+        self.record_duration();
+    }
+
+    /// Records the duration of the query, and logs a `WARN` event if the query exceeded the slow
+    /// query threshold.
+    fn record_duration(&self) {
+        let duration = self.started_at.elapsed();
+        let duration_ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.span.record("duration_ms", duration_ms);
+
+        if let Some(slow_query_threshold) = self.slow_query_threshold {
+            if duration > slow_query_threshold {
+                let slow_query_threshold_ms =
+                    u64::try_from(slow_query_threshold.as_millis()).unwrap_or(u64::MAX);
+                tracing::warn!(
+                    parent: &self.span,
+                    duration_ms,
+                    slow_query_threshold_ms,
+                    "Slow query"
+                );
+            }
+        }
+    }
+}
+
+/// Sanitizes the SQL statement for logging, by replacing string and numeric literals with `?`,
+/// and collapsing whitespace.
+///
+/// String literals include escape string literals (such as `E'it\'s'`) and dollar-quoted string
+/// literals (such as `$$it's$$` or `$tag$it's$tag$`), and numeric literals include hexadecimal
+/// and exponential literals (such as `0xDEAD` or `1e5`).
+/// Quoted identifiers and positional parameters (such as `$1`) are retained.
+pub fn sanitize_sql(sql: &str) -> String {
+    let mut sanitized = String::with_capacity(sql.len());
+    let mut chars = sql.char_indices().peekable();
+    // Whether the previous character is part of an identifier or a positional parameter
+    let mut in_word = false;
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\'' => {
+                skip_string_literal(&mut chars, false);
+                sanitized.push('?');
+                in_word = false;
+            }
+            'E' | 'e' if !in_word && chars.next_if(|(_, c)| *c == '\'').is_some() => {
+                skip_string_literal(&mut chars, true);
+                sanitized.push('?');
+                in_word = false;
+            }
+            '$' if !in_word => match dollar_quote_delimiter(sql.get(index..).unwrap_or_default()) {
+                Some(delimiter) => {
+                    // Skip the dollar-quoted string literal, up to the closing delimiter
+                    let body_start = index + delimiter.len();
+                    let end = sql
+                        .get(body_start..)
+                        .and_then(|body| body.find(delimiter))
+                        .map_or(sql.len(), |end| body_start + end + delimiter.len());
+                    while chars.next_if(|(index, _)| *index < end).is_some() {}
+                    sanitized.push('?');
+                    in_word = false;
+                }
+                None => {
+                    sanitized.push(c);
+                    in_word = true;
+                }
+            },
+            '"' => {
+                sanitized.push(c);
+                for (_, c) in chars.by_ref() {
+                    sanitized.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+                in_word = false;
+            }
+            c if c.is_ascii_digit() && !in_word => {
+                // Numeric literals may contain letters, such as in `0xDEAD` or `1e5`
+                while chars
+                    .next_if(|(_, c)| c.is_alphanumeric() || *c == '.' || *c == '_')
+                    .is_some()
+                {}
+                sanitized.push('?');
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                if !sanitized.is_empty() && chars.peek().is_some() {
+                    sanitized.push(' ');
+                }
+                in_word = false;
+            }
+            c => {
+                sanitized.push(c);
+                in_word = c.is_alphanumeric() || c == '_' || c == '$';
+            }
+        }
+    }
+
+    sanitized
+}
+
+/// Skips the remainder of a string literal following its opening quote, where quotes are escaped
+/// by doubling them, or with backslashes in escape string literals.
+fn skip_string_literal(chars: &mut Peekable<CharIndices<'_>>, backslash_escapes: bool) {
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\\' if backslash_escapes => {
+                chars.next();
+            }
+            '\'' if chars.next_if(|(_, c)| *c == '\'').is_none() => break,
+            _ => (),
+        }
+    }
+}
+
+/// Returns the opening delimiter of the dollar-quoted string literal at the start of the SQL
+/// (such as `$$` or `$tag$`), if any.
+fn dollar_quote_delimiter(sql: &str) -> Option<&str> {
+    let rest = sql.strip_prefix('$')?;
+    // Tags follow the rules of unquoted identifiers, while `$1` is a positional parameter
+    if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let tag_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+    rest.get(tag_len..)?
+        .starts_with('$')
+        .then(|| sql.get(..tag_len + 2))?
+}
+
+/// A [`diesel::connection::Instrumentation`] that logs the queries executed by a `diesel`
+/// connection as spans, using the specified [`SqlQueryLogging`].
+///
+/// The number of rows is not reported by `diesel`, and hence not logged.
+///
+/// The instrumentation can be installed on a connection using `Connection::set_instrumentation()`,
+/// or on all connections using `diesel::connection::set_default_instrumentation()`.
+#[cfg(feature = "diesel")]
+#[derive(Debug, Default)]
+pub struct DieselInstrumentation {
+    logging: SqlQueryLogging,
+    current_query: Option<QuerySpan>,
+}
+
+#[cfg(feature = "diesel")]
+impl DieselInstrumentation {
+    /// Creates a new [`DieselInstrumentation`] using the specified [`SqlQueryLogging`].
+    pub fn new(logging: SqlQueryLogging) -> Self {
+        Self {
+            logging,
+            current_query: None,
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+impl diesel::connection::Instrumentation for DieselInstrumentation {
+    fn on_connection_event(&mut self, event: diesel::connection::InstrumentationEvent<'_>) {
+        use diesel::connection::InstrumentationEvent;
+
+        match event {
+            InstrumentationEvent::StartQuery { query, .. } => {
+                // The query is displayed along with its bind values, which are not logged
+                let sql = query.to_string();
+                let sql = sql.split(" -- binds: ").next().unwrap_or_default();
+                self.current_query = Some(self.logging.start_query(sql));
+            }
+            InstrumentationEvent::FinishQuery { error, .. } => {
+                if let Some(query) = self.current_query.take() {
+                    match error {
+                        Some(error) => query.fail(error),
+                        None => query.finish(None),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
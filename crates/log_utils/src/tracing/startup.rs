@@ -0,0 +1,73 @@
+//! Provides the [`log_startup_info!`][crate::log_startup_info] macro for emitting a startup
+//! record carrying the build information of the application.
+
+/// Emits a single `INFO` record describing the build of the application and the effective
+/// filtering directives of the logging sinks, typically right after logging is initialized.
+///
+/// The record carries the following fields:
+///
+/// - `git_sha`, `git_describe` and `git_branch`: the git commit the application was built from.
+/// - `git_dirty`: whether the working tree had uncommitted changes when the application was built.
+/// - `build_timestamp`: the time at which the application was built.
+/// - `rustc_version`: the version of the Rust compiler used to build the application.
+/// - `target_triple`: the target triple the application was built for.
+/// - `cargo_profile` and `cargo_debug_assertions`: the cargo profile the application was built
+///   with, and whether debug assertions were enabled, to flag debug builds reaching production.
+/// - `global_filtering_directive`, `file_filtering_directive` and `console_filtering_directive`:
+///   the effective filtering directives, if any are specified (such as by passing
+///   [`LoggingComponents::filtering_directives`][crate::LoggingComponents::filtering_directives]).
+///
+/// This is a macro rather than a function since the build information is read from the
+/// environment variables set by `build_info::generate_vergen_cargo_instructions()` in the build
+/// script of the calling crate, using the [`build_info`] macros.
+/// Hence, the build script of the calling crate must call that function, otherwise compilation
+/// fails.
+/// The record is emitted with the module of the caller as its target, so that it is not filtered
+/// out by directives enabling the crates of the application alone.
+///
+/// # Example
+///
+/// Refer to the [`vergen_integration` example][vergen-integration-example] for a complete example
+/// of emitting the startup record.
+///
+/// ```text
+/// let components = log_utils::build_logging_components(config)?;
+/// // Initialize the subscriber using the components ...
+/// log_utils::log_startup_info!(&components.filtering_directives);
+/// ```
+///
+/// [vergen-integration-example]: https://github.com/juspay/framework-libs-rs/tree/main/examples/vergen_integration
+#[macro_export]
+macro_rules! log_startup_info {
+    () => {
+        $crate::log_startup_info!(&[])
+    };
+    ($filtering_directives:expr) => {{
+        let filtering_directives: &[($crate::DirectiveSink, ::std::string::String)] =
+            $filtering_directives;
+        let filtering_directive = |sink: $crate::DirectiveSink| {
+            filtering_directives
+                .iter()
+                .find(|(directive_sink, _)| *directive_sink == sink)
+                .map(|(_, directive)| directive.as_str())
+        };
+
+        let build_info = $crate::__private::build_info::collect!();
+
+        $crate::__private::tracing::info!(
+            git_sha = build_info.git_sha,
+            git_describe = build_info.git_describe,
+            git_branch = build_info.git_branch,
+            git_dirty = build_info.git_dirty,
+            build_timestamp = build_info.build_timestamp,
+            rustc_version = build_info.rustc_version,
+            target_triple = build_info.target_triple,
+            cargo_profile = build_info.cargo_profile,
+            cargo_debug_assertions = build_info.cargo_debug_assertions,
+            global_filtering_directive = filtering_directive($crate::DirectiveSink::Global),
+            file_filtering_directive = filtering_directive($crate::DirectiveSink::File),
+            console_filtering_directive = filtering_directive($crate::DirectiveSink::Console),
+            "Application started"
+        );
+    }};
+}
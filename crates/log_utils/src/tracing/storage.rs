@@ -2,23 +2,180 @@
 //! key-value data from tracing spans.
 
 use std::{
-    collections::{HashMap, HashSet},
-    fmt,
-    time::Instant,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, io,
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
 };
 
 use tracing::{
-    Id, Subscriber,
+    Event, Id, Subscriber,
     field::{Field, Visit},
     span::{Attributes, Record},
 };
-use tracing_subscriber::{Layer, layer::Context};
+use tracing_subscriber::{Layer, Registry, layer::Context, registry::LookupSpan};
+
+use super::{ErrorStats, RecordCounts, keys::ReservedKeys};
 
 /// A [`tracing_subscriber::Layer`] that enables storing key-value data within span extensions.
 /// It also handles propagation of "persistent" keys to parent spans and records span duration.
 #[derive(Clone, Debug)]
 pub struct SpanStorageLayer {
-    persistent_keys: HashSet<&'static str>,
+    persistent_keys: PersistentKeys,
+    field_inheritance: SpanFieldInheritance,
+    persistent_key_propagation: PersistentKeyPropagation,
+    storage_limits: SpanStorageLimits,
+    overwrite_policies: Option<Arc<FieldOverwritePolicies>>,
+    reserved_keys: ReservedKeys,
+    request_summary: bool,
+    busy_idle_time: bool,
+    span_counters: bool,
+    memory_usage: bool,
+    record_counts: Option<RecordCounts>,
+    error_stats: Option<ErrorStats>,
+    #[cfg(feature = "opentelemetry")]
+    baggage_keys: HashSet<&'static str>,
+}
+
+/// A shared handle to the set of persistent keys of a [`SpanStorageLayer`], whose values are
+/// propagated to parent spans.
+///
+/// Clones of the handle share the same set of keys, allowing keys to be added or removed at
+/// runtime (such as keys derived from configuration loaded after the layer is installed).
+/// Changes take effect for spans closed after the change.
+#[derive(Clone, Debug, Default)]
+pub struct PersistentKeys(Arc<RwLock<HashSet<String>>>);
+
+impl PersistentKeys {
+    /// Creates a new set of persistent keys with the specified keys.
+    pub fn new(keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(Arc::new(RwLock::new(
+            keys.into_iter().map(Into::into).collect(),
+        )))
+    }
+
+    /// Adds the key to the set of persistent keys.
+    ///
+    /// Returns `true` if the key was not already present.
+    pub fn insert(&self, key: impl Into<String>) -> bool {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key.into())
+    }
+
+    /// Removes the key from the set of persistent keys.
+    ///
+    /// Returns `true` if the key was present.
+    pub fn remove(&self, key: &str) -> bool {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+    }
+
+    /// Checks whether the key is one of the persistent keys.
+    pub fn contains(&self, key: &str) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(key)
+    }
+
+    /// Returns the current set of persistent keys.
+    pub fn keys(&self) -> HashSet<String> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Specifies which ancestors of a span the values of persistent keys are propagated to, when the
+/// span is closed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PersistentKeyPropagation {
+    /// Propagate the values to the parent span only.
+    /// The values reach the ancestors further up as each of the intermediate spans is closed.
+    #[default]
+    Parent,
+
+    /// Propagate the values to all ancestors up to the root span, so that the values are
+    /// available to the records of the root span (such as the root span's exit record) as soon as
+    /// the span is closed, even if intermediate spans are still open.
+    AllAncestors,
+}
+
+/// Limits on the values held in the storage of each span, to prevent unbounded memory growth in
+/// long-lived spans that keep recording fields (such as spans of streaming connections).
+///
+/// Values that would exceed the limits are dropped, and the number of dropped values is recorded
+/// in the `dropped_fields` field of the span.
+/// The limits apply to the fields recorded in the span as well as the fields inherited from the
+/// parent span and the persistent keys propagated from child spans.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpanStorageLimits {
+    /// The maximum number of fields held for a span.
+    pub max_fields: Option<usize>,
+
+    /// The maximum total size (in bytes) of the fields held for a span, measured as the length
+    /// of the keys and the JSON-serialized values.
+    pub max_total_bytes: Option<usize>,
+}
+
+/// Specifies how a field is handled when it is recorded for a span which already holds a value
+/// for the field, such as when a span attribute is updated with `Span::record()`, or when a child
+/// span records a field inherited from its parent span.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldOverwritePolicy {
+    /// The latest value replaces the existing value.
+    #[default]
+    LastWins,
+
+    /// The existing value is retained, and later values are ignored.
+    FirstWins,
+
+    /// All values are collected into an array, in the order they were recorded.
+    CollectIntoArray,
+}
+
+/// The [`FieldOverwritePolicy`] to apply to fields recorded for spans, globally and per key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldOverwritePolicies {
+    /// The policy for fields without a policy of their own.
+    pub default_policy: FieldOverwritePolicy,
+
+    /// The policies for specific fields, by key, which take precedence over the
+    /// [`default_policy`][Self::default_policy].
+    pub key_policies: HashMap<String, FieldOverwritePolicy>,
+}
+
+impl FieldOverwritePolicies {
+    /// Returns the policy for the field with the specified key.
+    fn policy(&self, key: &str) -> FieldOverwritePolicy {
+        self.key_policies
+            .get(key)
+            .copied()
+            .unwrap_or(self.default_policy)
+    }
+}
+
+/// Specifies which fields of a parent span are inherited by its child spans, and hence included
+/// in the records of the child spans and their events.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SpanFieldInheritance {
+    /// Child spans inherit all fields of their parent span.
+    #[default]
+    All,
+
+    /// Child spans do not inherit any fields of their parent span.
+    None,
+
+    /// Child spans only inherit the persistent keys of their parent span.
+    PersistentKeys,
+
+    /// Child spans only inherit the specified fields of their parent span.
+    Only(HashSet<&'static str>),
 }
 
 impl SpanStorageLayer {
@@ -27,10 +184,347 @@ impl SpanStorageLayer {
     /// The values of persistent keys would be propagated to parent spans, if they are set or
     /// updated in the current span.
     pub fn new(persistent_keys: impl IntoIterator<Item = &'static str>) -> Self {
+        Self::with_persistent_keys(PersistentKeys::new(persistent_keys))
+    }
+
+    /// Creates a new [`SpanStorageLayer`] layer with the specified shared set of persistent keys,
+    /// which can be updated at runtime through the [`PersistentKeys`] handle.
+    pub fn with_persistent_keys(persistent_keys: PersistentKeys) -> Self {
         Self {
-            persistent_keys: HashSet::from_iter(persistent_keys),
+            persistent_keys,
+            field_inheritance: SpanFieldInheritance::All,
+            persistent_key_propagation: PersistentKeyPropagation::Parent,
+            storage_limits: SpanStorageLimits::default(),
+            overwrite_policies: None,
+            reserved_keys: ReservedKeys::default(),
+            request_summary: false,
+            busy_idle_time: false,
+            span_counters: false,
+            memory_usage: false,
+            record_counts: None,
+            error_stats: None,
+            #[cfg(feature = "opentelemetry")]
+            baggage_keys: HashSet::new(),
+        }
+    }
+
+    /// Returns a handle to the persistent keys of this layer, which allows adding or removing
+    /// persistent keys at runtime, after the layer has been installed.
+    pub fn persistent_keys(&self) -> PersistentKeys {
+        self.persistent_keys.clone()
+    }
+
+    /// Specifies which fields of a parent span are inherited by its child spans.
+    ///
+    /// By default, child spans inherit all fields of their parent span, which can bloat records,
+    /// and leak fields into unrelated child spans.
+    /// The propagation of persistent keys to parent spans is not affected by this setting.
+    pub fn with_field_inheritance(mut self, field_inheritance: SpanFieldInheritance) -> Self {
+        self.field_inheritance = field_inheritance;
+        self
+    }
+
+    /// Specifies which ancestors of a span the values of persistent keys are propagated to, when
+    /// the span is closed.
+    pub fn with_persistent_key_propagation(
+        mut self,
+        persistent_key_propagation: PersistentKeyPropagation,
+    ) -> Self {
+        self.persistent_key_propagation = persistent_key_propagation;
+        self
+    }
+
+    /// Specifies the limits on the values held in the storage of each span.
+    ///
+    /// By default, the storage of a span is unbounded.
+    pub fn with_storage_limits(mut self, storage_limits: SpanStorageLimits) -> Self {
+        self.storage_limits = storage_limits;
+        self
+    }
+
+    /// Specifies how fields that are recorded multiple times for a span are handled.
+    ///
+    /// By default, the latest value of a field replaces the existing value.
+    pub fn with_field_overwrite_policies(
+        mut self,
+        overwrite_policies: FieldOverwritePolicies,
+    ) -> Self {
+        self.overwrite_policies = Some(Arc::new(overwrite_policies));
+        self
+    }
+
+    /// Reserves the keys of the optional implicit fields included by the formatting layers, such
+    /// as the `pod_name` field if environment enrichment is enabled, in addition to the implicit
+    /// keys.
+    pub(crate) fn with_reserved_keys(mut self, reserved_keys: ReservedKeys) -> Self {
+        self.reserved_keys = reserved_keys;
+        self
+    }
+
+    /// Checks whether child spans inherit the field with the specified key from their parent.
+    fn is_inherited(&self, key: &str) -> bool {
+        match &self.field_inheritance {
+            SpanFieldInheritance::All => true,
+            SpanFieldInheritance::None => false,
+            SpanFieldInheritance::PersistentKeys => self.persistent_keys.contains(key),
+            SpanFieldInheritance::Only(keys) => keys.contains(key),
         }
     }
+
+    /// Enables aggregating a summary of each root span (typically corresponding to a request),
+    /// which is recorded in the root span's data when it is closed, under the
+    /// `request_summary` key.
+    ///
+    /// The summary includes the counts of events by level, the number of child spans, the
+    /// slowest child span, and the persistent keys propagated from the child spans.
+    /// This allows the root span's exit record to serve as a single summary line for the
+    /// request, which is suitable for cheap retention.
+    pub fn with_request_summary(mut self) -> Self {
+        self.request_summary = true;
+        self
+    }
+
+    /// Enables tracking the time each span is busy (entered) separately from the time it is idle
+    /// (created but not entered), which are recorded in the span's data when it is closed,
+    /// under the `busy_ms` and `idle_ms` keys.
+    ///
+    /// The busy time is accumulated across all the times the span is entered and exited, such as
+    /// each poll of an instrumented future.
+    /// In asynchronous code, a large idle time with a small busy time indicates that the span is
+    /// waiting, either on slow downstream services or due to executor starvation.
+    pub fn with_busy_idle_time(mut self) -> Self {
+        self.busy_idle_time = true;
+        self
+    }
+
+    /// Enables counting the events and spans within each span, which are recorded in the span's
+    /// data when it is closed, under the `event_count`, `error_event_count` and
+    /// `child_span_count` keys.
+    ///
+    /// The counts include the events and spans within the descendant spans of the span, allowing
+    /// exit records to quantify the work done within the span.
+    pub fn with_span_counters(mut self) -> Self {
+        self.span_counters = true;
+        self
+    }
+
+    /// Enables recording the resident set size (RSS) of the process in bytes in the data of each
+    /// span when it is closed, under the `rss_bytes` key, helping correlate memory growth with
+    /// specific request flows.
+    ///
+    /// The resident set size is sampled at most once per second and cached in between, so that
+    /// the cost per span is negligible; hence, the value may lag behind by up to a second.
+    /// The resident set size is only available on Linux, and is not recorded on other
+    /// platforms.
+    pub fn with_memory_usage(mut self) -> Self {
+        self.memory_usage = true;
+        self
+    }
+
+    /// Enables counting the events by level in the specified handle, such as for summarizing
+    /// the records emitted over the lifetime of the application.
+    pub fn with_record_counts(mut self, record_counts: RecordCounts) -> Self {
+        self.record_counts = Some(record_counts);
+        self
+    }
+
+    /// Enables tracking the `ERROR` events over sliding windows in the specified handle, such as
+    /// for readiness probes and circuit breakers to react to a storm of errors.
+    pub fn with_error_stats(mut self, error_stats: ErrorStats) -> Self {
+        self.error_stats = Some(error_stats);
+        self
+    }
+
+    /// Specifies the keys of the OpenTelemetry baggage entries to be recorded as fields of each
+    /// span, from the baggage of the OpenTelemetry context current when the span is created.
+    ///
+    /// This allows metadata set by upstream services (such as the originating channel or the
+    /// tenant) to be included in the logs.
+    /// Fields of the span with the same keys take precedence over the baggage entries, as per
+    /// the default [`FieldOverwritePolicy`].
+    #[cfg(feature = "opentelemetry")]
+    pub fn with_baggage_keys(
+        mut self,
+        baggage_keys: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        self.baggage_keys = HashSet::from_iter(baggage_keys);
+        self
+    }
+}
+
+/// Returns the fields captured by the [`SpanStorageLayer`] for the current span, including the
+/// fields inherited from its parent span and the persistent keys propagated from its closed child
+/// spans.
+///
+/// This allows application code and middleware to use contextual values (such as the request ID)
+/// for purposes other than logging, such as response headers or error reports.
+/// An empty map is returned if there is no current span, or if the [`SpanStorageLayer`] is not
+/// installed in a subscriber built on a [`tracing_subscriber::Registry`].
+pub fn current_span_fields() -> HashMap<String, serde_json::Value> {
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            let span = registry.span(id)?;
+            let extensions = span.extensions();
+            let storage = extensions.get::<Storage<'_>>()?;
+
+            Some(
+                storage
+                    .values
+                    .iter()
+                    .map(|(key, value)| ((*key).to_owned(), value.clone()))
+                    .collect(),
+            )
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Records the field on the current span, in the storage of the [`SpanStorageLayer`].
+///
+/// Unlike [`tracing::Span::record`], the field need not be declared when the span is created, and
+/// is included in the records of the span and its events, as well as the records of child spans
+/// created afterwards (subject to the configured [`SpanFieldInheritance`]).
+/// The configured [`FieldOverwritePolicies`] and [`SpanStorageLimits`] apply to the field, and
+/// the value is propagated to parent spans if the key is one of the persistent keys.
+///
+/// Returns `false` if the field could not be recorded, since there is no current span, or the
+/// [`SpanStorageLayer`] is not installed in a subscriber built on a
+/// [`tracing_subscriber::Registry`].
+pub fn record_on_current_span(key: &'static str, value: impl Into<serde_json::Value>) -> bool {
+    record_all_on_current_span([(key, value.into())])
+}
+
+/// Records the fields on the current span, in the storage of the [`SpanStorageLayer`].
+///
+/// Refer to [`record_on_current_span`] for more details.
+pub fn record_all_on_current_span(
+    fields: impl IntoIterator<Item = (&'static str, serde_json::Value)>,
+) -> bool {
+    // Reserved keys are rejected upfront, since the warning cannot be logged while the storage of
+    // the current span is locked
+    let reserved_keys = tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            let span = registry.span(id)?;
+            let extensions = span.extensions();
+            Some(extensions.get::<Storage<'_>>()?.reserved_keys.clone())
+        })
+        .flatten()
+        .unwrap_or_default();
+    let (reserved, fields): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .partition(|(key, _)| reserved_keys.contains(key));
+    for (key, value) in reserved {
+        super::structured::log_skipped_reserved_key(
+            &value,
+            format_args!(
+                "Attempting to record a reserved key `{key}` (value: {}). Skipping.",
+                super::structured::ReservedKeyValue(&value)
+            ),
+        );
+    }
+
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            let span = registry.span(id)?;
+            let mut extensions = span.extensions_mut();
+            let storage = extensions.get_mut::<Storage<'_>>()?;
+
+            for (key, value) in fields {
+                storage.record_value(key, value);
+            }
+            Some(())
+        })
+        .flatten()
+        .is_some()
+}
+
+/// Busy time of a span, accumulated across the times it is entered and exited, stored in the
+/// span's extensions.
+#[derive(Debug)]
+struct BusyTime {
+    /// The time the span was created.
+    created_at: Instant,
+
+    /// The total time the span was entered, excluding the current entry (if any).
+    busy: Duration,
+
+    /// The time the span was last entered, if it is currently entered.
+    entered_at: Option<Instant>,
+
+    /// The number of times the span is currently entered, since a span can be entered again
+    /// (or on multiple threads) before being exited.
+    entered_count: usize,
+}
+
+impl BusyTime {
+    /// Creates a new [`BusyTime`] for a span created now.
+    fn new() -> Self {
+        Self {
+            created_at: Instant::now(),
+            busy: Duration::ZERO,
+            entered_at: None,
+            entered_count: 0,
+        }
+    }
+
+    /// Returns the busy and idle time of the span (in milliseconds) as of now.
+    fn busy_idle_milliseconds(&self) -> (u128, u128) {
+        let now = Instant::now();
+        let busy = self.busy
+            + self
+                .entered_at
+                .map_or(Duration::ZERO, |entered_at| now.duration_since(entered_at));
+        let idle = now.duration_since(self.created_at).saturating_sub(busy);
+        (busy.as_millis(), idle.as_millis())
+    }
+}
+
+/// Counts of the events and spans within a span (including its descendants), stored in the span's
+/// extensions.
+#[derive(Clone, Copy, Debug, Default)]
+struct SpanCounters {
+    /// Number of events within the span.
+    event_count: u64,
+
+    /// Number of events at the `ERROR` level within the span.
+    error_event_count: u64,
+
+    /// Number of descendant spans of the span.
+    child_span_count: u64,
+}
+
+/// Aggregated summary of a root span and its descendants, stored in the root span's extensions.
+#[derive(Debug, Default)]
+struct RequestSummary {
+    /// Counts of events within the root span (including its descendants), by level.
+    event_counts: BTreeMap<&'static str, u64>,
+
+    /// Number of descendant spans of the root span.
+    child_span_count: u64,
+
+    /// Name and duration (in milliseconds) of the slowest closed descendant span.
+    slowest_child_span: Option<(&'static str, u128)>,
+}
+
+impl RequestSummary {
+    /// Converts the summary into its JSON representation.
+    fn to_value(&self) -> serde_json::Value {
+        let slowest_child_span = self.slowest_child_span.map(|(name, elapsed)| {
+            serde_json::json!({
+                "name": name,
+                super::keys::ELAPSED_MILLISECONDS: elapsed,
+            })
+        });
+
+        serde_json::json!({
+            "event_counts": self.event_counts,
+            "child_span_count": self.child_span_count,
+            "slowest_child_span": slowest_child_span,
+        })
+    }
 }
 
 /// Holds key-value data recorded for a span or an event.
@@ -43,23 +537,156 @@ pub(crate) struct Storage<'a> {
 
     /// The primary message of an event, if captured.
     message: Option<String>,
+
+    /// The limits on the values held in the storage.
+    limits: SpanStorageLimits,
+
+    /// The total size (in bytes) of the values held in the storage, tracked only if a size limit
+    /// is configured.
+    total_bytes: usize,
+
+    /// The policies for handling fields that are recorded multiple times, if configured.
+    overwrite_policies: Option<Arc<FieldOverwritePolicies>>,
+
+    /// The keys whose values have been collected into arrays, as per
+    /// [`FieldOverwritePolicy::CollectIntoArray`].
+    collected_keys: HashSet<&'a str>,
+
+    /// The keys which cannot be recorded into the storage.
+    reserved_keys: ReservedKeys,
 }
 
 impl<'a> Storage<'a> {
     /// Records a key-value pair into the storage.
     ///
-    /// If the `key` is one of the [`IMPLICIT_KEYS`][crate::keys::IMPLICIT_KEYS],
-    /// a warning is logged, and the value is not inserted.
+    /// If the `key` is one of the reserved keys, a warning is logged, and the value is not
+    /// inserted.
+    /// If the storage already holds a value for the `key`, the configured
+    /// [`FieldOverwritePolicy`] determines the resulting value.
     pub(crate) fn record_value(&mut self, key: &'a str, value: serde_json::Value) {
-        if super::keys::IMPLICIT_KEYS.contains(key) {
-            tracing::warn!(
-                "Attempting to record a reserved key `{key}` (value: {value:?}). Skipping."
+        if self.reserved_keys.contains(key) {
+            super::structured::log_skipped_reserved_key(
+                &value,
+                format_args!(
+                    "Attempting to record a reserved key `{key}` (value: {}). Skipping.",
+                    super::structured::ReservedKeyValue(&value)
+                ),
+            );
+            return;
+        }
+
+        let Some(value) = self.resolve_overwrite(key, value) else {
+            return;
+        };
+
+        if !self.is_within_limits(key, &value) {
+            let dropped = self
+                .values
+                .get(super::keys::DROPPED_FIELDS)
+                .and_then(serde_json::Value::as_u64)
+                .unwrap_or_default();
+            self.values.insert(
+                super::keys::DROPPED_FIELDS,
+                serde_json::Value::from(dropped.saturating_add(1)),
             );
         } else {
             self.values.insert(key, value);
         }
     }
 
+    /// Determines the value to be stored for the `key` as per the configured
+    /// [`FieldOverwritePolicy`], returning `None` if the existing value is to be retained.
+    fn resolve_overwrite(
+        &mut self,
+        key: &'a str,
+        value: serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        let policy = self
+            .overwrite_policies
+            .as_ref()
+            .map_or(FieldOverwritePolicy::LastWins, |policies| {
+                policies.policy(key)
+            });
+        let Some(existing) = self.values.get(key) else {
+            return Some(value);
+        };
+
+        match policy {
+            FieldOverwritePolicy::LastWins => Some(value),
+            FieldOverwritePolicy::FirstWins => None,
+            FieldOverwritePolicy::CollectIntoArray => {
+                let mut values = match existing {
+                    serde_json::Value::Array(values) if self.collected_keys.contains(key) => {
+                        values.clone()
+                    }
+                    existing => vec![existing.clone()],
+                };
+                values.push(value);
+                self.collected_keys.insert(key);
+                Some(serde_json::Value::Array(values))
+            }
+        }
+    }
+
+    /// Records a key-value pair computed by [`SpanStorageLayer`] (such as the elapsed time) into
+    /// the storage, regardless of the limits.
+    fn record_internal_value(&mut self, key: &'a str, value: serde_json::Value) {
+        self.values.insert(key, value);
+    }
+
+    /// Applies the reserved keys to the storage.
+    fn set_reserved_keys(&mut self, reserved_keys: ReservedKeys) {
+        self.reserved_keys = reserved_keys;
+    }
+
+    /// Applies the policies for handling fields that are recorded multiple times to the storage.
+    fn set_overwrite_policies(&mut self, overwrite_policies: Option<Arc<FieldOverwritePolicies>>) {
+        self.overwrite_policies = overwrite_policies;
+    }
+
+    /// Applies the limits to the storage, resetting the dropped values count (which may have
+    /// been inherited from the parent span).
+    fn set_limits(&mut self, limits: SpanStorageLimits) {
+        self.limits = limits;
+        self.values.remove(super::keys::DROPPED_FIELDS);
+        self.total_bytes = if limits.max_total_bytes.is_some() {
+            self.values
+                .iter()
+                .map(|(key, value)| entry_size(key, value))
+                .sum()
+        } else {
+            0
+        };
+    }
+
+    /// Checks whether inserting the key-value pair keeps the storage within the limits, and if
+    /// so, updates the tracked total size of the storage.
+    fn is_within_limits(&mut self, key: &str, value: &serde_json::Value) -> bool {
+        let existing = self.values.get(key);
+        if existing.is_none()
+            && self
+                .limits
+                .max_fields
+                .is_some_and(|max_fields| self.values.len() >= max_fields)
+        {
+            return false;
+        }
+
+        if let Some(max_total_bytes) = self.limits.max_total_bytes {
+            let replaced = existing.map_or(0, |existing| entry_size(key, existing));
+            let total_bytes = self
+                .total_bytes
+                .saturating_sub(replaced)
+                .saturating_add(entry_size(key, value));
+            if total_bytes > max_total_bytes {
+                return false;
+            }
+            self.total_bytes = total_bytes;
+        }
+
+        true
+    }
+
     pub(crate) fn values(&self) -> &HashMap<&'a str, serde_json::Value> {
         &self.values
     }
@@ -69,6 +696,28 @@ impl<'a> Storage<'a> {
     }
 }
 
+/// Returns the size (in bytes) of a key-value pair, as the length of the key and the
+/// JSON-serialized value.
+fn entry_size(key: &str, value: &serde_json::Value) -> usize {
+    /// A writer that only counts the number of bytes written to it.
+    struct ByteCounter(usize);
+
+    impl io::Write for ByteCounter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 = self.0.saturating_add(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = ByteCounter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    key.len().saturating_add(counter.0)
+}
+
 // Implement `Visit` to capture span or event fields into the `Storage` map.
 impl Visit for Storage<'_> {
     fn record_f64(&mut self, field: &Field, value: f64) {
@@ -119,6 +768,26 @@ impl Visit for Storage<'_> {
         }
     }
 
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        if field.name() == super::keys::MESSAGE {
+            if self.message.is_none() {
+                self.message = Some(value.to_string());
+            }
+        } else {
+            // Record the chain of sources structurally, rather than as a debug-formatted string
+            let sources = std::iter::successors(value.source(), |error| error.source())
+                .map(|error| serde_json::Value::from(error.to_string()))
+                .collect::<Vec<_>>();
+            self.record_value(
+                field.name(),
+                serde_json::json!({
+                    "message": value.to_string(),
+                    "sources": sources,
+                }),
+            );
+        }
+    }
+
     fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
         if field.name() == super::keys::MESSAGE {
             if self.message.is_none() {
@@ -146,9 +815,7 @@ impl Visit for Storage<'_> {
     }
 }
 
-impl<S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>> Layer<S>
-    for SpanStorageLayer
-{
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanStorageLayer {
     fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
         #[expect(clippy::expect_used)]
         let span = ctx
@@ -157,18 +824,103 @@ impl<S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>> Layer
         let mut extensions = span.extensions_mut();
 
         // Inherit storage from parent span if it exists, otherwise create a new span.
-        let mut visitor = if let Some(parent_span) = span.parent() {
-            parent_span
-                .extensions()
-                .get::<Storage<'_>>()
-                .cloned()
-                .unwrap_or_default()
-        } else {
-            Storage::default()
+        let mut visitor = match span.parent() {
+            Some(parent_span) if self.field_inheritance != SpanFieldInheritance::None => {
+                let mut storage = parent_span
+                    .extensions()
+                    .get::<Storage<'_>>()
+                    .cloned()
+                    .unwrap_or_default();
+                if self.field_inheritance != SpanFieldInheritance::All {
+                    storage.values.retain(|key, _| self.is_inherited(key));
+                }
+                storage
+            }
+            _ => Storage::default(),
         };
+        visitor.set_limits(self.storage_limits);
+        visitor.set_overwrite_policies(self.overwrite_policies.clone());
+        visitor.set_reserved_keys(self.reserved_keys.clone());
+
+        #[cfg(feature = "opentelemetry")]
+        if !self.baggage_keys.is_empty() {
+            super::baggage::record_baggage(&self.baggage_keys, &mut visitor);
+        }
 
         attrs.record(&mut visitor);
         extensions.insert(visitor);
+        if self.busy_idle_time {
+            extensions.insert(BusyTime::new());
+        }
+        if self.span_counters {
+            extensions.insert(SpanCounters::default());
+        }
+        drop(extensions);
+
+        if self.span_counters {
+            for ancestor in span.scope().skip(1) {
+                if let Some(counters) = ancestor.extensions_mut().get_mut::<SpanCounters>() {
+                    counters.child_span_count += 1;
+                }
+            }
+        }
+
+        if self.request_summary {
+            match span.scope().from_root().next() {
+                Some(root) if root.id() != span.id() => {
+                    if let Some(summary) = root.extensions_mut().get_mut::<RequestSummary>() {
+                        summary.child_span_count += 1;
+                    }
+                }
+                _ => span.extensions_mut().insert(RequestSummary::default()),
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if let Some(record_counts) = &self.record_counts {
+            record_counts.increment(*event.metadata().level());
+        }
+        if let Some(error_stats) = &self.error_stats {
+            if *event.metadata().level() == tracing::Level::ERROR {
+                error_stats.increment();
+            }
+        }
+
+        if self.span_counters {
+            let is_error = *event.metadata().level() == tracing::Level::ERROR;
+            for span in ctx
+                .event_span(event)
+                .into_iter()
+                .flat_map(|span| span.scope())
+            {
+                if let Some(counters) = span.extensions_mut().get_mut::<SpanCounters>() {
+                    counters.event_count += 1;
+                    if is_error {
+                        counters.error_event_count += 1;
+                    }
+                }
+            }
+        }
+
+        if !self.request_summary {
+            return;
+        }
+
+        let Some(root) = ctx
+            .event_span(event)
+            .and_then(|span| span.scope().from_root().next())
+        else {
+            return;
+        };
+
+        let mut extensions = root.extensions_mut();
+        if let Some(summary) = extensions.get_mut::<RequestSummary>() {
+            *summary
+                .event_counts
+                .entry(event.metadata().level().as_str())
+                .or_default() += 1;
+        }
     }
 
     fn on_record(&self, span_id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
@@ -197,6 +949,34 @@ impl<S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>> Layer
         if extensions.get_mut::<Instant>().is_none() {
             extensions.insert(Instant::now());
         }
+
+        if let Some(busy_time) = extensions.get_mut::<BusyTime>() {
+            if busy_time.entered_count == 0 {
+                busy_time.entered_at = Some(Instant::now());
+            }
+            busy_time.entered_count += 1;
+        }
+    }
+
+    fn on_exit(&self, span_id: &Id, ctx: Context<'_, S>) {
+        if !self.busy_idle_time {
+            return;
+        }
+
+        #[expect(clippy::expect_used)]
+        let span = ctx
+            .span(span_id)
+            .expect("span with specified id does not exist in `on_exit()`");
+        let mut extensions = span.extensions_mut();
+
+        if let Some(busy_time) = extensions.get_mut::<BusyTime>() {
+            busy_time.entered_count = busy_time.entered_count.saturating_sub(1);
+            if busy_time.entered_count == 0 {
+                if let Some(entered_at) = busy_time.entered_at.take() {
+                    busy_time.busy += entered_at.elapsed();
+                }
+            }
+        }
     }
 
     fn on_close(&self, id: Id, ctx: Context<'_, S>) {
@@ -210,21 +990,38 @@ impl<S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>> Layer
             .get::<Instant>()
             .map(|i| i.elapsed().as_millis())
             .unwrap_or(0);
+        let busy_idle_milliseconds = span
+            .extensions()
+            .get::<BusyTime>()
+            .map(BusyTime::busy_idle_milliseconds);
+        let counters = span.extensions_mut().remove::<SpanCounters>();
+        let resident_set_size = self
+            .memory_usage
+            .then(super::memory::resident_set_size)
+            .flatten();
 
-        // Propagate persistent keys to parent
+        // Propagate persistent keys to the parent, or to all ancestors
         if let Some(storage) = span.extensions().get::<Storage<'_>>() {
-            storage
-                .values
-                .iter()
-                .filter(|(k, _v)| self.persistent_keys.contains(*k))
-                .for_each(|(k, v)| {
-                    span.parent().and_then(|parent_span| {
-                        parent_span
-                            .extensions_mut()
-                            .get_mut::<Storage<'_>>()
-                            .map(|parent_storage| parent_storage.record_value(k, v.to_owned()))
-                    });
-                });
+            let persistent_keys = self
+                .persistent_keys
+                .0
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            let ancestors = span.scope().skip(1);
+            let ancestors = match self.persistent_key_propagation {
+                PersistentKeyPropagation::Parent => ancestors.take(1),
+                PersistentKeyPropagation::AllAncestors => ancestors.take(usize::MAX),
+            };
+
+            for ancestor in ancestors {
+                if let Some(ancestor_storage) = ancestor.extensions_mut().get_mut::<Storage<'_>>() {
+                    storage
+                        .values
+                        .iter()
+                        .filter(|(k, _v)| persistent_keys.contains(**k))
+                        .for_each(|(k, v)| ancestor_storage.record_value(k, v.to_owned()));
+                }
+            }
         }
 
         let mut extensions = span.extensions_mut();
@@ -235,7 +1032,70 @@ impl<S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>> Layer
 
         // Record elapsed time in the span's storage
         if let Ok(elapsed_time_value) = serde_json::to_value(elapsed_milliseconds) {
-            visitor.record_value(super::keys::ELAPSED_MILLISECONDS, elapsed_time_value);
+            visitor.record_internal_value(super::keys::ELAPSED_MILLISECONDS, elapsed_time_value);
+        }
+
+        // Record busy and idle time in the span's storage
+        if let Some((busy_milliseconds, idle_milliseconds)) = busy_idle_milliseconds {
+            if let (Ok(busy), Ok(idle)) = (
+                serde_json::to_value(busy_milliseconds),
+                serde_json::to_value(idle_milliseconds),
+            ) {
+                visitor.record_internal_value(super::keys::BUSY_MILLISECONDS, busy);
+                visitor.record_internal_value(super::keys::IDLE_MILLISECONDS, idle);
+            }
+        }
+
+        // Record the resident set size in the span's storage
+        if let Some(resident_set_size) = resident_set_size {
+            visitor.record_internal_value(
+                super::keys::RSS_BYTES,
+                serde_json::Value::from(resident_set_size),
+            );
+        }
+
+        // Record counts of events and spans in the span's storage
+        if let Some(counters) = counters {
+            visitor.record_internal_value(
+                super::keys::EVENT_COUNT,
+                serde_json::Value::from(counters.event_count),
+            );
+            visitor.record_internal_value(
+                super::keys::ERROR_EVENT_COUNT,
+                serde_json::Value::from(counters.error_event_count),
+            );
+            visitor.record_internal_value(
+                super::keys::CHILD_SPAN_COUNT,
+                serde_json::Value::from(counters.child_span_count),
+            );
+        }
+
+        if self.request_summary {
+            match span.scope().from_root().next() {
+                Some(root) if root.id() != span.id() => {
+                    drop(extensions);
+                    if let Some(summary) = root.extensions_mut().get_mut::<RequestSummary>() {
+                        let name = span.name();
+                        if summary
+                            .slowest_child_span
+                            .is_none_or(|(_, slowest)| elapsed_milliseconds > slowest)
+                        {
+                            summary.slowest_child_span = Some((name, elapsed_milliseconds));
+                        }
+                    }
+                }
+                _ => {
+                    let summary = extensions
+                        .remove::<RequestSummary>()
+                        .as_ref()
+                        .map(RequestSummary::to_value);
+                    if let (Some(summary), Some(visitor)) =
+                        (summary, extensions.get_mut::<Storage<'_>>())
+                    {
+                        visitor.record_internal_value(super::keys::REQUEST_SUMMARY, summary);
+                    }
+                }
+            }
         }
     }
 }
@@ -0,0 +1,212 @@
+//! Provides the [`log_error!`][crate::log_error] macro for logging errors following the logging
+//! conventions, along with the [`ErrorCodeRegistry`] the error codes are validated against.
+
+/// A registry of the error codes of an application, which the codes passed to
+/// [`log_error!`][crate::log_error] are validated against at compile time.
+///
+/// The registry is expected to be available as a constant named `ERROR_CODES` at the call sites
+/// of [`log_error!`][crate::log_error] specifying error codes, typically by importing it.
+///
+/// # Example
+///
+/// ```
+/// use log_utils::ErrorCodeRegistry;
+///
+/// pub const ERROR_CODES: ErrorCodeRegistry = ErrorCodeRegistry::new(&["PAY_001", "PAY_002"]);
+///
+/// const _: () = assert!(ERROR_CODES.contains("PAY_001"));
+/// assert!(!ERROR_CODES.contains("PAY_003"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorCodeRegistry {
+    codes: &'static [&'static str],
+}
+
+impl ErrorCodeRegistry {
+    /// Creates a new [`ErrorCodeRegistry`] with the specified error codes.
+    pub const fn new(codes: &'static [&'static str]) -> Self {
+        Self { codes }
+    }
+
+    /// Returns the error codes in the registry.
+    pub const fn codes(&self) -> &'static [&'static str] {
+        self.codes
+    }
+
+    /// Returns `true` if the error code is in the registry.
+    pub const fn contains(&self, code: &str) -> bool {
+        contains(self.codes, code)
+    }
+}
+
+/// Returns `true` if the key is one of the keys set by the formatter for every record, which
+/// cannot be logged via events.
+#[doc(hidden)]
+pub const fn is_reserved_key(key: &str) -> bool {
+    contains(super::keys::IMPLICIT_KEY_LIST, key)
+}
+
+/// Logs the message describing a skipped field with a reserved key, as a warning.
+///
+/// With the `masking` feature enabled, the message is logged as an error in debug builds if the
+/// value of the field came from a masked type, so that secrets recorded under reserved keys are
+/// caught during development, while other collisions with reserved keys remain warnings.
+pub(crate) fn log_skipped_reserved_key(
+    value: &serde_json::Value,
+    message: std::fmt::Arguments<'_>,
+) {
+    if cfg!(all(feature = "masking", debug_assertions)) && holds_masked_value(value) {
+        tracing::error!("{message}");
+    } else {
+        tracing::warn!("{message}");
+    }
+}
+
+/// Returns `true` if the value holds the masked representation of a masked type, going by the
+/// `***` included in the masked representations of all the masking strategies.
+fn holds_masked_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::String(string) => string.contains("***"),
+        serde_json::Value::Array(values) => values.iter().any(holds_masked_value),
+        serde_json::Value::Object(map) => map.values().any(holds_masked_value),
+        serde_json::Value::Null | serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+            false
+        }
+    }
+}
+
+/// The value of a field with a reserved key, as included in the records logged when such fields
+/// are skipped.
+///
+/// With the `masking` feature enabled, the value is masked using the
+/// [`JsonMaskStrategy`][hyperswitch_masking::JsonMaskStrategy], so that the records do not expose
+/// the value if it holds a secret.
+pub(crate) struct ReservedKeyValue<'a>(pub(crate) &'a serde_json::Value);
+
+impl std::fmt::Display for ReservedKeyValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(feature = "masking")]
+        {
+            use hyperswitch_masking::{JsonMaskStrategy, Strategy};
+
+            JsonMaskStrategy::fmt(self.0, f)
+        }
+        #[cfg(not(feature = "masking"))]
+        {
+            std::fmt::Debug::fmt(self.0, f)
+        }
+    }
+}
+
+/// Returns `true` if the string is in the list, usable in constant contexts.
+const fn contains(mut list: &[&str], string: &str) -> bool {
+    while let Some((first, rest)) = list.split_first() {
+        if bytes_eq(first.as_bytes(), string.as_bytes()) {
+            return true;
+        }
+        list = rest;
+    }
+    false
+}
+
+/// Returns `true` if the byte slices are equal, usable in constant contexts.
+const fn bytes_eq(mut a: &[u8], mut b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    while let (Some((a_first, a_rest)), Some((b_first, b_rest))) =
+        (a.split_first(), b.split_first())
+    {
+        if *a_first != *b_first {
+            return false;
+        }
+        a = a_rest;
+        b = b_rest;
+    }
+    true
+}
+
+/// Emits an `ERROR` record for an error, enforcing the logging conventions at compile time.
+///
+/// The macro accepts the error (any type implementing [`std::error::Error`]), an optional error
+/// code, any number of `key = value` fields (with the `?` and `%` sigils of [`tracing`]
+/// supported), and a message with optional format arguments, in that order:
+///
+/// - The error is recorded in the `error` field as a JSON object carrying its `message` and the
+///   messages of its chain of `sources`, rather than as a single debug-formatted string.
+/// - The error code, if specified, is recorded in the `code` field, and must be a string literal
+///   present in the [`ErrorCodeRegistry`] named `ERROR_CODES` in scope at the call site.
+///   Unregistered codes fail compilation.
+/// - Keys reserved by the formatter (such as `level` or `time`) fail compilation, rather than
+///   being dropped with a warning when the record is formatted.
+///
+/// # Example
+///
+/// ```
+/// use log_utils::{ErrorCodeRegistry, log_error};
+///
+/// const ERROR_CODES: ErrorCodeRegistry = ErrorCodeRegistry::new(&["PAY_001"]);
+///
+/// # let payment_id = "pay_123";
+/// # let attempt = 2;
+/// let error = std::io::Error::other("connection reset");
+/// log_error!(error, code = "PAY_001", payment_id = %payment_id, attempt, "Payment failed");
+/// ```
+///
+/// The following fail compilation, due to an unregistered code and a reserved key respectively:
+///
+/// ```compile_fail
+/// # use log_utils::{ErrorCodeRegistry, log_error};
+/// # const ERROR_CODES: ErrorCodeRegistry = ErrorCodeRegistry::new(&["PAY_001"]);
+/// # let error = std::io::Error::other("connection reset");
+/// log_error!(error, code = "PAY_999", "Payment failed");
+/// ```
+///
+/// ```compile_fail
+/// # use log_utils::log_error;
+/// # let error = std::io::Error::other("connection reset");
+/// log_error!(error, level = "high", "Payment failed");
+/// ```
+#[macro_export]
+macro_rules! log_error {
+    // Validate each field and accumulate it, until the message is reached
+    (@fields ($error:expr) ($($fields:tt)*) $key:ident = ?$value:expr, $($rest:tt)+) => {{
+        $crate::log_error!(@key $key);
+        $crate::log_error!(@fields ($error) ($($fields)* $key = ?$value,) $($rest)+)
+    }};
+    (@fields ($error:expr) ($($fields:tt)*) $key:ident = %$value:expr, $($rest:tt)+) => {{
+        $crate::log_error!(@key $key);
+        $crate::log_error!(@fields ($error) ($($fields)* $key = %$value,) $($rest)+)
+    }};
+    (@fields ($error:expr) ($($fields:tt)*) $key:ident = $value:expr, $($rest:tt)+) => {{
+        $crate::log_error!(@key $key);
+        $crate::log_error!(@fields ($error) ($($fields)* $key = $value,) $($rest)+)
+    }};
+    (@fields ($error:expr) ($($fields:tt)*) $key:ident, $($rest:tt)+) => {{
+        $crate::log_error!(@key $key);
+        $crate::log_error!(@fields ($error) ($($fields)* $key,) $($rest)+)
+    }};
+    (@fields ($error:expr) ($($fields:tt)*) $($message:tt)+) => {
+        $crate::__private::tracing::error!(
+            $($fields)*
+            error = &$error as &(dyn ::std::error::Error + 'static),
+            $($message)+
+        )
+    };
+    (@key $key:ident) => {
+        const _: () = ::std::assert!(
+            !$crate::__private::is_reserved_key(::std::stringify!($key)),
+            ::std::concat!("`", ::std::stringify!($key), "` is a reserved key")
+        );
+    };
+    ($error:expr, code = $code:literal, $($rest:tt)+) => {{
+        const _: () = ::std::assert!(
+            ERROR_CODES.contains($code),
+            ::std::concat!("Error code `", $code, "` is not present in `ERROR_CODES`")
+        );
+        $crate::log_error!(@fields ($error) (code = $code,) $($rest)+)
+    }};
+    ($error:expr, $($rest:tt)+) => {{
+        $crate::log_error!(@fields ($error) () $($rest)+)
+    }};
+}
@@ -0,0 +1,147 @@
+//! Provides helpers for spawning `tokio` tasks within named spans, logging panics of the spawned
+//! tasks.
+
+use std::{
+    any::Any,
+    future::Future,
+    panic::{AssertUnwindSafe, catch_unwind, resume_unwind},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::task::JoinHandle;
+use tracing::{Dispatch, Span};
+
+/// Spawns the future as a `tokio` task, running within a span named `task` (carrying the task
+/// name in the `task_name` field), created as a child of the current span.
+///
+/// The task runs with the current subscriber (even if it is not the global default subscriber),
+/// and since the span is a child of the current span, the spawned task inherits the fields of the
+/// current span (according to the configured [`SpanFieldInheritance`][crate::SpanFieldInheritance]),
+/// and the persistent keys recorded within the task are propagated to the current span.
+/// With the `opentelemetry` feature enabled, the current OpenTelemetry context (along with its
+/// baggage) is also propagated to the task.
+///
+/// If the task panics, the panic is logged as an `ERROR` record within the span, carrying the
+/// panic message in the `panic` field, before being propagated to the returned [`JoinHandle`].
+///
+/// # Panics
+///
+/// Panics if called outside of a `tokio` runtime, as with [`tokio::spawn`].
+///
+/// # Example
+///
+/// ```
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let handle = log_utils::spawn_instrumented("refresh_cache", async {
+///     tracing::info!("Refreshing cache");
+/// });
+/// # handle.await.unwrap();
+/// # });
+/// ```
+pub fn spawn_instrumented<F>(name: &'static str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let span = tracing::info_span!("task", task_name = name);
+    let context = PropagatedContext::current();
+
+    tokio::spawn(InstrumentedTask {
+        inner: future,
+        span,
+        context,
+    })
+}
+
+/// Runs the closure on the blocking thread pool of `tokio`, within a span named `task` (carrying
+/// the task name in the `task_name` field), created as a child of the current span.
+///
+/// The context is propagated and panics are logged as with [`spawn_instrumented`].
+///
+/// # Panics
+///
+/// Panics if called outside of a `tokio` runtime, as with [`tokio::task::spawn_blocking`].
+pub fn spawn_blocking_instrumented<F, R>(name: &'static str, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = tracing::info_span!("task", task_name = name);
+    let context = PropagatedContext::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _context_guard = context.attach();
+        let _guard = span.enter();
+
+        catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| log_panic(payload))
+    })
+}
+
+pin_project_lite::pin_project! {
+    /// A future running the inner future within the span of the task, logging panics of the
+    /// inner future.
+    struct InstrumentedTask<F> {
+        #[pin]
+        inner: F,
+        span: Span,
+        context: PropagatedContext,
+    }
+}
+
+impl<F: Future> Future for InstrumentedTask<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _context_guard = this.context.attach();
+        let _guard = this.span.enter();
+
+        let inner = this.inner;
+        catch_unwind(AssertUnwindSafe(|| inner.poll(cx)))
+            .unwrap_or_else(|payload| log_panic(payload))
+    }
+}
+
+/// Logs the panic of a task as an `ERROR` record within the current span, and resumes unwinding.
+fn log_panic(payload: Box<dyn Any + Send>) -> ! {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string panic payload>");
+    tracing::error!(panic = message, "Task panicked");
+
+    resume_unwind(payload)
+}
+
+/// The context propagated from the spawning task to the spawned task, in addition to the span.
+#[derive(Debug)]
+struct PropagatedContext {
+    dispatch: Dispatch,
+    #[cfg(feature = "opentelemetry")]
+    otel_context: opentelemetry::Context,
+}
+
+impl PropagatedContext {
+    /// Captures the context of the current task.
+    fn current() -> Self {
+        Self {
+            dispatch: tracing::dispatcher::get_default(Dispatch::clone),
+            #[cfg(feature = "opentelemetry")]
+            otel_context: opentelemetry::Context::current(),
+        }
+    }
+
+    /// Attaches the context to the current thread, until the returned guard is dropped.
+    fn attach(&self) -> impl Sized {
+        let dispatch_guard = tracing::dispatcher::set_default(&self.dispatch);
+        #[cfg(feature = "opentelemetry")]
+        let otel_context_guard = self.otel_context.clone().attach();
+        #[cfg(not(feature = "opentelemetry"))]
+        let otel_context_guard = ();
+
+        (dispatch_guard, otel_context_guard)
+    }
+}
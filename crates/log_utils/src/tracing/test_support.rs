@@ -0,0 +1,407 @@
+//! Helpers for snapshot (golden file) testing of the JSON log output, and for initializing
+//! logging in tests.
+//!
+//! Records are rendered using a [`JsonFormattingLayer`] with fixed values for the time, process
+//! ID and hostname, so that the rendered records are stable across runs and machines.
+//! Consumers can use these helpers to pin their log contract and catch accidental changes to
+//! the log format, such as when upgrading `log_utils`.
+//!
+//! This module is only available when the `test-support` feature is enabled.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::collections::{HashMap, HashSet};
+//!
+//! use log_utils::{
+//!     AdditionalFieldsPlacement, JsonFormattingLayerConfig,
+//!     test_support::{assert_matches_golden, render_json_records},
+//! };
+//!
+//! let config = JsonFormattingLayerConfig {
+//!     static_top_level_fields: HashMap::new(),
+//!     top_level_keys: HashSet::from(["request_id"]),
+//!     log_span_lifecycles: false,
+//!     additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+//!     ..Default::default()
+//! };
+//!
+//! let records = render_json_records(config, [], || {
+//!     tracing::info!(request_id = "req_123", "Payment created");
+//! })
+//! .unwrap();
+//!
+//! assert_matches_golden(&records, "tests/golden/payment_created.json");
+//! ```
+//!
+//! Logging can be initialized for each test using [`init_for_tests`], which captures the records
+//! emitted by the test for assertions, and prints them in a human-readable format if the test
+//! fails:
+//!
+//! ```
+//! let logs = log_utils::init_for_tests();
+//!
+//! tracing::info!(payment_id = "pay_1", "Payment created");
+//!
+//! assert_eq!(logs.records()[0]["payment_id"], "pay_1");
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+    sync::{Arc, Mutex, Once, PoisonError},
+};
+
+use serde_json::Value;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use super::{
+    AdditionalFieldsPlacement, DirectiveSink, HumanReadableFormattingLayer, HumanReadableOptions,
+    JsonFormattingLayer, JsonFormattingLayerConfig, Level, LoggerError, SpanStorageLayer, keys,
+};
+
+/// The fixed time included in rendered records.
+pub const FIXED_TIME: &str = "2025-01-01T00:00:00.000000000Z";
+
+/// The fixed process ID included in rendered records.
+pub const FIXED_PID: u32 = 1;
+
+/// The fixed hostname included in rendered records.
+pub const FIXED_HOSTNAME: &str = "localhost";
+
+/// The environment variable which, when set to `1`, causes [`assert_matches_golden`] to
+/// overwrite the golden files with the rendered records instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "LOG_UTILS_UPDATE_GOLDEN";
+
+/// The environment variable specifying the filtering directive (e.g., `"info,my_crate=trace"`)
+/// for the records emitted in tests, when logging is initialized using [`init_for_tests`] or
+/// [`init_global_for_tests`].
+/// If unset, records at the `DEBUG` level and above are emitted.
+pub const TEST_LOG_ENV: &str = "LOG_UTILS_TEST_LOG";
+
+/// Renders the records emitted within the closure as JSON values, using a
+/// [`JsonFormattingLayer`] with the specified configuration and a [`SpanStorageLayer`] with the
+/// specified persistent keys.
+///
+/// In addition to fixing the time, process ID and hostname, the span durations
+/// (`elapsed_milliseconds`) are set to zero, since they vary across runs.
+///
+/// # Errors
+///
+/// Returns [`LoggerError`] if the formatting layer could not be constructed with the specified
+/// configuration, or if a rendered record is not valid JSON.
+pub fn render_json_records(
+    config: JsonFormattingLayerConfig,
+    persistent_keys: impl IntoIterator<Item = &'static str>,
+    f: impl FnOnce(),
+) -> Result<Vec<Value>, LoggerError> {
+    let writer = CaptureWriter::default();
+    let formatting_layer =
+        JsonFormattingLayer::new(config, writer.clone(), serde_json::ser::CompactFormatter)?
+            .with_fixed_metadata(FIXED_PID, FIXED_HOSTNAME, FIXED_TIME);
+
+    let subscriber = tracing_subscriber::registry()
+        .with(SpanStorageLayer::new(persistent_keys))
+        .with(formatting_layer);
+    tracing::subscriber::with_default(subscriber, f);
+
+    let buffer = writer.buffer.lock().unwrap_or_else(PoisonError::into_inner);
+
+    serde_json::Deserializer::from_slice(&buffer)
+        .into_iter::<Value>()
+        .map(|record| {
+            let mut record = record?;
+            normalize_elapsed_time(&mut record);
+            Ok(record)
+        })
+        .collect()
+}
+
+/// Asserts that the records match the records stored in the golden file at the specified path.
+///
+/// If the golden file does not exist, or if the [`UPDATE_GOLDEN_ENV`] environment variable is
+/// set to `1`, the golden file is (over)written with the records instead.
+/// The records are stored as a pretty-printed JSON array, to produce readable diffs on review.
+///
+/// # Panics
+///
+/// Panics if the records do not match the records stored in the golden file, or if the golden
+/// file could not be read or written.
+#[expect(clippy::panic)]
+pub fn assert_matches_golden(records: &[Value], path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let update = std::env::var(UPDATE_GOLDEN_ENV).is_ok_and(|value| value == "1");
+
+    if update || !path.exists() {
+        if let Err(error) = write_golden(records, path) {
+            panic!("Failed to write golden file `{}`: {error}", path.display());
+        }
+        return;
+    }
+
+    let expected = match read_golden(path) {
+        Ok(expected) => expected,
+        Err(error) => panic!("Failed to read golden file `{}`: {error}", path.display()),
+    };
+
+    if expected != records {
+        panic!(
+            "Rendered records do not match golden file `{}`\n\nExpected:\n{}\n\nActual:\n{}\n\n\
+             Set `{UPDATE_GOLDEN_ENV}=1` to update the golden file.",
+            path.display(),
+            to_pretty_json(&expected),
+            to_pretty_json(records),
+        );
+    }
+}
+
+/// Initializes logging for the current test, returning a [`TestLogGuard`] which captures the
+/// records emitted by the test (on the current thread) until it is dropped.
+///
+/// The logging subscriber is installed as the default subscriber of the current thread rather
+/// than the global subscriber, so that each test can initialize logging independently, without
+/// failing due to a global subscriber having been set already.
+/// If the test fails, the captured records are printed in a human-readable format when the guard
+/// is dropped, which the test harness displays along with the failure.
+///
+/// The records are filtered using the directive specified by the [`TEST_LOG_ENV`] environment
+/// variable.
+/// Records emitted on other threads (such as by tasks spawned on a multi-threaded runtime) are
+/// not captured; use [`init_global_for_tests`] for printing such records instead.
+///
+/// # Panics
+///
+/// Panics if the [`TEST_LOG_ENV`] environment variable specifies an invalid directive.
+pub fn init_for_tests() -> TestLogGuard {
+    let json_writer = CaptureWriter::default();
+    let human_readable_writer = CaptureWriter::default();
+
+    let config = JsonFormattingLayerConfig {
+        static_top_level_fields: HashMap::new(),
+        top_level_keys: HashSet::new(),
+        log_span_lifecycles: false,
+        additional_fields_placement: AdditionalFieldsPlacement::TopLevel,
+        ..Default::default()
+    };
+    #[expect(clippy::expect_used)]
+    let json_layer = JsonFormattingLayer::new(
+        config,
+        json_writer.clone(),
+        serde_json::ser::CompactFormatter,
+    )
+    .expect("the formatting layer configuration has no static top-level fields");
+
+    let subscriber = tracing_subscriber::registry()
+        .with(SpanStorageLayer::new([]))
+        .with(
+            json_layer
+                .and_then(HumanReadableFormattingLayer::new(
+                    test_human_readable_options(),
+                    human_readable_writer.clone(),
+                ))
+                .with_filter(test_env_filter()),
+        );
+
+    TestLogGuard {
+        json_writer,
+        human_readable_writer,
+        _default_guard: tracing::subscriber::set_default(subscriber),
+    }
+}
+
+/// Initializes logging for all the tests in the current process, writing the records emitted on
+/// any thread in a human-readable format to the output of the test harness, which displays the
+/// output of failed tests.
+///
+/// The logging subscriber is installed as the global subscriber the first time this function is
+/// called, and subsequent calls have no effect.
+/// If a global subscriber has been set already by other means, it is left as is.
+/// The records are filtered using the directive specified by the [`TEST_LOG_ENV`] environment
+/// variable.
+///
+/// # Panics
+///
+/// Panics if the [`TEST_LOG_ENV`] environment variable specifies an invalid directive.
+pub fn init_global_for_tests() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        let _ = tracing_subscriber::registry()
+            .with(SpanStorageLayer::new([]))
+            .with(
+                HumanReadableFormattingLayer::new(test_human_readable_options(), TestOutputWriter)
+                    .with_filter(test_env_filter()),
+            )
+            .try_init();
+    });
+}
+
+/// A guard returned by [`init_for_tests`], which captures the records emitted by the test until
+/// it is dropped.
+#[derive(Debug)]
+pub struct TestLogGuard {
+    json_writer: CaptureWriter,
+    human_readable_writer: CaptureWriter,
+    _default_guard: DefaultGuard,
+}
+
+impl TestLogGuard {
+    /// Returns the records captured so far, as JSON values.
+    pub fn records(&self) -> Vec<Value> {
+        let buffer = self
+            .json_writer
+            .buffer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        serde_json::Deserializer::from_slice(&buffer)
+            .into_iter::<Value>()
+            .filter_map(Result::ok)
+            .collect()
+    }
+
+    /// Returns `true` if any of the records captured so far has the specified message.
+    pub fn contains_message(&self, message: &str) -> bool {
+        self.records()
+            .iter()
+            .any(|record| record[keys::MESSAGE] == message)
+    }
+
+    /// Discards the records captured so far.
+    pub fn clear(&self) {
+        for writer in [&self.json_writer, &self.human_readable_writer] {
+            writer
+                .buffer
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+}
+
+impl Drop for TestLogGuard {
+    #[expect(clippy::print_stderr)]
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let buffer = self
+                .human_readable_writer
+                .buffer
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            eprintln!(
+                "Logs captured by the failed test:\n{}",
+                String::from_utf8_lossy(&buffer)
+            );
+        }
+    }
+}
+
+/// Returns the filter for the records emitted in tests, as specified by the [`TEST_LOG_ENV`]
+/// environment variable.
+#[expect(clippy::panic)]
+fn test_env_filter() -> EnvFilter {
+    let directive = std::env::var(TEST_LOG_ENV).unwrap_or_default();
+    super::build_env_filter(DirectiveSink::Global, &directive, Level::DEBUG)
+        .unwrap_or_else(|error| panic!("Invalid `{TEST_LOG_ENV}` directive: {error}"))
+}
+
+/// Returns the options for the human-readable output of tests, without ANSI colors since the
+/// output may be displayed by tools other than terminals.
+fn test_human_readable_options() -> HumanReadableOptions {
+    HumanReadableOptions {
+        ansi_colors: false,
+        ..HumanReadableOptions::default()
+    }
+}
+
+/// Writes the records to the golden file, creating the parent directories if required.
+fn write_golden(records: &[Value], path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(path, to_pretty_json(records) + "\n")
+}
+
+/// Reads the records from the golden file.
+fn read_golden(path: &Path) -> io::Result<Vec<Value>> {
+    let contents = std::fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(io::Error::from)
+}
+
+/// Serializes the records as a pretty-printed JSON array.
+fn to_pretty_json(records: &[Value]) -> String {
+    serde_json::to_string_pretty(records).unwrap_or_else(|error| error.to_string())
+}
+
+/// Sets the span duration in the record to zero, wherever it is placed in the record.
+fn normalize_elapsed_time(record: &mut Value) {
+    if let Value::Object(map) = record {
+        for (key, value) in map.iter_mut() {
+            if key == keys::ELAPSED_MILLISECONDS {
+                *value = Value::from(0);
+            } else {
+                normalize_elapsed_time(value);
+            }
+        }
+    }
+}
+
+/// A writer capturing the rendered records in memory.
+#[derive(Clone, Debug, Default)]
+struct CaptureWriter {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MakeWriter<'_> for CaptureWriter {
+    type Writer = Self;
+
+    fn make_writer(&self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// A writer writing the rendered records to the output of the test harness, which captures the
+/// output of each test and displays it only if the test fails.
+///
+/// Unlike writing to [`io::stderr`] directly, the `eprint!` macro is captured by the test harness.
+#[derive(Clone, Copy, Debug)]
+struct TestOutputWriter;
+
+impl io::Write for TestOutputWriter {
+    #[expect(clippy::print_stderr)]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        eprint!("{}", String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MakeWriter<'_> for TestOutputWriter {
+    type Writer = Self;
+
+    fn make_writer(&self) -> Self::Writer {
+        *self
+    }
+}
@@ -0,0 +1,52 @@
+//! Provides integration with [`tokio-console`](https://github.com/tokio-rs/console), for
+//! diagnosing the tasks and resources of the Tokio runtime.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+/// Configuration for the `tokio-console` instrumentation layer.
+///
+/// The layer is only available when the `tokio-console` feature is enabled.
+/// In addition, the application must be compiled with `RUSTFLAGS="--cfg tokio_unstable"` and
+/// with the `tracing` feature of `tokio` enabled, for the Tokio runtime to emit the
+/// instrumentation consumed by the layer.
+/// Building the logging components with this configuration panics if the `tokio_unstable`
+/// configuration option is not set.
+#[derive(Clone, Copy, Debug)]
+pub struct TokioConsoleConfig {
+    /// The address on which the `tokio-console` gRPC server listens for console clients.
+    /// Defaults to `127.0.0.1:6669`.
+    pub server_addr: SocketAddr,
+
+    /// How long the data of completed tasks and resources are retained for.
+    /// Defaults to 1 hour.
+    pub retention: Duration,
+}
+
+impl Default for TokioConsoleConfig {
+    fn default() -> Self {
+        Self {
+            server_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6669),
+            retention: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Builds the `tokio-console` layer, spawning the gRPC server on a background thread.
+///
+/// The layer is filtered to only receive the instrumentation emitted by the Tokio runtime, and
+/// hence does not interfere with the filters configured for the logging sinks.
+#[cfg(feature = "tokio-console")]
+pub(crate) fn build_layer(
+    config: TokioConsoleConfig,
+) -> Box<dyn tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync + 'static> {
+    use tracing_subscriber::Layer;
+
+    console_subscriber::ConsoleLayer::builder()
+        .server_addr(config.server_addr)
+        .retention(config.retention)
+        .spawn()
+        .boxed()
+}
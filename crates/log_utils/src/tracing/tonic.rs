@@ -0,0 +1,393 @@
+//! Provides integration with [`tonic`](https://docs.rs/tonic) gRPC clients and servers, following
+//! the key conventions of this crate.
+//!
+//! - On the server, the [`GrpcServerLayer`] handles each RPC within a root span named
+//!   `grpc_request`, carrying the `request_id`, `rpc_service` and `rpc_method` fields, as well as
+//!   the `grpc_status` and `latency_ms` fields once the response is complete (or the `failed`
+//!   field if the service fails).
+//!   When the span is closed, the [`JsonFormattingLayer`][crate::JsonFormattingLayer] logs its
+//!   exit record, which serves as the access log record of the RPC.
+//!   The request ID and the propagated fields are extracted from the metadata of the request.
+//! - On the client, the [`GrpcClientInterceptor`] injects the request ID and the propagated
+//!   fields of the current span into the metadata of outgoing requests, so that the context is
+//!   carried across services.
+//!
+//! With the `opentelemetry` feature enabled, the trace context (such as the W3C `traceparent` and
+//! `tracestate` entries) and the baggage are also extracted from and injected into the metadata,
+//! using the global OpenTelemetry text map propagator (set with
+//! `opentelemetry::global::set_text_map_propagator()`).
+//! On the server, the extracted OpenTelemetry context is attached while the RPC is handled, so
+//! that the baggage entries configured on the [`SpanStorageLayer`][crate::SpanStorageLayer] are
+//! included in the logs.
+//!
+//! Since [`tonic::service::Interceptor`]s cannot observe responses, the server side is provided
+//! as a [`tower_layer::Layer`], to be added using `tonic::transport::Server::layer()`.
+//! The spans are created with the `log_utils::tracing::tonic` target, which must be enabled by
+//! the filters of the logging sinks for the spans to be logged.
+//!
+//! This module is only available when the `tonic` feature is enabled.
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Instant,
+};
+
+use http::{HeaderMap, HeaderName, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use serde_json::Value;
+use tonic::{
+    Status,
+    metadata::{MetadataKey, MetadataValue},
+    service::Interceptor,
+};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{Span, field::Empty};
+
+use super::{
+    keys,
+    storage::{current_span_fields, record_all_on_current_span},
+    tower::{DEFAULT_REQUEST_ID_HEADER, RequestId, request_id_or_generate},
+};
+
+/// The name of the header carrying the status of a gRPC response, in the trailers (or in the
+/// headers of trailers-only responses).
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+
+/// A [`tower_layer::Layer`] that wraps gRPC services with the [`GrpcServerService`], for logging
+/// RPCs.
+#[derive(Clone, Debug, Default)]
+pub struct GrpcServerLayer {
+    propagated_fields: HashSet<&'static str>,
+}
+
+impl GrpcServerLayer {
+    /// Creates a new [`GrpcServerLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies the fields to be extracted from the metadata entries with the same keys, and
+    /// recorded on the span of each RPC, such as the fields propagated by the
+    /// [`GrpcClientInterceptor`] of the calling service.
+    pub fn with_propagated_fields(
+        mut self,
+        propagated_fields: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        self.propagated_fields = HashSet::from_iter(propagated_fields);
+        self
+    }
+}
+
+impl<S> Layer<S> for GrpcServerLayer {
+    type Service = GrpcServerService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcServerService {
+            inner,
+            propagated_fields: self.propagated_fields.clone(),
+        }
+    }
+}
+
+/// A [`tower_service::Service`] that handles each RPC within a root span, logged as an access log
+/// record when the response is complete.
+///
+/// Refer to the [module-level documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct GrpcServerService<S> {
+    inner: S,
+    propagated_fields: HashSet<&'static str>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for GrpcServerService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = Response<GrpcResponseBody<ResBody>>;
+    type Error = S::Error;
+    type Future = GrpcResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        #[cfg(feature = "opentelemetry")]
+        let otel_context = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&MetadataCarrier(request.headers_mut()))
+        });
+        #[cfg(feature = "opentelemetry")]
+        let _otel_context_guard = otel_context.clone().attach();
+        #[cfg(not(feature = "opentelemetry"))]
+        let otel_context = ();
+
+        let request_id_header = HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER);
+        let request_id = request_id_or_generate(request.headers(), &request_id_header);
+        request
+            .headers_mut()
+            .insert(request_id_header, request_id.clone());
+        request
+            .extensions_mut()
+            .insert(RequestId::new(request_id.clone()));
+
+        // The path of gRPC requests is of the form `/{package}.{service}/{method}`
+        let (rpc_service, rpc_method) = request
+            .uri()
+            .path()
+            .trim_start_matches('/')
+            .split_once('/')
+            .unwrap_or_default();
+        let span = tracing::info_span!(
+            parent: None,
+            "grpc_request",
+            request_id = request_id.to_str().unwrap_or_default(),
+            rpc_service,
+            rpc_method,
+            grpc_status = Empty,
+            latency_ms = Empty,
+            failed = Empty,
+        );
+
+        let started_at = Instant::now();
+        let inner = span.in_scope(|| {
+            record_all_on_current_span(self.propagated_fields.iter().filter_map(|key| {
+                let value = request.headers().get(*key)?.to_str().ok()?;
+                Some((*key, Value::from(value)))
+            }));
+            self.inner.call(request)
+        });
+
+        GrpcResponseFuture {
+            inner,
+            span: Some(span),
+            started_at,
+            otel_context,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response future of the [`GrpcServerService`].
+    #[derive(Debug)]
+    pub struct GrpcResponseFuture<F> {
+        #[pin]
+        inner: F,
+        span: Option<Span>,
+        started_at: Instant,
+        otel_context: OtelContext,
+    }
+}
+
+/// The OpenTelemetry context extracted from the metadata of the request, attached while the RPC
+/// is handled.
+#[cfg(feature = "opentelemetry")]
+type OtelContext = opentelemetry::Context;
+#[cfg(not(feature = "opentelemetry"))]
+type OtelContext = ();
+
+impl<F, ResBody, E> Future for GrpcResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = Result<Response<GrpcResponseBody<ResBody>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = {
+            #[cfg(feature = "opentelemetry")]
+            let _otel_context_guard = this.otel_context.clone().attach();
+            let _guard = this.span.as_ref().map(Span::enter);
+            ready!(this.inner.poll(cx))
+        };
+        let span = this.span.take().unwrap_or_else(Span::none);
+
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                record_latency(&span, *this.started_at);
+                span.record("failed", true);
+                return Poll::Ready(Err(error));
+            }
+        };
+
+        // Trailers-only responses (such as errors) carry the status in the headers
+        let span = if record_grpc_status(&span, response.headers()) {
+            record_latency(&span, *this.started_at);
+            None
+        } else {
+            Some(span)
+        };
+
+        Poll::Ready(Ok(response.map(|inner| GrpcResponseBody {
+            inner,
+            span,
+            started_at: *this.started_at,
+        })))
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response body of the [`GrpcServerService`], which holds the span of the RPC open until
+    /// the status is received in the trailers.
+    #[derive(Debug)]
+    pub struct GrpcResponseBody<B> {
+        #[pin]
+        inner: B,
+        span: Option<Span>,
+        started_at: Instant,
+    }
+}
+
+impl<B: Body> Body for GrpcResponseBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let result = ready!(this.inner.poll_frame(cx));
+
+        if let Some(span) = this.span.as_ref() {
+            let is_complete = match &result {
+                Some(Ok(frame)) => frame
+                    .trailers_ref()
+                    .is_some_and(|trailers| record_grpc_status(span, trailers)),
+                Some(Err(_)) => {
+                    span.record("failed", true);
+                    true
+                }
+                None => true,
+            };
+            if is_complete {
+                record_latency(span, *this.started_at);
+                *this.span = None;
+            }
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Records the gRPC status carried by the headers or trailers on the span, returning `true` if
+/// the status was present.
+fn record_grpc_status(span: &Span, headers: &HeaderMap) -> bool {
+    let Some(status) = headers
+        .get(GRPC_STATUS_HEADER)
+        .and_then(|status| status.to_str().ok())
+        .and_then(|status| status.parse::<i32>().ok())
+    else {
+        return false;
+    };
+
+    span.record("grpc_status", status);
+    true
+}
+
+/// Records the time elapsed since the RPC started on the span.
+fn record_latency(span: &Span, started_at: Instant) {
+    let latency_ms = u64::try_from(started_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+    span.record("latency_ms", latency_ms);
+}
+
+/// A [`tonic::service::Interceptor`] for gRPC clients, which injects the request ID and the
+/// propagated fields of the current span into the metadata of outgoing requests.
+///
+/// The request ID is injected into the `x-request-id` metadata entry, and the propagated fields
+/// into the metadata entries with the same keys, which the [`GrpcServerLayer`] of the called
+/// service extracts.
+/// Fields absent from the current span, or whose values are not valid metadata values, are
+/// skipped.
+/// With the `opentelemetry` feature enabled, the current OpenTelemetry context is also injected,
+/// using the global text map propagator.
+#[derive(Clone, Debug, Default)]
+pub struct GrpcClientInterceptor {
+    propagated_fields: HashSet<&'static str>,
+}
+
+impl GrpcClientInterceptor {
+    /// Creates a new [`GrpcClientInterceptor`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies the fields of the current span to be injected into the metadata of outgoing
+    /// requests, in addition to the request ID.
+    pub fn with_propagated_fields(
+        mut self,
+        propagated_fields: impl IntoIterator<Item = &'static str>,
+    ) -> Self {
+        self.propagated_fields = HashSet::from_iter(propagated_fields);
+        self
+    }
+}
+
+impl Interceptor for GrpcClientInterceptor {
+    fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        let fields = current_span_fields();
+        let entries = std::iter::once((DEFAULT_REQUEST_ID_HEADER, keys::REQUEST_ID))
+            .chain(self.propagated_fields.iter().map(|key| (*key, *key)));
+
+        for (metadata_key, field) in entries {
+            let Some(value) = fields.get(field) else {
+                continue;
+            };
+            let value = match value {
+                Value::String(value) => MetadataValue::try_from(value.as_str()),
+                value => MetadataValue::try_from(value.to_string()),
+            };
+            if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(metadata_key.as_bytes()), value)
+            {
+                request.metadata_mut().insert(key, value);
+            }
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject(&mut MetadataCarrier(request.metadata_mut().as_mut()));
+        });
+
+        Ok(request)
+    }
+}
+
+/// A carrier of the OpenTelemetry context in the metadata of gRPC requests, for use with the
+/// text map propagators.
+#[cfg(feature = "opentelemetry")]
+struct MetadataCarrier<'a>(&'a mut HeaderMap);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Extractor for MetadataCarrier<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key)?.to_str().ok()
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Injector for MetadataCarrier<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            http::HeaderValue::try_from(value),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Provides a [`tower`](https://docs.rs/tower) middleware ([`RequestLoggingLayer`]) for logging
+//! HTTP requests.
+//!
+//! Each request is handled within a root span named `http_request`, carrying the `request_id`,
+//! `method` and `path` fields, as well as the `status` and `latency_ms` fields once the response
+//! is produced (or the `failed` field if the inner service fails).
+//! When the span is closed, the [`JsonFormattingLayer`][crate::JsonFormattingLayer] logs its exit
+//! record, which serves as the access log record of the request, and includes the persistent
+//! keys propagated from the spans within the request.
+//!
+//! The spans are created with the `log_utils::tracing::tower` target, which must be enabled by
+//! the filters of the logging sinks for the spans to be logged.
+//!
+//! This module is only available when the `tower` feature is enabled.
+//!
+//! # Example
+//!
+//! The layer is typically added to a `tower::ServiceBuilder`, or to the router of a web framework
+//! built on `tower`, such as `axum`.
+//!
+//! ```
+//! use http::HeaderName;
+//! use log_utils::tower::RequestLoggingLayer;
+//! use tower_layer::Layer;
+//!
+//! # #[derive(Clone)]
+//! # struct PaymentsService;
+//! let request_logging_layer = RequestLoggingLayer::new()
+//!     .with_request_id_header(HeaderName::from_static("x-correlation-id"));
+//! let service = request_logging_layer.layer(PaymentsService);
+//! # drop(service);
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Instant,
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{Span, field::Empty};
+
+/// The name of the header carrying the request ID, used by default.
+pub const DEFAULT_REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The ID of a request, inserted into the extensions of the request by the
+/// [`RequestLoggingService`], for use by the inner services.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(HeaderValue);
+
+impl RequestId {
+    /// Creates a new [`RequestId`] from the header value carrying the request ID.
+    pub(crate) fn new(request_id: HeaderValue) -> Self {
+        Self(request_id)
+    }
+
+    /// Returns the request ID as a string.
+    pub fn as_str(&self) -> &str {
+        self.0.to_str().unwrap_or_default()
+    }
+}
+
+/// A [`tower_layer::Layer`] that wraps services with the [`RequestLoggingService`], for logging
+/// HTTP requests.
+#[derive(Clone, Debug)]
+pub struct RequestLoggingLayer {
+    request_id_header: HeaderName,
+}
+
+impl RequestLoggingLayer {
+    /// Creates a new [`RequestLoggingLayer`], which reads the request ID from the
+    /// [`DEFAULT_REQUEST_ID_HEADER`] header.
+    pub fn new() -> Self {
+        Self {
+            request_id_header: HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER),
+        }
+    }
+
+    /// Specifies the header carrying the request ID.
+    ///
+    /// If a request does not carry the header (or the header value is not valid UTF-8), a random
+    /// request ID (a version 4 UUID) is generated, and set in the header of the request passed to the inner service.
+    /// The request ID is also set in the header of the response, unless the inner service has
+    /// set the header already.
+    pub fn with_request_id_header(mut self, request_id_header: HeaderName) -> Self {
+        self.request_id_header = request_id_header;
+        self
+    }
+}
+
+impl Default for RequestLoggingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for RequestLoggingLayer {
+    type Service = RequestLoggingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestLoggingService {
+            inner,
+            request_id_header: self.request_id_header.clone(),
+        }
+    }
+}
+
+/// A [`tower_service::Service`] that handles each request within a root span, logged as an
+/// access log record when the request completes.
+///
+/// Refer to the [module-level documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct RequestLoggingService<S> {
+    inner: S,
+    request_id_header: HeaderName,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RequestLoggingService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let request_id = request_id_or_generate(request.headers(), &self.request_id_header);
+        request
+            .headers_mut()
+            .insert(self.request_id_header.clone(), request_id.clone());
+        request
+            .extensions_mut()
+            .insert(RequestId::new(request_id.clone()));
+
+        let span = tracing::info_span!(
+            parent: None,
+            "http_request",
+            request_id = request_id.to_str().unwrap_or_default(),
+            method = request.method().as_str(),
+            path = request.uri().path(),
+            status = Empty,
+            latency_ms = Empty,
+            failed = Empty,
+        );
+        // The latency includes the time spent synchronously in the inner service's `call()`
+        let started_at = Instant::now();
+        let inner = span.in_scope(|| self.inner.call(request));
+
+        ResponseFuture {
+            inner,
+            span,
+            request_id,
+            request_id_header: self.request_id_header.clone(),
+            started_at,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response future of the [`RequestLoggingService`].
+    #[derive(Debug)]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        span: Span,
+        request_id: HeaderValue,
+        request_id_header: HeaderName,
+        started_at: Instant,
+    }
+}
+
+impl<F, ResBody, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _guard = this.span.enter();
+        let mut result = ready!(this.inner.poll(cx));
+
+        let latency_ms = u64::try_from(this.started_at.elapsed().as_millis()).unwrap_or(u64::MAX);
+        this.span.record("latency_ms", latency_ms);
+        match &mut result {
+            Ok(response) => {
+                this.span.record("status", response.status().as_u16());
+                response
+                    .headers_mut()
+                    .entry(&*this.request_id_header)
+                    .or_insert_with(|| this.request_id.clone());
+            }
+            Err(_) => {
+                this.span.record("failed", true);
+            }
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+/// Returns the request ID carried by the header, or a newly generated request ID if the header is
+/// absent (or its value is not valid UTF-8).
+pub(crate) fn request_id_or_generate(headers: &HeaderMap, header: &HeaderName) -> HeaderValue {
+    headers
+        .get(header)
+        .filter(|value| value.to_str().is_ok())
+        .cloned()
+        .unwrap_or_else(generate_request_id)
+}
+
+/// Generates a random request ID, as a hyphenated version 4 UUID.
+fn generate_request_id() -> HeaderValue {
+    let request_id = uuid::Uuid::new_v4().hyphenated().to_string();
+
+    #[expect(clippy::expect_used)]
+    HeaderValue::try_from(request_id).expect("UUIDs are valid header values")
+}
@@ -1,7 +1,11 @@
 use build_info::{
-    build_date, build_timestamp, cargo_target_triple, git_commit_timestamp, git_describe, git_sha,
-    rustc_commit_date, rustc_commit_hash, rustc_semver,
+    build_date, build_timestamp, cargo_debug, cargo_debug_assertions, cargo_opt_level,
+    cargo_profile, cargo_target_triple, enabled_features, git_branch, git_commit_count,
+    git_commit_timestamp, git_describe, git_dirty, git_sha, rustc_channel, rustc_commit_date,
+    rustc_commit_hash, rustc_host_triple, rustc_llvm_version, rustc_semver,
 };
+use log_utils::{HumanReadableFormattingLayer, HumanReadableOptions};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 fn main() {
     let build_date = build_date!();
@@ -12,17 +16,61 @@ fn main() {
     let target_triple = cargo_target_triple!();
     println!("Target triple: {target_triple}");
 
+    let cargo_profile = cargo_profile!();
+    let cargo_opt_level = cargo_opt_level!();
+    let cargo_debug = cargo_debug!();
+    let cargo_debug_assertions = cargo_debug_assertions!();
+    println!("Cargo profile: {cargo_profile}");
+    println!("Cargo optimization level: {cargo_opt_level}");
+    println!("Cargo debug information: {cargo_debug}");
+    println!("Cargo debug assertions: {cargo_debug_assertions}");
+
+    let mut enabled_features = enabled_features!().into_iter().collect::<Vec<_>>();
+    enabled_features.sort_unstable();
+    println!("Enabled features: {enabled_features:?}");
+
     let rustc_version = rustc_semver!();
     let rustc_commit_hash = rustc_commit_hash!();
     let rustc_commit_date = rustc_commit_date!();
+    let rustc_channel = rustc_channel!();
+    let rustc_host_triple = rustc_host_triple!();
+    let rustc_llvm_version = rustc_llvm_version!();
     println!("Rustc version: {rustc_version}");
     println!("Rustc commit hash: {rustc_commit_hash}");
     println!("Rustc commit date: {rustc_commit_date}");
+    println!("Rustc channel: {rustc_channel}");
+    println!("Rustc host triple: {rustc_host_triple}");
+    println!("Rustc LLVM version: {rustc_llvm_version}");
 
     let git_timestamp = git_commit_timestamp!();
     let git_describe = git_describe!();
     let git_sha = git_sha!();
+    let git_branch = git_branch!();
+    let git_dirty = git_dirty!();
     println!("Git commit timestamp: {git_timestamp}");
     println!("Git describe: {git_describe}");
     println!("Git SHA: {git_sha}");
+    println!("Git branch: {git_branch}");
+    println!("Git dirty: {git_dirty}");
+
+    let git_commit_count = git_commit_count!();
+    println!("Git commit count: {git_commit_count}");
+
+    // Alternatively, all of the information can be obtained as a single struct
+    let build_info = build_info::collect!();
+    assert_eq!(build_info.git_sha, git_sha);
+    assert_eq!(build_info.git_branch, git_branch);
+    assert_eq!(build_info.cargo_profile, cargo_profile);
+
+    // The `try_` variants of the macros return `None` if the build script was not run
+    assert_eq!(build_info::try_git_sha!(), Some(git_sha));
+
+    // The same information can be logged as a single structured record using `log_utils`
+    tracing_subscriber::registry()
+        .with(HumanReadableFormattingLayer::new(
+            HumanReadableOptions::default(),
+            std::io::stdout,
+        ))
+        .init();
+    log_utils::log_startup_info!();
 }